@@ -531,6 +531,7 @@ where
 }
 
 /// A type that holds emitters.
+#[derive(Clone)]
 pub struct EmitterRegistry<M> {
     emitters: HashMap<usize, Emitter<M>>,
     capacity: usize,
@@ -1,6 +1,7 @@
 use std::env;
 use std::io;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
 
@@ -14,6 +15,9 @@ use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::Stream;
 
+use crate::metrics::BroadcasterCounters;
+use crate::ts_stats::TsPacketInspector;
+use crate::ts_stats::TsStatsHandle;
 use crate::tuner::TunerSessionId as BroadcasterId;
 use crate::tuner::TunerSubscriptionId as SubscriberId;
 
@@ -63,6 +67,11 @@ pub struct Broadcaster {
     time_limit: Duration,
     last_received: Instant,
     stream_bound: bool,
+    health_emitter: Option<Emitter<StreamHealthChecked>>,
+    health_checked: bool,
+    ts_stats: TsStatsHandle,
+    inspector: TsPacketInspector,
+    counters: Arc<BroadcasterCounters>,
 }
 
 impl Broadcaster {
@@ -73,12 +82,53 @@ impl Broadcaster {
     const CHUNK_SIZE: usize = 4096 * 8;
 
     pub fn new(id: BroadcasterId, time_limit: u64) -> Self {
+        let ts_stats = TsStatsHandle::default();
         Self {
             id,
             subscribers: Vec::new(),
             time_limit: Duration::from_millis(time_limit),
             last_received: Instant::now(),
             stream_bound: false,
+            health_emitter: None,
+            health_checked: false,
+            ts_stats: ts_stats.clone(),
+            inspector: TsPacketInspector::new(ts_stats),
+            counters: crate::metrics::broadcaster_counters(id.tuner_index()),
+        }
+    }
+
+    pub fn time_limit(&self) -> Duration {
+        self.time_limit
+    }
+
+    // Statistics of TS packets broadcast.
+    pub fn ts_stats(&self) -> TsStatsHandle {
+        self.ts_stats.clone()
+    }
+
+    pub fn with_health_emitter(mut self, emitter: Emitter<StreamHealthChecked>) -> Self {
+        self.health_emitter = Some(emitter);
+        self
+    }
+
+    // The health of the stream is checked only once.  The stream is healthy if
+    // at least one chunk comes before the stream ends.
+    fn check_health(&mut self, healthy: bool, ctx: &mut Context<Self>) {
+        if self.health_checked {
+            return;
+        }
+        self.health_checked = true;
+        if let Some(ref emitter) = self.health_emitter {
+            let emitter = emitter.clone();
+            let msg = StreamHealthChecked {
+                id: self.id,
+                healthy,
+            };
+            // Emit asynchronously in order to avoid a deadlock with an actor
+            // calling this actor.
+            ctx.spawn_task(async move {
+                emitter.emit(msg).await;
+            });
         }
     }
 
@@ -112,16 +162,21 @@ impl Broadcaster {
         let (sender, receiver) = mpsc::channel(Self::MAX_CHUNKS);
         self.subscribers
             .push(Subscriber::new(id, sender, max_stuck_time));
-        BroadcasterStream::new(receiver)
+        self.counters.add_subscribers(1);
+        BroadcasterStream::new(receiver, self.ts_stats.clone())
     }
 
     fn unsubscribe(&mut self, id: SubscriberId) {
         // Log warning message if the user haven't subscribed.
+        let len = self.subscribers.len();
         self.subscribers.retain(|subscriber| subscriber.id != id);
+        self.counters
+            .remove_subscribers(len - self.subscribers.len());
     }
 
     fn broadcast(&mut self, chunk: Bytes) {
         let chunk_size = chunk.len();
+        self.inspector.inspect(&chunk);
         let active_subscribers = self
             .subscribers
             .iter_mut()
@@ -144,6 +199,7 @@ impl Broadcaster {
                     );
                 }
                 Err(mpsc::error::TrySendError::Full(chunk)) => {
+                    self.counters.add_dropped_chunk(chunk.len());
                     if let Some(dropped_bytes) = subscriber.dropped_bytes {
                         // `subscriber.dropped_bytes` might overflow.
                         subscriber.dropped_bytes = Some(dropped_bytes + chunk.len());
@@ -167,6 +223,7 @@ impl Broadcaster {
             }
         }
 
+        self.counters.add_bytes(chunk_size);
         self.last_received = Instant::now();
     }
 
@@ -217,6 +274,7 @@ impl Actor for Broadcaster {
     }
 
     async fn stopped(&mut self, _ctx: &mut Context<Self>) {
+        self.counters.remove_subscribers(self.subscribers.len());
         tracing::debug!(broadcaster.id = %self.id, "Stopped");
     }
 }
@@ -288,9 +346,10 @@ impl Handler<Broadcast> for Broadcaster {
     async fn handle(
         &mut self,
         msg: Broadcast,
-        _ctx: &mut Context<Self>,
+        ctx: &mut Context<Self>,
     ) -> <Broadcast as Message>::Reply {
         tracing::trace!(broadcaster.id = %self.id, msg.name = "Broadcast", msg.chunk.size = msg.0.len());
+        self.check_health(true, ctx);
         self.broadcast(msg.0);
     }
 }
@@ -322,6 +381,7 @@ pub struct StreamEnded;
 impl Handler<StreamEnded> for Broadcaster {
     async fn handle(&mut self, _msg: StreamEnded, ctx: &mut Context<Self>) {
         tracing::debug!(broadcaster.id = %self.id, msg.name = "StreamEnded");
+        self.check_health(false, ctx);
         self.unbind_stream();
         if self.is_inactive() {
             tracing::debug!(broadcaster.id = %self.id, "Inactive, stop");
@@ -330,6 +390,14 @@ impl Handler<StreamEnded> for Broadcaster {
     }
 }
 
+// stream health checked
+
+#[derive(Clone, Message)]
+pub struct StreamHealthChecked {
+    pub id: BroadcasterId,
+    pub healthy: bool,
+}
+
 // chunk source
 
 struct ChunkSource<R, B> {
@@ -490,17 +558,28 @@ where
 // broadcaster stream
 
 #[cfg_attr(test, derive(Debug))]
-pub struct BroadcasterStream(ReceiverStream<Bytes>);
+pub struct BroadcasterStream {
+    inner: ReceiverStream<Bytes>,
+    ts_stats: TsStatsHandle,
+}
 
 impl BroadcasterStream {
-    fn new(rx: mpsc::Receiver<Bytes>) -> Self {
-        Self(ReceiverStream::new(rx))
+    fn new(rx: mpsc::Receiver<Bytes>, ts_stats: TsStatsHandle) -> Self {
+        BroadcasterStream {
+            inner: ReceiverStream::new(rx),
+            ts_stats,
+        }
     }
 
     #[cfg(test)]
     pub fn new_for_test() -> (mpsc::Sender<Bytes>, Self) {
         let (tx, rx) = mpsc::channel(10);
-        (tx, BroadcasterStream::new(rx))
+        (tx, BroadcasterStream::new(rx, Default::default()))
+    }
+
+    // Statistics of TS packets in the tuner session, not in this stream.
+    pub fn ts_stats(&self) -> TsStatsHandle {
+        self.ts_stats.clone()
     }
 }
 
@@ -511,7 +590,7 @@ impl Stream for BroadcasterStream {
         mut self: Pin<&mut Self>,
         cx: &mut std::task::Context,
     ) -> std::task::Poll<Option<Self::Item>> {
-        Pin::new(&mut self.0)
+        Pin::new(&mut self.inner)
             .poll_next(cx)
             .map(|item| item.map(|chunk| Ok(chunk)))
     }
@@ -566,6 +645,18 @@ mod tests {
         assert!(broadcaster.subscribers[0].stuck_start_time.is_none());
     }
 
+    #[tokio::test]
+    async fn test_broadcast_ts_stats() {
+        let mut broadcaster = Broadcaster::new(Default::default(), 0);
+        let ts_stats = broadcaster.ts_stats();
+
+        let mut packet = [0; 188];
+        packet[0] = 0x47;
+        broadcaster.broadcast(Bytes::from(packet.repeat(2)));
+        assert_eq!(ts_stats.get().packets, 2);
+        assert_eq!(ts_stats.get().bytes, 376);
+    }
+
     #[tokio::test]
     async fn test_broadcast() {
         let system = System::new();
@@ -10,14 +10,28 @@ use std::sync::Arc;
 use std::time::Duration;
 use std::time::SystemTime;
 
+use chrono::DateTime;
+use chrono::FixedOffset;
+use chrono::NaiveTime;
 use indexmap::IndexMap;
 use itertools::Itertools;
 use serde::Deserialize;
 use url::Url;
 
+use crate::error::Error;
 use crate::models::*;
 use crate::tuner::TunerSubscriptionId;
 
+// Returns an error message from the enclosing validation function if the
+// condition is not satisfied.
+macro_rules! ensure {
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            return Err(format!($($arg)+));
+        }
+    };
+}
+
 pub fn load<P: AsRef<Path>>(config_path: P) -> Arc<Config> {
     let config_path = config_path.as_ref();
 
@@ -32,7 +46,11 @@ pub fn load<P: AsRef<Path>>(config_path: P) -> Arc<Config> {
 
     match std::env::var_os("MIRAKC_CONFIG_SKIP_VALIDATION") {
         Some(v) if v == "1" => tracing::warn!("Skip validation"),
-        _ => config.validate(),
+        _ => {
+            if let Err(msg) = config.validate() {
+                panic!("{msg}");
+            }
+        }
     }
 
     config.last_modified = std::fs::metadata(config_path)
@@ -43,6 +61,33 @@ pub fn load<P: AsRef<Path>>(config_path: P) -> Arc<Config> {
     Arc::new(config)
 }
 
+/// Loads the config again in order to reload it at runtime.
+///
+/// Unlike `load()`, this function returns an error instead of panicking when
+/// the config is invalid.
+pub fn reload<P: AsRef<Path>>(config_path: P) -> Result<Arc<Config>, Error> {
+    let config_path = config_path.as_ref();
+
+    let reader = File::open(config_path)
+        .map_err(|err| Error::InvalidConfig(format!("Failed to open {config_path:?}: {err}")))?;
+    let mut config: Config = serde_yaml::from_reader(reader)
+        .map_err(|err| Error::InvalidConfig(format!("Failed to parse {config_path:?}: {err}")))?;
+
+    config = config.normalize();
+
+    match std::env::var_os("MIRAKC_CONFIG_SKIP_VALIDATION") {
+        Some(v) if v == "1" => tracing::warn!("Skip validation"),
+        _ => config.validate().map_err(Error::InvalidConfig)?,
+    }
+
+    config.last_modified = std::fs::metadata(config_path)
+        .map(|metadata| metadata.modified().ok())
+        .ok()
+        .flatten();
+    tracing::info!(?config_path, "Reloaded");
+    Ok(Arc::new(config))
+}
+
 // Use the widely used map-style representation for enum types in YAML.
 //
 // `serde_yaml` serializes enum types using YAML tags by default.
@@ -70,6 +115,10 @@ pub struct Config {
     #[serde(default)]
     pub tuners: Vec<TunerConfig>,
     #[serde(default)]
+    pub tuner_selection: TunerSelectionConfig,
+    #[serde(default)]
+    pub tuner_reservations: Vec<TunerReservationConfig>,
+    #[serde(default)]
     pub filters: FiltersConfig,
     #[serde(default)]
     pub pre_filters: HashMap<String, FilterConfig>,
@@ -82,6 +131,8 @@ pub struct Config {
     #[serde(default)]
     pub timeshift: TimeshiftConfig,
     #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+    #[serde(default)]
     #[serde(with = "serde_yaml::with::singleton_map_recursive")]
     pub onair_program_trackers: HashMap<String, OnairProgramTrackerConfig>,
     #[serde(default)]
@@ -99,13 +150,12 @@ impl Config {
         !self.onair_program_trackers.is_empty()
     }
 
-    fn validate(&self) {
-        self.epg.validate();
-        self.server.validate();
-        self.channels
-            .iter()
-            .enumerate()
-            .for_each(|(i, config)| config.validate(i));
+    fn validate(&self) -> Result<(), String> {
+        self.epg.validate()?;
+        self.server.validate()?;
+        for (i, config) in self.channels.iter().enumerate() {
+            config.validate(i)?;
+        }
         // The channels[].name property should be a unique, but some scripts generating a
         // channels.yml use the same name in multiple channels.
         //
@@ -117,38 +167,44 @@ impl Config {
         //            .unique()
         //            .count(),
         //            "config.channels: `name` must be a unique");
-        self.tuners.iter().enumerate().for_each(|(i, config)| {
-            config.validate(i);
+        for (i, config) in self.tuners.iter().enumerate() {
+            config.validate(i)?;
             if let Some(ref name) = config.dedicated_for {
-                assert!(
+                ensure!(
                     self.onair_program_trackers.contains_key(name),
                     "config.tuners: `dedicated-for` must hold an existing name"
                 );
             }
-        });
-        assert_eq!(
-            self.tuners.len(),
-            self.tuners
-                .iter()
-                .map(|config| &config.name)
-                .unique()
-                .count(),
+        }
+        ensure!(
+            self.tuners.len()
+                == self
+                    .tuners
+                    .iter()
+                    .map(|config| &config.name)
+                    .unique()
+                    .count(),
             "config.tuners: `name` must be a unique"
         );
-        self.filters.validate();
-        self.pre_filters
-            .iter()
-            .for_each(|(name, config)| config.validate("pre-filters", name));
-        self.post_filters
-            .iter()
-            .for_each(|(name, config)| config.validate(name));
-        self.jobs.validate();
-        self.recording.validate();
-        self.timeshift.validate();
-        self.onair_program_trackers
-            .iter()
-            .for_each(|(name, config)| config.validate(name));
-        self.resource.validate();
+        self.tuner_selection.validate(&self.tuners)?;
+        for (i, config) in self.tuner_reservations.iter().enumerate() {
+            config.validate(i, &self.tuners)?;
+        }
+        self.filters.validate()?;
+        for (name, config) in self.pre_filters.iter() {
+            config.validate("pre-filters", name)?;
+        }
+        for (name, config) in self.post_filters.iter() {
+            config.validate(name)?;
+        }
+        self.jobs.validate()?;
+        self.recording.validate()?;
+        self.timeshift.validate()?;
+        self.maintenance.validate()?;
+        for (name, config) in self.onair_program_trackers.iter() {
+            config.validate(name)?;
+        }
+        self.resource.validate()
     }
 }
 
@@ -161,9 +217,9 @@ pub struct EpgConfig {
 }
 
 impl EpgConfig {
-    fn validate(&self) {
+    fn validate(&self) -> Result<(), String> {
         if let Some(cache_dir) = self.cache_dir.as_ref() {
-            assert!(
+            ensure!(
                 cache_dir.is_dir(),
                 "config.epg: `cache_dir` must be a path to an existing directory"
             );
@@ -172,6 +228,7 @@ impl EpgConfig {
                 tracing::warn!(config = "epg.cache-dir", "Not specified");
             }
         }
+        Ok(())
     }
 }
 
@@ -232,34 +289,37 @@ impl ServerConfig {
         16 * 1000 // 16s
     }
 
-    fn validate(&self) {
+    fn validate(&self) -> Result<(), String> {
         const SERVER_STREAM_TIME_LIMIT_MIN: u64 = 15_000;
 
-        assert!(
+        ensure!(
             self.stream_time_limit >= SERVER_STREAM_TIME_LIMIT_MIN,
             "config.server: `stream_time_limit` must be larger than or equal to {}",
             SERVER_STREAM_TIME_LIMIT_MIN
         );
 
         if let Some(max_start_delay) = self.program_stream_max_start_delay {
-            assert!(
+            ensure!(
                 max_start_delay < Duration::from_secs(24 * 3600),
                 "config.server: `program-stream-max-start-delay` \
                  must not be less than 24h"
             );
         }
 
-        self.addrs.iter().for_each(|addr| addr.validate());
-        self.mounts
-            .iter()
-            .for_each(|(mp, config)| config.validate(mp));
+        for addr in self.addrs.iter() {
+            addr.validate()?;
+        }
+        for (mp, config) in self.mounts.iter() {
+            config.validate(mp)?;
+        }
 
         if let Some(ref path) = self.folder_view_template_path {
-            assert!(
+            ensure!(
                 path.is_file(),
                 "config.server: `folder_view_template_path` must be a path to an existing file"
             );
         }
+        Ok(())
     }
 }
 
@@ -285,15 +345,16 @@ pub enum ServerAddr {
 }
 
 impl ServerAddr {
-    fn validate(&self) {
+    fn validate(&self) -> Result<(), String> {
         match self {
-            Self::Http(addr) => assert!(
+            Self::Http(addr) => ensure!(
                 addr.to_socket_addrs().is_ok(),
                 "config.server.addrs.{}: invalid socket address",
                 addr
             ),
             Self::Unix(_) => (),
         }
+        Ok(())
     }
 }
 
@@ -315,27 +376,27 @@ pub struct MountConfig {
 }
 
 impl MountConfig {
-    fn validate(&self, mount_point: &str) {
+    fn validate(&self, mount_point: &str) -> Result<(), String> {
         const MOUNT_POINT_BLOCK_LIST: [&'static str; 3] = ["/", "/api", "/events"];
         for blocked in MOUNT_POINT_BLOCK_LIST {
-            assert!(
+            ensure!(
                 mount_point != blocked,
                 r#"config.server.mounts[{blocked}]: cannot mount onto "{blocked}""#,
             );
         }
-        assert!(
+        ensure!(
             mount_point.starts_with("/"),
             "config.server.mounts[{}]: \
              a mount point must starts with '/'",
             mount_point
         );
-        assert!(
+        ensure!(
             !mount_point.ends_with("/"),
             "config.server.mounts[{}]: \
              a mount point must not ends with '/'",
             mount_point
         );
-        assert!(
+        ensure!(
             self.path.exists(),
             "config.server.mounts[{}]: \
              `path` must be a path to an existing entry",
@@ -343,13 +404,14 @@ impl MountConfig {
         );
         if let Some(index) = self.index.as_ref() {
             let path = self.path.join(index);
-            assert!(
+            ensure!(
                 path.is_file(),
                 "config.server.mounts[{}]: \
                  `index` must be an existing file if it exists",
                 mount_point
             );
         }
+        Ok(())
     }
 }
 
@@ -412,18 +474,19 @@ impl ChannelConfig {
         normalized
     }
 
-    fn validate(&self, index: usize) {
+    fn validate(&self, index: usize) -> Result<(), String> {
         debug_assert!(!self.disabled);
-        assert!(
+        ensure!(
             !self.name.is_empty(),
             "config.channels[{}]: `name` must be a non-empty string",
             index
         );
-        assert!(
+        ensure!(
             !self.channel.is_empty(),
             "config.channels[{}]: `channel` must be a non-empty string",
             index
         );
+        Ok(())
     }
 }
 
@@ -440,6 +503,7 @@ pub struct TunerConfig {
     pub name: String,
     #[serde(rename = "types")]
     pub channel_types: Vec<ChannelType>,
+    #[serde(default)]
     pub command: String,
     #[serde(default = "TunerConfig::default_time_limit")]
     pub time_limit: u64,
@@ -449,6 +513,16 @@ pub struct TunerConfig {
     pub decoded: bool,
     #[serde(default)]
     pub dedicated_for: Option<String>,
+    #[serde(default = "TunerConfig::default_fault_threshold")]
+    pub fault_threshold: usize,
+    #[serde(default = "TunerConfig::default_fault_retry_interval")]
+    pub fault_retry_interval: u64,
+    #[serde(default)]
+    pub upstream: Option<Url>,
+    #[serde(default = "TunerConfig::default_weight")]
+    pub weight: u32,
+    #[serde(default)]
+    pub role: TunerRole,
 }
 
 impl TunerConfig {
@@ -456,25 +530,56 @@ impl TunerConfig {
         30 * 1000 // 30s
     }
 
-    fn validate(&self, index: usize) {
+    fn default_fault_threshold() -> usize {
+        3
+    }
+
+    fn default_fault_retry_interval() -> u64 {
+        60 * 1000 // 1m
+    }
+
+    fn default_weight() -> u32 {
+        1
+    }
+
+    fn validate(&self, index: usize) -> Result<(), String> {
         if self.disabled {
-            return;
+            return Ok(());
         }
-        assert!(
+        ensure!(
             !self.name.is_empty(),
             "config.tuners[{}]: `name` must be a non-empty string",
             index
         );
-        assert!(
+        ensure!(
             !self.channel_types.is_empty(),
             "config.tuners[{}]: `types` must be a non-empty list",
             index
         );
-        assert!(
-            !self.command.is_empty(),
-            "config.tuners[{}]: `command` must be a non-empty string",
+        if let Some(ref upstream) = self.upstream {
+            ensure!(
+                self.command.is_empty(),
+                "config.tuners[{}]: `command` and `upstream` are exclusive",
+                index
+            );
+            ensure!(
+                matches!(upstream.scheme(), "http" | "https"),
+                "config.tuners[{}]: `upstream` must be a HTTP or HTTPS URL",
+                index
+            );
+        } else {
+            ensure!(
+                !self.command.is_empty(),
+                "config.tuners[{}]: `command` must be a non-empty string",
+                index
+            );
+        }
+        ensure!(
+            self.fault_threshold == 0 || self.fault_retry_interval > 0,
+            "config.tuners[{}]: `fault-retry-interval` must be larger than 0",
             index
         );
+        Ok(())
     }
 }
 
@@ -488,10 +593,122 @@ impl Default for TunerConfig {
             disabled: false,
             decoded: false,
             dedicated_for: None,
+            fault_threshold: Self::default_fault_threshold(),
+            fault_retry_interval: Self::default_fault_retry_interval(),
+            upstream: None,
+            weight: Self::default_weight(),
+            role: TunerRole::Any,
         }
     }
 }
 
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub struct TunerSelectionConfig {
+    #[serde(default)]
+    pub policy: TunerSelectionPolicy,
+    #[serde(default)]
+    pub preferred: HashMap<ChannelType, Vec<String>>,
+}
+
+impl TunerSelectionConfig {
+    fn validate(&self, tuners: &[TunerConfig]) -> Result<(), String> {
+        for (channel_type, names) in self.preferred.iter() {
+            for name in names.iter() {
+                ensure!(
+                    tuners.iter().any(|tuner| tuner.name == *name),
+                    "config.tuner-selection.preferred[{}]: \
+                     `{}` must be a name of a tuner",
+                    channel_type,
+                    name
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub struct TunerReservationConfig {
+    #[serde(default)]
+    pub tuner: Option<String>,
+    #[serde(default)]
+    pub channel_type: Option<ChannelType>,
+    #[serde(default)]
+    pub start: Option<NaiveTime>,
+    #[serde(default)]
+    pub end: Option<NaiveTime>,
+    #[serde(default)]
+    pub start_at: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
+    pub end_at: Option<DateTime<FixedOffset>>,
+    pub owner: String,
+    #[serde(default = "TunerReservationConfig::default_priority")]
+    pub priority: i32,
+}
+
+impl TunerReservationConfig {
+    fn default_priority() -> i32 {
+        TunerUserPriority::MAX
+    }
+
+    fn validate(&self, index: usize, tuners: &[TunerConfig]) -> Result<(), String> {
+        match (&self.tuner, self.channel_type) {
+            (Some(name), None) => {
+                let tuner = tuners.iter().find(|tuner| tuner.name == *name);
+                ensure!(
+                    tuner.is_some(),
+                    "config.tuner-reservations[{}]: `tuner` must be a name of a tuner",
+                    index
+                );
+                ensure!(
+                    tuner.unwrap().dedicated_for.is_none(),
+                    "config.tuner-reservations[{}]: `tuner` must not be a dedicated tuner",
+                    index
+                );
+            }
+            (None, Some(_)) => (),
+            _ => {
+                return Err(format!(
+                    "config.tuner-reservations[{}]: \
+                     either `tuner` or `channel-type` must be specified",
+                    index
+                ))
+            }
+        }
+        match (self.start, self.end, self.start_at, self.end_at) {
+            (Some(start), Some(end), None, None) => ensure!(
+                start != end,
+                "config.tuner-reservations[{}]: `start` and `end` must be different",
+                index
+            ),
+            (None, None, Some(start_at), Some(end_at)) => ensure!(
+                start_at < end_at,
+                "config.tuner-reservations[{}]: `start-at` must be earlier than `end-at`",
+                index
+            ),
+            _ => {
+                return Err(format!(
+                    "config.tuner-reservations[{}]: \
+                     either `start` and `end` or `start-at` and `end-at` must be specified",
+                    index
+                ))
+            }
+        }
+        ensure!(
+            (TunerUserPriority::MIN..=TunerUserPriority::MAX).contains(&self.priority),
+            "config.tuner-reservations[{}]: `priority` must be in the range {}..={}",
+            index,
+            TunerUserPriority::MIN,
+            TunerUserPriority::MAX
+        );
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq)]
 #[serde(rename_all = "kebab-case")]
 #[serde(deny_unknown_fields)]
@@ -529,7 +746,9 @@ impl FiltersConfig {
         FilterConfig {
             command: "mirakc-arib filter-program --sid={{{sid}}} --eid={{{eid}}} \
                       --clock-pid={{{clock_pid}}} --clock-pcr={{{clock_pcr}}} \
-                      --clock-time={{{clock_time}}} --end-margin=2000\
+                      --clock-time={{{clock_time}}}\
+                      {{#start_margin}} --start-margin={{{.}}}{{/start_margin}}\
+                      {{#end_margin}} --end-margin={{{.}}}{{/end_margin}}\
                       {{#video_tags}} --video-tag={{{.}}}{{/video_tags}}\
                       {{#audio_tags}} --audio-tag={{{.}}}{{/audio_tags}}\
                       {{#wait_until}} --wait-until={{{.}}}{{/wait_until}}\
@@ -538,11 +757,12 @@ impl FiltersConfig {
         }
     }
 
-    fn validate(&self) {
+    fn validate(&self) -> Result<(), String> {
         // self.tuner_filter.command may be an empty string.
-        self.service_filter.validate("filters", "service-filter");
-        self.program_filter.validate("filters", "program-filter");
+        self.service_filter.validate("filters", "service-filter")?;
+        self.program_filter.validate("filters", "program-filter")?;
         // self.decode_filter.command may be an empty string.
+        Ok(())
     }
 }
 
@@ -566,13 +786,14 @@ pub struct FilterConfig {
 }
 
 impl FilterConfig {
-    fn validate(&self, group: &str, name: &str) {
-        assert!(
+    fn validate(&self, group: &str, name: &str) -> Result<(), String> {
+        ensure!(
             !self.command.is_empty(),
             "config.{}.{}: `command` must be a non-empty string",
             group,
             name
         );
+        Ok(())
     }
 }
 
@@ -587,19 +808,20 @@ pub struct PostFilterConfig {
 }
 
 impl PostFilterConfig {
-    fn validate(&self, name: &str) {
-        assert!(
+    fn validate(&self, name: &str) -> Result<(), String> {
+        ensure!(
             !self.command.is_empty(),
             "config.post-filters.{}: `command` must be a non-empty string",
             name
         );
         if let Some(content_type) = self.content_type.as_ref() {
-            assert!(
+            ensure!(
                 !content_type.is_empty(),
                 "config.post-filters.{}: `content-type` must be a non-empty string",
                 name
             );
         }
+        Ok(())
     }
 }
 
@@ -677,10 +899,10 @@ impl JobsConfig {
         self
     }
 
-    fn validate(&self) {
-        self.scan_services.validate("scan-services");
-        self.sync_clocks.validate("sync-clocks");
-        self.update_schedules.validate("update-schedules");
+    fn validate(&self) -> Result<(), String> {
+        self.scan_services.validate("scan-services")?;
+        self.sync_clocks.validate("sync-clocks")?;
+        self.update_schedules.validate("update-schedules")
     }
 }
 
@@ -707,23 +929,24 @@ pub struct JobConfig {
 }
 
 impl JobConfig {
-    fn validate(&self, name: &str) {
+    fn validate(&self, name: &str) -> Result<(), String> {
         if self.disabled {
             if !crate::timeshift::is_rebuild_mode() {
                 tracing::warn!(config = format!("jobs.{}", name), "Disabled");
             }
         } else {
-            assert!(
+            ensure!(
                 !self.command.is_empty(),
                 "config.jobs.{}: `command` must be a non-empty string",
                 name
             );
-            assert!(
+            ensure!(
                 cron::Schedule::from_str(&self.schedule).is_ok(),
                 "config.jobs.{}: `schedule` is not valid",
                 name
             );
         }
+        Ok(())
     }
 }
 
@@ -732,30 +955,133 @@ impl JobConfig {
 #[serde(deny_unknown_fields)]
 pub struct RecordingConfig {
     pub basedir: Option<PathBuf>,
+    #[serde(default, with = "humantime_serde")]
+    pub start_margin: Duration,
+    #[serde(
+        default = "RecordingConfig::default_end_margin",
+        with = "humantime_serde"
+    )]
+    pub end_margin: Duration,
+    #[serde(default)]
+    pub max_total_size: Option<u64>,
+    #[serde(default)]
+    pub min_free_space: Option<u64>,
+    #[serde(default)]
+    pub jobs: RecordingJobsConfig,
+    #[serde(default = "RecordingConfig::default_content_path")]
+    pub content_path: PathBuf,
 }
 
 impl RecordingConfig {
+    pub const MAX_MARGIN: Duration = Duration::from_secs(3600);
+
     pub fn is_enabled(&self) -> bool {
         self.basedir.is_some()
     }
 
-    fn validate(&self) {
+    fn default_end_margin() -> Duration {
+        // The same margin is used in the program-filter for streaming.
+        Duration::from_secs(2)
+    }
+
+    fn default_content_path() -> PathBuf {
+        "{{{program_id}}}.m2ts".into()
+    }
+
+    fn validate(&self) -> Result<(), String> {
         if let Some(ref basedir) = self.basedir {
-            assert!(
+            ensure!(
                 basedir.is_absolute(),
                 "config.recording: `basedir` must be an absolute path"
             );
-            assert!(
+            ensure!(
                 basedir.is_dir(),
                 "config.recording: `basedir` must be a path to an existing directory"
             );
         }
+        ensure!(
+            self.start_margin <= Self::MAX_MARGIN,
+            "config.recording: `start-margin` must be less than or equal to 1h"
+        );
+        ensure!(
+            self.end_margin <= Self::MAX_MARGIN,
+            "config.recording: `end-margin` must be less than or equal to 1h"
+        );
+        ensure!(
+            !self.content_path.as_os_str().is_empty() && self.content_path.is_relative(),
+            "config.recording: `content-path` must be a relative path"
+        );
+        self.jobs.validate()
     }
 }
 
 impl Default for RecordingConfig {
     fn default() -> Self {
-        RecordingConfig { basedir: None }
+        RecordingConfig {
+            basedir: None,
+            start_margin: Duration::ZERO,
+            end_margin: Self::default_end_margin(),
+            max_total_size: None,
+            min_free_space: None,
+            jobs: Default::default(),
+            content_path: Self::default_content_path(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub struct RecordingJobsConfig {
+    #[serde(default)]
+    pub commands: Vec<String>,
+    #[serde(default = "RecordingJobsConfig::default_max_concurrency")]
+    pub max_concurrency: usize,
+    #[serde(default = "RecordingJobsConfig::default_max_retries")]
+    pub max_retries: u32,
+    #[serde(
+        default = "RecordingJobsConfig::default_retry_delay",
+        with = "humantime_serde"
+    )]
+    pub retry_delay: Duration,
+}
+
+impl RecordingJobsConfig {
+    fn default_max_concurrency() -> usize {
+        1
+    }
+
+    fn default_max_retries() -> u32 {
+        3
+    }
+
+    fn default_retry_delay() -> Duration {
+        Duration::from_secs(60)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for command in self.commands.iter() {
+            ensure!(
+                !command.is_empty(),
+                "config.recording.jobs: `commands` must not contain an empty command"
+            );
+        }
+        ensure!(
+            self.max_concurrency > 0,
+            "config.recording.jobs: `max-concurrency` must be larger than 0"
+        );
+        Ok(())
+    }
+}
+
+impl Default for RecordingJobsConfig {
+    fn default() -> Self {
+        RecordingJobsConfig {
+            commands: vec![],
+            max_concurrency: Self::default_max_concurrency(),
+            max_retries: Self::default_max_retries(),
+            retry_delay: Self::default_retry_delay(),
+        }
     }
 }
 
@@ -781,14 +1107,15 @@ impl TimeshiftConfig {
             .to_string()
     }
 
-    fn validate(&self) {
-        assert!(
+    fn validate(&self) -> Result<(), String> {
+        ensure!(
             !self.command.is_empty(),
             "config.timeshift: `command` must be a non-empty string"
         );
-        self.recorders
-            .iter()
-            .for_each(|(name, config)| config.validate(name));
+        for (name, config) in self.recorders.iter() {
+            config.validate(name)?;
+        }
+        Ok(())
     }
 }
 
@@ -830,20 +1157,20 @@ impl TimeshiftRecorderConfig {
         self.num_chunks - self.num_reserves
     }
 
-    fn validate(&self, name: &str) {
-        assert!(
+    fn validate(&self, name: &str) -> Result<(), String> {
+        ensure!(
             self.ts_file.is_absolute(),
             "config.timeshift.recorders.{}: `ts-file` must be an absolute path",
             name
         );
-        assert!(
+        ensure!(
             self.ts_file.to_str().is_some(),
             "config.timeshift.recorders.{}: `ts-file` path must consist \
              only of UTF-8 compatible characters",
             name
         );
         if let Some(parent) = self.ts_file.parent() {
-            assert!(
+            ensure!(
                 parent.is_dir(),
                 "config.timeshift.recorders.{}: \
                      The parent directory of `ts-file` must exists",
@@ -855,13 +1182,13 @@ impl TimeshiftRecorderConfig {
                 name
             );
         }
-        assert!(
+        ensure!(
             self.data_file.is_absolute(),
             "config.timeshift.recorders.{}: `data-file` must be an absolute path",
             name
         );
         if let Some(parent) = self.data_file.parent() {
-            assert!(
+            ensure!(
                 parent.is_dir(),
                 "config.timeshift.recorders.{}: \
                      The parent directory of `data-file` must exists",
@@ -883,33 +1210,33 @@ impl TimeshiftRecorderConfig {
         //
         // We may support a binary format in the future if there is a crate that works well with
         // our data formats.
-        assert!(
+        ensure!(
             self.data_file.extension().is_some() && self.data_file.extension().unwrap() == "json",
             "config.timeshift.recorders.{}: `data-file` must be a JSON file",
             name
         );
-        assert!(
+        ensure!(
             self.chunk_size > 0,
             "config.timeshift.recorders.{}: `chunk-size` must be larger than 0",
             name
         );
-        assert!(
+        ensure!(
             self.chunk_size % (Self::BUFSIZE) == 0,
             "config.timeshift.recorders.{}: `chunk-size` must be a multiple of {}",
             name,
             Self::BUFSIZE,
         );
-        assert!(
+        ensure!(
             self.num_chunks > 2,
             "config.timeshift.recorders.{}: `num-chunks` must be larger than 2",
             name
         );
-        assert!(
+        ensure!(
             self.num_reserves > 0,
             "config.timeshift.recorders.{}: `num-reserves` must be larger than 0",
             name
         );
-        assert!(
+        ensure!(
             self.num_chunks - self.num_reserves > 1,
             "config.timeshift.recorders.{}: Maximum number of available chunks \
              (`num-chunks` - `num-reserves`) must be larger than 1",
@@ -918,18 +1245,20 @@ impl TimeshiftRecorderConfig {
 
         let ts_file_size = match self.ts_file.metadata() {
             Ok(metadata) => metadata.len(),
-            Err(err) => unreachable!(
-                "config.timeshift.recorders.{}: Failed to get the size of `ts-file`: {}",
-                name, err
-            ),
+            Err(err) => {
+                return Err(format!(
+                    "config.timeshift.recorders.{}: Failed to get the size of `ts-file`: {}",
+                    name, err
+                ))
+            }
         };
-        assert_eq!(
-            self.max_file_size(),
-            ts_file_size,
+        ensure!(
+            self.max_file_size() == ts_file_size,
             "config.timeshift.recorders.{}: `ts-file` must be allocated with {} in advance",
             name,
             self.max_file_size()
         );
+        Ok(())
     }
 
     fn default_chunk_size() -> usize {
@@ -955,7 +1284,7 @@ pub enum OnairProgramTrackerConfig {
 }
 
 impl OnairProgramTrackerConfig {
-    fn validate(&self, name: &str) {
+    fn validate(&self, name: &str) -> Result<(), String> {
         match self {
             Self::Local(config) => config.validate(name),
             Self::Remote(config) => config.validate(name),
@@ -985,17 +1314,18 @@ impl LocalOnairProgramTrackerConfig {
         "timeout 5s mirakc-arib collect-eitpf --sids={{{sid}}}".to_string()
     }
 
-    fn validate(&self, name: &str) {
-        assert!(
+    fn validate(&self, name: &str) -> Result<(), String> {
+        ensure!(
             !self.channel_types.is_empty(),
             "config.onair-program-trackers[{name}]: \
              `channel-types` must be a non-empty list"
         );
-        assert!(
+        ensure!(
             !self.command.is_empty(),
             "config.onair-program-trackers[{name}]: \
              `command` must be a non-empty string"
         );
+        Ok(())
     }
 }
 
@@ -1039,7 +1369,9 @@ impl RemoteOnairProgramTrackerConfig {
         "/api/onair".to_string()
     }
 
-    fn validate(&self, _name: &str) {}
+    fn validate(&self, _name: &str) -> Result<(), String> {
+        Ok(())
+    }
 }
 
 impl Default for RemoteOnairProgramTrackerConfig {
@@ -1087,19 +1419,20 @@ impl ResourceConfig {
         "/etc/mirakc/strings.yml".to_string()
     }
 
-    fn validate(&self) {
-        assert!(
+    fn validate(&self) -> Result<(), String> {
+        ensure!(
             Path::new(&self.strings_yaml).is_file(),
             "config.resources: `strings-yaml` must be a path to an existing YAML file"
         );
         for (service_id, image) in self.logos.iter() {
-            assert!(
+            ensure!(
                 Path::new(image).is_file(),
                 "config.resources: `logos[{}]` must be a path to an existing \
                      file",
                 service_id
             );
         }
+        Ok(())
     }
 
     fn deserialize_logos<'de, D>(deserializer: D) -> Result<HashMap<ServiceId, String>, D::Error>
@@ -1124,12 +1457,33 @@ impl Default for ResourceConfig {
     }
 }
 
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub struct MaintenanceConfig {
+    #[serde(default)]
+    pub state_dir: Option<PathBuf>,
+}
+
+impl MaintenanceConfig {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(state_dir) = self.state_dir.as_ref() {
+            ensure!(
+                state_dir.is_dir(),
+                "config.maintenance: `state-dir` must be a path to an existing directory"
+            );
+        }
+        Ok(())
+    }
+}
+
 // <coverage:exclude>
 #[cfg(test)]
 mod tests {
     use std::os::fd::AsRawFd;
 
     use super::*;
+    use assert_matches::assert_matches;
     use indexmap::indexmap;
     use maplit::hashmap;
     use maplit::hashset;
@@ -1167,11 +1521,34 @@ mod tests {
         assert!(config.has_onair_program_trackers());
     }
 
+    #[test]
+    fn test_reload() {
+        use std::io::Write;
+
+        let mut file = NamedTempFile::new().unwrap();
+        // Use an existing file in order to pass the validation.
+        writeln!(file, "resource:\n  strings-yaml: /bin/sh").unwrap();
+        assert!(reload(file.path()).is_ok());
+
+        // A valid YAML which doesn't pass the validation.
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "resource:\n  strings-yaml: /path/not/found").unwrap();
+        assert_matches!(reload(file.path()), Err(Error::InvalidConfig(msg)) => {
+            assert!(msg.contains("strings-yaml"));
+        });
+
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "server:\n  stream-time-limit: 1").unwrap();
+        assert_matches!(reload(file.path()), Err(Error::InvalidConfig(msg)) => {
+            assert!(msg.contains("stream_time_limit"));
+        });
+    }
+
     #[test]
     fn test_config_validate() {
         let mut config = Config::default();
         config.resource.strings_yaml = "/bin/sh".to_string();
-        config.validate();
+        config.validate().unwrap();
     }
 
     #[test]
@@ -1190,7 +1567,7 @@ mod tests {
         "#,
         )
         .unwrap();
-        config.validate();
+        config.validate().unwrap();
     }
 
     #[test]
@@ -1210,7 +1587,7 @@ mod tests {
         "#,
         )
         .unwrap();
-        config.validate();
+        config.validate().unwrap();
     }
 
     #[test]
@@ -1226,7 +1603,7 @@ mod tests {
         "#,
         )
         .unwrap();
-        config.validate();
+        config.validate().unwrap();
     }
 
     #[test]
@@ -1260,7 +1637,7 @@ mod tests {
     #[test]
     fn test_epg_config_validate() {
         let config = EpgConfig::default();
-        config.validate();
+        config.validate().unwrap();
     }
 
     #[test]
@@ -1268,7 +1645,7 @@ mod tests {
     fn test_epg_config_validate_cache_dir() {
         let mut config = EpgConfig::default();
         config.cache_dir = Some("/path/to/dir".into());
-        config.validate();
+        config.validate().unwrap();
     }
 
     #[test]
@@ -1483,7 +1860,7 @@ mod tests {
     #[test]
     fn test_server_config_validate() {
         let config = ServerConfig::default();
-        config.validate();
+        config.validate().unwrap();
     }
 
     #[test]
@@ -1491,7 +1868,7 @@ mod tests {
     fn test_server_config_validate_stream_time_limit() {
         let mut config = ServerConfig::default();
         config.stream_time_limit = 1;
-        config.validate();
+        config.validate().unwrap();
     }
 
     #[test]
@@ -1502,7 +1879,7 @@ mod tests {
         "#,
         )
         .unwrap();
-        config.validate();
+        config.validate().unwrap();
 
         let config = serde_yaml::from_str::<ServerConfig>(
             r#"
@@ -1510,7 +1887,7 @@ mod tests {
         "#,
         )
         .unwrap();
-        config.validate();
+        config.validate().unwrap();
     }
 
     #[test]
@@ -1522,7 +1899,7 @@ mod tests {
         "#,
         )
         .unwrap();
-        config.validate();
+        config.validate().unwrap();
     }
 
     #[test]
@@ -1530,7 +1907,7 @@ mod tests {
     fn test_server_config_validate_addrs() {
         let mut config = ServerConfig::default();
         config.addrs = vec![ServerAddr::Http("invalid".to_string())];
-        config.validate();
+        config.validate().unwrap();
     }
 
     #[test]
@@ -1538,7 +1915,7 @@ mod tests {
     fn test_server_config_validate_folder_view_template_path() {
         let mut config = ServerConfig::default();
         config.folder_view_template_path = Some("not_found".into());
-        config.validate();
+        config.validate().unwrap();
     }
 
     #[test]
@@ -1548,7 +1925,7 @@ mod tests {
             index: None,
             listing: false,
         };
-        config.validate("/test");
+        config.validate("/test").unwrap();
     }
 
     macro_rules! impl_test_mount_config_validate_panic {
@@ -1586,7 +1963,7 @@ mod tests {
                         index: $index,
                         listing: false,
                     };
-                    config.validate($mount_point);
+                    config.validate($mount_point).unwrap();
                 }
             }
         };
@@ -1895,7 +2272,7 @@ mod tests {
             excluded_services: vec![],
             disabled: false,
         };
-        config.validate(0);
+        config.validate(0).unwrap();
     }
 
     #[test]
@@ -1910,7 +2287,7 @@ mod tests {
             excluded_services: vec![],
             disabled: false,
         };
-        config.validate(0);
+        config.validate(0).unwrap();
     }
 
     #[test]
@@ -1925,7 +2302,7 @@ mod tests {
             excluded_services: vec![],
             disabled: false,
         };
-        config.validate(0);
+        config.validate(0).unwrap();
     }
 
     #[test]
@@ -1954,6 +2331,11 @@ mod tests {
                 disabled: false,
                 decoded: false,
                 dedicated_for: None,
+                fault_threshold: TunerConfig::default_fault_threshold(),
+                fault_retry_interval: TunerConfig::default_fault_retry_interval(),
+                upstream: None,
+                weight: TunerConfig::default_weight(),
+                role: TunerRole::Any,
             }
         );
 
@@ -1980,6 +2362,11 @@ mod tests {
                 disabled: false,
                 decoded: false,
                 dedicated_for: None,
+                fault_threshold: TunerConfig::default_fault_threshold(),
+                fault_retry_interval: TunerConfig::default_fault_retry_interval(),
+                upstream: None,
+                weight: TunerConfig::default_weight(),
+                role: TunerRole::Any,
             }
         );
 
@@ -2006,6 +2393,11 @@ mod tests {
                 disabled: true,
                 decoded: false,
                 dedicated_for: None,
+                fault_threshold: TunerConfig::default_fault_threshold(),
+                fault_retry_interval: TunerConfig::default_fault_retry_interval(),
+                upstream: None,
+                weight: TunerConfig::default_weight(),
+                role: TunerRole::Any,
             }
         );
 
@@ -2032,6 +2424,11 @@ mod tests {
                 disabled: false,
                 decoded: true,
                 dedicated_for: None,
+                fault_threshold: TunerConfig::default_fault_threshold(),
+                fault_retry_interval: TunerConfig::default_fault_retry_interval(),
+                upstream: None,
+                weight: TunerConfig::default_weight(),
+                role: TunerRole::Any,
             }
         );
 
@@ -2058,6 +2455,90 @@ mod tests {
                 disabled: false,
                 decoded: false,
                 dedicated_for: Some("user".to_string()),
+                fault_threshold: TunerConfig::default_fault_threshold(),
+                fault_retry_interval: TunerConfig::default_fault_retry_interval(),
+                upstream: None,
+                weight: TunerConfig::default_weight(),
+                role: TunerRole::Any,
+            }
+        );
+
+        assert_eq!(
+            serde_yaml::from_str::<TunerConfig>(
+                r#"
+                name: x
+                types: [GR]
+                command: open tuner
+                fault-threshold: 5
+                fault-retry-interval: 1000
+            "#
+            )
+            .unwrap(),
+            TunerConfig {
+                name: "x".to_string(),
+                channel_types: vec![ChannelType::GR],
+                command: "open tuner".to_string(),
+                time_limit: TunerConfig::default_time_limit(),
+                disabled: false,
+                decoded: false,
+                dedicated_for: None,
+                fault_threshold: 5,
+                fault_retry_interval: 1000,
+                upstream: None,
+                weight: TunerConfig::default_weight(),
+                role: TunerRole::Any,
+            }
+        );
+
+        assert_eq!(
+            serde_yaml::from_str::<TunerConfig>(
+                r#"
+                name: x
+                types: [GR]
+                command: open tuner
+                weight: 10
+                role: recording
+            "#
+            )
+            .unwrap(),
+            TunerConfig {
+                name: "x".to_string(),
+                channel_types: vec![ChannelType::GR],
+                command: "open tuner".to_string(),
+                time_limit: TunerConfig::default_time_limit(),
+                disabled: false,
+                decoded: false,
+                dedicated_for: None,
+                fault_threshold: TunerConfig::default_fault_threshold(),
+                fault_retry_interval: TunerConfig::default_fault_retry_interval(),
+                upstream: None,
+                weight: 10,
+                role: TunerRole::Recording,
+            }
+        );
+
+        assert_eq!(
+            serde_yaml::from_str::<TunerConfig>(
+                r#"
+                name: x
+                types: [GR, BS]
+                upstream: http://upstream:40772/
+            "#
+            )
+            .unwrap(),
+            TunerConfig {
+                name: "x".to_string(),
+                channel_types: vec![ChannelType::GR, ChannelType::BS],
+                command: "".to_string(),
+                time_limit: TunerConfig::default_time_limit(),
+                disabled: false,
+                decoded: false,
+                dedicated_for: None,
+                fault_threshold: TunerConfig::default_fault_threshold(),
+                fault_retry_interval: TunerConfig::default_fault_retry_interval(),
+                upstream: Some(Url::parse("http://upstream:40772/").unwrap()),
+                weight: TunerConfig::default_weight(),
+                role: TunerRole::Any,
             }
         );
 
@@ -2089,8 +2570,13 @@ mod tests {
             disabled: false,
             decoded: false,
             dedicated_for: None,
+            fault_threshold: TunerConfig::default_fault_threshold(),
+            fault_retry_interval: TunerConfig::default_fault_retry_interval(),
+            upstream: None,
+            weight: TunerConfig::default_weight(),
+            role: TunerRole::Any,
         };
-        config.validate(0);
+        config.validate(0).unwrap();
     }
 
     #[test]
@@ -2104,8 +2590,13 @@ mod tests {
             disabled: false,
             decoded: false,
             dedicated_for: None,
+            fault_threshold: TunerConfig::default_fault_threshold(),
+            fault_retry_interval: TunerConfig::default_fault_retry_interval(),
+            upstream: None,
+            weight: TunerConfig::default_weight(),
+            role: TunerRole::Any,
         };
-        config.validate(0);
+        config.validate(0).unwrap();
     }
 
     #[test]
@@ -2119,8 +2610,13 @@ mod tests {
             disabled: false,
             decoded: false,
             dedicated_for: None,
+            fault_threshold: TunerConfig::default_fault_threshold(),
+            fault_retry_interval: TunerConfig::default_fault_retry_interval(),
+            upstream: None,
+            weight: TunerConfig::default_weight(),
+            role: TunerRole::Any,
         };
-        config.validate(0);
+        config.validate(0).unwrap();
     }
 
     #[test]
@@ -2134,8 +2630,72 @@ mod tests {
             disabled: false,
             decoded: false,
             dedicated_for: None,
+            fault_threshold: TunerConfig::default_fault_threshold(),
+            fault_retry_interval: TunerConfig::default_fault_retry_interval(),
+            upstream: None,
+            weight: TunerConfig::default_weight(),
+            role: TunerRole::Any,
         };
-        config.validate(0);
+        config.validate(0).unwrap();
+    }
+
+    #[test]
+    fn test_tuner_config_validate_upstream() {
+        let config = TunerConfig {
+            name: "test".to_string(),
+            channel_types: vec![ChannelType::GR],
+            command: "".to_string(),
+            time_limit: TunerConfig::default_time_limit(),
+            disabled: false,
+            decoded: false,
+            dedicated_for: None,
+            fault_threshold: TunerConfig::default_fault_threshold(),
+            fault_retry_interval: TunerConfig::default_fault_retry_interval(),
+            upstream: Some(Url::parse("http://upstream:40772/").unwrap()),
+            weight: TunerConfig::default_weight(),
+            role: TunerRole::Any,
+        };
+        config.validate(0).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_tuner_config_validate_command_and_upstream() {
+        let config = TunerConfig {
+            name: "test".to_string(),
+            channel_types: vec![ChannelType::GR],
+            command: "test".to_string(),
+            time_limit: TunerConfig::default_time_limit(),
+            disabled: false,
+            decoded: false,
+            dedicated_for: None,
+            fault_threshold: TunerConfig::default_fault_threshold(),
+            fault_retry_interval: TunerConfig::default_fault_retry_interval(),
+            upstream: Some(Url::parse("http://upstream:40772/").unwrap()),
+            weight: TunerConfig::default_weight(),
+            role: TunerRole::Any,
+        };
+        config.validate(0).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_tuner_config_validate_zero_fault_retry_interval() {
+        let config = TunerConfig {
+            name: "test".to_string(),
+            channel_types: vec![ChannelType::GR],
+            command: "test".to_string(),
+            time_limit: TunerConfig::default_time_limit(),
+            disabled: false,
+            decoded: false,
+            dedicated_for: None,
+            fault_threshold: TunerConfig::default_fault_threshold(),
+            fault_retry_interval: 0,
+            upstream: None,
+            weight: TunerConfig::default_weight(),
+            role: TunerRole::Any,
+        };
+        config.validate(0).unwrap();
     }
 
     #[test]
@@ -2148,8 +2708,185 @@ mod tests {
             disabled: true,
             decoded: false,
             dedicated_for: None,
+            fault_threshold: TunerConfig::default_fault_threshold(),
+            fault_retry_interval: TunerConfig::default_fault_retry_interval(),
+            upstream: None,
+            weight: TunerConfig::default_weight(),
+            role: TunerRole::Any,
         };
-        config.validate(0);
+        config.validate(0).unwrap();
+    }
+
+    #[test]
+    fn test_tuner_selection_config() {
+        assert_eq!(
+            serde_yaml::from_str::<TunerSelectionConfig>("{}").unwrap(),
+            Default::default()
+        );
+
+        assert_eq!(
+            serde_yaml::from_str::<TunerSelectionConfig>(
+                r#"
+                policy: least-recently-used
+                preferred:
+                  GR: [gr1, gr0]
+            "#
+            )
+            .unwrap(),
+            TunerSelectionConfig {
+                policy: TunerSelectionPolicy::LeastRecentlyUsed,
+                preferred: hashmap! {
+                    ChannelType::GR => vec!["gr1".to_string(), "gr0".to_string()],
+                },
+            }
+        );
+
+        let result = serde_yaml::from_str::<TunerSelectionConfig>(
+            r#"
+            policy: unknown
+        "#,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_tuner_selection_config_validate_preferred() {
+        let config = serde_yaml::from_str::<Config>(
+            r#"
+            tuners:
+              - name: test
+                types: [GR]
+                command: test
+            tuner-selection:
+              preferred:
+                GR: [unknown]
+            resource:
+              strings-yaml: /bin/sh
+        "#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn test_tuner_reservation_config() {
+        assert_eq!(
+            serde_yaml::from_str::<TunerReservationConfig>(
+                r#"
+                tuner: test
+                start: '19:00:00'
+                end: '21:00:00'
+                owner: other
+            "#
+            )
+            .unwrap(),
+            TunerReservationConfig {
+                tuner: Some("test".to_string()),
+                channel_type: None,
+                start: NaiveTime::from_hms_opt(19, 0, 0),
+                end: NaiveTime::from_hms_opt(21, 0, 0),
+                start_at: None,
+                end_at: None,
+                owner: "other".to_string(),
+                priority: TunerUserPriority::MAX,
+            }
+        );
+
+        assert_eq!(
+            serde_yaml::from_str::<TunerReservationConfig>(
+                r#"
+                channel-type: BS
+                start-at: '2026-10-17T19:00:00+09:00'
+                end-at: '2026-10-17T21:00:00+09:00'
+                owner: other
+                priority: 10
+            "#
+            )
+            .unwrap(),
+            TunerReservationConfig {
+                tuner: None,
+                channel_type: Some(ChannelType::BS),
+                start: None,
+                end: None,
+                start_at: DateTime::parse_from_rfc3339("2026-10-17T19:00:00+09:00").ok(),
+                end_at: DateTime::parse_from_rfc3339("2026-10-17T21:00:00+09:00").ok(),
+                owner: "other".to_string(),
+                priority: 10,
+            }
+        );
+
+        let result = serde_yaml::from_str::<TunerReservationConfig>(
+            r#"
+            tuner: test
+            start: '19:00:00'
+            end: '21:00:00'
+        "#,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_tuner_reservation_config_validate() {
+        let tuners = vec![TunerConfig {
+            name: "test".to_string(),
+            channel_types: vec![ChannelType::GR],
+            command: "test".to_string(),
+            ..Default::default()
+        }];
+        let config = serde_yaml::from_str::<TunerReservationConfig>(
+            r#"
+            tuner: test
+            start: '19:00:00'
+            end: '21:00:00'
+            owner: other
+        "#,
+        )
+        .unwrap();
+        config.validate(0, &tuners).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_tuner_reservation_config_validate_unknown_tuner() {
+        let config = serde_yaml::from_str::<TunerReservationConfig>(
+            r#"
+            tuner: unknown
+            start: '19:00:00'
+            end: '21:00:00'
+            owner: other
+        "#,
+        )
+        .unwrap();
+        config.validate(0, &[]).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_tuner_reservation_config_validate_no_target() {
+        let config = serde_yaml::from_str::<TunerReservationConfig>(
+            r#"
+            start: '19:00:00'
+            end: '21:00:00'
+            owner: other
+        "#,
+        )
+        .unwrap();
+        config.validate(0, &[]).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_tuner_reservation_config_validate_no_window() {
+        let config = serde_yaml::from_str::<TunerReservationConfig>(
+            r#"
+            channel-type: GR
+            start: '19:00:00'
+            owner: other
+        "#,
+        )
+        .unwrap();
+        config.validate(0, &[]).unwrap();
     }
 
     #[test]
@@ -2273,7 +3010,7 @@ mod tests {
         let config = FilterConfig {
             command: "test".to_string(),
         };
-        config.validate("filters", "test");
+        config.validate("filters", "test").unwrap();
     }
 
     #[test]
@@ -2282,7 +3019,7 @@ mod tests {
         let config = FilterConfig {
             command: "".to_string(),
         };
-        config.validate("filters", "test");
+        config.validate("filters", "test").unwrap();
     }
 
     #[test]
@@ -2334,7 +3071,7 @@ mod tests {
             command: "test".to_string(),
             content_type: None,
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -2344,7 +3081,7 @@ mod tests {
             command: "".to_string(),
             content_type: None,
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -2354,7 +3091,7 @@ mod tests {
             command: "test".to_string(),
             content_type: Some("".to_string()),
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -2524,7 +3261,7 @@ mod tests {
             schedule: "0 30 9,12,15 1,15 May-Aug Mon,Wed,Fri 2018/2".to_string(),
             disabled: false,
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -2535,7 +3272,7 @@ mod tests {
             schedule: "0 30 9,12,15 1,15 May-Aug Mon,Wed,Fri 2018/2".to_string(),
             disabled: false,
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -2546,7 +3283,7 @@ mod tests {
             schedule: "".to_string(),
             disabled: false,
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -2556,7 +3293,7 @@ mod tests {
             schedule: "".to_string(),
             disabled: true,
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -2578,6 +3315,80 @@ mod tests {
                 ..Default::default()
             }
         );
+
+        assert_eq!(
+            serde_yaml::from_str::<RecordingConfig>(
+                r#"
+                basedir: /tmp
+                start-margin: 5s
+                end-margin: 10s
+            "#
+            )
+            .unwrap(),
+            RecordingConfig {
+                basedir: Some("/tmp".into()),
+                start_margin: Duration::from_secs(5),
+                end_margin: Duration::from_secs(10),
+                ..Default::default()
+            }
+        );
+
+        assert_eq!(
+            serde_yaml::from_str::<RecordingConfig>(
+                r#"
+                basedir: /tmp
+                max-total-size: 1000000000
+                min-free-space: 100000000
+            "#
+            )
+            .unwrap(),
+            RecordingConfig {
+                basedir: Some("/tmp".into()),
+                max_total_size: Some(1_000_000_000),
+                min_free_space: Some(100_000_000),
+                ..Default::default()
+            }
+        );
+
+        assert_eq!(
+            serde_yaml::from_str::<RecordingConfig>(
+                r#"
+                basedir: /tmp
+                jobs:
+                  commands:
+                    - echo {{{content_path}}}
+                  max-concurrency: 2
+                  max-retries: 0
+                  retry-delay: 10s
+            "#
+            )
+            .unwrap(),
+            RecordingConfig {
+                basedir: Some("/tmp".into()),
+                jobs: RecordingJobsConfig {
+                    commands: vec!["echo {{{content_path}}}".to_string()],
+                    max_concurrency: 2,
+                    max_retries: 0,
+                    retry_delay: Duration::from_secs(10),
+                },
+                ..Default::default()
+            }
+        );
+
+        assert_eq!(
+            serde_yaml::from_str::<RecordingConfig>(
+                r#"
+                basedir: /tmp
+                content-path: "{{{service_name}}}/{{{title}}}.m2ts"
+            "#
+            )
+            .unwrap(),
+            RecordingConfig {
+                basedir: Some("/tmp".into()),
+                content_path: "{{{service_name}}}/{{{title}}}.m2ts".into(),
+                ..Default::default()
+            }
+        );
     }
 
     #[test]
@@ -2601,7 +3412,7 @@ mod tests {
         "#,
         )
         .unwrap();
-        config.validate();
+        config.validate().unwrap();
     }
 
     #[test]
@@ -2613,7 +3424,7 @@ mod tests {
         "#,
         )
         .unwrap();
-        config.validate();
+        config.validate().unwrap();
     }
 
     #[test]
@@ -2625,7 +3436,56 @@ mod tests {
         "#,
         )
         .unwrap();
-        config.validate();
+        config.validate().unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_recording_config_validate_jobs_max_concurrency() {
+        let config = serde_yaml::from_str::<RecordingConfig>(
+            r#"
+            jobs:
+              max-concurrency: 0
+        "#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_recording_config_validate_start_margin() {
+        let config = serde_yaml::from_str::<RecordingConfig>(
+            r#"
+            start-margin: 2h
+        "#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_recording_config_validate_end_margin() {
+        let config = serde_yaml::from_str::<RecordingConfig>(
+            r#"
+            end-margin: 2h
+        "#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_recording_config_validate_content_path() {
+        let config = serde_yaml::from_str::<RecordingConfig>(
+            r#"
+            content-path: /{{{program_id}}}.m2ts
+        "#,
+        )
+        .unwrap();
+        config.validate().unwrap();
     }
 
     #[test]
@@ -2711,7 +3571,7 @@ mod tests {
             command: "test".to_string(),
             recorders: indexmap! {},
         };
-        config.validate();
+        config.validate().unwrap();
     }
 
     #[test]
@@ -2721,7 +3581,7 @@ mod tests {
             command: "".to_string(),
             recorders: indexmap! {},
         };
-        config.validate();
+        config.validate().unwrap();
     }
 
     #[test]
@@ -2806,7 +3666,7 @@ mod tests {
             num_reserves: TimeshiftRecorderConfig::default_num_reserves(),
             priority: TimeshiftRecorderConfig::default_priority(),
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -2821,7 +3681,7 @@ mod tests {
             num_reserves: TimeshiftRecorderConfig::default_num_reserves(),
             priority: TimeshiftRecorderConfig::default_priority(),
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -2836,7 +3696,7 @@ mod tests {
             num_reserves: TimeshiftRecorderConfig::default_num_reserves(),
             priority: TimeshiftRecorderConfig::default_priority(),
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -2851,7 +3711,7 @@ mod tests {
             num_reserves: TimeshiftRecorderConfig::default_num_reserves(),
             priority: TimeshiftRecorderConfig::default_priority(),
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -2866,7 +3726,7 @@ mod tests {
             num_reserves: TimeshiftRecorderConfig::default_num_reserves(),
             priority: TimeshiftRecorderConfig::default_priority(),
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -2881,7 +3741,7 @@ mod tests {
             num_reserves: TimeshiftRecorderConfig::default_num_reserves(),
             priority: TimeshiftRecorderConfig::default_priority(),
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -2896,7 +3756,7 @@ mod tests {
             num_reserves: TimeshiftRecorderConfig::default_num_reserves(),
             priority: TimeshiftRecorderConfig::default_priority(),
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -2911,7 +3771,7 @@ mod tests {
             num_reserves: TimeshiftRecorderConfig::default_num_reserves(),
             priority: TimeshiftRecorderConfig::default_priority(),
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -2926,7 +3786,7 @@ mod tests {
             num_reserves: TimeshiftRecorderConfig::default_num_reserves(),
             priority: TimeshiftRecorderConfig::default_priority(),
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -2941,7 +3801,7 @@ mod tests {
             num_reserves: TimeshiftRecorderConfig::default_num_reserves(),
             priority: TimeshiftRecorderConfig::default_priority(),
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[cfg(not(target_os = "macos"))]
@@ -2963,7 +3823,7 @@ mod tests {
             num_reserves: TimeshiftRecorderConfig::default_num_reserves(),
             priority: TimeshiftRecorderConfig::default_priority(),
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[cfg(not(target_os = "macos"))]
@@ -2987,7 +3847,7 @@ mod tests {
             num_reserves: TimeshiftRecorderConfig::default_num_reserves(),
             priority: TimeshiftRecorderConfig::default_priority(),
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -3002,7 +3862,7 @@ mod tests {
             num_reserves: TimeshiftRecorderConfig::default_num_reserves(),
             priority: TimeshiftRecorderConfig::default_priority(),
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -3017,7 +3877,7 @@ mod tests {
             num_reserves: 0,
             priority: TimeshiftRecorderConfig::default_priority(),
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -3032,7 +3892,7 @@ mod tests {
             num_reserves: 9,
             priority: TimeshiftRecorderConfig::default_priority(),
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -3047,7 +3907,7 @@ mod tests {
             num_reserves: TimeshiftRecorderConfig::default_num_reserves(),
             priority: TimeshiftRecorderConfig::default_priority(),
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -3063,7 +3923,7 @@ mod tests {
             num_reserves: TimeshiftRecorderConfig::default_num_reserves(),
             priority: TimeshiftRecorderConfig::default_priority(),
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -3113,7 +3973,7 @@ mod tests {
             command: LocalOnairProgramTrackerConfig::default_command(),
             stream_id: None,
         };
-        config.validate("test");
+        config.validate("test").unwrap();
 
         let config = LocalOnairProgramTrackerConfig {
             channel_types: hashset![ChannelType::GR],
@@ -3122,7 +3982,7 @@ mod tests {
             command: "true".to_string(),
             stream_id: None,
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -3135,7 +3995,7 @@ mod tests {
             command: LocalOnairProgramTrackerConfig::default_command(),
             stream_id: None,
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -3148,7 +4008,7 @@ mod tests {
             command: "".to_string(),
             stream_id: None,
         };
-        config.validate("test");
+        config.validate("test").unwrap();
     }
 
     #[test]
@@ -3184,6 +4044,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_maintenance_config() {
+        assert_eq!(
+            serde_yaml::from_str::<MaintenanceConfig>("{}").unwrap(),
+            Default::default()
+        );
+
+        assert_eq!(
+            serde_yaml::from_str::<MaintenanceConfig>(
+                r#"
+                state-dir: /path/to/dir
+            "#
+            )
+            .unwrap(),
+            MaintenanceConfig {
+                state_dir: Some("/path/to/dir".into()),
+            }
+        );
+
+        let result = serde_yaml::from_str::<MaintenanceConfig>(
+            r#"
+            unknown:
+              property: value
+        "#,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_maintenance_config_validate() {
+        let config = MaintenanceConfig::default();
+        config.validate().unwrap();
+
+        let config = MaintenanceConfig {
+            state_dir: Some("/tmp".into()),
+        };
+        config.validate().unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_maintenance_config_validate_state_dir() {
+        let config = MaintenanceConfig {
+            state_dir: Some("/path/to/dir".into()),
+        };
+        config.validate().unwrap();
+    }
+
     #[test]
     fn test_resource_config() {
         assert_eq!(
@@ -3234,7 +4142,7 @@ mod tests {
     fn test_resource_config_validate_existing_strings_yaml() {
         let mut config = ResourceConfig::default();
         config.strings_yaml = "/bin/sh".to_string();
-        config.validate();
+        config.validate().unwrap();
     }
 
     #[test]
@@ -3242,7 +4150,7 @@ mod tests {
     fn test_resource_config_validate_non_existing_strings_yaml() {
         let mut config = ResourceConfig::default();
         config.strings_yaml = "/path/to/non-existing".to_string();
-        config.validate();
+        config.validate().unwrap();
     }
 
     #[test]
@@ -3253,7 +4161,7 @@ mod tests {
                 1.into() => "/bin/sh".to_string(),
             },
         };
-        config.validate();
+        config.validate().unwrap();
     }
 
     #[test]
@@ -3265,7 +4173,7 @@ mod tests {
                 1.into() => "/path/to/non-existing".to_string(),
             },
         };
-        config.validate();
+        config.validate().unwrap();
     }
 }
 // </coverage:exclude>
@@ -90,6 +90,26 @@ where
     }
 }
 
+// config reloaded
+
+#[async_trait]
+impl<T, E> Handler<ConfigReloaded> for EitFeeder<T, E>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Clone + Send + Sync + 'static,
+    E: Call<QueryServices>,
+    E: Emit<FlushSchedule>,
+    E: Emit<PrepareSchedule>,
+    E: Emit<UpdateSchedule>,
+{
+    async fn handle(&mut self, msg: ConfigReloaded, _ctx: &mut Context<Self>) {
+        tracing::debug!(msg.name = "ConfigReloaded");
+        self.config = msg.0;
+    }
+}
+
 // feed eit sections
 
 #[derive(Message)]
@@ -80,6 +80,7 @@ where
         self.epg.emit(UpdateServices { results }).await;
         self.scanning_services = false;
         let elapsed = now.elapsed();
+        crate::metrics::record_job("scan-services", elapsed, true);
         tracing::info!(job = "scan-services", elapsed = %humantime::format_duration(elapsed), "Done");
     }
 
@@ -124,6 +125,7 @@ where
         self.epg.emit(UpdateClocks { results }).await;
         self.synchronizing_clocks = false;
         let elapsed = now.elapsed();
+        crate::metrics::record_job("sync-clocks", elapsed, true);
         tracing::info!(job = "sync-clocks", elapsed = %humantime::format_duration(elapsed), "Done");
     }
 
@@ -161,12 +163,19 @@ where
         let now = Instant::now();
         self.updating_schedules = true;
         let eit_feeder = self.eit_feeder.clone();
-        match eit_feeder.call(FeedEitSections).await {
-            Ok(_) => self.epg.emit(SaveSchedules).await,
-            Err(err) => tracing::error!(%err, job = "update-schedules"),
-        }
+        let success = match eit_feeder.call(FeedEitSections).await {
+            Ok(_) => {
+                self.epg.emit(SaveSchedules).await;
+                true
+            }
+            Err(err) => {
+                tracing::error!(%err, job = "update-schedules");
+                false
+            }
+        };
         self.updating_schedules = false;
         let elapsed = now.elapsed();
+        crate::metrics::record_job("update-schedules", elapsed, success);
         tracing::info!(job = "update-schedules", elapsed = %humantime::format_duration(elapsed), "Done");
     }
 
@@ -386,6 +395,33 @@ where
     }
 }
 
+// config reloaded
+
+#[async_trait]
+impl<T, E, F> Handler<ConfigReloaded> for JobManager<T, E, F>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Clone + Send + Sync + 'static,
+    E: Emit<SaveSchedules>,
+    E: Emit<UpdateClocks>,
+    E: Emit<UpdateServices>,
+    F: Clone + Send + Sync + 'static,
+    F: Call<FeedEitSections>,
+{
+    async fn handle(&mut self, msg: ConfigReloaded, ctx: &mut Context<Self>) {
+        tracing::debug!(msg.name = "ConfigReloaded");
+        let channels_changed = self.config.channels != msg.0.channels;
+        self.config = msg.0;
+        // Scan services in order to apply changes of channels to EPG.
+        if channels_changed && !self.config.jobs.scan_services.disabled {
+            tracing::info!(job = "scan-services", "Channels changed");
+            self.invoke_scan_services(ctx).await;
+        }
+    }
+}
+
 // used for debugging purposes
 
 static EPG_FRESH_PERIOD: Lazy<Option<std::time::Duration>> = Lazy::new(|| {
@@ -2,15 +2,19 @@ mod clock_synchronizer;
 mod eit_feeder;
 mod job;
 mod models;
+mod search;
 mod service_scanner;
 
 #[cfg(test)]
 pub(crate) mod stub;
 
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt;
 use std::fs::File;
 use std::io::BufReader;
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use actlet::prelude::*;
@@ -34,6 +38,8 @@ use crate::tuner::*;
 
 use eit_feeder::EitFeeder;
 use job::JobManager;
+use search::ProgramSearchIndex;
+use search::ProgramSearchMatcher;
 
 pub use clock_synchronizer::SyncClock;
 pub use models::AudioComponentDescriptor;
@@ -42,13 +48,27 @@ pub use models::EitDescriptor;
 pub use models::EitEvent;
 pub use models::EitSection;
 pub use models::EventGroupDescriptor;
+pub use models::EventGroupEvent;
 pub use models::SeriesDescriptor;
+pub use search::ProgramSearchGenre;
+pub use search::ProgramSearchQuery;
+pub use search::ProgramSearchResult;
+pub use search::ProgramSearchSort;
 
 pub struct Epg<T> {
+    // `base_config` without channels disabled at runtime.
     config: Arc<Config>,
+    base_config: Arc<Config>,
     tuner_manager: T,
 
+    // Channels and services disabled at runtime.
+    maintenance: EpgMaintenance,
+    // Changes of `config.maintenance` are applied after restart.
+    maintenance_path: Option<PathBuf>,
+
     services: Arc<IndexMap<ServiceId, EpgService>>, // keeps insertion order
+    // Services disabled at runtime.  They're restored when enabled.
+    disabled_services: IndexMap<ServiceId, EpgService>,
     clocks: Arc<HashMap<ServiceId, Clock>>,
     // Allocate EpgSchedule in the heap in order to avoid stack overflow in
     // serialization using serde_json.
@@ -57,19 +77,31 @@ pub struct Epg<T> {
     service_updated: EmitterRegistry<ServicesUpdated>,
     clocks_updated: EmitterRegistry<ClocksUpdated>,
     programs_updated: EmitterRegistry<ProgramsUpdated>,
+    // Internal actors using the config.
+    config_reloaded: EmitterRegistry<ConfigReloaded>,
 }
 
 impl<T> Epg<T> {
     pub fn new(config: Arc<Config>, tuner_manager: T) -> Self {
+        let maintenance_path = config
+            .maintenance
+            .state_dir
+            .as_ref()
+            .map(|dir| dir.join("channels.json"));
         Epg {
-            config,
+            config: config.clone(),
+            base_config: config,
             tuner_manager,
+            maintenance: Default::default(),
+            maintenance_path,
             services: Default::default(),
+            disabled_services: Default::default(),
             clocks: Default::default(),
             schedules: Default::default(),
             service_updated: Default::default(),
             clocks_updated: Default::default(),
             programs_updated: Default::default(),
+            config_reloaded: Default::default(),
         }
     }
 
@@ -87,7 +119,11 @@ impl<T> Epg<T> {
                 None => {
                     // Failed to scan services for some reason.  Reuse old
                     // services if properties of the channel hasn't changed.
-                    for service in self.services.values() {
+                    for service in self
+                        .services
+                        .values()
+                        .chain(self.disabled_services.values())
+                    {
                         if service.channel == channel {
                             services.insert(service.id, service.clone());
                         }
@@ -96,7 +132,16 @@ impl<T> Epg<T> {
             }
         }
 
+        self.set_services(services).await;
+    }
+
+    // Updates services, but services disabled at runtime are hidden.
+    async fn set_services(&mut self, services: IndexMap<ServiceId, EpgService>) {
+        let (services, disabled_services) = services
+            .into_iter()
+            .partition(|(service_id, _)| !self.maintenance.services.contains(service_id));
         self.services = Arc::new(services);
+        self.disabled_services = disabled_services;
 
         let msg = ServicesUpdated {
             services: self.services.clone(),
@@ -148,6 +193,10 @@ impl<T> Epg<T> {
     }
 
     fn prepare_schedule(&mut self, service_id: ServiceId, today: NaiveDate) {
+        // The service may be disabled while collecting EIT sections.
+        if !self.services.contains_key(&service_id) {
+            return;
+        }
         self.schedules
             .entry(service_id)
             .and_modify(|sched| sched.update_start_index(today))
@@ -170,7 +219,11 @@ impl<T> Epg<T> {
             None => 0,
         };
 
-        let service = self.services.get(&service_id).expect("Service must exist");
+        // The service may be disabled while collecting EIT sections.
+        let service = match self.services.get(&service_id) {
+            Some(service) => service,
+            None => return,
+        };
 
         if num_programs > 0 {
             tracing::info!(%service.id, programs.len = num_programs, "Collected programs");
@@ -207,7 +260,10 @@ impl<T> Epg<T> {
                 }
                 not_changed
             });
-            self.services = Arc::new(IndexMap::from_iter(iter));
+            let (services, disabled_services) =
+                iter.partition(|(service_id, _)| !self.maintenance.services.contains(service_id));
+            self.services = Arc::new(services);
+            self.disabled_services = disabled_services;
             tracing::info!(services.len = self.services.len(), "Loaded services");
         }
         Ok(())
@@ -272,7 +328,13 @@ impl<T> Epg<T> {
             //
             // We can implement `Serialize` for `Wrapper(Iterator<Item = (&K, &V)>)`,
             // but we simply create `Vec<(&K, &V)>` in order to reduce maintenance cost.
-            let services = self.services.iter().collect_vec();
+            // Disabled services are also saved so that they're restored
+            // without scanning services when enabled after restart.
+            let services = self
+                .services
+                .iter()
+                .chain(self.disabled_services.iter())
+                .collect_vec();
             if file_util::save_json(&services, cache_dir.join("services.json")) {
                 tracing::info!(services.len = self.services.len(), "Saved services");
             } else {
@@ -311,6 +373,125 @@ impl<T> Epg<T> {
         }
     }
 
+    fn load_maintenance(&mut self) {
+        fn do_load(path: &Path) -> Result<EpgMaintenance, Error> {
+            let file = File::open(path)?;
+            Ok(serde_json::from_reader(BufReader::new(file))?)
+        }
+
+        let path = match self.maintenance_path {
+            Some(ref path) => path,
+            None => return,
+        };
+
+        if !path.exists() {
+            return;
+        }
+
+        match do_load(path) {
+            Ok(maintenance) => {
+                tracing::info!(
+                    ?path,
+                    channels.len = maintenance.channels.len(),
+                    services.len = maintenance.services.len(),
+                    "Loaded disabled channels and services"
+                );
+                self.maintenance = maintenance;
+            }
+            Err(err) => {
+                tracing::warn!(%err, ?path, "Failed to load");
+            }
+        }
+    }
+
+    fn save_maintenance(&self) {
+        let path = match self.maintenance_path {
+            Some(ref path) => path,
+            None => return,
+        };
+
+        if file_util::save_json(&self.maintenance, path) {
+            tracing::info!(
+                channels.len = self.maintenance.channels.len(),
+                services.len = self.maintenance.services.len(),
+                "Saved disabled channels and services"
+            );
+        } else {
+            tracing::error!("Failed to save disabled channels and services");
+        }
+    }
+
+    // Removes channels disabled at runtime from the base config.
+    fn make_config(&self) -> Arc<Config> {
+        if self.maintenance.channels.is_empty() {
+            return self.base_config.clone();
+        }
+        let mut config = (*self.base_config).clone();
+        config
+            .channels
+            .retain(|channel| !self.maintenance.is_channel_disabled(channel));
+        Arc::new(config)
+    }
+
+    // Applies changes of the maintenance state.
+    async fn update_maintenance(&mut self) {
+        self.save_maintenance();
+
+        let config = self.make_config();
+        let channels_changed = config.channels != self.config.channels;
+        self.config = config;
+
+        // Services in disabled channels are removed at this point, but
+        // services in enabled channels are not added until services are
+        // scanned.
+        let channels: Vec<EpgChannel> = self
+            .config
+            .channels
+            .iter()
+            .cloned()
+            .map(EpgChannel::from)
+            .collect();
+        let services = self
+            .services
+            .values()
+            .chain(self.disabled_services.values())
+            .filter(|service| channels.contains(&service.channel))
+            .map(|service| (service.id, service.clone()))
+            .collect();
+        self.set_services(services).await;
+
+        if channels_changed {
+            // Internal actors may be calling this actor.  Emit the message in
+            // order to avoid a deadlock.
+            self.config_reloaded
+                .emit(ConfigReloaded(self.config.clone()))
+                .await;
+        }
+    }
+
+    fn search_programs(&self, query: &ProgramSearchQuery) -> ProgramSearchResult {
+        let matcher = ProgramSearchMatcher::new(query);
+        let mut programs = vec![];
+        // Programs of services disabled at runtime are never included.
+        for (service_id, service) in self.services.iter() {
+            if !matcher.matches_service(service) {
+                continue;
+            }
+            let schedule = match self.schedules.get(service_id) {
+                Some(schedule) => schedule,
+                None => continue,
+            };
+            programs.extend(
+                schedule
+                    .programs
+                    .values()
+                    .filter(|program| matcher.matches(program, &schedule.index))
+                    .cloned(),
+            );
+        }
+        ProgramSearchResult::new(query, programs)
+    }
+
     fn collect_programs(&mut self) {
         for schedule in self.schedules.values_mut() {
             schedule.collect_programs();
@@ -328,6 +509,8 @@ where
     async fn started(&mut self, ctx: &mut Context<Self>) {
         // It's guaranteed that no response is sent before cached EPG data is loaded.
         tracing::debug!("Started");
+        self.load_maintenance();
+        self.config = self.make_config();
         if let Err(err) = self.load_services() {
             tracing::warn!(%err, "Failed to load services");
         }
@@ -347,7 +530,10 @@ where
             ))
             .await;
 
-        let _ = ctx
+        self.config_reloaded
+            .register(Emitter::new(eit_feeder.clone()));
+
+        let job_manager = ctx
             .spawn_actor(JobManager::new(
                 self.config.clone(),
                 self.tuner_manager.clone(),
@@ -355,6 +541,7 @@ where
                 eit_feeder,
             ))
             .await;
+        self.config_reloaded.register(Emitter::new(job_manager));
     }
 
     async fn stopped(&mut self, _ctx: &mut Context<Self>) {
@@ -580,6 +767,31 @@ where
     }
 }
 
+// search programs
+
+#[derive(Message)]
+#[reply(ProgramSearchResult)]
+pub struct SearchPrograms {
+    pub query: ProgramSearchQuery,
+}
+
+#[async_trait]
+impl<T> Handler<SearchPrograms> for Epg<T>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+{
+    async fn handle(
+        &mut self,
+        msg: SearchPrograms,
+        _ctx: &mut Context<Self>,
+    ) -> <SearchPrograms as Message>::Reply {
+        tracing::debug!(msg.name = "SearchPrograms");
+        self.search_programs(&msg.query)
+    }
+}
+
 // update services
 
 #[derive(Message)]
@@ -706,6 +918,142 @@ where
     }
 }
 
+// reload config
+
+#[derive(Message)]
+#[reply()]
+pub struct ReloadConfig(pub Arc<Config>);
+
+#[async_trait]
+impl<T> Handler<ReloadConfig> for Epg<T>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+{
+    async fn handle(
+        &mut self,
+        msg: ReloadConfig,
+        _ctx: &mut Context<Self>,
+    ) -> <ReloadConfig as Message>::Reply {
+        tracing::debug!(msg.name = "ReloadConfig");
+        self.base_config = msg.0;
+        self.config = self.make_config();
+        // Internal actors may be calling this actor.  Emit the message in order
+        // to avoid a deadlock.
+        self.config_reloaded
+            .emit(ConfigReloaded(self.config.clone()))
+            .await;
+    }
+}
+
+// set channel enabled
+
+#[derive(Message)]
+#[reply(Result<(), Error>)]
+pub struct SetChannelEnabled {
+    pub channel_type: ChannelType,
+    pub channel: String,
+    pub enabled: bool,
+}
+
+#[async_trait]
+impl<T> Handler<SetChannelEnabled> for Epg<T>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+{
+    async fn handle(
+        &mut self,
+        msg: SetChannelEnabled,
+        _ctx: &mut Context<Self>,
+    ) -> <SetChannelEnabled as Message>::Reply {
+        tracing::debug!(
+            msg.name = "SetChannelEnabled",
+            %msg.channel_type,
+            msg.channel,
+            msg.enabled,
+        );
+        let found =
+            self.base_config.channels.iter().any(|config| {
+                config.channel_type == msg.channel_type && config.channel == msg.channel
+            });
+        if !found {
+            return Err(Error::ChannelNotFound);
+        }
+        let key = (msg.channel_type, msg.channel);
+        let changed = if msg.enabled {
+            self.maintenance.channels.remove(&key)
+        } else {
+            self.maintenance.channels.insert(key.clone())
+        };
+        if changed {
+            let channel = format!("{}/{}", key.0, key.1);
+            if msg.enabled {
+                tracing::info!(channel, "Enabled");
+            } else {
+                tracing::info!(channel, "Disabled");
+            }
+            self.update_maintenance().await;
+        }
+        Ok(())
+    }
+}
+
+// set service enabled
+
+#[derive(Message)]
+#[reply(Result<(), Error>)]
+pub struct SetServiceEnabled {
+    pub service_id: ServiceId,
+    pub enabled: bool,
+}
+
+#[async_trait]
+impl<T> Handler<SetServiceEnabled> for Epg<T>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+{
+    async fn handle(
+        &mut self,
+        msg: SetServiceEnabled,
+        _ctx: &mut Context<Self>,
+    ) -> <SetServiceEnabled as Message>::Reply {
+        tracing::debug!(
+            msg.name = "SetServiceEnabled",
+            %msg.service_id,
+            msg.enabled,
+        );
+        // A disabled service can be enabled even if it's unknown so that
+        // users can enable it after its channel changed.
+        let changed = if msg.enabled {
+            self.maintenance.services.remove(&msg.service_id)
+        } else if self.services.contains_key(&msg.service_id) {
+            self.maintenance.services.insert(msg.service_id)
+        } else if self.disabled_services.contains_key(&msg.service_id) {
+            false
+        } else {
+            return Err(Error::ServiceNotFound);
+        };
+        if changed {
+            if msg.enabled {
+                tracing::info!(service.id = %msg.service_id, "Enabled");
+            } else {
+                tracing::info!(service.id = %msg.service_id, "Disabled");
+            }
+            self.update_maintenance().await;
+        }
+        Ok(())
+    }
+}
+
+// Sent to internal actors when the config is reloaded.
+#[derive(Clone, Message)]
+pub struct ConfigReloaded(pub Arc<Config>);
+
 // register emitter
 
 #[derive(Message)]
@@ -822,6 +1170,24 @@ pub struct ProgramsUpdated {
     pub service_id: ServiceId,
 }
 
+// Channels and services disabled at runtime, saved in
+// `config.maintenance.state-dir`.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EpgMaintenance {
+    #[serde(default)]
+    channels: HashSet<(ChannelType, String)>,
+    #[serde(default)]
+    services: HashSet<ServiceId>,
+}
+
+impl EpgMaintenance {
+    fn is_channel_disabled(&self, config: &ChannelConfig) -> bool {
+        self.channels
+            .contains(&(config.channel_type, config.channel.clone()))
+    }
+}
+
 // EpgSchedule holds sections of H-EIT[schedule basic] and H-EIT[schedule extended]
 // for a particular service.  See ARIB TR-B14 for details.
 #[derive(Deserialize, Serialize)]
@@ -834,6 +1200,8 @@ struct EpgSchedule {
     start_index: usize, // used for implementing a ring buffer on `units`.
     #[serde(skip)]
     programs: Arc<IndexMap<Eid, EpgProgram>>,
+    #[serde(skip)]
+    index: ProgramSearchIndex,
 }
 
 impl EpgSchedule {
@@ -845,6 +1213,7 @@ impl EpgSchedule {
             units: Default::default(),
             start_index: 0,
             programs: Default::default(),
+            index: Default::default(),
         }
     }
 
@@ -894,6 +1263,7 @@ impl EpgSchedule {
             self.units[i].collect_programs(service_id, &mut programs);
         }
         programs.shrink_to_fit();
+        self.index = ProgramSearchIndex::new(&programs);
         self.programs = Arc::new(programs);
     }
 }
@@ -1317,6 +1687,216 @@ mod tests {
         assert!(epg.schedules.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_update_maintenance() {
+        let dir = tempfile::tempdir().unwrap();
+        let config: Arc<Config> = Arc::new(
+            serde_yaml::from_str(&format!(
+                r#"
+            channels:
+              - name: ch1
+                type: GR
+                channel: '1'
+              - name: ch2
+                type: GR
+                channel: '2'
+            maintenance:
+              state-dir: {}
+            "#,
+                dir.path().display(),
+            ))
+            .unwrap(),
+        );
+        let mut epg = Epg::new(config.clone(), TunerManagerStub);
+
+        let ch1 = channel!("ch1", ChannelType::GR, "1");
+        let ch2 = channel!("ch2", ChannelType::GR, "2");
+
+        let id1_1 = ServiceId::from((1, 1));
+        let id1_2 = ServiceId::from((1, 2));
+        let id2_3 = ServiceId::from((2, 3));
+
+        epg.update_services(vec![
+            (
+                ch1.clone(),
+                Some(indexmap::indexmap! {
+                    id1_1 => service!(id1_1, "sv1", ch1.clone()),
+                    id1_2 => service!(id1_2, "sv2", ch1.clone()),
+                }),
+            ),
+            (
+                ch2.clone(),
+                Some(indexmap::indexmap! {
+                    id2_3 => service!(id2_3, "sv3", ch2.clone()),
+                }),
+            ),
+        ])
+        .await;
+
+        // disable a service
+
+        epg.maintenance.services.insert(id1_2);
+        epg.update_maintenance().await;
+        {
+            let iter = epg.services.values().map(|sv| &sv.name);
+            assert!(iter.eq(["sv1", "sv3"].iter()));
+        }
+        assert!(epg.disabled_services.contains_key(&id1_2));
+        assert_eq!(epg.config.channels.len(), 2);
+
+        // Disabled services are hidden even after scanning services.
+        epg.update_services(vec![
+            (
+                ch1.clone(),
+                Some(indexmap::indexmap! {
+                    id1_1 => service!(id1_1, "sv1", ch1.clone()),
+                    id1_2 => service!(id1_2, "sv2", ch1.clone()),
+                }),
+            ),
+            (ch2.clone(), None),
+        ])
+        .await;
+        {
+            let iter = epg.services.values().map(|sv| &sv.name);
+            assert!(iter.eq(["sv1", "sv3"].iter()));
+        }
+
+        // disable a channel
+
+        epg.maintenance
+            .channels
+            .insert((ChannelType::GR, "2".to_string()));
+        epg.update_maintenance().await;
+        {
+            let iter = epg.services.values().map(|sv| &sv.name);
+            assert!(iter.eq(["sv1"].iter()));
+        }
+        assert_eq!(epg.config.channels.len(), 1);
+        assert_eq!(epg.base_config.channels.len(), 2);
+
+        // The state is restored after restart.
+        let mut epg2 = Epg::new(config.clone(), TunerManagerStub);
+        epg2.load_maintenance();
+        assert!(epg2.maintenance.services.contains(&id1_2));
+        assert_eq!(epg2.make_config().channels.len(), 1);
+
+        // enable the service and the channel
+
+        epg.maintenance.services.clear();
+        epg.maintenance.channels.clear();
+        epg.update_maintenance().await;
+        {
+            // Services in the channel are added after scanning services.
+            let iter = epg.services.values().map(|sv| &sv.name);
+            assert!(iter.eq(["sv1", "sv2"].iter()));
+        }
+        assert!(epg.disabled_services.is_empty());
+        assert_eq!(epg.config.channels.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_search_programs() {
+        let config: Arc<Config> = Arc::new(
+            serde_yaml::from_str(
+                r#"
+            channels:
+              - name: gr
+                type: GR
+                channel: '1'
+              - name: bs
+                type: BS
+                channel: '101'
+            "#,
+            )
+            .unwrap(),
+        );
+        let mut epg = Epg::new(config, TunerManagerStub);
+
+        let gr = channel!("gr", ChannelType::GR, "1");
+        let bs = channel!("bs", ChannelType::BS, "101");
+
+        let id1 = ServiceId::from((1, 1));
+        let id2 = ServiceId::from((2, 2));
+
+        epg.update_services(vec![
+            (
+                gr.clone(),
+                Some(indexmap::indexmap! {
+                    id1 => service!(id1, "sv1", gr.clone()),
+                }),
+            ),
+            (
+                bs.clone(),
+                Some(indexmap::indexmap! {
+                    id2 => service!(id2, "sv2", bs.clone()),
+                }),
+            ),
+        ])
+        .await;
+
+        let mut add_program = |service_id: ServiceId, eid: u16, start_at, name: &str| {
+            let mut program = program!((service_id, Eid::from(eid)), start_at, "1h");
+            program.name = Some(name.to_string());
+            let schedule = epg
+                .schedules
+                .entry(service_id)
+                .or_insert(Box::new(EpgSchedule::new(service_id)));
+            let mut programs = schedule.programs.as_ref().clone();
+            programs.insert(program.eid(), program);
+            schedule.index = ProgramSearchIndex::new(&programs);
+            schedule.programs = Arc::new(programs);
+        };
+        add_program(id1, 1, jst!("2023-07-31T21:00:00+09:00"), "ニュース");
+        add_program(id1, 2, jst!("2023-07-31T20:00:00+09:00"), "ドラマ");
+        add_program(id2, 3, jst!("2023-07-31T19:00:00+09:00"), "ﾆｭｰｽ");
+
+        let search = |query: ProgramSearchQuery| {
+            let result = epg.search_programs(&query);
+            let eids: Vec<_> = result
+                .programs
+                .iter()
+                .map(|program| program.eid().value())
+                .collect();
+            (result.total, eids)
+        };
+
+        assert_eq!(search(Default::default()), (3, vec![3, 2, 1]));
+        assert_eq!(
+            search(ProgramSearchQuery {
+                keyword: Some("ニュース".to_string()),
+                ..Default::default()
+            }),
+            (2, vec![3, 1])
+        );
+        assert_eq!(
+            search(ProgramSearchQuery {
+                channel_types: vec![ChannelType::GR],
+                ..Default::default()
+            }),
+            (2, vec![2, 1])
+        );
+        assert_eq!(
+            search(ProgramSearchQuery {
+                services: vec![id2],
+                ..Default::default()
+            }),
+            (1, vec![3])
+        );
+        assert_eq!(
+            search(ProgramSearchQuery {
+                limit: Some(1),
+                ..Default::default()
+            }),
+            (3, vec![3])
+        );
+
+        // Programs of disabled services are not included.
+        epg.maintenance.services.insert(id2);
+        epg.update_maintenance().await;
+        let result = epg.search_programs(&Default::default());
+        assert_eq!(result.total, 2);
+    }
+
     #[test]
     fn test_epg_schedule_update_start_index() {
         let id = ServiceId::from((1, 2));
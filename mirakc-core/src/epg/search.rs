@@ -0,0 +1,516 @@
+use std::collections::HashMap;
+
+use chrono::DateTime;
+use chrono::Duration;
+use chrono_jst::Jst;
+use indexmap::IndexMap;
+use serde::Deserialize;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::models::ChannelType;
+use crate::models::Eid;
+use crate::models::ServiceId;
+
+use super::EpgProgram;
+use super::EpgService;
+
+/// Conditions and options used for searching TV programs.
+///
+/// Every condition specified must be satisfied.  Conditions omitted are
+/// ignored.
+#[derive(Clone, Debug, Default)]
+pub struct ProgramSearchQuery {
+    /// Keywords separated by whitespaces.
+    ///
+    /// Every keyword must be contained in the name, the description or the
+    /// extended information of a TV program.  Full-width and half-width
+    /// characters are not distinguished.
+    pub keyword: Option<String>,
+    /// Any of the genres must match one of the genres of a TV program.
+    pub genres: Vec<ProgramSearchGenre>,
+    pub services: Vec<ServiceId>,
+    pub channel_types: Vec<ChannelType>,
+    /// TV programs ending after this time.
+    pub since: Option<DateTime<Jst>>,
+    /// TV programs starting before this time.
+    pub until: Option<DateTime<Jst>>,
+    /// Inclusive.
+    pub min_duration: Option<Duration>,
+    /// Inclusive.
+    pub max_duration: Option<Duration>,
+    /// `true` for free TV programs, `false` for scrambled TV programs.
+    pub free: Option<bool>,
+    pub sort: ProgramSearchSort,
+    pub desc: bool,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+/// A genre used in the program search.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[derive(ToSchema)]
+#[schema(title = "ProgramSearchGenre")]
+pub struct ProgramSearchGenre {
+    /// The `lv1` value of a genre.
+    pub lv1: u8,
+    /// The `lv2` value of a genre.
+    ///
+    /// Any `lv2` value matches if omitted.
+    #[serde(default)]
+    pub lv2: Option<u8>,
+}
+
+/// A key to sort TV programs found.
+///
+/// TV programs having the same key are sorted by their start time.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProgramSearchSort {
+    #[default]
+    StartAt,
+    Duration,
+    Name,
+}
+
+/// TV programs found.
+#[derive(Clone, Debug, Default)]
+pub struct ProgramSearchResult {
+    /// The number of TV programs matched before the pagination.
+    pub total: usize,
+    pub programs: Vec<EpgProgram>,
+}
+
+// An index of TV programs of a service.
+//
+// Texts of TV programs are normalized in advance so that each search doesn't
+// need to normalize them.
+#[derive(Default)]
+pub(super) struct ProgramSearchIndex {
+    texts: HashMap<Eid, String>,
+}
+
+impl ProgramSearchIndex {
+    pub(super) fn new(programs: &IndexMap<Eid, EpgProgram>) -> Self {
+        let texts = programs
+            .iter()
+            .map(|(&eid, program)| (eid, make_text(program)))
+            .collect();
+        ProgramSearchIndex { texts }
+    }
+
+    fn text(&self, eid: Eid) -> &str {
+        self.texts.get(&eid).map(String::as_str).unwrap_or("")
+    }
+}
+
+// A compiled form of `ProgramSearchQuery`.
+pub(super) struct ProgramSearchMatcher<'a> {
+    query: &'a ProgramSearchQuery,
+    keywords: Vec<String>,
+}
+
+impl<'a> ProgramSearchMatcher<'a> {
+    pub(super) fn new(query: &'a ProgramSearchQuery) -> Self {
+        let keywords = match query.keyword {
+            // Whitespaces are normalized before splitting.
+            Some(ref keyword) => normalize(keyword)
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            None => vec![],
+        };
+        ProgramSearchMatcher { query, keywords }
+    }
+
+    pub(super) fn matches_service(&self, service: &EpgService) -> bool {
+        let query = self.query;
+        if !query.services.is_empty() && !query.services.contains(&service.id) {
+            return false;
+        }
+        if !query.channel_types.is_empty()
+            && !query.channel_types.contains(&service.channel.channel_type)
+        {
+            return false;
+        }
+        true
+    }
+
+    pub(super) fn matches(&self, program: &EpgProgram, index: &ProgramSearchIndex) -> bool {
+        let query = self.query;
+
+        if let Some(since) = query.since {
+            match program.end_at() {
+                Some(end_at) if end_at > since => (),
+                _ => return false,
+            }
+        }
+
+        if let Some(until) = query.until {
+            match program.start_at {
+                Some(start_at) if start_at < until => (),
+                _ => return false,
+            }
+        }
+
+        if query.min_duration.is_some() || query.max_duration.is_some() {
+            let duration = match program.duration {
+                Some(duration) => duration,
+                None => return false,
+            };
+            if query.min_duration.is_some_and(|min| duration < min) {
+                return false;
+            }
+            if query.max_duration.is_some_and(|max| duration > max) {
+                return false;
+            }
+        }
+
+        if let Some(free) = query.free {
+            if program.scrambled == free {
+                return false;
+            }
+        }
+
+        if !query.genres.is_empty() {
+            let genres = match program.genres {
+                Some(ref genres) => genres,
+                None => return false,
+            };
+            let found = genres.iter().any(|genre| {
+                query.genres.iter().any(|cond| {
+                    cond.lv1 == genre.lv1 && (cond.lv2.is_none() || cond.lv2 == Some(genre.lv2))
+                })
+            });
+            if !found {
+                return false;
+            }
+        }
+
+        if !self.keywords.is_empty() {
+            let text = index.text(program.eid());
+            if !self
+                .keywords
+                .iter()
+                .all(|keyword| text.contains(keyword.as_str()))
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl ProgramSearchResult {
+    pub(super) fn new(query: &ProgramSearchQuery, mut programs: Vec<EpgProgram>) -> Self {
+        // Use the program ID as the last key so that the order is stable
+        // between pages.
+        match query.sort {
+            ProgramSearchSort::StartAt => {
+                programs.sort_by(|a, b| {
+                    a.start_at
+                        .cmp(&b.start_at)
+                        .then(a.id.value().cmp(&b.id.value()))
+                });
+            }
+            ProgramSearchSort::Duration => {
+                programs.sort_by(|a, b| {
+                    a.duration
+                        .cmp(&b.duration)
+                        .then(a.start_at.cmp(&b.start_at))
+                        .then(a.id.value().cmp(&b.id.value()))
+                });
+            }
+            ProgramSearchSort::Name => {
+                programs.sort_by(|a, b| {
+                    a.name
+                        .cmp(&b.name)
+                        .then(a.start_at.cmp(&b.start_at))
+                        .then(a.id.value().cmp(&b.id.value()))
+                });
+            }
+        }
+        if query.desc {
+            programs.reverse();
+        }
+
+        let total = programs.len();
+        let programs = programs
+            .into_iter()
+            .skip(query.offset)
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect();
+        ProgramSearchResult { total, programs }
+    }
+}
+
+fn make_text(program: &EpgProgram) -> String {
+    let mut text = String::new();
+    if let Some(ref name) = program.name {
+        text.push_str(&normalize(name));
+    }
+    if let Some(ref description) = program.description {
+        // Keywords never contain '\n'.  So, no keyword matches across fields.
+        text.push('\n');
+        text.push_str(&normalize(description));
+    }
+    if let Some(ref extended) = program.extended {
+        for (key, value) in extended.iter() {
+            text.push('\n');
+            text.push_str(&normalize(key));
+            text.push('\n');
+            text.push_str(&normalize(value));
+        }
+    }
+    text
+}
+
+// Half-width katakana and symbols in U+FF61..=U+FF9F.
+const HALFWIDTH_KATAKANA: [char; 63] = [
+    '。', '「', '」', '、', '・', 'ヲ', 'ァ', 'ィ', 'ゥ', 'ェ', 'ォ', 'ャ', 'ュ', 'ョ', 'ッ', 'ー',
+    'ア', 'イ', 'ウ', 'エ', 'オ', 'カ', 'キ', 'ク', 'ケ', 'コ', 'サ', 'シ', 'ス', 'セ', 'ソ', 'タ',
+    'チ', 'ツ', 'テ', 'ト', 'ナ', 'ニ', 'ヌ', 'ネ', 'ノ', 'ハ', 'ヒ', 'フ', 'ヘ', 'ホ', 'マ', 'ミ',
+    'ム', 'メ', 'モ', 'ヤ', 'ユ', 'ヨ', 'ラ', 'リ', 'ル', 'レ', 'ロ', 'ワ', 'ン', '゛', '゜',
+];
+
+// Normalizes a text for the program search.
+//
+// * Full-width ASCII characters are converted into half-width ones
+// * Half-width katakana are converted into full-width ones
+// * The ideographic space is converted into the ASCII space
+// * ASCII characters are converted into lowercase
+pub(crate) fn normalize(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let c = match c {
+            '\u{3000}' => ' ',
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap(),
+            '\u{FF61}'..='\u{FF9F}' => {
+                let c = HALFWIDTH_KATAKANA[(c as u32 - 0xFF61) as usize];
+                match chars.peek() {
+                    Some('\u{FF9E}') => match compose_voiced(c) {
+                        Some(c) => {
+                            chars.next();
+                            c
+                        }
+                        None => c,
+                    },
+                    Some('\u{FF9F}') => match compose_semi_voiced(c) {
+                        Some(c) => {
+                            chars.next();
+                            c
+                        }
+                        None => c,
+                    },
+                    _ => c,
+                }
+            }
+            _ => c,
+        };
+        normalized.push(c.to_ascii_lowercase());
+    }
+    normalized
+}
+
+// `c` must be a character in `HALFWIDTH_KATAKANA`.  Characters in the ranges
+// below are followed by their voiced forms in the Unicode table.  'ッ' is the
+// only exception.
+fn compose_voiced(c: char) -> Option<char> {
+    match c {
+        'ウ' => Some('ヴ'),
+        'ッ' => None,
+        'カ'..='ト' | 'ハ'..='ホ' => char::from_u32(c as u32 + 1),
+        _ => None,
+    }
+}
+
+// `c` must be a character in `HALFWIDTH_KATAKANA`.
+fn compose_semi_voiced(c: char) -> Option<char> {
+    match c {
+        'ハ'..='ホ' => char::from_u32(c as u32 + 2),
+        _ => None,
+    }
+}
+
+// <coverage:exclude>
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EpgGenre;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("ＮＨＫニュース７"), "nhkニュース7");
+        assert_eq!(normalize("ﾆｭｰｽ"), "ニュース");
+        assert_eq!(normalize("ｶﾞﾝﾀﾞﾑ"), "ガンダム");
+        assert_eq!(normalize("ﾎﾟｹｯﾄ ﾓﾝｽﾀｰ"), "ポケット モンスター");
+        assert_eq!(normalize("ｳﾞ"), "ヴ");
+        assert_eq!(normalize("ｱﾞ"), "ア゛");
+        assert_eq!(normalize("Ａ\u{3000}Ｂ"), "a b");
+        assert_eq!(normalize("（再）"), "(再)");
+    }
+
+    #[test]
+    fn test_matcher_keyword() {
+        let mut program = program!((0, 1, 1), jst!("2023-07-31T21:00:00+09:00"), "1h");
+        program.name = Some("ＮＨＫニュース７".to_string());
+        program.description = Some("今日のﾆｭｰｽ".to_string());
+        program.extended = Some(indexmap::indexmap! {
+            "出演者".to_string() => "山田太郎".to_string(),
+        });
+        let programs = indexmap::indexmap! { program.eid() => program.clone() };
+        let index = ProgramSearchIndex::new(&programs);
+
+        let matches = |keyword: &str| {
+            let query = ProgramSearchQuery {
+                keyword: Some(keyword.to_string()),
+                ..Default::default()
+            };
+            ProgramSearchMatcher::new(&query).matches(&program, &index)
+        };
+
+        assert!(matches(""));
+        assert!(matches("nhk"));
+        assert!(matches("NHK ニュース"));
+        assert!(matches("ニュース７"));
+        assert!(matches("今日のニュース"));
+        assert!(matches("山田"));
+        assert!(matches("出演者"));
+        assert!(!matches("ニュース9"));
+        // Keywords never match across fields.
+        assert!(!matches("ニュース7今日"));
+    }
+
+    #[test]
+    fn test_matcher_conditions() {
+        let mut program = program!((0, 1, 1), jst!("2023-07-31T21:00:00+09:00"), "1h");
+        program.genres = Some(vec![EpgGenre::new((1, 2, 0, 0))]);
+        let index = ProgramSearchIndex::default();
+
+        let matches =
+            |query: ProgramSearchQuery| ProgramSearchMatcher::new(&query).matches(&program, &index);
+
+        assert!(matches(Default::default()));
+
+        assert!(matches(ProgramSearchQuery {
+            since: Some(jst!("2023-07-31T21:59:59+09:00")),
+            until: Some(jst!("2023-07-31T21:00:01+09:00")),
+            ..Default::default()
+        }));
+        assert!(!matches(ProgramSearchQuery {
+            since: Some(jst!("2023-07-31T22:00:00+09:00")),
+            ..Default::default()
+        }));
+        assert!(!matches(ProgramSearchQuery {
+            until: Some(jst!("2023-07-31T21:00:00+09:00")),
+            ..Default::default()
+        }));
+
+        assert!(matches(ProgramSearchQuery {
+            min_duration: Some(Duration::hours(1)),
+            max_duration: Some(Duration::hours(1)),
+            ..Default::default()
+        }));
+        assert!(!matches(ProgramSearchQuery {
+            min_duration: Some(Duration::minutes(61)),
+            ..Default::default()
+        }));
+        assert!(!matches(ProgramSearchQuery {
+            max_duration: Some(Duration::minutes(59)),
+            ..Default::default()
+        }));
+
+        assert!(matches(ProgramSearchQuery {
+            free: Some(true),
+            ..Default::default()
+        }));
+        assert!(!matches(ProgramSearchQuery {
+            free: Some(false),
+            ..Default::default()
+        }));
+
+        assert!(matches(ProgramSearchQuery {
+            genres: vec![ProgramSearchGenre { lv1: 1, lv2: None }],
+            ..Default::default()
+        }));
+        assert!(matches(ProgramSearchQuery {
+            genres: vec![
+                ProgramSearchGenre { lv1: 0, lv2: None },
+                ProgramSearchGenre {
+                    lv1: 1,
+                    lv2: Some(2)
+                },
+            ],
+            ..Default::default()
+        }));
+        assert!(!matches(ProgramSearchQuery {
+            genres: vec![ProgramSearchGenre {
+                lv1: 1,
+                lv2: Some(3)
+            }],
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn test_result() {
+        let mut p1 = program!((0, 1, 1), jst!("2023-07-31T21:00:00+09:00"), "1h");
+        p1.name = Some("b".to_string());
+        let mut p2 = program!((0, 1, 2), jst!("2023-07-31T20:00:00+09:00"), "30m");
+        p2.name = Some("c".to_string());
+        let mut p3 = program!((0, 1, 3), jst!("2023-07-31T22:00:00+09:00"), "30m");
+        p3.name = Some("a".to_string());
+        let programs = vec![p1, p2, p3];
+
+        let eids = |query: ProgramSearchQuery| {
+            let result = ProgramSearchResult::new(&query, programs.clone());
+            assert_eq!(result.total, 3);
+            result
+                .programs
+                .iter()
+                .map(|program| program.eid().value())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(eids(Default::default()), [2, 1, 3]);
+        assert_eq!(
+            eids(ProgramSearchQuery {
+                desc: true,
+                ..Default::default()
+            }),
+            [3, 1, 2]
+        );
+        assert_eq!(
+            eids(ProgramSearchQuery {
+                sort: ProgramSearchSort::Duration,
+                ..Default::default()
+            }),
+            [2, 3, 1]
+        );
+        assert_eq!(
+            eids(ProgramSearchQuery {
+                sort: ProgramSearchSort::Name,
+                ..Default::default()
+            }),
+            [3, 1, 2]
+        );
+        assert_eq!(
+            eids(ProgramSearchQuery {
+                offset: 1,
+                limit: Some(1),
+                ..Default::default()
+            }),
+            [1]
+        );
+        assert_eq!(
+            eids(ProgramSearchQuery {
+                offset: 3,
+                ..Default::default()
+            }),
+            [] as [u16; 0]
+        );
+    }
+}
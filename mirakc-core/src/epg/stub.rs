@@ -111,6 +111,19 @@ impl Call<QueryPrograms> for EpgStub {
     }
 }
 
+#[async_trait]
+impl Call<SearchPrograms> for EpgStub {
+    async fn call(
+        &self,
+        _msg: SearchPrograms,
+    ) -> actlet::Result<<SearchPrograms as Message>::Reply> {
+        Ok(ProgramSearchResult {
+            total: 1,
+            programs: vec![program!((0, 1, 1), Jst::now(), "1h")],
+        })
+    }
+}
+
 #[async_trait]
 impl Call<QueryProgram> for EpgStub {
     async fn call(&self, msg: QueryProgram) -> actlet::Result<<QueryProgram as Message>::Reply> {
@@ -121,6 +134,40 @@ impl Call<QueryProgram> for EpgStub {
     }
 }
 
+#[async_trait]
+impl Call<SetChannelEnabled> for EpgStub {
+    async fn call(
+        &self,
+        msg: SetChannelEnabled,
+    ) -> actlet::Result<<SetChannelEnabled as Message>::Reply> {
+        if msg.channel == "0" {
+            Ok(Err(Error::ChannelNotFound))
+        } else {
+            Ok(Ok(()))
+        }
+    }
+}
+
+#[async_trait]
+impl Call<SetServiceEnabled> for EpgStub {
+    async fn call(
+        &self,
+        msg: SetServiceEnabled,
+    ) -> actlet::Result<<SetServiceEnabled as Message>::Reply> {
+        match msg.service_id.sid().value() {
+            0 => Ok(Err(Error::ServiceNotFound)),
+            _ => Ok(Ok(())),
+        }
+    }
+}
+
+#[async_trait]
+impl Call<ReloadConfig> for EpgStub {
+    async fn call(&self, _msg: ReloadConfig) -> actlet::Result<<ReloadConfig as Message>::Reply> {
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl Call<RegisterEmitter> for EpgStub {
     async fn call(
@@ -24,12 +24,22 @@ pub enum Error {
     ScheduleNotFound,
     #[error("Recorder not found")]
     RecorderNotFound,
+    #[error("Rule not found")]
+    RuleNotFound,
+    #[error("Series not found")]
+    SeriesNotFound,
+    #[error("Job not found")]
+    JobNotFound,
+    #[error("Reservation not found")]
+    ReservationNotFound,
     #[error("Out of range")]
     OutOfRange,
     #[error("No content")]
     NoContent,
     #[error("No logo data")]
     NoLogoData,
+    #[error("No series")]
+    NoSeries,
     #[error("Access denied")]
     AccessDenied,
     #[error("Already exists")]
@@ -38,8 +48,20 @@ pub enum Error {
     ProgramEnded,
     #[error("Invalid path")]
     InvalidPath,
+    #[error("Invalid rule")]
+    InvalidRule,
+    #[error("Invalid margin")]
+    InvalidMargin,
+    #[error("Invalid time range")]
+    InvalidTimeRange,
+    #[error("Invalid reservation")]
+    InvalidReservation,
+    #[error("Invalid schedule state")]
+    InvalidScheduleState,
     #[error("Timeshift config inconsistent")]
     TimeshiftConfigInconsistent,
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
     #[error("Command failed: {0}")]
     CommandFailed(command_util::Error),
     #[error("std::fmt error: {0}")]
@@ -64,6 +86,8 @@ pub enum Error {
     HyperError(hyper::Error),
     #[error("axum::http error: {0:?}")]
     AxumHttpError(axum::http::Error),
+    #[error("reqwest error: {0}")]
+    ReqwestError(reqwest::Error),
     #[error("actlet error: {0}")]
     ActletError(actlet::Error),
     #[error(transparent)]
@@ -148,6 +172,12 @@ impl From<axum::http::Error> for Error {
     }
 }
 
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Self::ReqwestError(err)
+    }
+}
+
 impl From<actlet::Error> for Error {
     fn from(err: actlet::Error) -> Self {
         Self::ActletError(err)
@@ -6,10 +6,13 @@ use chrono_jst::Jst;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::models::MirakurunTunerUser;
 use crate::models::ProgramId;
 use crate::models::ServiceId;
 use crate::models::TimeshiftRecordId;
 use crate::recording::RecordingFailedReason;
+use crate::recording::RecordingJobState;
+use crate::ts_stats::TsStats;
 
 #[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -17,6 +20,14 @@ pub struct TunerStatusChanged {
     pub tuner_index: usize,
 }
 
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TunerPreempted {
+    pub tuner_index: usize,
+    pub user: MirakurunTunerUser,
+    pub preemptor: MirakurunTunerUser,
+}
+
 #[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EpgProgramsUpdated {
@@ -40,6 +51,8 @@ pub struct RecordingStopped {
 pub struct RecordingFailed {
     pub program_id: ProgramId,
     pub reason: RecordingFailedReason,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ts_stats: Option<TsStats>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -48,6 +61,14 @@ pub struct RecordingRescheduled {
     pub program_id: ProgramId,
 }
 
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingJobUpdated {
+    pub job_id: u32,
+    pub record_id: u32,
+    pub state: RecordingJobState,
+}
+
 #[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TimeshiftTimeline {
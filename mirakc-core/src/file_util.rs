@@ -82,6 +82,23 @@ where
     true
 }
 
+// Returns the size of free space in bytes available for unprivileged users in
+// the filesystem containing `path`.
+pub fn get_free_space<P>(path: P) -> std::io::Result<u64>
+where
+    P: AsRef<Path>,
+{
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_ref().as_os_str().as_bytes())?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::statvfs(path.as_ptr(), &mut stat) };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
 fn append_extension<P, S>(path: P, ext: S) -> PathBuf
 where
     P: AsRef<Path>,
@@ -131,6 +148,13 @@ mod tests {
         assert!(!new_path.exists());
     }
 
+    #[test]
+    fn test_get_free_space() {
+        let temp_dir = TempDir::new().unwrap();
+        assert_matches!(get_free_space(temp_dir.path()), Ok(_));
+        assert_matches!(get_free_space("/no/such/dir"), Err(_));
+    }
+
     #[test]
     #[cfg(target_os = "linux")]
     fn test_save_data_file_create_fails() {
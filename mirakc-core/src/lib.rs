@@ -12,11 +12,15 @@ pub mod models;
 pub mod mpeg_ts_stream;
 pub mod onair;
 pub mod recording;
+pub mod reloader;
 pub mod string_table;
 pub mod timeshift;
 pub mod tracing_ext;
+pub mod ts_stats;
 pub mod tuner;
+pub mod tuner_reservation;
 pub mod web;
 
 mod events;
 mod file_util;
+mod metrics;
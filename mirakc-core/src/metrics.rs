@@ -0,0 +1,407 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fmt::Write;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use once_cell::sync::Lazy;
+
+// Upper bounds of buckets of the histogram for HTTP request latencies, in
+// seconds.
+const HTTP_LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+// HTTP methods used as labels.  Other methods are counted as `OTHER`.
+const HTTP_METHODS: [&str; 8] = [
+    "GET", "HEAD", "POST", "PUT", "DELETE", "PATCH", "OPTIONS", "OTHER",
+];
+
+// The range of HTTP status codes counted.
+const HTTP_STATUS_MIN: u16 = 100;
+const HTTP_STATUS_MAX: u16 = 599;
+
+// Metrics collected while the process is running.
+//
+// Metrics which can be computed from the state of actors (e.g. the state of
+// tuners) are not stored here.  They're computed when the metrics are
+// requested.
+//
+// Metrics updated on hot paths such as broadcasting chunks and processing HTTP
+// requests are atomic counters so that they can be updated without locks.
+
+// Counters of broadcasters, indexed by the tuner index.  The map is locked only
+// when a broadcaster is created.
+static BROADCASTERS: Lazy<Mutex<BTreeMap<usize, Arc<BroadcasterCounters>>>> =
+    Lazy::new(Default::default);
+
+static JOBS: Lazy<Mutex<BTreeMap<&'static str, JobMetrics>>> = Lazy::new(Default::default);
+
+// Indexed in the same order as `HTTP_METHODS`.
+static HTTP_REQUESTS: Lazy<Vec<HttpMetrics>> =
+    Lazy::new(|| HTTP_METHODS.iter().map(|_| HttpMetrics::new()).collect());
+
+// Counters shared by broadcasters for the same tuner.
+#[derive(Default)]
+pub(crate) struct BroadcasterCounters {
+    subscribers: AtomicUsize,
+    bytes: AtomicU64,
+    dropped_chunks: AtomicU64,
+    dropped_bytes: AtomicU64,
+}
+
+impl BroadcasterCounters {
+    pub(crate) fn add_subscribers(&self, n: usize) {
+        self.subscribers.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub(crate) fn remove_subscribers(&self, n: usize) {
+        self.subscribers.fetch_sub(n, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_bytes(&self, bytes: usize) {
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_dropped_chunk(&self, bytes: usize) {
+        self.dropped_chunks.fetch_add(1, Ordering::Relaxed);
+        self.dropped_bytes
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+}
+
+#[derive(Default)]
+struct JobMetrics {
+    last_duration: Duration,
+    last_success_time: Option<SystemTime>,
+    successes: u64,
+    failures: u64,
+}
+
+struct HttpMetrics {
+    // Indexed by `status - HTTP_STATUS_MIN`.
+    requests: Vec<AtomicU64>,
+    latencies: Histogram,
+}
+
+impl HttpMetrics {
+    fn new() -> Self {
+        HttpMetrics {
+            requests: (HTTP_STATUS_MIN..=HTTP_STATUS_MAX)
+                .map(|_| AtomicU64::new(0))
+                .collect(),
+            latencies: Default::default(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Histogram {
+    buckets: [AtomicU64; HTTP_LATENCY_BUCKETS.len()],
+    // In microseconds so that it can be updated atomically.
+    sum: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, value: Duration) {
+        let secs = value.as_secs_f64();
+        for (i, le) in HTTP_LATENCY_BUCKETS.iter().enumerate() {
+            if secs <= *le {
+                self.buckets[i].fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.sum
+            .fetch_add(value.as_micros() as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn sum(&self) -> f64 {
+        self.sum.load(Ordering::Relaxed) as f64 / 1_000_000.0
+    }
+}
+
+// Returns counters for broadcasters for the tuner.
+pub(crate) fn broadcaster_counters(tuner_index: usize) -> Arc<BroadcasterCounters> {
+    BROADCASTERS
+        .lock()
+        .unwrap()
+        .entry(tuner_index)
+        .or_default()
+        .clone()
+}
+
+pub(crate) fn record_job(job: &'static str, duration: Duration, success: bool) {
+    let mut jobs = JOBS.lock().unwrap();
+    let job = jobs.entry(job).or_default();
+    job.last_duration = duration;
+    if success {
+        job.last_success_time = Some(SystemTime::now());
+        job.successes += 1;
+    } else {
+        job.failures += 1;
+    }
+}
+
+pub(crate) fn record_http_request(method: &str, status: u16, latency: Duration) {
+    let index = HTTP_METHODS
+        .iter()
+        .position(|&name| name == method)
+        .unwrap_or(HTTP_METHODS.len() - 1);
+    let metrics = &HTTP_REQUESTS[index];
+    let counter = status
+        .checked_sub(HTTP_STATUS_MIN)
+        .and_then(|i| metrics.requests.get(i as usize));
+    if let Some(counter) = counter {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+    metrics.latencies.observe(latency);
+}
+
+// Renders the collected metrics in the Prometheus text format.
+pub(crate) fn render(w: &mut MetricsWriter) {
+    let broadcasters: Vec<(usize, Arc<BroadcasterCounters>)> = BROADCASTERS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(&index, counters)| (index, counters.clone()))
+        .collect();
+    w.family(
+        "mirakc_broadcaster_subscribers",
+        "gauge",
+        "The number of active subscribers of the broadcaster for the tuner.",
+    );
+    for (index, counters) in broadcasters.iter() {
+        w.sample(
+            "mirakc_broadcaster_subscribers",
+            &[("tuner", index)],
+            counters.subscribers.load(Ordering::Relaxed),
+        );
+    }
+    w.family(
+        "mirakc_broadcaster_bytes_total",
+        "counter",
+        "The total number of bytes broadcast from the tuner.",
+    );
+    for (index, counters) in broadcasters.iter() {
+        w.sample(
+            "mirakc_broadcaster_bytes_total",
+            &[("tuner", index)],
+            counters.bytes.load(Ordering::Relaxed),
+        );
+    }
+    w.family(
+        "mirakc_broadcaster_dropped_chunks_total",
+        "counter",
+        "The total number of chunks dropped due to slow subscribers.",
+    );
+    for (index, counters) in broadcasters.iter() {
+        w.sample(
+            "mirakc_broadcaster_dropped_chunks_total",
+            &[("tuner", index)],
+            counters.dropped_chunks.load(Ordering::Relaxed),
+        );
+    }
+    w.family(
+        "mirakc_broadcaster_dropped_bytes_total",
+        "counter",
+        "The total number of bytes dropped due to slow subscribers.",
+    );
+    for (index, counters) in broadcasters.iter() {
+        w.sample(
+            "mirakc_broadcaster_dropped_bytes_total",
+            &[("tuner", index)],
+            counters.dropped_bytes.load(Ordering::Relaxed),
+        );
+    }
+
+    let jobs = JOBS.lock().unwrap();
+    w.family(
+        "mirakc_job_last_duration_seconds",
+        "gauge",
+        "The duration of the last run of the job.",
+    );
+    for (job, metrics) in jobs.iter() {
+        w.sample(
+            "mirakc_job_last_duration_seconds",
+            &[("job", job)],
+            metrics.last_duration.as_secs_f64(),
+        );
+    }
+    w.family(
+        "mirakc_job_last_success_timestamp_seconds",
+        "gauge",
+        "The UNIX time when the job succeeded last time.",
+    );
+    for (job, metrics) in jobs.iter() {
+        if let Some(time) = metrics.last_success_time {
+            let timestamp = time
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64();
+            w.sample(
+                "mirakc_job_last_success_timestamp_seconds",
+                &[("job", job)],
+                timestamp,
+            );
+        }
+    }
+    w.family(
+        "mirakc_job_runs_total",
+        "counter",
+        "The total number of runs of the job.",
+    );
+    for (job, metrics) in jobs.iter() {
+        w.sample(
+            "mirakc_job_runs_total",
+            &[("job", job), ("result", &"success")],
+            metrics.successes,
+        );
+        w.sample(
+            "mirakc_job_runs_total",
+            &[("job", job), ("result", &"failure")],
+            metrics.failures,
+        );
+    }
+    drop(jobs);
+
+    w.family(
+        "mirakc_http_requests_total",
+        "counter",
+        "The total number of HTTP requests processed.",
+    );
+    for (method, metrics) in HTTP_METHODS.iter().zip(HTTP_REQUESTS.iter()) {
+        for (status, counter) in (HTTP_STATUS_MIN..).zip(metrics.requests.iter()) {
+            let n = counter.load(Ordering::Relaxed);
+            if n > 0 {
+                w.sample(
+                    "mirakc_http_requests_total",
+                    &[("method", method), ("status", &status)],
+                    n,
+                );
+            }
+        }
+    }
+    w.family(
+        "mirakc_http_request_duration_seconds",
+        "histogram",
+        "The time until the response headers are sent.",
+    );
+    for (method, metrics) in HTTP_METHODS.iter().zip(HTTP_REQUESTS.iter()) {
+        let histogram = &metrics.latencies;
+        let count = histogram.count.load(Ordering::Relaxed);
+        if count == 0 {
+            continue;
+        }
+        for (le, n) in HTTP_LATENCY_BUCKETS.iter().zip(histogram.buckets.iter()) {
+            w.sample(
+                "mirakc_http_request_duration_seconds_bucket",
+                &[("method", method), ("le", le)],
+                n.load(Ordering::Relaxed),
+            );
+        }
+        w.sample(
+            "mirakc_http_request_duration_seconds_bucket",
+            &[("method", method), ("le", &"+Inf")],
+            count,
+        );
+        w.sample(
+            "mirakc_http_request_duration_seconds_sum",
+            &[("method", method)],
+            histogram.sum(),
+        );
+        w.sample(
+            "mirakc_http_request_duration_seconds_count",
+            &[("method", method)],
+            count,
+        );
+    }
+}
+
+/// A writer to build a text in the Prometheus text exposition format.
+#[derive(Default)]
+pub(crate) struct MetricsWriter(String);
+
+impl MetricsWriter {
+    pub(crate) fn family(&mut self, name: &str, kind: &str, help: &str) {
+        writeln!(self.0, "# HELP {name} {help}").unwrap();
+        writeln!(self.0, "# TYPE {name} {kind}").unwrap();
+    }
+
+    pub(crate) fn sample<V: Display>(
+        &mut self,
+        name: &str,
+        labels: &[(&str, &dyn Display)],
+        value: V,
+    ) {
+        self.0.push_str(name);
+        if !labels.is_empty() {
+            self.0.push('{');
+            for (i, (label, label_value)) in labels.iter().enumerate() {
+                if i > 0 {
+                    self.0.push(',');
+                }
+                write!(self.0, "{label}=\"").unwrap();
+                for c in label_value.to_string().chars() {
+                    match c {
+                        '\\' => self.0.push_str("\\\\"),
+                        '"' => self.0.push_str("\\\""),
+                        '\n' => self.0.push_str("\\n"),
+                        _ => self.0.push(c),
+                    }
+                }
+                self.0.push('"');
+            }
+            self.0.push('}');
+        }
+        writeln!(self.0, " {value}").unwrap();
+    }
+
+    pub(crate) fn into_string(self) -> String {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_writer() {
+        let mut w = MetricsWriter::default();
+        w.family("test", "gauge", "Test.");
+        w.sample("test", &[], 1);
+        w.sample("test", &[("a", &"x\"y\\z\n"), ("b", &2)], 0.5);
+        assert_eq!(
+            w.into_string(),
+            "# HELP test Test.\n\
+             # TYPE test gauge\n\
+             test 1\n\
+             test{a=\"x\\\"y\\\\z\\n\",b=\"2\"} 0.5\n"
+        );
+    }
+
+    #[test]
+    fn test_histogram() {
+        let histogram = Histogram::default();
+        histogram.observe(Duration::from_millis(1));
+        histogram.observe(Duration::from_millis(200));
+        histogram.observe(Duration::from_secs(100));
+        let buckets: Vec<u64> = histogram
+            .buckets
+            .iter()
+            .map(|n| n.load(Ordering::Relaxed))
+            .collect();
+        assert_eq!(buckets[0], 1);
+        assert_eq!(buckets[4], 1);
+        assert_eq!(buckets[5], 2);
+        assert_eq!(buckets[10], 2);
+        assert_eq!(histogram.count.load(Ordering::Relaxed), 3);
+        assert_eq!(histogram.sum(), 100.201);
+    }
+}
@@ -18,6 +18,7 @@ use crate::epg::EpgChannel;
 use crate::epg::EpgProgram;
 use crate::epg::EpgService;
 use crate::epg::SeriesDescriptor;
+use crate::ts_stats::TsStats;
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Deserialize, Serialize, ToSchema)]
 pub enum ChannelType {
@@ -278,6 +279,7 @@ pub enum TunerUserInfo {
     Job { name: String },
     OnairProgramTracker(String),
     Recorder { name: String },
+    Reservation { owner: String },
     Web { id: String, agent: Option<String> },
 }
 
@@ -302,6 +304,7 @@ impl TunerUserInfo {
             Self::Job { name } => (format!("job:{}", name), None),
             Self::OnairProgramTracker(name) => (format!("onair-program-tracker:{name}"), None),
             Self::Recorder { name } => (format!("recorder:{}", name), None),
+            Self::Reservation { owner } => (format!("reservation:{owner}"), None),
             Self::Web { id, agent } => (id.clone(), agent.clone()),
         }
     }
@@ -313,6 +316,7 @@ impl fmt::Display for TunerUserInfo {
             Self::Job { name } => write!(f, "Job({})", name),
             Self::OnairProgramTracker(name) => write!(f, "OnairProgramTracker({name})"),
             Self::Recorder { name } => write!(f, "Recorder({})", name),
+            Self::Reservation { owner } => write!(f, "Reservation({owner})"),
             Self::Web { id, agent: None } => write!(f, r#"Web(id="{}")"#, id),
             Self::Web {
                 id,
@@ -347,6 +351,47 @@ impl fmt::Display for TunerUserPriority {
     }
 }
 
+/// A policy to select a tuner from available tuners.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum TunerSelectionPolicy {
+    /// Select the first available tuner in the order of `config.tuners`.
+    #[default]
+    First,
+    /// Select available tuners in turn.
+    RoundRobin,
+    /// Select the least recently used tuner.
+    LeastRecentlyUsed,
+}
+
+/// A role of a tuner.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum TunerRole {
+    /// Used for any purposes.
+    #[default]
+    Any,
+    /// Never used for streaming to Web clients.
+    Recording,
+    /// Never used for recording.
+    Streaming,
+}
+
+impl TunerRole {
+    pub fn accepts(&self, user: &TunerUserInfo) -> bool {
+        !matches!(
+            (self, user),
+            (Self::Recording, TunerUserInfo::Web { .. })
+                | (Self::Streaming, TunerUserInfo::Recorder { .. })
+        )
+    }
+
+    // Same as `accepts()` with `TunerUserInfo::Recorder`.
+    pub fn accepts_recorder(&self) -> bool {
+        !matches!(self, Self::Streaming)
+    }
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct TunerUser {
     pub info: TunerUserInfo,
@@ -434,10 +479,11 @@ pub struct MirakurunTuner {
     #[schema(inline)]
     pub users: Vec<MirakurunTunerUser>,
 
-    /// Always `true`.
+    /// `false` if the upstream server refused the last request, `true`
+    /// otherwise.
     pub is_available: bool,
 
-    /// Always `false`.
+    /// `true` if the tuner is backed by an upstream server, `false` otherwise.
     pub is_remote: bool,
 
     /// `true` if the tuner is free, `false` otherwise.
@@ -446,11 +492,31 @@ pub struct MirakurunTuner {
     /// `false` if the tuner is free, `true` otherwise.
     pub is_using: bool,
 
-    /// Always `false`.
+    /// `true` if the tuner is quarantined due to consecutive failures, `false`
+    /// otherwise.
     pub is_fault: bool,
+
+    /// `true` if the tuner is in maintenance, `false` otherwise.
+    ///
+    /// Users using the tuner keep using it, but no new user can use it.
+    pub is_maintenance: bool,
+
+    /// The weight of the tuner used in the tuner selection.
+    pub weight: u32,
+
+    /// The role of the tuner.
+    pub role: TunerRole,
+
+    /// The policy used for selecting a tuner.
+    pub selection_policy: TunerSelectionPolicy,
+
+    /// Statistics of TS packets in the current session.
+    ///
+    /// `null` if the tuner is not in use.
+    pub ts_stats: Option<TsStats>,
 }
 
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct MirakurunTunerUser {
     /// User ID.
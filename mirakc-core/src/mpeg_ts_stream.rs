@@ -8,7 +8,9 @@ use tokio::io::AsyncWriteExt;
 use tokio_stream::Stream;
 use tokio_stream::StreamExt;
 
+use crate::broadcaster::BroadcasterStream;
 use crate::error::Error;
+use crate::ts_stats::TsStatsHandle;
 
 #[cfg_attr(test, derive(Debug))]
 pub struct MpegTsStream<T, S> {
@@ -60,6 +62,12 @@ where
     }
 }
 
+impl<T> MpegTsStream<T, BroadcasterStream> {
+    pub fn ts_stats(&self) -> TsStatsHandle {
+        self.stream.ts_stats()
+    }
+}
+
 impl<T, S> MpegTsStream<T, S>
 where
     T: fmt::Display + Clone + Unpin,
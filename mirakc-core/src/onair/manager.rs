@@ -491,6 +491,8 @@ mod tests {}
 #[cfg(test)]
 pub(crate) mod stub {
     use super::*;
+    use chrono::Duration;
+    use chrono_jst::Jst;
 
     #[derive(Clone)]
     pub(crate) struct OnairProgramManagerStub;
@@ -514,10 +516,21 @@ pub(crate) mod stub {
         ) -> actlet::Result<<QueryOnairProgram as Message>::Reply> {
             match msg.service_id.sid().value() {
                 0 => Ok(Err(Error::ServiceNotFound)),
-                _ => Ok(Ok(OnairProgram {
+                1 => Ok(Ok(OnairProgram {
                     current: None,
                     next: None,
                 })),
+                _ => {
+                    let now = Jst::now();
+                    Ok(Ok(OnairProgram {
+                        current: Some(Arc::new(program!((msg.service_id, 3.into()), now, "1h"))),
+                        next: Some(Arc::new(program!(
+                            (msg.service_id, 4.into()),
+                            now + Duration::hours(1),
+                            "1h"
+                        ))),
+                    }))
+                }
             }
         }
     }
@@ -0,0 +1,859 @@
+use std::collections::HashSet;
+
+use chrono::DateTime;
+use chrono_jst::serde::ts_milliseconds;
+use chrono_jst::Jst;
+use indexmap::IndexMap;
+use itertools::Itertools;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::config::Config;
+use crate::config::TunerSelectionConfig;
+use crate::epg::EpgChannel;
+use crate::epg::EpgService;
+use crate::models::ChannelType;
+use crate::models::ProgramId;
+use crate::models::ServiceId;
+use crate::models::TunerUserPriority;
+use crate::tuner::selection_order;
+use crate::tuner_reservation::assign_tuners;
+use crate::tuner_reservation::TunerReservation;
+
+use super::RecordingSchedule;
+
+/// A conflict of a recording schedule predicted by simulating the tuner
+/// allocation.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(title = "RecordingConflict")]
+pub struct RecordingConflict {
+    /// A Mirakurun program ID of the TV program which will fail to be recorded.
+    #[schema(value_type = u64)]
+    pub program_id: ProgramId,
+    /// The reason of the conflict.
+    pub reason: RecordingConflictReason,
+    /// Tuner users competing with the recording schedule.
+    pub competitors: Vec<RecordingConflictCompetitor>,
+}
+
+/// The reason of a conflict.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum RecordingConflictReason {
+    /// No tuner will be available when the recording starts.
+    NoTunerAvailable,
+    /// The tuner will be grabbed by a user with a higher priority while
+    /// recording.
+    #[serde(rename_all = "camelCase")]
+    Preempted {
+        /// A time when the tuner will be grabbed in UNIX time (milliseconds).
+        #[serde(with = "ts_milliseconds")]
+        #[schema(value_type = i64)]
+        time: DateTime<Jst>,
+    },
+}
+
+/// A tuner user competing with a recording schedule.
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum RecordingConflictCompetitor {
+    /// A recording schedule.
+    #[serde(rename_all = "camelCase")]
+    Recording {
+        /// A Mirakurun program ID of the target TV program.
+        #[schema(value_type = u64)]
+        program_id: ProgramId,
+    },
+    /// A timeshift recorder.
+    #[serde(rename_all = "camelCase")]
+    Timeshift {
+        /// The name of the timeshift recorder.
+        name: String,
+    },
+    /// A tuner reservation.
+    #[serde(rename_all = "camelCase")]
+    TunerReservation {
+        /// The ID of the tuner reservation.
+        id: u32,
+        /// The owner of the tuner reservation.
+        owner: String,
+    },
+}
+
+// Simulates the tuner allocation in `TunerManager` over recording schedules.
+//
+// Timeshift recorders hold tuners all the time.  Tuners dedicated for on-air
+// program trackers, tuners for streaming and tuners in maintenance are never
+// used for recordings.  Other tuner users such as
+// EPG jobs are ignored because they usually have the lowest priority.
+//
+// A recording schedule cannot use a tuner reserved in a time window
+// overlapping with the schedule unless the schedule has a higher priority
+// than the reservation, because the reservation stops the recording when it
+// starts.  Owners of reservations are ignored because recording schedules
+// are never owners in practice.
+pub(super) fn predict_conflicts<'a, I>(
+    config: &Config,
+    schedules: I,
+    services: &IndexMap<ServiceId, EpgService>,
+    reservations: &[TunerReservation],
+    maintenance: &HashSet<String>,
+) -> Vec<RecordingConflict>
+where
+    I: Iterator<Item = &'a RecordingSchedule>,
+{
+    let mut simulator = Simulator::new(config, reservations, maintenance);
+
+    for (name, recorder) in config.timeshift.recorders.iter() {
+        let channel = match services.get(&recorder.service_id) {
+            Some(service) => &service.channel,
+            None => continue,
+        };
+        let user = User {
+            competitor: RecordingConflictCompetitor::Timeshift { name: name.clone() },
+            priority: recorder.priority.into(),
+            end_time: None,
+        };
+        if simulator.allocate(channel, user).is_err() {
+            tracing::warn!(recorder.name = name, "No tuner for the timeshift recorder");
+        }
+    }
+
+    let items = schedules
+        .filter(|schedule| schedule.is_ready_for_recording() || schedule.is_recording())
+        .filter_map(|schedule| {
+            let service_id = ServiceId::from(schedule.program.id);
+            let channel = &services.get(&service_id)?.channel;
+            Some(Item {
+                program_id: schedule.program.id,
+                channel,
+                start_time: schedule.start_time(&config.recording),
+                end_time: schedule.end_time(&config.recording)?,
+                priority: schedule.options.priority.into(),
+            })
+        })
+        // The same order as the recording queue.
+        .sorted_by_key(|item| {
+            (
+                item.start_time,
+                std::cmp::Reverse(item.priority),
+                item.program_id.value(),
+            )
+        });
+
+    let mut conflicts: IndexMap<ProgramId, RecordingConflict> = IndexMap::new();
+    for item in items {
+        simulator.release(item.start_time);
+        let user = User {
+            competitor: RecordingConflictCompetitor::Recording {
+                program_id: item.program_id,
+            },
+            priority: item.priority,
+            end_time: Some(item.end_time),
+        };
+        simulator.reserve(item.start_time, item.end_time, item.priority);
+        match simulator.allocate(item.channel, user) {
+            Ok(preempted) => {
+                let competitor = RecordingConflictCompetitor::Recording {
+                    program_id: item.program_id,
+                };
+                for user in preempted.into_iter() {
+                    if let RecordingConflictCompetitor::Recording { program_id } = user.competitor {
+                        conflicts.entry(program_id).or_insert(RecordingConflict {
+                            program_id,
+                            reason: RecordingConflictReason::Preempted {
+                                time: item.start_time,
+                            },
+                            competitors: vec![competitor.clone()],
+                        });
+                    }
+                }
+            }
+            Err(competitors) => {
+                conflicts
+                    .entry(item.program_id)
+                    .or_insert(RecordingConflict {
+                        program_id: item.program_id,
+                        reason: RecordingConflictReason::NoTunerAvailable,
+                        competitors,
+                    });
+            }
+        }
+    }
+
+    conflicts.into_values().collect()
+}
+
+struct Item<'a> {
+    program_id: ProgramId,
+    channel: &'a EpgChannel,
+    start_time: DateTime<Jst>,
+    end_time: DateTime<Jst>,
+    priority: TunerUserPriority,
+}
+
+struct Simulator<'a> {
+    tuners: Vec<Tuner<'a>>,
+    selection: &'a TunerSelectionConfig,
+    reservations: &'a [TunerReservation],
+}
+
+impl<'a> Simulator<'a> {
+    fn new(
+        config: &'a Config,
+        reservations: &'a [TunerReservation],
+        maintenance: &HashSet<String>,
+    ) -> Self {
+        let tuners = config
+            .tuners
+            .iter()
+            .filter(|config| !config.disabled)
+            .filter(|config| config.dedicated_for.is_none())
+            .filter(|config| config.role.accepts_recorder())
+            .filter(|config| !maintenance.contains(&config.name))
+            .map(|config| Tuner {
+                name: &config.name,
+                channel_types: config.channel_types.clone(),
+                weight: config.weight,
+                channel: None,
+                users: vec![],
+                reserved_by: None,
+            })
+            .collect();
+        Simulator {
+            tuners,
+            selection: &config.tuner_selection,
+            reservations,
+        }
+    }
+
+    // Marks tuners which cannot be used by a user with `priority` from
+    // `start_time` to `end_time` due to reservations.
+    fn reserve(
+        &mut self,
+        start_time: DateTime<Jst>,
+        end_time: DateTime<Jst>,
+        priority: TunerUserPriority,
+    ) {
+        let targets: Vec<(&str, &[ChannelType])> = self
+            .tuners
+            .iter()
+            .map(|tuner| (tuner.name, tuner.channel_types.as_slice()))
+            .collect();
+        let reservations = self
+            .reservations
+            .iter()
+            .filter(|reservation| reservation.window.overlaps(start_time, end_time));
+        let assigned = assign_tuners(reservations, &targets);
+        for (tuner, reservations) in self.tuners.iter_mut().zip(assigned) {
+            tuner.reserved_by = reservations
+                .into_iter()
+                .find(|reservation| priority <= reservation.priority());
+        }
+    }
+
+    fn release(&mut self, now: DateTime<Jst>) {
+        for tuner in self.tuners.iter_mut() {
+            tuner.users.retain(|user| match user.end_time {
+                Some(end_time) => end_time > now,
+                None => true,
+            });
+            if tuner.users.is_empty() {
+                tuner.channel = None;
+            }
+        }
+    }
+
+    // Returns users preempted by the user if a tuner is allocated.  Otherwise,
+    // returns users competing with the user.
+    fn allocate(
+        &mut self,
+        channel: &EpgChannel,
+        user: User,
+    ) -> Result<Vec<User>, Vec<RecordingConflictCompetitor>> {
+        // The same order as `TunerManager::activate_tuner()`.
+        let found = self
+            .tuners
+            .iter_mut()
+            .filter(|tuner| tuner.reserved_by.is_none())
+            .find(|tuner| tuner.is_reuseable(channel));
+        if let Some(tuner) = found {
+            tuner.users.push(user);
+            return Ok(vec![]);
+        }
+
+        // The same order as `TunerManager::select_tuners()`.  The selection
+        // policy is not simulated because it depends on the runtime state of
+        // tuners.
+        let candidates = (0..self.tuners.len())
+            .filter(|&i| self.tuners[i].reserved_by.is_none())
+            .sorted_by_key(|&i| {
+                let tuner = &self.tuners[i];
+                selection_order(
+                    self.selection,
+                    channel.channel_type,
+                    tuner.name,
+                    tuner.weight,
+                )
+            })
+            .collect_vec();
+
+        let found = candidates
+            .iter()
+            .copied()
+            .find(|&i| self.tuners[i].is_available_for(channel));
+        if let Some(i) = found {
+            let tuner = &mut self.tuners[i];
+            tuner.channel = Some(channel.clone());
+            tuner.users.push(user);
+            return Ok(vec![]);
+        }
+
+        let found = candidates.iter().copied().find(|&i| {
+            let tuner = &self.tuners[i];
+            tuner.is_supported_type(channel.channel_type) && tuner.can_grab(user.priority)
+        });
+        if let Some(i) = found {
+            let tuner = &mut self.tuners[i];
+            tuner.channel = Some(channel.clone());
+            return Ok(std::mem::replace(&mut tuner.users, vec![user]));
+        }
+
+        Err(self
+            .tuners
+            .iter()
+            .filter(|tuner| tuner.is_supported_type(channel.channel_type))
+            .flat_map(|tuner| match tuner.reserved_by {
+                Some(reservation) => vec![RecordingConflictCompetitor::TunerReservation {
+                    id: reservation.id,
+                    owner: reservation.owner.clone(),
+                }],
+                None => tuner
+                    .users
+                    .iter()
+                    .map(|user| user.competitor.clone())
+                    .collect(),
+            })
+            .collect())
+    }
+}
+
+struct Tuner<'a> {
+    name: &'a str,
+    channel_types: Vec<ChannelType>,
+    weight: u32,
+    channel: Option<EpgChannel>,
+    users: Vec<User>,
+    // A reservation which prevents the current user from using the tuner.
+    reserved_by: Option<&'a TunerReservation>,
+}
+
+impl<'a> Tuner<'a> {
+    fn is_supported_type(&self, channel_type: ChannelType) -> bool {
+        self.channel_types.contains(&channel_type)
+    }
+
+    fn is_available_for(&self, channel: &EpgChannel) -> bool {
+        self.channel.is_none() && self.is_supported_type(channel.channel_type)
+    }
+
+    fn is_reuseable(&self, channel: &EpgChannel) -> bool {
+        match self.channel {
+            Some(ref active) => {
+                active.channel_type == channel.channel_type && active.channel == channel.channel
+            }
+            None => false,
+        }
+    }
+
+    fn can_grab(&self, priority: TunerUserPriority) -> bool {
+        priority.is_grab() || self.users.iter().all(|user| priority > user.priority)
+    }
+}
+
+struct User {
+    competitor: RecordingConflictCompetitor,
+    priority: TunerUserPriority,
+    // `None` means that the user holds the tuner all the time.
+    end_time: Option<DateTime<Jst>>,
+}
+
+// <coverage:exclude>
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TimeshiftRecorderConfig;
+    use crate::config::TunerConfig;
+    use crate::models::TunerRole;
+    use crate::recording::RecordingOptions;
+    use crate::recording::RecordingScheduleState;
+    use crate::tuner_reservation::TunerReservationTarget;
+    use crate::tuner_reservation::TunerReservationWindow;
+    use assert_matches::assert_matches;
+    use chrono::Duration;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_predict_conflicts() {
+        let now = Jst::now();
+        let config = config_for_test(2, vec![]);
+        let services = services_for_test();
+
+        // 3 overlapping schedules on different channels with 2 GR tuners.
+        let schedules = [
+            schedule_for_test((0, 1, 1), now, 0),
+            schedule_for_test((0, 2, 1), now + Duration::minutes(10), 0),
+            schedule_for_test((0, 3, 1), now + Duration::minutes(20), 0),
+        ];
+        let conflicts = predict_conflicts(
+            &config,
+            schedules.iter(),
+            &services,
+            &[],
+            &Default::default(),
+        );
+        assert_eq!(conflicts.len(), 1);
+        assert_matches!(&conflicts[0], conflict => {
+            assert_eq!(conflict.program_id, (0, 3, 1).into());
+            assert_matches!(conflict.reason, RecordingConflictReason::NoTunerAvailable);
+            assert_eq!(conflict.competitors.len(), 2);
+        });
+
+        // No conflict after the first schedule ends.
+        let schedules = [
+            schedule_for_test((0, 1, 1), now, 0),
+            schedule_for_test((0, 2, 1), now + Duration::minutes(10), 0),
+            schedule_for_test((0, 3, 1), now + Duration::hours(1), 0),
+        ];
+        let conflicts = predict_conflicts(
+            &config,
+            schedules.iter(),
+            &services,
+            &[],
+            &Default::default(),
+        );
+        assert!(conflicts.is_empty());
+
+        // Schedules on the same channel share a tuner.
+        let schedules = [
+            schedule_for_test((0, 1, 1), now, 0),
+            schedule_for_test((0, 2, 1), now + Duration::minutes(10), 0),
+            schedule_for_test((0, 4, 1), now + Duration::minutes(20), 0),
+        ];
+        let conflicts = predict_conflicts(
+            &config,
+            schedules.iter(),
+            &services,
+            &[],
+            &Default::default(),
+        );
+        assert!(conflicts.is_empty());
+
+        // A higher priority schedule grabs a tuner.
+        let schedules = [
+            schedule_for_test((0, 1, 1), now, 0),
+            schedule_for_test((0, 2, 1), now + Duration::minutes(10), 1),
+            schedule_for_test((0, 3, 1), now + Duration::minutes(20), 2),
+        ];
+        let conflicts = predict_conflicts(
+            &config,
+            schedules.iter(),
+            &services,
+            &[],
+            &Default::default(),
+        );
+        assert_eq!(conflicts.len(), 1);
+        assert_matches!(&conflicts[0], conflict => {
+            assert_eq!(conflict.program_id, (0, 1, 1).into());
+            assert_matches!(conflict.reason, RecordingConflictReason::Preempted { time } => {
+                assert_eq!(time, now + Duration::minutes(20));
+            });
+            assert_eq!(
+                conflict.competitors,
+                vec![RecordingConflictCompetitor::Recording {
+                    program_id: (0, 3, 1).into(),
+                }]
+            );
+        });
+
+        // Finished schedules are ignored.
+        let mut schedules = [
+            schedule_for_test((0, 1, 1), now, 0),
+            schedule_for_test((0, 2, 1), now + Duration::minutes(10), 0),
+            schedule_for_test((0, 3, 1), now + Duration::minutes(20), 0),
+        ];
+        schedules[0].state = RecordingScheduleState::Finished;
+        let conflicts = predict_conflicts(
+            &config,
+            schedules.iter(),
+            &services,
+            &[],
+            &Default::default(),
+        );
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_predict_conflicts_channel_types() {
+        let now = Jst::now();
+        let services = services_for_test();
+
+        // No tuner supports BS.
+        let config = config_for_test(2, vec![]);
+        let schedules = [schedule_for_test((0, 5, 1), now, 0)];
+        let conflicts = predict_conflicts(
+            &config,
+            schedules.iter(),
+            &services,
+            &[],
+            &Default::default(),
+        );
+        assert_eq!(conflicts.len(), 1);
+        assert_matches!(&conflicts[0], conflict => {
+            assert_eq!(conflict.program_id, (0, 5, 1).into());
+            assert_matches!(conflict.reason, RecordingConflictReason::NoTunerAvailable);
+            assert!(conflict.competitors.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_predict_conflicts_selection_order() {
+        let now = Jst::now();
+        let services = services_for_test();
+
+        // tuner1 is selected for GR prior to tuner0 supporting BS.
+        let mut config = config_for_test(2, vec![]);
+        {
+            let config = Arc::get_mut(&mut config).unwrap();
+            config.tuners[0].channel_types.push(ChannelType::BS);
+            config.tuners[1].weight = 2;
+        }
+        let schedules = [
+            schedule_for_test((0, 1, 1), now, 0),
+            schedule_for_test((0, 5, 1), now + Duration::minutes(10), 0),
+        ];
+        let conflicts = predict_conflicts(
+            &config,
+            schedules.iter(),
+            &services,
+            &[],
+            &Default::default(),
+        );
+        assert!(conflicts.is_empty());
+
+        // tuner0 is preferred.
+        Arc::get_mut(&mut config)
+            .unwrap()
+            .tuner_selection
+            .preferred
+            .insert(ChannelType::GR, vec!["tuner0".to_string()]);
+        let conflicts = predict_conflicts(
+            &config,
+            schedules.iter(),
+            &services,
+            &[],
+            &Default::default(),
+        );
+        assert_eq!(conflicts.len(), 1);
+        assert_matches!(&conflicts[0], conflict => {
+            assert_eq!(conflict.program_id, (0, 5, 1).into());
+        });
+    }
+
+    #[test]
+    fn test_predict_conflicts_dedicated_for() {
+        let now = Jst::now();
+        let services = services_for_test();
+
+        let mut config = config_for_test(2, vec![]);
+        Arc::get_mut(&mut config).unwrap().tuners[1].dedicated_for = Some("test".to_string());
+
+        let schedules = [
+            schedule_for_test((0, 1, 1), now, 0),
+            schedule_for_test((0, 2, 1), now + Duration::minutes(10), 0),
+        ];
+        let conflicts = predict_conflicts(
+            &config,
+            schedules.iter(),
+            &services,
+            &[],
+            &Default::default(),
+        );
+        assert_eq!(conflicts.len(), 1);
+        assert_matches!(&conflicts[0], conflict => {
+            assert_eq!(conflict.program_id, (0, 2, 1).into());
+        });
+    }
+
+    #[test]
+    fn test_predict_conflicts_role() {
+        let now = Jst::now();
+        let services = services_for_test();
+
+        let mut config = config_for_test(3, vec![]);
+        Arc::get_mut(&mut config).unwrap().tuners[0].role = TunerRole::Streaming;
+        Arc::get_mut(&mut config).unwrap().tuners[1].role = TunerRole::Recording;
+
+        let schedules = [
+            schedule_for_test((0, 1, 1), now, 0),
+            schedule_for_test((0, 2, 1), now + Duration::minutes(10), 0),
+        ];
+        let conflicts = predict_conflicts(
+            &config,
+            schedules.iter(),
+            &services,
+            &[],
+            &Default::default(),
+        );
+        assert!(conflicts.is_empty());
+
+        // The streaming-only tuner is never used for recordings.
+        let schedules = [
+            schedule_for_test((0, 1, 1), now, 0),
+            schedule_for_test((0, 2, 1), now + Duration::minutes(10), 0),
+            schedule_for_test((0, 3, 1), now + Duration::minutes(20), 0),
+        ];
+        let conflicts = predict_conflicts(
+            &config,
+            schedules.iter(),
+            &services,
+            &[],
+            &Default::default(),
+        );
+        assert_eq!(conflicts.len(), 1);
+        assert_matches!(&conflicts[0], conflict => {
+            assert_eq!(conflict.program_id, (0, 3, 1).into());
+            assert_matches!(conflict.reason, RecordingConflictReason::NoTunerAvailable);
+            assert_eq!(conflict.competitors.len(), 2);
+        });
+    }
+
+    #[test]
+    fn test_predict_conflicts_maintenance() {
+        let now = Jst::now();
+        let services = services_for_test();
+
+        let config = config_for_test(2, vec![]);
+        let maintenance = ["tuner0".to_string()].into();
+
+        let schedules = [
+            schedule_for_test((0, 1, 1), now, 0),
+            schedule_for_test((0, 2, 1), now + Duration::minutes(10), 0),
+        ];
+        let conflicts = predict_conflicts(
+            &config,
+            schedules.iter(),
+            &services,
+            &[],
+            &Default::default(),
+        );
+        assert!(conflicts.is_empty());
+
+        // The tuner in maintenance is never used for recordings.
+        let conflicts = predict_conflicts(&config, schedules.iter(), &services, &[], &maintenance);
+        assert_eq!(conflicts.len(), 1);
+        assert_matches!(&conflicts[0], conflict => {
+            assert_eq!(conflict.program_id, (0, 2, 1).into());
+            assert_matches!(conflict.reason, RecordingConflictReason::NoTunerAvailable);
+            assert_eq!(
+                conflict.competitors,
+                vec![RecordingConflictCompetitor::Recording {
+                    program_id: (0, 1, 1).into(),
+                }]
+            );
+        });
+    }
+
+    #[test]
+    fn test_predict_conflicts_timeshift() {
+        let now = Jst::now();
+        let services = services_for_test();
+
+        let config = config_for_test(2, vec![("ts", (0, 3).into(), 0)]);
+
+        let schedules = [
+            schedule_for_test((0, 1, 1), now, 0),
+            schedule_for_test((0, 2, 1), now + Duration::minutes(10), 0),
+        ];
+        let conflicts = predict_conflicts(
+            &config,
+            schedules.iter(),
+            &services,
+            &[],
+            &Default::default(),
+        );
+        assert_eq!(conflicts.len(), 1);
+        assert_matches!(&conflicts[0], conflict => {
+            assert_eq!(conflict.program_id, (0, 2, 1).into());
+            assert_matches!(conflict.reason, RecordingConflictReason::NoTunerAvailable);
+            assert!(conflict
+                .competitors
+                .contains(&RecordingConflictCompetitor::Timeshift {
+                    name: "ts".to_string()
+                }));
+        });
+
+        // A schedule on the same channel as the timeshift recorder.
+        let schedules = [
+            schedule_for_test((0, 1, 1), now, 0),
+            schedule_for_test((0, 3, 1), now + Duration::minutes(10), 0),
+        ];
+        let conflicts = predict_conflicts(
+            &config,
+            schedules.iter(),
+            &services,
+            &[],
+            &Default::default(),
+        );
+        assert!(conflicts.is_empty());
+
+        // A higher priority schedule grabs the tuner used by the timeshift
+        // recorder.
+        let schedules = [
+            schedule_for_test((0, 1, 1), now, 0),
+            schedule_for_test((0, 2, 1), now + Duration::minutes(10), 1),
+        ];
+        let conflicts = predict_conflicts(
+            &config,
+            schedules.iter(),
+            &services,
+            &[],
+            &Default::default(),
+        );
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_predict_conflicts_reservations() {
+        let now = Jst::now();
+        let services = services_for_test();
+        let config = config_for_test(2, vec![]);
+
+        let reservations = [TunerReservation {
+            id: 1,
+            target: TunerReservationTarget::Tuner {
+                name: "tuner0".to_string(),
+            },
+            window: TunerReservationWindow::Once {
+                start_at: now + Duration::minutes(30),
+                end_at: now + Duration::hours(2),
+            },
+            owner: "other".to_string(),
+            priority: 0,
+            from_config: false,
+        }];
+
+        // The reservation starts while the first schedule is recorded.
+        let schedules = [
+            schedule_for_test((0, 1, 1), now, 0),
+            schedule_for_test((0, 2, 1), now + Duration::minutes(10), 0),
+        ];
+        let conflicts = predict_conflicts(
+            &config,
+            schedules.iter(),
+            &services,
+            &reservations,
+            &Default::default(),
+        );
+        assert_eq!(conflicts.len(), 1);
+        assert_matches!(&conflicts[0], conflict => {
+            assert_eq!(conflict.program_id, (0, 2, 1).into());
+            assert_matches!(conflict.reason, RecordingConflictReason::NoTunerAvailable);
+            assert!(conflict
+                .competitors
+                .contains(&RecordingConflictCompetitor::TunerReservation {
+                    id: 1,
+                    owner: "other".to_string(),
+                }));
+        });
+
+        // A higher priority schedule can use the reserved tuner.
+        let schedules = [
+            schedule_for_test((0, 1, 1), now, 1),
+            schedule_for_test((0, 2, 1), now + Duration::minutes(10), 0),
+        ];
+        let conflicts = predict_conflicts(
+            &config,
+            schedules.iter(),
+            &services,
+            &reservations,
+            &Default::default(),
+        );
+        assert!(conflicts.is_empty());
+
+        // No conflict after the reservation ends.
+        let schedules = [
+            schedule_for_test((0, 1, 1), now + Duration::hours(2), 0),
+            schedule_for_test((0, 2, 1), now + Duration::hours(2), 0),
+        ];
+        let conflicts = predict_conflicts(
+            &config,
+            schedules.iter(),
+            &services,
+            &reservations,
+            &Default::default(),
+        );
+        assert!(conflicts.is_empty());
+    }
+
+    fn config_for_test(num_tuners: usize, timeshift: Vec<(&str, ServiceId, i32)>) -> Arc<Config> {
+        let mut config = Config::default();
+        config.recording.end_margin = std::time::Duration::ZERO;
+        for i in 0..num_tuners {
+            config.tuners.push(TunerConfig {
+                name: format!("tuner{}", i),
+                channel_types: vec![ChannelType::GR],
+                command: "true".to_string(),
+                ..Default::default()
+            });
+        }
+        for (name, service_id, priority) in timeshift.into_iter() {
+            config.timeshift.recorders.insert(
+                name.to_string(),
+                TimeshiftRecorderConfig {
+                    service_id,
+                    ts_file: "/dev/null".into(),
+                    data_file: "/dev/null".into(),
+                    chunk_size: 0,
+                    num_chunks: 0,
+                    num_reserves: 0,
+                    priority,
+                },
+            );
+        }
+        Arc::new(config)
+    }
+
+    fn services_for_test() -> IndexMap<ServiceId, EpgService> {
+        indexmap::indexmap! {
+            (0, 1).into() => service!((0, 1), "1", channel_gr!("ch1", "1")),
+            (0, 2).into() => service!((0, 2), "2", channel_gr!("ch2", "2")),
+            (0, 3).into() => service!((0, 3), "3", channel_gr!("ch3", "3")),
+            (0, 4).into() => service!((0, 4), "4", channel_gr!("ch1", "1")),
+            (0, 5).into() => service!((0, 5), "5", channel!("bs", ChannelType::BS, "bs")),
+        }
+    }
+
+    fn schedule_for_test(
+        program_id: (u16, u16, u16),
+        start_at: DateTime<Jst>,
+        priority: i32,
+    ) -> RecordingSchedule {
+        let mut schedule = RecordingSchedule::new(
+            Arc::new(program!(program_id, start_at, "1h")),
+            RecordingOptions {
+                content_path: "test.m2ts".into(),
+                priority,
+                pre_filters: vec![],
+                post_filters: vec![],
+                start_margin: None,
+                end_margin: None,
+            },
+            Default::default(),
+        );
+        schedule.state = RecordingScheduleState::Scheduled;
+        schedule
+    }
+}
+// </coverage:exclude>
@@ -0,0 +1,211 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use path_dedot::ParseDot;
+
+use crate::epg::EpgProgram;
+use crate::epg::EpgService;
+use crate::error::Error;
+use crate::models::ServiceId;
+
+// The maximum number of suffixes tried when resolving a collision.
+const MAX_SUFFIX: usize = 1000;
+
+// `options.contentPath` is a mustache template rendered when the recording
+// starts.
+//
+// String values are sanitized so that each of them can be used as a part of a
+// filename.
+pub(super) fn render_content_path(
+    template: &Path,
+    program: &EpgProgram,
+    service: &EpgService,
+) -> Result<PathBuf, Error> {
+    let start_at = program.start_at;
+    let data = mustache::MapBuilder::new()
+        .insert("program_id", &program.id.value())?
+        .insert("service_id", &ServiceId::from(program.id).value())?
+        .insert("sid", &program.id.sid().value())?
+        .insert("eid", &program.id.eid().value())?
+        .insert_str(
+            "start_time",
+            start_at
+                .map(|start_at| start_at.format("%Y%m%d%H%M").to_string())
+                .unwrap_or_default(),
+        )
+        .insert_str(
+            "start_date",
+            start_at
+                .map(|start_at| start_at.format("%Y%m%d").to_string())
+                .unwrap_or_default(),
+        )
+        .insert_str(
+            "title",
+            sanitize(program.name.as_deref().unwrap_or_default()),
+        )
+        .insert_str(
+            "episode",
+            program
+                .series
+                .as_ref()
+                .filter(|series| series.episode_number > 0)
+                .map(|series| series.episode_number.to_string())
+                .unwrap_or_default(),
+        )
+        .insert_str("service_name", sanitize(&service.name))
+        .insert_str("channel_name", sanitize(&service.channel.name))
+        .insert("channel_type", &service.channel.channel_type)?
+        .build();
+    let template = mustache::compile_str(&template.to_string_lossy())?;
+    Ok(template.render_data_to_string(&data)?.into())
+}
+
+// The template is validated when it's submitted, but variables are not
+// expanded at that point.  For example, `{{{title}}}/a.m2ts` is rendered into
+// an absolute path `/a.m2ts` when the program has no name.
+pub(super) fn validate_content_path(basedir: &Path, content_path: &Path) -> Result<(), Error> {
+    if content_path.is_absolute() || content_path.file_name().is_none() {
+        return Err(Error::InvalidPath);
+    }
+    if !basedir.join(content_path).parse_dot()?.starts_with(basedir) {
+        return Err(Error::InvalidPath);
+    }
+    Ok(())
+}
+
+// Replaces characters which cannot be used in filenames on major filesystems.
+pub(super) fn sanitize(value: &str) -> String {
+    let sanitized: String = value
+        .trim()
+        .chars()
+        .map(|ch| match ch {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            ch if ch.is_control() => '_',
+            ch => ch,
+        })
+        .collect();
+    // "." and ".." have special meanings.
+    if !sanitized.is_empty() && sanitized.chars().all(|ch| ch == '.') {
+        return "_".repeat(sanitized.len());
+    }
+    sanitized
+}
+
+// Appends a suffix like `-1` to the stem of the filename while `is_used()`
+// returns `true`.
+pub(super) fn resolve_collision<F>(content_path: &Path, is_used: F) -> Option<PathBuf>
+where
+    F: Fn(&Path) -> bool,
+{
+    if !is_used(content_path) {
+        return Some(content_path.to_owned());
+    }
+    let stem = content_path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let ext = content_path.extension().map(|ext| ext.to_string_lossy());
+    (1..=MAX_SUFFIX)
+        .map(|n| {
+            let filename = match ext {
+                Some(ref ext) => format!("{}-{}.{}", stem, n, ext),
+                None => format!("{}-{}", stem, n),
+            };
+            content_path.with_file_name(filename)
+        })
+        .find(|path| !is_used(path))
+}
+
+// <coverage:exclude>
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn test_render_content_path() {
+        let mut program = program!((0, 1, 2), jst!("2023-01-02T03:04:05+09:00"), "1h");
+        program.name = Some("a/b: c".to_string());
+        let service = service!((0, 1), "service/1", channel_gr!("ch", "1"));
+
+        let result = render_content_path(
+            Path::new("{{{service_name}}}/{{{start_date}}}_{{{title}}}#{{{episode}}}.m2ts"),
+            &program,
+            &service,
+        );
+        assert_matches!(result, Ok(path) => {
+            assert_eq!(path, Path::new("service_1/20230102_a_b_ c#.m2ts"));
+        });
+
+        let result = render_content_path(
+            Path::new("{{{program_id}}}_{{{start_time}}}_{{{channel_type}}}.m2ts"),
+            &program,
+            &service,
+        );
+        assert_matches!(result, Ok(path) => {
+            assert_eq!(
+                path,
+                PathBuf::from(format!("{}_202301020304_GR.m2ts", program.id.value()))
+            );
+        });
+
+        let result = render_content_path(Path::new("{{"), &program, &service);
+        assert_matches!(result, Err(_));
+    }
+
+    #[test]
+    fn test_validate_content_path() {
+        let basedir = Path::new("/base");
+
+        assert_matches!(
+            validate_content_path(basedir, Path::new("a/b.m2ts")),
+            Ok(())
+        );
+        assert_matches!(
+            validate_content_path(basedir, Path::new("a/../b.m2ts")),
+            Ok(())
+        );
+        assert_matches!(
+            validate_content_path(basedir, Path::new("/b.m2ts")),
+            Err(Error::InvalidPath)
+        );
+        assert_matches!(
+            validate_content_path(basedir, Path::new("../b.m2ts")),
+            Err(Error::InvalidPath)
+        );
+        assert_matches!(
+            validate_content_path(basedir, Path::new("a/..")),
+            Err(Error::InvalidPath)
+        );
+        assert_matches!(
+            validate_content_path(basedir, Path::new("")),
+            Err(Error::InvalidPath)
+        );
+    }
+
+    #[test]
+    fn test_sanitize() {
+        assert_eq!(sanitize(" a/b\\c:d*e?f\"g<h>i|j\n "), "a_b_c_d_e_f_g_h_i_j");
+        assert_eq!(sanitize(".."), "__");
+        assert_eq!(sanitize("..a"), "..a");
+        assert_eq!(sanitize(""), "");
+    }
+
+    #[test]
+    fn test_resolve_collision() {
+        let result = resolve_collision(Path::new("dir/a.m2ts"), |_| false);
+        assert_eq!(result, Some(PathBuf::from("dir/a.m2ts")));
+
+        let result = resolve_collision(Path::new("dir/a.m2ts"), |path| {
+            path == Path::new("dir/a.m2ts") || path == Path::new("dir/a-1.m2ts")
+        });
+        assert_eq!(result, Some(PathBuf::from("dir/a-2.m2ts")));
+
+        let result = resolve_collision(Path::new("a"), |path| path == Path::new("a"));
+        assert_eq!(result, Some(PathBuf::from("a-1")));
+
+        let result = resolve_collision(Path::new("a"), |_| true);
+        assert_eq!(result, None);
+    }
+}
+// </coverage:exclude>
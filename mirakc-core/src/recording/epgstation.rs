@@ -0,0 +1,452 @@
+use std::collections::HashSet;
+
+use chrono::DateTime;
+use chrono::NaiveTime;
+use chrono::Weekday;
+use chrono_jst::serde::ts_milliseconds;
+use chrono_jst::Jst;
+use indexmap::IndexMap;
+use serde::Deserialize;
+
+use crate::epg::EpgService;
+use crate::models::ChannelType;
+use crate::models::ProgramId;
+use crate::models::ServiceId;
+
+use super::RecordingOptions;
+use super::RecordingRule;
+use super::RecordingRuleCondition;
+use super::RecordingRuleGenre;
+use super::RecordingRuleTimeRange;
+
+// Data models used in EPGStation v2.
+//
+// Only properties used for the conversion are defined.  Others are ignored.
+
+/// A rule exported from EPGStation.
+///
+/// An element of `rules` in the response of `GET /api/rules` of EPGStation.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpgstationRule {
+    pub id: u32,
+    #[serde(default)]
+    pub is_time_specification: bool,
+    pub search_option: EpgstationSearchOption,
+    #[serde(default)]
+    pub reserve_option: EpgstationReserveOption,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpgstationSearchOption {
+    #[serde(default)]
+    pub keyword: Option<String>,
+    #[serde(default)]
+    pub ignore_keyword: Option<String>,
+    #[serde(default)]
+    pub key_reg_exp: bool,
+    #[serde(default)]
+    pub ignore_key_reg_exp: bool,
+    #[serde(default)]
+    pub description: bool,
+    #[serde(default)]
+    pub extended: bool,
+    #[serde(default, rename = "GR")]
+    pub gr: bool,
+    #[serde(default, rename = "BS")]
+    pub bs: bool,
+    #[serde(default, rename = "CS")]
+    pub cs: bool,
+    #[serde(default, rename = "SKY")]
+    pub sky: bool,
+    #[serde(default)]
+    pub channel_ids: Vec<u64>,
+    #[serde(default)]
+    pub genres: Vec<EpgstationGenre>,
+    #[serde(default)]
+    pub times: Vec<EpgstationSearchTime>,
+    #[serde(default)]
+    pub is_free: bool,
+    #[serde(default)]
+    pub duration_min: Option<u32>,
+    #[serde(default)]
+    pub duration_max: Option<u32>,
+    #[serde(default)]
+    pub search_periods: Vec<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpgstationGenre {
+    pub genre: u8,
+    #[serde(default)]
+    pub sub_genre: Option<u8>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpgstationSearchTime {
+    // The hour when the time range starts.
+    #[serde(default)]
+    pub start: Option<u32>,
+    // The length of the time range in hours.
+    #[serde(default)]
+    pub range: Option<u32>,
+    // A bit set of days of the week.  Sunday is the LSB.
+    pub week: u8,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpgstationReserveOption {
+    #[serde(default = "EpgstationReserveOption::default_enable")]
+    pub enable: bool,
+}
+
+impl EpgstationReserveOption {
+    fn default_enable() -> bool {
+        true
+    }
+}
+
+impl Default for EpgstationReserveOption {
+    fn default() -> Self {
+        EpgstationReserveOption {
+            enable: Self::default_enable(),
+        }
+    }
+}
+
+/// A reserve exported from EPGStation.
+///
+/// An element of `reserves` in the response of `GET /api/reserves` of
+/// EPGStation.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpgstationReserve {
+    pub id: u32,
+    #[serde(default)]
+    pub is_skip: bool,
+    #[serde(default)]
+    pub is_time_specified: bool,
+    #[serde(default)]
+    pub program_id: Option<u64>,
+    pub channel_id: u64,
+    #[serde(with = "ts_milliseconds")]
+    pub start_at: DateTime<Jst>,
+    #[serde(with = "ts_milliseconds")]
+    pub end_at: DateTime<Jst>,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+// Days of the week in the order of bits in `EpgstationSearchTime::week`.
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Sun,
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+];
+
+impl EpgstationRule {
+    // Returns a reason as an error if the rule cannot be converted.
+    //
+    // `services` is used for resolving services from broadcast types.
+    pub fn to_rule(
+        &self,
+        options: &RecordingOptions,
+        tags: &HashSet<String>,
+        services: &IndexMap<ServiceId, EpgService>,
+    ) -> Result<RecordingRule, String> {
+        if self.is_time_specification {
+            return Err("time specification rules are not supported".to_string());
+        }
+        let search = &self.search_option;
+        if search.is_free {
+            return Err("isFree is not supported".to_string());
+        }
+        if search.duration_min.is_some() || search.duration_max.is_some() {
+            return Err("durationMin and durationMax are not supported".to_string());
+        }
+        if !search.search_periods.is_empty() {
+            return Err("searchPeriods is not supported".to_string());
+        }
+        if search.keyword.is_some()
+            && search.ignore_keyword.is_some()
+            && search.key_reg_exp != search.ignore_key_reg_exp
+        {
+            return Err("keyRegExp and ignoreKeyRegExp must be the same".to_string());
+        }
+
+        let (weekdays, time_range) = match search.times.as_slice() {
+            [] => (vec![], None),
+            [time] => (time.weekdays(), time.time_range()?),
+            _ => return Err("multiple times are not supported".to_string()),
+        };
+
+        Ok(RecordingRule {
+            id: 0,
+            disabled: !self.reserve_option.enable,
+            condition: RecordingRuleCondition {
+                keyword: non_empty(search.keyword.as_deref()),
+                excluded_keyword: non_empty(search.ignore_keyword.as_deref()),
+                regex: search.key_reg_exp || search.ignore_key_reg_exp,
+                description: search.description || search.extended,
+                genres: search
+                    .genres
+                    .iter()
+                    .map(|genre| RecordingRuleGenre {
+                        lv1: genre.genre,
+                        lv2: genre.sub_genre,
+                    })
+                    .collect(),
+                services: search.services(services)?,
+                weekdays,
+                time_range,
+            },
+            options: options.clone(),
+            tags: tags.clone(),
+            excluded_programs: vec![],
+        })
+    }
+}
+
+impl EpgstationSearchOption {
+    fn services(
+        &self,
+        services: &IndexMap<ServiceId, EpgService>,
+    ) -> Result<Vec<ServiceId>, String> {
+        if !self.channel_ids.is_empty() {
+            return Ok(self
+                .channel_ids
+                .iter()
+                .map(|&id| ServiceId::from(id))
+                .collect());
+        }
+        let types: Vec<ChannelType> = [
+            (self.gr, ChannelType::GR),
+            (self.bs, ChannelType::BS),
+            (self.cs, ChannelType::CS),
+            (self.sky, ChannelType::SKY),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, channel_type)| enabled.then_some(channel_type))
+        .collect();
+        match types.len() {
+            // An empty list means all services.
+            0 | 4 => Ok(vec![]),
+            _ => {
+                let service_ids: Vec<ServiceId> = services
+                    .values()
+                    .filter(|service| types.contains(&service.channel.channel_type))
+                    .map(|service| service.id)
+                    .collect();
+                if service_ids.is_empty() {
+                    return Err("no service found for the broadcast types".to_string());
+                }
+                Ok(service_ids)
+            }
+        }
+    }
+}
+
+impl EpgstationSearchTime {
+    fn weekdays(&self) -> Vec<Weekday> {
+        if self.week & 0x7F == 0x7F {
+            return vec![];
+        }
+        WEEKDAYS
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.week & (1 << i) != 0)
+            .map(|(_, &weekday)| weekday)
+            .collect()
+    }
+
+    fn time_range(&self) -> Result<Option<RecordingRuleTimeRange>, String> {
+        let start = match self.start {
+            Some(start) => start,
+            None => return Ok(None),
+        };
+        let range = self.range.unwrap_or(24);
+        if start >= 24 || range == 0 {
+            return Err("invalid time range".to_string());
+        }
+        if range >= 24 {
+            return Ok(None);
+        }
+        Ok(Some(RecordingRuleTimeRange {
+            start: NaiveTime::from_hms_opt(start, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt((start + range) % 24, 0, 0).unwrap(),
+        }))
+    }
+}
+
+impl EpgstationReserve {
+    pub fn service_id(&self) -> ServiceId {
+        ServiceId::from(self.channel_id)
+    }
+
+    // Returns `None` for a time-specified reserve.
+    pub fn program_id(&self) -> Option<ProgramId> {
+        if self.is_time_specified {
+            return None;
+        }
+        self.program_id.map(ProgramId::from)
+    }
+}
+
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+// <coverage:exclude>
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use indexmap::indexmap;
+
+    macro_rules! rule {
+        ($json:expr) => {
+            serde_json::from_str::<EpgstationRule>($json).unwrap()
+        };
+    }
+
+    #[test]
+    fn test_to_rule() {
+        let options = RecordingOptions::default();
+        let tags: HashSet<String> = ["epgstation".to_string()].into();
+        let services = indexmap! {
+            (0, 1).into() => service!((0, 1), "gr", channel_gr!("gr", "1")),
+        };
+
+        let rule = rule!(
+            r#"{
+                "id": 1,
+                "isTimeSpecification": false,
+                "searchOption": {
+                    "keyword": "a b",
+                    "ignoreKeyword": "c",
+                    "keyCS": true,
+                    "description": true,
+                    "GR": true,
+                    "BS": false,
+                    "CS": false,
+                    "SKY": false,
+                    "genres": [{ "genre": 7 }, { "genre": 0, "subGenre": 1 }],
+                    "times": [{ "start": 23, "range": 2, "week": 65 }]
+                },
+                "reserveOption": {
+                    "enable": false,
+                    "allowEndLack": true,
+                    "avoidDuplicate": false
+                },
+                "saveOption": {}
+            }"#
+        );
+        assert_matches!(rule.to_rule(&options, &tags, &services), Ok(rule) => {
+            assert!(rule.disabled);
+            assert_eq!(rule.condition.keyword.as_deref(), Some("a b"));
+            assert_eq!(rule.condition.excluded_keyword.as_deref(), Some("c"));
+            assert!(!rule.condition.regex);
+            assert!(rule.condition.description);
+            assert_eq!(rule.condition.genres.len(), 2);
+            assert_eq!(rule.condition.genres[1].lv2, Some(1));
+            assert_eq!(rule.condition.services, vec![(0, 1).into()]);
+            assert_eq!(rule.condition.weekdays, vec![Weekday::Sun, Weekday::Sat]);
+            assert_matches!(rule.condition.time_range, Some(time_range) => {
+                assert_eq!(time_range.start, NaiveTime::from_hms_opt(23, 0, 0).unwrap());
+                assert_eq!(time_range.end, NaiveTime::from_hms_opt(1, 0, 0).unwrap());
+            });
+            assert!(rule.tags.contains("epgstation"));
+        });
+
+        let rule = rule!(
+            r#"{
+                "id": 2,
+                "searchOption": {
+                    "keyword": "a",
+                    "channelIds": [1],
+                    "times": [{ "week": 127 }]
+                }
+            }"#
+        );
+        assert_matches!(rule.to_rule(&options, &tags, &services), Ok(rule) => {
+            assert!(!rule.disabled);
+            assert_eq!(rule.condition.services, vec![1.into()]);
+            assert!(rule.condition.weekdays.is_empty());
+            assert!(rule.condition.time_range.is_none());
+        });
+
+        let rule = rule!(r#"{ "id": 3, "isTimeSpecification": true, "searchOption": {} }"#);
+        assert_matches!(rule.to_rule(&options, &tags, &services), Err(_));
+
+        let rule = rule!(r#"{ "id": 4, "searchOption": { "durationMin": 60 } }"#);
+        assert_matches!(rule.to_rule(&options, &tags, &services), Err(_));
+
+        let rule = rule!(
+            r#"{
+                "id": 5,
+                "searchOption": {
+                    "times": [{ "week": 1 }, { "week": 2 }]
+                }
+            }"#
+        );
+        assert_matches!(rule.to_rule(&options, &tags, &services), Err(_));
+
+        let rule = rule!(r#"{ "id": 6, "searchOption": { "BS": true } }"#);
+        assert_matches!(rule.to_rule(&options, &tags, &services), Err(_));
+
+        let rule = rule!(
+            r#"{
+                "id": 7,
+                "searchOption": {
+                    "keyword": "a",
+                    "keyRegExp": true,
+                    "ignoreKeyword": "b"
+                }
+            }"#
+        );
+        assert_matches!(rule.to_rule(&options, &tags, &services), Err(_));
+    }
+
+    #[test]
+    fn test_reserve() {
+        let reserve: EpgstationReserve = serde_json::from_str(
+            r#"{
+                "id": 1,
+                "isSkip": false,
+                "isTimeSpecified": false,
+                "programId": 1234567890,
+                "channelId": 12345,
+                "startAt": 0,
+                "endAt": 1000,
+                "name": "test"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(reserve.program_id(), Some(ProgramId::from(1234567890)));
+        assert_eq!(reserve.service_id(), ServiceId::from(12345));
+
+        let reserve: EpgstationReserve = serde_json::from_str(
+            r#"{
+                "id": 2,
+                "isTimeSpecified": true,
+                "channelId": 12345,
+                "startAt": 0,
+                "endAt": 1000
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(reserve.program_id(), None);
+    }
+}
+// </coverage:exclude>
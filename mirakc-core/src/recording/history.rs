@@ -0,0 +1,260 @@
+use std::collections::HashSet;
+use std::io::BufRead;
+use std::io::Write;
+use std::path::Path;
+
+use chrono::DateTime;
+use chrono_jst::serde::ts_milliseconds;
+use chrono_jst::serde::ts_milliseconds_option;
+use chrono_jst::Jst;
+use serde::Deserialize;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::error::Error;
+use crate::models::ProgramId;
+use crate::ts_stats::TsStats;
+
+use super::RecordingFailedReason;
+use super::RecordingSchedule;
+use super::RecordingScheduleState;
+
+/// An entry of the recording history.
+///
+/// Entries are appended to `history.jsonl` in `config.recording.basedir` and
+/// never modified.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingHistoryEntry {
+    #[serde(with = "ts_milliseconds")]
+    pub time: DateTime<Jst>,
+    pub event: RecordingHistoryEvent,
+    pub program_id: ProgramId,
+    #[serde(default)]
+    pub program_name: Option<String>,
+    #[serde(default, with = "ts_milliseconds_option")]
+    pub program_start_at: Option<DateTime<Jst>>,
+    // The state of the schedule after the event.
+    pub state: RecordingScheduleState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failed_reason: Option<RecordingFailedReason>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tuner_index: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ts_stats: Option<TsStats>,
+    #[serde(default)]
+    pub tags: HashSet<String>,
+}
+
+impl RecordingHistoryEntry {
+    pub(super) fn new(event: RecordingHistoryEvent, schedule: &RecordingSchedule) -> Self {
+        RecordingHistoryEntry {
+            time: Jst::now(),
+            event,
+            program_id: schedule.program.id,
+            program_name: schedule.program.name.clone(),
+            program_start_at: schedule.program.start_at,
+            state: schedule.state.clone(),
+            failed_reason: schedule.failed_reason.clone(),
+            exit_code: None,
+            tuner_index: None,
+            ts_stats: None,
+            tags: schedule.tags.clone(),
+        }
+    }
+
+    // Returns `None` if the schedule has already finished.  Its outcome has
+    // already been recorded in this case.
+    pub(super) fn removed(
+        schedule: &RecordingSchedule,
+        reason: Option<RecordingFailedReason>,
+    ) -> Option<Self> {
+        match schedule.state {
+            RecordingScheduleState::Finished | RecordingScheduleState::Failed => None,
+            _ => {
+                let mut entry = Self::new(RecordingHistoryEvent::Removed, schedule);
+                if reason.is_some() {
+                    entry.failed_reason = reason;
+                }
+                Some(entry)
+            }
+        }
+    }
+
+    fn matches(&self, filter: &RecordingHistoryFilter) -> bool {
+        if let Some(since) = filter.since {
+            if self.time < since {
+                return false;
+            }
+        }
+        if let Some(until) = filter.until {
+            if self.time >= until {
+                return false;
+            }
+        }
+        if let Some(ref tag) = filter.tag {
+            if !self.tags.contains(tag) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum RecordingHistoryEvent {
+    // A schedule was added.
+    Added,
+    // The state of a schedule was changed.
+    Updated,
+    // A schedule was removed before the recording finished.
+    Removed,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RecordingHistoryFilter {
+    // Inclusive.
+    pub since: Option<DateTime<Jst>>,
+    // Exclusive.
+    pub until: Option<DateTime<Jst>>,
+    pub tag: Option<String>,
+}
+
+pub(super) fn append_history(path: &Path, entry: &RecordingHistoryEntry) -> Result<(), Error> {
+    // Serialize the entry in advance so that a broken line is never written.
+    let mut buf = serde_json::to_vec(entry)?;
+    buf.push(b'\n');
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    file.write_all(&buf)?;
+    Ok(())
+}
+
+pub(super) fn load_history(
+    path: &Path,
+    filter: &RecordingHistoryFilter,
+) -> Result<Vec<RecordingHistoryEntry>, Error> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+    let mut entries = vec![];
+    for (i, line) in file.lines().enumerate() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<RecordingHistoryEntry>(&line) {
+            Ok(entry) if entry.matches(filter) => entries.push(entry),
+            Ok(_) => (),
+            Err(err) => tracing::warn!(%err, ?path, line = i + 1, "Skip a broken entry"),
+        }
+    }
+    Ok(entries)
+}
+
+// <coverage:exclude>
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recording::RecordingOptions;
+    use assert_matches::assert_matches;
+    use chrono::Duration;
+    use maplit::hashset;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_append_and_load_history() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("history.jsonl");
+
+        let result = load_history(&path, &Default::default());
+        assert_matches!(result, Ok(entries) => {
+            assert!(entries.is_empty());
+        });
+
+        let schedule = RecordingSchedule {
+            state: RecordingScheduleState::Scheduled,
+            program: Arc::new(program!((0, 1, 1), now, "1h")),
+            options: RecordingOptions {
+                content_path: "1.m2ts".into(),
+                priority: 0,
+                pre_filters: vec![],
+                post_filters: vec![],
+                start_margin: None,
+                end_margin: None,
+            },
+            tags: hashset!["tag1".to_string()],
+            failed_reason: None,
+            relayed_from: None,
+            relayed_to: None,
+            content_path: None,
+        };
+
+        let mut entry = RecordingHistoryEntry::new(RecordingHistoryEvent::Added, &schedule);
+        entry.time = now - Duration::hours(1);
+        assert_matches!(append_history(&path, &entry), Ok(()));
+
+        let mut entry = RecordingHistoryEntry::new(RecordingHistoryEvent::Updated, &schedule);
+        entry.time = now;
+        entry.state = RecordingScheduleState::Failed;
+        entry.exit_code = Some(1);
+        entry.tags.clear();
+        assert_matches!(append_history(&path, &entry), Ok(()));
+
+        // Broken lines are skipped.
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"broken\n")
+            .unwrap();
+
+        let result = load_history(&path, &Default::default());
+        assert_matches!(result, Ok(entries) => {
+            assert_eq!(entries.len(), 2);
+            assert_eq!(entries[0].event, RecordingHistoryEvent::Added);
+            assert_eq!(entries[1].event, RecordingHistoryEvent::Updated);
+            assert_eq!(entries[1].exit_code, Some(1));
+        });
+
+        let filter = RecordingHistoryFilter {
+            since: Some(now - Duration::minutes(30)),
+            ..Default::default()
+        };
+        let result = load_history(&path, &filter);
+        assert_matches!(result, Ok(entries) => {
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].event, RecordingHistoryEvent::Updated);
+        });
+
+        let filter = RecordingHistoryFilter {
+            until: Some(now - Duration::minutes(30)),
+            ..Default::default()
+        };
+        let result = load_history(&path, &filter);
+        assert_matches!(result, Ok(entries) => {
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].event, RecordingHistoryEvent::Added);
+        });
+
+        let filter = RecordingHistoryFilter {
+            tag: Some("tag1".to_string()),
+            ..Default::default()
+        };
+        let result = load_history(&path, &filter);
+        assert_matches!(result, Ok(entries) => {
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].event, RecordingHistoryEvent::Added);
+        });
+    }
+}
+// </coverage:exclude>
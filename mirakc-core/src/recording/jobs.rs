@@ -0,0 +1,241 @@
+use std::path::Path;
+
+use chrono::DateTime;
+use chrono_jst::serde::ts_milliseconds;
+use chrono_jst::serde::ts_milliseconds_option;
+use chrono_jst::Jst;
+use serde::Deserialize;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::error::Error;
+use crate::models::ServiceId;
+
+use super::RecordingRecord;
+
+/// A post-processing job for a record.
+///
+/// A job is created for each command in `config.recording.jobs.commands` when
+/// a recording finished successfully.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingJob {
+    pub id: u32,
+    pub record_id: u32,
+    // The command rendered with the record.
+    pub command: String,
+    pub state: RecordingJobState,
+    // The number of attempts finished.  Interrupted attempts are not counted.
+    #[serde(default)]
+    pub attempts: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(with = "ts_milliseconds")]
+    pub created_at: DateTime<Jst>,
+    #[serde(default, with = "ts_milliseconds_option")]
+    pub retry_at: Option<DateTime<Jst>>,
+}
+
+impl RecordingJob {
+    pub(super) fn new(id: u32, record_id: u32, command: String) -> Self {
+        RecordingJob {
+            id,
+            record_id,
+            command,
+            state: RecordingJobState::Queued,
+            attempts: 0,
+            error: None,
+            created_at: Jst::now(),
+            retry_at: None,
+        }
+    }
+
+    pub(super) fn is_running(&self) -> bool {
+        matches!(self.state, RecordingJobState::Running)
+    }
+
+    pub(super) fn is_runnable(&self, now: DateTime<Jst>) -> bool {
+        if !matches!(self.state, RecordingJobState::Queued) {
+            return false;
+        }
+        match self.retry_at {
+            Some(retry_at) => retry_at <= now,
+            None => true,
+        }
+    }
+
+    pub(super) fn start(&mut self) {
+        self.state = RecordingJobState::Running;
+        self.retry_at = None;
+    }
+
+    // Jobs running when the process was terminated will be run again.
+    pub(super) fn reset(&mut self) {
+        if self.is_running() {
+            self.state = RecordingJobState::Queued;
+        }
+    }
+
+    // Returns `true` if the job will be retried.
+    pub(super) fn finish(
+        &mut self,
+        result: Result<(), String>,
+        max_retries: u32,
+        retry_at: DateTime<Jst>,
+    ) -> bool {
+        self.attempts += 1;
+        match result {
+            Ok(_) => {
+                self.state = RecordingJobState::Succeeded;
+                self.error = None;
+                false
+            }
+            Err(err) => {
+                self.error = Some(err);
+                if self.attempts > max_retries {
+                    self.state = RecordingJobState::Failed;
+                    false
+                } else {
+                    self.state = RecordingJobState::Queued;
+                    self.retry_at = Some(retry_at);
+                    true
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum RecordingJobState {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+// String values are quoted so that each of them can be used as a single
+// argument in the command.
+pub(super) fn render_job_command(
+    template: &str,
+    record: &RecordingRecord,
+    basedir: &Path,
+) -> Result<String, Error> {
+    let program = &record.program;
+    let data = mustache::MapBuilder::new()
+        .insert("record_id", &record.id)?
+        .insert("program_id", &program.id.value())?
+        .insert("service_id", &ServiceId::from(program.id).value())?
+        .insert("sid", &program.id.sid().value())?
+        .insert("eid", &program.id.eid().value())?
+        .insert_str(
+            "program_name",
+            quote(program.name.as_deref().unwrap_or_default()),
+        )
+        .insert_str("service_name", quote(&record.service.name))
+        .insert_str("channel_name", quote(&record.service.channel.name))
+        .insert("channel_type", &record.service.channel.channel_type)?
+        .insert_str(
+            "content_path",
+            quote(&record.content_path(basedir).to_string_lossy()),
+        )
+        .insert_str(
+            "start_time",
+            record.start_time.format("%Y%m%d%H%M").to_string(),
+        )
+        .insert_str("end_time", record.end_time.format("%Y%m%d%H%M").to_string())
+        .build();
+    let template = mustache::compile_str(template)?;
+    Ok(template.render_data_to_string(&data)?)
+}
+
+fn quote(value: &str) -> String {
+    shell_words::quote(value).into_owned()
+}
+
+// <coverage:exclude>
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use chrono::Duration;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_finish() {
+        let now = Jst::now();
+        let retry_at = now + Duration::minutes(1);
+
+        let mut job = RecordingJob::new(1, 1, "true".to_string());
+        assert!(job.is_runnable(now));
+
+        job.start();
+        assert!(job.is_running());
+        assert!(!job.is_runnable(now));
+        assert!(!job.finish(Ok(()), 1, retry_at));
+        assert_eq!(job.state, RecordingJobState::Succeeded);
+        assert_eq!(job.attempts, 1);
+
+        let mut job = RecordingJob::new(2, 1, "false".to_string());
+        job.start();
+        assert!(job.finish(Err("exit code: 1".to_string()), 1, retry_at));
+        assert_eq!(job.state, RecordingJobState::Queued);
+        assert_eq!(job.error, Some("exit code: 1".to_string()));
+        assert!(!job.is_runnable(now));
+        assert!(job.is_runnable(retry_at));
+
+        job.start();
+        assert!(!job.finish(Err("exit code: 1".to_string()), 1, retry_at));
+        assert_eq!(job.state, RecordingJobState::Failed);
+        assert_eq!(job.attempts, 2);
+        assert!(!job.is_runnable(retry_at));
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut job = RecordingJob::new(1, 1, "true".to_string());
+        job.start();
+        job.reset();
+        assert_eq!(job.state, RecordingJobState::Queued);
+        assert_eq!(job.attempts, 0);
+    }
+
+    #[test]
+    fn test_render_job_command() {
+        let now = Jst::now();
+        let mut program = program!((0, 1, 1), now, "1h");
+        program.name = Some("it's a test".to_string());
+        let record = RecordingRecord {
+            id: 1,
+            program: Arc::new(program),
+            service: service!((0, 1), "test", channel_gr!("test", "1")),
+            content_path: "a b.m2ts".into(),
+            start_time: now,
+            end_time: now,
+            size: 0,
+            failed_reason: None,
+            tags: Default::default(),
+            protected: false,
+            relayed_from: None,
+            relayed_to: None,
+            ts_stats: None,
+        };
+
+        let result = render_job_command(
+            "job {{record_id}} {{{program_name}}} {{{content_path}}}",
+            &record,
+            Path::new("/dir"),
+        );
+        assert_matches!(result, Ok(command) => {
+            assert_eq!(command, r#"job 1 'it'\''s a test' '/dir/a b.m2ts'"#);
+            assert_eq!(
+                shell_words::split(&command).unwrap(),
+                ["job", "1", "it's a test", "/dir/a b.m2ts"]
+            );
+        });
+
+        let result = render_job_command("{{", &record, Path::new("/dir"));
+        assert_matches!(result, Err(_));
+    }
+}
+// </coverage:exclude>
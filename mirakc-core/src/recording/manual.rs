@@ -0,0 +1,87 @@
+use chrono::DateTime;
+use chrono_jst::Jst;
+
+use crate::epg::EpgProgram;
+use crate::models::ProgramId;
+use crate::models::ServiceId;
+
+// Program IDs of manual schedules have "EIDs" out of the range of actual EIDs
+// so that they never conflict with actual TV programs.  The range is limited
+// by `ProgramId::MAGIC_NUMBER`.
+const MIN_MANUAL_EID: u64 = 0x10000;
+const MAX_MANUAL_EID: u64 = 99_999;
+
+pub(super) fn is_manual(program_id: ProgramId) -> bool {
+    let base = ProgramId::from((ServiceId::from(program_id), 0.into()));
+    program_id.value() - base.value() >= MIN_MANUAL_EID
+}
+
+// Returns the first program ID which is not used.
+pub(super) fn manual_program_id<F>(service_id: ServiceId, is_used: F) -> Option<ProgramId>
+where
+    F: Fn(ProgramId) -> bool,
+{
+    let base = ProgramId::from((service_id, 0.into())).value();
+    (MIN_MANUAL_EID..=MAX_MANUAL_EID)
+        .map(|eid| ProgramId::from(base + eid))
+        .find(|&program_id| !is_used(program_id))
+}
+
+// A pseudo TV program which has only the time range and the name.
+pub(super) fn manual_program(
+    program_id: ProgramId,
+    start_at: DateTime<Jst>,
+    end_at: DateTime<Jst>,
+    name: Option<String>,
+) -> EpgProgram {
+    let mut program = EpgProgram::new(program_id);
+    program.start_at = Some(start_at);
+    program.duration = Some(end_at - start_at);
+    program.name = name;
+    program
+}
+
+// <coverage:exclude>
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_is_manual() {
+        assert!(!is_manual((1, 2, 3).into()));
+        assert!(!is_manual((1, 2, 0xFFFF).into()));
+        let program_id = manual_program_id((1, 2).into(), |_| false).unwrap();
+        assert!(is_manual(program_id));
+        assert_eq!(ServiceId::from(program_id), (1, 2).into());
+    }
+
+    #[test]
+    fn test_manual_program_id() {
+        let first = manual_program_id((1, 2).into(), |_| false).unwrap();
+        let second = manual_program_id((1, 2).into(), |program_id| program_id == first).unwrap();
+        assert_ne!(first, second);
+        assert!(is_manual(second));
+        assert_eq!(ServiceId::from(second), (1, 2).into());
+
+        let result = manual_program_id((1, 2).into(), |_| true);
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn test_manual_program() {
+        let now = Jst::now();
+        let program_id = manual_program_id((1, 2).into(), |_| false).unwrap();
+        let program = manual_program(
+            program_id,
+            now,
+            now + Duration::hours(1),
+            Some("test".to_string()),
+        );
+        assert_eq!(program.id, program_id);
+        assert_eq!(program.start_at, Some(now));
+        assert_eq!(program.end_at(), Some(now + Duration::hours(1)));
+        assert_eq!(program.name.as_deref(), Some("test"));
+    }
+}
+// </coverage:exclude>
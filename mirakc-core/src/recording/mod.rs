@@ -0,0 +1,6856 @@
+mod conflicts;
+mod content_path;
+mod epgstation;
+mod history;
+mod jobs;
+mod manual;
+mod records;
+mod relay;
+mod rules;
+mod series;
+
+use std::collections::BTreeMap;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+use std::process::Stdio;
+use std::sync::Arc;
+
+use actlet::prelude::*;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono_jst::Jst;
+use indexmap::IndexMap;
+use itertools::Itertools;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::io::BufWriter;
+use tokio_util::sync::CancellationToken;
+use utoipa::ToSchema;
+
+use crate::command_util::spawn_pipeline;
+use crate::command_util::CommandBuilder;
+use crate::command_util::CommandPipeline;
+use crate::command_util::CommandPipelineProcessModel;
+use crate::config::Config;
+use crate::config::RecordingConfig;
+use crate::epg;
+use crate::epg::EpgProgram;
+use crate::epg::EpgService;
+use crate::epg::QueryClock;
+use crate::epg::QueryPrograms;
+use crate::epg::QueryService;
+use crate::epg::QueryServices;
+use crate::error::Error;
+use crate::file_util;
+use crate::filter::FilterPipelineBuilder;
+use crate::models::ProgramId;
+use crate::models::ServiceId;
+use crate::models::TunerUser;
+use crate::models::TunerUserInfo;
+use crate::onair;
+use crate::ts_stats::TsStats;
+use crate::ts_stats::TsStatsHandle;
+use crate::tuner::QueryTunerReservations;
+use crate::tuner::QueryTuners;
+use crate::tuner::StartStreaming;
+use crate::tuner::StopStreaming;
+use crate::tuner::TunerSubscriptionId;
+
+use jobs::render_job_command;
+use rules::RecordingRuleMatcher;
+
+pub use conflicts::RecordingConflict;
+pub use conflicts::RecordingConflictCompetitor;
+pub use conflicts::RecordingConflictReason;
+pub use epgstation::EpgstationReserve;
+pub use epgstation::EpgstationRule;
+pub use history::RecordingHistoryEntry;
+pub use history::RecordingHistoryEvent;
+pub use history::RecordingHistoryFilter;
+pub use jobs::RecordingJob;
+pub use jobs::RecordingJobState;
+pub use records::RecordingRecord;
+pub use records::RecordingRecordStream;
+pub use rules::RecordingRule;
+pub use rules::RecordingRuleCondition;
+pub use rules::RecordingRuleExclusion;
+pub use rules::RecordingRuleGenre;
+pub use rules::RecordingRuleTimeRange;
+pub use series::RecordingSeries;
+
+const EXIT_RETRY: i32 = 222;
+
+// Used for estimating the size of a content file when there is no record for
+// the service.  The value is the maximum bitrate of ISDB-S (about 24Mbps).
+const DEFAULT_BYTES_PER_SEC: u64 = 3_000_000;
+
+// chrono::Duration has no const function which can be used for defining
+// compile-time constants.
+const PREP_SECS: i64 = 15;
+const MAX_DELAY_HOURS: i64 = 15;
+
+pub struct RecordingManager<T, E, O> {
+    config: Arc<Config>,
+    tuner_manager: T,
+    epg: E,
+    onair_program_tracker: O,
+
+    // We use two types for managing recording schedules.  `std` provides
+    // `BTreeMap` for representing an ordered map, but it cannot provides the
+    // following two functionalities:
+    //
+    // * A priority queue sorted by the start time of each recording schedule
+    // * A hash map for a quick access to each recording schedule by a program
+    //   ID
+    queue: BinaryHeap<QueueItem>,
+    schedules: HashMap<ProgramId, RecordingSchedule>,
+    recorders: HashMap<ProgramId, Recorder>,
+    rules: BTreeMap<u32, RecordingRule>,
+    next_rule_id: u32,
+    series: BTreeMap<u32, RecordingSeries>,
+    next_series_id: u32,
+    records: BTreeMap<u32, RecordingRecord>,
+    next_record_id: u32,
+    jobs: BTreeMap<u32, RecordingJob>,
+    next_job_id: u32,
+    timer_token: Option<CancellationToken>,
+    job_timer_token: Option<CancellationToken>,
+
+    recording_started: EmitterRegistry<RecordingStarted>,
+    recording_stopped: EmitterRegistry<RecordingStopped>,
+    recording_failed: EmitterRegistry<RecordingFailed>,
+    recording_rescheduled: EmitterRegistry<RecordingRescheduled>,
+    recording_job_updated: EmitterRegistry<RecordingJobUpdated>,
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    pub fn new(config: Arc<Config>, tuner_manager: T, epg: E, onair_program_tracker: O) -> Self {
+        RecordingManager {
+            config,
+            tuner_manager,
+            epg,
+            onair_program_tracker,
+            queue: Default::default(),
+            schedules: Default::default(),
+            recorders: Default::default(),
+            rules: Default::default(),
+            next_rule_id: 1,
+            series: Default::default(),
+            next_series_id: 1,
+            records: Default::default(),
+            next_record_id: 1,
+            jobs: Default::default(),
+            next_job_id: 1,
+            timer_token: None,
+            job_timer_token: None,
+            recording_started: Default::default(),
+            recording_stopped: Default::default(),
+            recording_failed: Default::default(),
+            recording_rescheduled: Default::default(),
+            recording_job_updated: Default::default(),
+        }
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    fn load_schedules(&mut self) {
+        fn do_load(path: &Path) -> Result<Vec<RecordingSchedule>, Error> {
+            let file = std::fs::File::open(path)?;
+            Ok(serde_json::from_reader(file)?)
+        }
+
+        let basedir = match self.config.recording.basedir {
+            Some(ref basedir) => basedir,
+            None => return,
+        };
+
+        let path = basedir.join("schedules.json");
+        if !path.exists() {
+            return;
+        }
+
+        match do_load(&path) {
+            Ok(schedules) => {
+                tracing::info!(?path, "Loaded");
+                for schedule in schedules.into_iter() {
+                    let program_id = schedule.program.id;
+                    // Some of schedules may be expired, but `add_schedule()` doesn't check
+                    // that.
+                    match self.add_schedule(schedule) {
+                        Ok(_) => (),
+                        Err(err @ Error::AlreadyExists) => {
+                            // This error may happen when user changes schedules.json by hand.
+                            // We just output the warning message, ignore the schedule and
+                            // don't emit a RecordingFailed message.
+                            tracing::warn!(%err, %program_id, "Already added, ignore");
+                        }
+                        Err(_) => unreachable!(),
+                    }
+                }
+            }
+            Err(err) => {
+                tracing::warn!(%err, ?path, "Failed to load");
+            }
+        }
+    }
+
+    fn save_schedules(&self) {
+        let basedir = match self.config.recording.basedir {
+            Some(ref basedir) => basedir,
+            None => return,
+        };
+
+        let schedules = self.schedules.values().collect_vec();
+        if file_util::save_json(&schedules, basedir.join("schedules.json")) {
+            tracing::info!(schedules.len = schedules.len(), "Saved schedules");
+        } else {
+            tracing::error!("Failed to save schedules");
+        }
+    }
+
+    fn load_rules(&mut self) {
+        fn do_load(path: &Path) -> Result<NumberedItemsFile<RecordingRule>, Error> {
+            let file = std::fs::File::open(path)?;
+            Ok(serde_json::from_reader(file)?)
+        }
+
+        let basedir = match self.config.recording.basedir {
+            Some(ref basedir) => basedir,
+            None => return,
+        };
+
+        let path = basedir.join("rules.json");
+        if !path.exists() {
+            return;
+        }
+
+        match do_load(&path) {
+            Ok(file) => {
+                tracing::info!(?path, "Loaded");
+                let (next_id, rules) = file.into_parts(|rule| rule.id);
+                let now = Jst::now();
+                for mut rule in rules.into_iter() {
+                    rule.purge_exclusions(now);
+                    // Rules containing an invalid regular expression are kept
+                    // so that users can fix them, but they never match.
+                    if let Err(err) = RecordingRuleMatcher::new(&rule.condition) {
+                        tracing::warn!(%err, rule.id, "Invalid rule");
+                    }
+                    self.rules.insert(rule.id, rule);
+                }
+                self.next_rule_id = next_id;
+            }
+            Err(err) => {
+                tracing::warn!(%err, ?path, "Failed to load");
+            }
+        }
+    }
+
+    fn save_rules(&self) {
+        let basedir = match self.config.recording.basedir {
+            Some(ref basedir) => basedir,
+            None => return,
+        };
+
+        let rules = NumberedItems {
+            next_id: self.next_rule_id,
+            items: self.rules.values().collect_vec(),
+        };
+        if file_util::save_json(&rules, basedir.join("rules.json")) {
+            tracing::info!(rules.len = rules.items.len(), "Saved rules");
+        } else {
+            tracing::error!("Failed to save rules");
+        }
+    }
+
+    fn load_series(&mut self) {
+        fn do_load(path: &Path) -> Result<NumberedItemsFile<RecordingSeries>, Error> {
+            let file = std::fs::File::open(path)?;
+            Ok(serde_json::from_reader(file)?)
+        }
+
+        let basedir = match self.config.recording.basedir {
+            Some(ref basedir) => basedir,
+            None => return,
+        };
+
+        let path = basedir.join("series.json");
+        if !path.exists() {
+            return;
+        }
+
+        match do_load(&path) {
+            Ok(file) => {
+                tracing::info!(?path, "Loaded");
+                let (next_id, series) = file.into_parts(|series| series.id);
+                for series in series.into_iter() {
+                    self.series.insert(series.id, series);
+                }
+                self.next_series_id = next_id;
+            }
+            Err(err) => {
+                tracing::warn!(%err, ?path, "Failed to load");
+            }
+        }
+    }
+
+    fn save_series(&self) {
+        let basedir = match self.config.recording.basedir {
+            Some(ref basedir) => basedir,
+            None => return,
+        };
+
+        let series = NumberedItems {
+            next_id: self.next_series_id,
+            items: self.series.values().collect_vec(),
+        };
+        if file_util::save_json(&series, basedir.join("series.json")) {
+            tracing::info!(series.len = series.items.len(), "Saved series");
+        } else {
+            tracing::error!("Failed to save series");
+        }
+    }
+
+    fn load_records(&mut self) {
+        fn do_load(path: &Path) -> Result<NumberedItemsFile<RecordingRecord>, Error> {
+            let file = std::fs::File::open(path)?;
+            Ok(serde_json::from_reader(file)?)
+        }
+
+        let basedir = match self.config.recording.basedir {
+            Some(ref basedir) => basedir,
+            None => return,
+        };
+
+        let path = basedir.join("records.json");
+        if !path.exists() {
+            return;
+        }
+
+        match do_load(&path) {
+            Ok(file) => {
+                tracing::info!(?path, "Loaded");
+                let (next_id, records) = file.into_parts(|record| record.id);
+                for record in records.into_iter() {
+                    self.records.insert(record.id, record);
+                }
+                self.next_record_id = next_id;
+            }
+            Err(err) => {
+                tracing::warn!(%err, ?path, "Failed to load");
+            }
+        }
+    }
+
+    fn save_records(&self) {
+        let basedir = match self.config.recording.basedir {
+            Some(ref basedir) => basedir,
+            None => return,
+        };
+
+        let records = NumberedItems {
+            next_id: self.next_record_id,
+            items: self.records.values().collect_vec(),
+        };
+        if file_util::save_json(&records, basedir.join("records.json")) {
+            tracing::info!(records.len = records.items.len(), "Saved records");
+        } else {
+            tracing::error!("Failed to save records");
+        }
+    }
+
+    fn load_jobs(&mut self) {
+        fn do_load(path: &Path) -> Result<NumberedItemsFile<RecordingJob>, Error> {
+            let file = std::fs::File::open(path)?;
+            Ok(serde_json::from_reader(file)?)
+        }
+
+        let basedir = match self.config.recording.basedir {
+            Some(ref basedir) => basedir,
+            None => return,
+        };
+
+        let path = basedir.join("jobs.json");
+        if !path.exists() {
+            return;
+        }
+
+        match do_load(&path) {
+            Ok(file) => {
+                tracing::info!(?path, "Loaded");
+                let (next_id, jobs) = file.into_parts(|job| job.id);
+                for mut job in jobs.into_iter() {
+                    job.reset();
+                    self.jobs.insert(job.id, job);
+                }
+                self.next_job_id = next_id;
+            }
+            Err(err) => {
+                tracing::warn!(%err, ?path, "Failed to load");
+            }
+        }
+    }
+
+    fn save_jobs(&self) {
+        let basedir = match self.config.recording.basedir {
+            Some(ref basedir) => basedir,
+            None => return,
+        };
+
+        let jobs = NumberedItems {
+            next_id: self.next_job_id,
+            items: self.jobs.values().collect_vec(),
+        };
+        if file_util::save_json(&jobs, basedir.join("jobs.json")) {
+            tracing::info!(jobs.len = jobs.items.len(), "Saved jobs");
+        } else {
+            tracing::error!("Failed to save jobs");
+        }
+    }
+
+    fn append_history(&self, entry: RecordingHistoryEntry) {
+        let basedir = match self.config.recording.basedir {
+            Some(ref basedir) => basedir,
+            None => return,
+        };
+
+        let path = basedir.join("history.jsonl");
+        if let Err(err) = history::append_history(&path, &entry) {
+            tracing::error!(%err, ?path, "Failed to append history");
+        }
+    }
+
+    fn append_state_history(&self, program_id: ProgramId) {
+        if let Some(schedule) = self.schedules.get(&program_id) {
+            self.append_history(RecordingHistoryEntry::new(
+                RecordingHistoryEvent::Updated,
+                schedule,
+            ));
+        }
+    }
+
+    fn rebuild_queue(&mut self) {
+        self.queue.clear();
+        let schedules = self
+            .schedules
+            .values()
+            .filter(|schedule| schedule.is_ready_for_recording());
+        for schedule in schedules {
+            let mut start_at = schedule.start_time(&self.config.recording);
+            // Manual schedules are dequeued at the start time because there
+            // is no program-filter waiting for the start of the TV program.
+            if schedule.is_manual() {
+                start_at += Duration::seconds(PREP_SECS);
+            }
+            self.queue.push(QueueItem {
+                program_id: schedule.program.id,
+                start_at,
+                priority: schedule.options.priority,
+            });
+        }
+    }
+
+    fn set_timer<C>(&mut self, ctx: &C)
+    where
+        C: Spawn + EmitterFactory<ProcessRecording>,
+    {
+        if let Some(token) = self.timer_token.take() {
+            token.cancel();
+        }
+        if let Some(schedule) = self.queue.peek() {
+            let expires_at = schedule.start_at - Duration::seconds(PREP_SECS);
+            let duration = match (expires_at - Jst::now()).to_std() {
+                Ok(duration) => {
+                    tracing::debug!(%expires_at, "Set timer");
+                    duration
+                }
+                Err(_) => {
+                    tracing::warn!(
+                        %expires_at,
+                        "Preparation time too short, \
+                         the beginning of some programs may not be recorded",
+                    );
+                    std::time::Duration::ZERO
+                }
+            };
+            let emitter = ctx.emitter();
+            let token = ctx.spawn_task(async move {
+                tokio::time::sleep(duration).await;
+                emitter.emit(ProcessRecording).await;
+            });
+            self.timer_token = Some(token);
+        }
+    }
+}
+
+// The content of `rules.json`, `series.json`, `records.json` and `jobs.json`.
+//
+// `next_id` is saved so that IDs of removed items are never reused.
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NumberedItems<T> {
+    next_id: u32,
+    items: Vec<T>,
+}
+
+// Older versions saved only an array of items.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberedItemsFile<T> {
+    Numbered(NumberedItems<T>),
+    Array(Vec<T>),
+}
+
+impl<T> NumberedItemsFile<T> {
+    // Returns the next ID and the items.
+    fn into_parts<F>(self, id: F) -> (u32, Vec<T>)
+    where
+        F: Fn(&T) -> u32,
+    {
+        let (saved, items) = match self {
+            Self::Numbered(file) => (Some(file.next_id), file.items),
+            Self::Array(items) => (None, items),
+        };
+        let next_id = items.iter().map(id).max().map_or(1, |id| id + 1);
+        (saved.map_or(next_id, |saved| saved.max(next_id)), items)
+    }
+}
+
+// actor
+
+#[async_trait]
+impl<T, E, O> Actor for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn started(&mut self, ctx: &mut Context<Self>) {
+        tracing::debug!("Started");
+
+        if !self.config.recording.is_enabled() {
+            tracing::info!("Recording is disabled");
+            return;
+        }
+
+        self.epg
+            .call(epg::RegisterEmitter::ServicesUpdated(ctx.emitter()))
+            .await
+            .expect("Failed to register emitter for epg::ServicesUpdated");
+        self.epg
+            .call(epg::RegisterEmitter::ProgramsUpdated(ctx.emitter()))
+            .await
+            .expect("Failed to register emitter for epg::ProgramsUpdated");
+        self.onair_program_tracker
+            .call(onair::RegisterEmitter(ctx.emitter()))
+            .await
+            .expect("Failed to register emitter for OnairProgramUpdated");
+
+        self.load_schedules();
+        self.load_rules();
+        self.load_series();
+        self.load_records();
+        if self.apply_retention(0).await {
+            self.save_records();
+        }
+        self.load_jobs();
+        self.rebuild_queue();
+        self.set_timer(ctx);
+        self.dispatch_jobs(ctx).await;
+    }
+
+    async fn stopped(&mut self, _ctx: &mut Context<Self>) {
+        tracing::debug!("Stopped");
+    }
+}
+
+// reload config
+
+#[derive(Message)]
+#[reply()]
+pub struct ReloadConfig(pub Arc<Config>);
+
+#[async_trait]
+impl<T, E, O> Handler<ReloadConfig> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: ReloadConfig,
+        _ctx: &mut Context<Self>,
+    ) -> <ReloadConfig as Message>::Reply {
+        tracing::debug!(msg.name = "ReloadConfig");
+        // Only filters are applied.  Changes of other settings such as
+        // `config.recording` require restarting.  Pipelines of recorders
+        // running are not affected.
+        let mut config = (*self.config).clone();
+        config.filters = msg.0.filters.clone();
+        config.pre_filters = msg.0.pre_filters.clone();
+        config.post_filters = msg.0.post_filters.clone();
+        self.config = Arc::new(config);
+    }
+}
+
+// query recording schedules
+
+#[derive(Message)]
+#[reply(Vec<RecordingSchedule>)]
+pub struct QueryRecordingSchedules;
+
+#[async_trait]
+impl<T, E, O> Handler<QueryRecordingSchedules> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        _msg: QueryRecordingSchedules,
+        _ctx: &mut Context<Self>,
+    ) -> <QueryRecordingSchedules as Message>::Reply {
+        tracing::debug!(msg.name = "QueryRecordingSchedules");
+        self.query_schedules()
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    fn query_schedules(&self) -> Vec<RecordingSchedule> {
+        self.schedules
+            .values()
+            .sorted_by(|s1, s2| {
+                let e1 = QueueItem {
+                    program_id: s1.program.id,
+                    start_at: s1.program.start_at.unwrap(),
+                    priority: s1.options.priority,
+                };
+                let e2 = QueueItem {
+                    program_id: s2.program.id,
+                    start_at: s2.program.start_at.unwrap(),
+                    priority: s2.options.priority,
+                };
+                e1.cmp(&e2).reverse()
+            })
+            .cloned()
+            .collect_vec()
+    }
+}
+
+// query recording schedule
+
+#[derive(Message)]
+#[reply(Result<RecordingSchedule, Error>)]
+pub struct QueryRecordingSchedule {
+    pub program_id: ProgramId,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<QueryRecordingSchedule> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: QueryRecordingSchedule,
+        _ctx: &mut Context<Self>,
+    ) -> <QueryRecordingSchedule as Message>::Reply {
+        tracing::debug!(msg.name = "QueryRecordingSchedule", %msg.program_id);
+        self.query_schedule(msg.program_id)
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    fn query_schedule(&self, program_id: ProgramId) -> Result<RecordingSchedule, Error> {
+        self.schedules
+            .get(&program_id)
+            .cloned()
+            .ok_or(Error::ScheduleNotFound)
+    }
+}
+
+// add recording schedule
+
+#[derive(Message)]
+#[reply(Result<RecordingSchedule, Error>)]
+pub struct AddRecordingSchedule {
+    pub schedule: RecordingSchedule,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<AddRecordingSchedule> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::QueryOnairProgram>,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: AddRecordingSchedule,
+        ctx: &mut Context<Self>,
+    ) -> <AddRecordingSchedule as Message>::Reply {
+        tracing::debug!(
+            msg.name = "AddRecordingSchedule",
+            %msg.schedule.program.id,
+            ?msg.schedule.options.content_path,
+            %msg.schedule.options.priority,
+            ?msg.schedule.options.pre_filters,
+            ?msg.schedule.options.post_filters,
+        );
+        let program_id = msg.schedule.program.id;
+        self.add_schedule(msg.schedule)?;
+        // TODO: `schedule` should be updated before adding it.
+        self.update_schedule_with_onair_program(program_id.into())
+            .await;
+        self.save_schedules();
+        self.rebuild_queue();
+        self.set_timer(ctx);
+        self.query_schedule(program_id)
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O>
+where
+    O: Call<onair::QueryOnairProgram>,
+{
+    async fn update_schedule_with_onair_program(&mut self, service_id: ServiceId) {
+        let msg = onair::QueryOnairProgram { service_id };
+        match self.onair_program_tracker.call(msg).await {
+            Ok(Ok(onair_program)) => {
+                if let Some(program) = onair_program.current {
+                    // The start time in EIT[p/f] may be undefined.
+                    if program.start_at.is_some() {
+                        self.update_schedule_by_onair_program(program).await;
+                    }
+                }
+                if let Some(program) = onair_program.next {
+                    // The start time in EIT[p/f] may be undefined.
+                    if program.start_at.is_some() {
+                        self.update_schedule_by_onair_program(program).await;
+                    }
+                }
+            }
+            Ok(Err(_)) => {
+                // No tracker, nothing to do.
+            }
+            Err(err) => {
+                tracing::error!(
+                    %err,
+                    %service_id,
+                    "Failed to get on-air program",
+                );
+            }
+        }
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    fn add_schedule(&mut self, schedule: RecordingSchedule) -> Result<(), Error> {
+        let program_id = schedule.program.id;
+
+        if self.schedules.contains_key(&program_id) {
+            let err = Error::AlreadyExists;
+            tracing::warn!(%err, %schedule.program.id);
+            return Err(err);
+        }
+
+        // We don't check the start and end times of the TV program here and
+        // simply add a schedule for it.  Additional checks will be performed
+        // in later stages.
+        self.append_history(RecordingHistoryEntry::new(
+            RecordingHistoryEvent::Added,
+            &schedule,
+        ));
+        self.schedules.insert(program_id, schedule);
+        tracing::info!(
+            schedule.program.id = %program_id,
+            "Added",
+        );
+
+        Ok(())
+    }
+}
+
+// add manual recording schedule
+
+#[derive(Message)]
+#[reply(Result<RecordingSchedule, Error>)]
+pub struct AddManualRecordingSchedule {
+    pub service_id: ServiceId,
+    pub start_time: DateTime<Jst>,
+    pub end_time: DateTime<Jst>,
+    pub name: Option<String>,
+    pub options: RecordingOptions,
+    pub tags: HashSet<String>,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<AddManualRecordingSchedule> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: AddManualRecordingSchedule,
+        ctx: &mut Context<Self>,
+    ) -> <AddManualRecordingSchedule as Message>::Reply {
+        tracing::debug!(
+            msg.name = "AddManualRecordingSchedule",
+            %msg.service_id,
+            %msg.start_time,
+            %msg.end_time,
+            ?msg.options.content_path,
+            %msg.options.priority,
+        );
+        let schedule = self.add_manual_schedule(msg, Jst::now())?;
+        self.save_schedules();
+        self.rebuild_queue();
+        self.set_timer(ctx);
+        Ok(schedule)
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    fn add_manual_schedule(
+        &mut self,
+        msg: AddManualRecordingSchedule,
+        now: DateTime<Jst>,
+    ) -> Result<RecordingSchedule, Error> {
+        if msg.start_time >= msg.end_time {
+            let err = Error::InvalidTimeRange;
+            tracing::error!(%err, %msg.start_time, %msg.end_time);
+            return Err(err);
+        }
+        if msg.end_time <= now {
+            let err = Error::ProgramEnded;
+            tracing::error!(%err, %msg.end_time);
+            return Err(err);
+        }
+        let program_id = manual::manual_program_id(msg.service_id, |program_id| {
+            self.schedules.contains_key(&program_id)
+        })
+        .ok_or(Error::AlreadyExists)?;
+        let program = manual::manual_program(program_id, msg.start_time, msg.end_time, msg.name);
+        let schedule = RecordingSchedule::new(Arc::new(program), msg.options, msg.tags);
+        self.add_schedule(schedule.clone())?;
+        Ok(schedule)
+    }
+}
+
+// update recording schedule
+
+#[derive(Message)]
+#[reply(Result<RecordingSchedule, Error>)]
+pub struct UpdateRecordingSchedule {
+    pub program_id: ProgramId,
+    pub options: Option<RecordingOptions>,
+    pub tags: Option<HashSet<String>>,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<UpdateRecordingSchedule> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: UpdateRecordingSchedule,
+        ctx: &mut Context<Self>,
+    ) -> <UpdateRecordingSchedule as Message>::Reply {
+        tracing::debug!(
+            msg.name = "UpdateRecordingSchedule",
+            %msg.program_id,
+            ?msg.options,
+            ?msg.tags,
+        );
+        let schedule = self.update_schedule(msg.program_id, msg.options, msg.tags)?;
+        self.save_schedules();
+        // The priority and margins affect the order of the queue.
+        self.rebuild_queue();
+        self.set_timer(ctx);
+        Ok(schedule)
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    // Options can be updated only before the recording starts because the
+    // recording pipeline has already been built from them.  Tags can be
+    // updated until the recording stops.
+    fn update_schedule(
+        &mut self,
+        program_id: ProgramId,
+        options: Option<RecordingOptions>,
+        tags: Option<HashSet<String>>,
+    ) -> Result<RecordingSchedule, Error> {
+        use RecordingScheduleState::*;
+
+        let schedule = self
+            .schedules
+            .get_mut(&program_id)
+            .ok_or(Error::ScheduleNotFound)?;
+
+        let updatable = match schedule.state {
+            Scheduled | Tracking | Rescheduling => true,
+            Recording => options.is_none(),
+            Finished | Failed => false,
+        };
+        if !updatable {
+            let err = Error::InvalidScheduleState;
+            tracing::warn!(%err, %schedule.program.id, ?schedule.state);
+            return Err(err);
+        }
+
+        if let Some(options) = options {
+            schedule.options = options;
+        }
+        if let Some(tags) = tags {
+            schedule.tags = tags;
+        }
+        tracing::info!(%schedule.program.id, "Updated");
+        let schedule = schedule.clone();
+        self.append_history(RecordingHistoryEntry::new(
+            RecordingHistoryEvent::Updated,
+            &schedule,
+        ));
+        Ok(schedule)
+    }
+}
+
+// remove recording schedule
+
+#[derive(Message)]
+#[reply(Result<RecordingSchedule, Error>)]
+pub struct RemoveRecordingSchedule {
+    pub program_id: ProgramId,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<RemoveRecordingSchedule> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: RemoveRecordingSchedule,
+        ctx: &mut Context<Self>,
+    ) -> <RemoveRecordingSchedule as Message>::Reply {
+        tracing::debug!(msg.name = "RemoveRecordingSchedule", %msg.program_id);
+        let (schedule, rules_changed) = self.remove_schedule(msg.program_id, Jst::now())?;
+        if rules_changed {
+            self.save_rules();
+        }
+        self.save_schedules();
+        self.rebuild_queue();
+        self.set_timer(ctx);
+        Ok(schedule)
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    // Returns the removed schedule and whether the rules have been changed.
+    fn remove_schedule(
+        &mut self,
+        program_id: ProgramId,
+        now: DateTime<Jst>,
+    ) -> Result<(RecordingSchedule, bool), Error> {
+        match self.schedules.remove(&program_id) {
+            Some(schedule) => {
+                if schedule.is_recording() {
+                    let _ = self.stop_recorder(program_id);
+                }
+                if let Some(entry) = RecordingHistoryEntry::removed(&schedule, None) {
+                    self.append_history(entry);
+                }
+                let rules_changed = self.exclude_from_rules(&schedule.program, now);
+                Ok((schedule, rules_changed))
+            }
+            None => {
+                tracing::warn!(
+                    schedule.program.id = %program_id,
+                    "No such schedule added",
+                );
+                Err(Error::ScheduleNotFound)
+            }
+        }
+    }
+
+    fn stop_recorder(&mut self, program_id: ProgramId) -> Result<(), Error> {
+        match self.recorders.get_mut(&program_id) {
+            Some(recorder) => {
+                match recorder.stop_trigger.take() {
+                    Some(_) => {
+                        tracing::info!(
+                            schedule.program.id = %program_id,
+                            recorder.pipeline.id = %recorder.pipeline.id(),
+                            "Stop recorder pipeline",
+                        );
+                    }
+                    None => {
+                        tracing::warn!(
+                            schedule.program.id = %program_id,
+                            recorder.pipeline.id = %recorder.pipeline.id(),
+                            "Recorder pipeline already stopped",
+                        );
+                    }
+                }
+                Ok(())
+            }
+            None => {
+                tracing::error!(
+                    schedule.program.id = %program_id,
+                    "No such recorder found, maybe already stopped",
+                );
+                Err(Error::RecorderNotFound)
+            }
+        }
+    }
+
+    // Prevents rules from adding a schedule removed by a user again.
+    //
+    // Returns `true` if the rules have been changed.
+    fn exclude_from_rules(&mut self, program: &EpgProgram, now: DateTime<Jst>) -> bool {
+        let mut changed = false;
+        for rule in self.rules.values_mut() {
+            if rule.purge_exclusions(now) {
+                changed = true;
+            }
+            // Disabled rules also exclude the TV program because they may be
+            // enabled later.
+            let matched = RecordingRuleMatcher::new(&rule.condition)
+                .is_ok_and(|matcher| matcher.matches(program));
+            if matched && rule.exclude(program) {
+                tracing::info!(rule.id, %program.id, "Excluded");
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+// remove recording schedules
+
+#[derive(Message)]
+#[reply()]
+pub struct RemoveRecordingSchedules {
+    pub target: RemovalTarget,
+}
+
+#[derive(Debug)]
+pub enum RemovalTarget {
+    All,
+    Tag(String),
+}
+
+#[async_trait]
+impl<T, E, O> Handler<RemoveRecordingSchedules> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: RemoveRecordingSchedules,
+        ctx: &mut Context<Self>,
+    ) -> <RemoveRecordingSchedules as Message>::Reply {
+        tracing::debug!(msg.name = "RemoveRecordingSchedules", ?msg.target);
+        self.remove_schedules(msg.target, Jst::now());
+        self.save_schedules();
+        self.rebuild_queue();
+        self.set_timer(ctx);
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    fn remove_schedules(&mut self, target: RemovalTarget, now: DateTime<Jst>) {
+        match target {
+            RemovalTarget::All => self.clear_schedules(),
+            RemovalTarget::Tag(tag) => self.remove_schedules_by_tag(&tag, now),
+        }
+    }
+
+    fn clear_schedules(&mut self) {
+        tracing::info!("Remove all schedules");
+        let schedules = std::mem::replace(&mut self.schedules, Default::default());
+        for schedule in schedules.into_values() {
+            if schedule.is_recording() {
+                let _ = self.stop_recorder(schedule.program.id);
+            }
+            if let Some(entry) = RecordingHistoryEntry::removed(&schedule, None) {
+                self.append_history(entry);
+            }
+        }
+    }
+
+    fn remove_schedules_by_tag(&mut self, tag: &str, now: DateTime<Jst>) {
+        use RecordingScheduleState::*;
+
+        // No notification message will be emitted.
+        // Users know what they are doing.
+
+        let prep_time = Duration::seconds(PREP_SECS);
+        let config = &self.config.recording;
+        let mut entries = vec![];
+        tracing::info!(tag, "Remove tagged schedules");
+        self.schedules.retain(|_, schedule| {
+            if !schedule.tags.contains(tag) {
+                return true;
+            }
+            // Schedules in "Tracking" and "Recording" are retained.
+            let retained = match schedule.state {
+                Scheduled => {
+                    let start_time = schedule.start_time(config);
+                    // Always retain schedules which will start soon
+                    // (or have already started).
+                    start_time - now <= prep_time
+                }
+                Tracking | Recording => {
+                    // Always retained.
+                    true
+                }
+                Rescheduling | Finished | Failed => {
+                    // Always removed.
+                    false
+                }
+            };
+            if !retained {
+                entries.extend(RecordingHistoryEntry::removed(schedule, None));
+            }
+            retained
+        });
+        for entry in entries.into_iter() {
+            self.append_history(entry);
+        }
+    }
+}
+
+// query recording rules
+
+#[derive(Message)]
+#[reply(Vec<RecordingRule>)]
+pub struct QueryRecordingRules;
+
+#[async_trait]
+impl<T, E, O> Handler<QueryRecordingRules> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        _msg: QueryRecordingRules,
+        _ctx: &mut Context<Self>,
+    ) -> <QueryRecordingRules as Message>::Reply {
+        tracing::debug!(msg.name = "QueryRecordingRules");
+        self.rules.values().cloned().collect()
+    }
+}
+
+// query recording rule
+
+#[derive(Message)]
+#[reply(Result<RecordingRule, Error>)]
+pub struct QueryRecordingRule {
+    pub id: u32,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<QueryRecordingRule> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: QueryRecordingRule,
+        _ctx: &mut Context<Self>,
+    ) -> <QueryRecordingRule as Message>::Reply {
+        tracing::debug!(msg.name = "QueryRecordingRule", msg.id);
+        self.rules.get(&msg.id).cloned().ok_or(Error::RuleNotFound)
+    }
+}
+
+// add recording rule
+
+#[derive(Message)]
+#[reply(Result<RecordingRule, Error>)]
+pub struct AddRecordingRule {
+    // `rule.id` is ignored.  A new ID will be assigned.
+    pub rule: RecordingRule,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<AddRecordingRule> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: AddRecordingRule,
+        ctx: &mut Context<Self>,
+    ) -> <AddRecordingRule as Message>::Reply {
+        tracing::debug!(msg.name = "AddRecordingRule", ?msg.rule.condition);
+        let rule = self.add_rule(msg.rule)?;
+        self.save_rules();
+        if self.apply_rules_to_all_services(Jst::now()).await {
+            self.save_schedules();
+            self.rebuild_queue();
+            self.set_timer(ctx);
+        }
+        Ok(rule)
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    fn add_rule(&mut self, mut rule: RecordingRule) -> Result<RecordingRule, Error> {
+        rule.validate()?;
+        // IDs of removed rules are never reused.
+        rule.id = self.next_rule_id;
+        self.next_rule_id += 1;
+        self.rules.insert(rule.id, rule.clone());
+        tracing::info!(rule.id, "Added rule");
+        Ok(rule)
+    }
+}
+
+// update recording rule
+
+#[derive(Message)]
+#[reply(Result<RecordingRule, Error>)]
+pub struct UpdateRecordingRule {
+    pub rule: RecordingRule,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<UpdateRecordingRule> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: UpdateRecordingRule,
+        ctx: &mut Context<Self>,
+    ) -> <UpdateRecordingRule as Message>::Reply {
+        tracing::debug!(msg.name = "UpdateRecordingRule", msg.rule.id, ?msg.rule.condition);
+        let rule = self.update_rule(msg.rule)?;
+        self.save_rules();
+        if self.apply_rules_to_all_services(Jst::now()).await {
+            self.save_schedules();
+            self.rebuild_queue();
+            self.set_timer(ctx);
+        }
+        Ok(rule)
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    fn update_rule(&mut self, mut rule: RecordingRule) -> Result<RecordingRule, Error> {
+        rule.validate()?;
+        // Schedules already added by the rule are not changed.
+        match self.rules.get_mut(&rule.id) {
+            Some(entry) => {
+                // TV programs removed by users are still excluded.
+                rule.excluded_programs = std::mem::take(&mut entry.excluded_programs);
+                *entry = rule.clone();
+                tracing::info!(rule.id, "Updated rule");
+                Ok(rule)
+            }
+            None => Err(Error::RuleNotFound),
+        }
+    }
+}
+
+// remove recording rule
+
+#[derive(Message)]
+#[reply(Result<RecordingRule, Error>)]
+pub struct RemoveRecordingRule {
+    pub id: u32,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<RemoveRecordingRule> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: RemoveRecordingRule,
+        _ctx: &mut Context<Self>,
+    ) -> <RemoveRecordingRule as Message>::Reply {
+        tracing::debug!(msg.name = "RemoveRecordingRule", msg.id);
+        // Schedules already added by the rule are not removed.
+        match self.rules.remove(&msg.id) {
+            Some(rule) => {
+                tracing::info!(rule.id, "Removed rule");
+                self.save_rules();
+                Ok(rule)
+            }
+            None => Err(Error::RuleNotFound),
+        }
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O>
+where
+    E: Call<QueryPrograms>,
+    E: Call<QueryServices>,
+{
+    async fn apply_rules_to_all_services(&mut self, now: DateTime<Jst>) -> bool {
+        if self.rules.is_empty() {
+            return false;
+        }
+
+        let services = match self.epg.call(QueryServices).await {
+            Ok(services) => services,
+            Err(err) => {
+                tracing::error!(%err, "Failed to apply rules");
+                return false;
+            }
+        };
+
+        let mut changed = false;
+        for &service_id in services.keys() {
+            if self.apply_rules_to_service(now, service_id).await {
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    async fn apply_rules_to_service(&mut self, now: DateTime<Jst>, service_id: ServiceId) -> bool {
+        if self.rules.values().all(|rule| rule.disabled) {
+            return false;
+        }
+
+        let programs = match self.epg.call(QueryPrograms { service_id }).await {
+            Ok(programs) => programs,
+            Err(err) => {
+                tracing::error!(%err, %service_id, "Failed to apply rules");
+                return false;
+            }
+        };
+
+        self.apply_rules(now, programs.values())
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    fn apply_rules<'a, I>(&mut self, now: DateTime<Jst>, programs: I) -> bool
+    where
+        I: Iterator<Item = &'a EpgProgram>,
+    {
+        let matchers = self
+            .rules
+            .values()
+            .filter(|rule| !rule.disabled)
+            .filter_map(|rule| {
+                RecordingRuleMatcher::new(&rule.condition)
+                    .ok()
+                    .map(|matcher| (rule, matcher))
+            })
+            .collect_vec();
+
+        let mut schedules = vec![];
+        for program in programs {
+            // TV programs already started are ignored.
+            match program.start_at {
+                Some(start_at) if start_at > now => (),
+                _ => continue,
+            }
+            if self.schedules.contains_key(&program.id) {
+                continue;
+            }
+            // The first matched rule is used.
+            let rule = match matchers
+                .iter()
+                .find(|(rule, matcher)| !rule.is_excluded(program.id) && matcher.matches(program))
+            {
+                Some((rule, _)) => rule,
+                None => continue,
+            };
+            tracing::info!(rule.id, %program.id, "Matched");
+            schedules.push(rule.create_schedule(program));
+        }
+
+        let changed = !schedules.is_empty();
+        for schedule in schedules.into_iter() {
+            // Never fails because we checked it in the loop above.
+            let _ = self.add_schedule(schedule);
+        }
+        changed
+    }
+}
+
+// query recording series list
+
+#[derive(Message)]
+#[reply(Vec<RecordingSeries>)]
+pub struct QueryRecordingSeriesList;
+
+#[async_trait]
+impl<T, E, O> Handler<QueryRecordingSeriesList> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        _msg: QueryRecordingSeriesList,
+        _ctx: &mut Context<Self>,
+    ) -> <QueryRecordingSeriesList as Message>::Reply {
+        tracing::debug!(msg.name = "QueryRecordingSeriesList");
+        self.series.values().cloned().collect()
+    }
+}
+
+// query recording series
+
+#[derive(Message)]
+#[reply(Result<RecordingSeries, Error>)]
+pub struct QueryRecordingSeries {
+    pub id: u32,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<QueryRecordingSeries> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: QueryRecordingSeries,
+        _ctx: &mut Context<Self>,
+    ) -> <QueryRecordingSeries as Message>::Reply {
+        tracing::debug!(msg.name = "QueryRecordingSeries", msg.id);
+        self.series
+            .get(&msg.id)
+            .cloned()
+            .ok_or(Error::SeriesNotFound)
+    }
+}
+
+// add recording series
+
+#[derive(Message)]
+#[reply(Result<RecordingSeries, Error>)]
+pub struct AddRecordingSeries {
+    // A TV program in the series.
+    pub program_id: ProgramId,
+    pub options: RecordingOptions,
+    pub tags: HashSet<String>,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<AddRecordingSeries> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: AddRecordingSeries,
+        ctx: &mut Context<Self>,
+    ) -> <AddRecordingSeries as Message>::Reply {
+        tracing::debug!(msg.name = "AddRecordingSeries", %msg.program_id);
+        let service_id = msg.program_id.into();
+        let programs = self.epg.call(QueryPrograms { service_id }).await?;
+        let program = programs
+            .get(&msg.program_id.eid())
+            .ok_or(Error::ProgramNotFound)?;
+        let series = self.add_series(program, msg.options, msg.tags)?;
+        self.save_series();
+        if self.apply_series(Jst::now(), programs.values()) {
+            self.save_schedules();
+            self.rebuild_queue();
+            self.set_timer(ctx);
+        }
+        // The series may be updated in `apply_series()`.
+        Ok(self.series.get(&series.id).cloned().unwrap_or(series))
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    fn add_series(
+        &mut self,
+        program: &EpgProgram,
+        options: RecordingOptions,
+        tags: HashSet<String>,
+    ) -> Result<RecordingSeries, Error> {
+        let series = RecordingSeries::new(self.next_series_id, program, options, tags)?;
+        let found = self.series.values().any(|entry| {
+            entry.service_id == series.service_id && entry.series_id == series.series_id
+        });
+        if found {
+            return Err(Error::AlreadyExists);
+        }
+        // IDs of removed series are never reused.
+        self.next_series_id += 1;
+        self.series.insert(series.id, series.clone());
+        tracing::info!(series.id, %series.service_id, series.series_id, "Added series");
+        Ok(series)
+    }
+}
+
+// remove recording series
+
+#[derive(Message)]
+#[reply(Result<RecordingSeries, Error>)]
+pub struct RemoveRecordingSeries {
+    pub id: u32,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<RemoveRecordingSeries> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: RemoveRecordingSeries,
+        _ctx: &mut Context<Self>,
+    ) -> <RemoveRecordingSeries as Message>::Reply {
+        tracing::debug!(msg.name = "RemoveRecordingSeries", msg.id);
+        // Schedules already added for the series are not removed.
+        match self.series.remove(&msg.id) {
+            Some(series) => {
+                tracing::info!(series.id, "Removed series");
+                self.save_series();
+                Ok(series)
+            }
+            None => Err(Error::SeriesNotFound),
+        }
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O>
+where
+    E: Call<QueryPrograms>,
+{
+    async fn apply_series_to_service(&mut self, now: DateTime<Jst>, service_id: ServiceId) -> bool {
+        if !self
+            .series
+            .values()
+            .any(|series| series.service_id == service_id)
+        {
+            return false;
+        }
+
+        let programs = match self.epg.call(QueryPrograms { service_id }).await {
+            Ok(programs) => programs,
+            Err(err) => {
+                tracing::error!(%err, %service_id, "Failed to apply series");
+                return false;
+            }
+        };
+
+        self.apply_series(now, programs.values())
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    fn apply_series<'a, I>(&mut self, now: DateTime<Jst>, programs: I) -> bool
+    where
+        I: Iterator<Item = &'a EpgProgram>,
+    {
+        let mut series_changed = false;
+        let mut schedules = vec![];
+
+        // Episodes are processed in chronological order so that the first
+        // broadcast of each episode is scheduled rather than its rebroadcasts.
+        let programs = programs
+            .filter(|program| program.series.is_some())
+            .sorted_by_key(|program| program.start_at);
+        for program in programs {
+            let series = match self
+                .series
+                .values_mut()
+                .find(|series| series.matches(program))
+            {
+                Some(series) => series,
+                None => continue,
+            };
+            if series.update(program.series.as_ref().unwrap()) {
+                series_changed = true;
+            }
+            if self.schedules.contains_key(&program.id) {
+                // The episode may have been scheduled by other ways.
+                if series.add_episode(program) {
+                    series_changed = true;
+                }
+                continue;
+            }
+            // TV programs already started are ignored.
+            match program.start_at {
+                Some(start_at) if start_at > now => (),
+                _ => continue,
+            }
+            if series.is_expired(now) || series.is_completed() {
+                continue;
+            }
+            if !series.add_episode(program) {
+                tracing::debug!(series.id, %program.id, "Already scheduled, skip");
+                continue;
+            }
+            series_changed = true;
+            tracing::info!(series.id, %program.id, "Found new episode");
+            schedules.push(series.create_schedule(program));
+        }
+
+        self.series.retain(|_, series| {
+            if series.is_expired(now) {
+                tracing::info!(series.id, "Expired, remove");
+                series_changed = true;
+                false
+            } else if series.is_completed() {
+                tracing::info!(series.id, "Completed, remove");
+                series_changed = true;
+                false
+            } else {
+                true
+            }
+        });
+
+        if series_changed {
+            self.save_series();
+        }
+
+        let changed = !schedules.is_empty();
+        for schedule in schedules.into_iter() {
+            // Never fails because we checked it in the loop above.
+            let _ = self.add_schedule(schedule);
+        }
+        changed
+    }
+}
+
+// query recording records
+
+#[derive(Message)]
+#[reply(Vec<RecordingRecord>)]
+pub struct QueryRecordingRecords;
+
+#[async_trait]
+impl<T, E, O> Handler<QueryRecordingRecords> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        _msg: QueryRecordingRecords,
+        _ctx: &mut Context<Self>,
+    ) -> <QueryRecordingRecords as Message>::Reply {
+        tracing::debug!(msg.name = "QueryRecordingRecords");
+        self.records.values().cloned().collect()
+    }
+}
+
+// query recording record
+
+#[derive(Message)]
+#[reply(Result<RecordingRecord, Error>)]
+pub struct QueryRecordingRecord {
+    pub id: u32,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<QueryRecordingRecord> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: QueryRecordingRecord,
+        _ctx: &mut Context<Self>,
+    ) -> <QueryRecordingRecord as Message>::Reply {
+        tracing::debug!(msg.name = "QueryRecordingRecord", msg.id);
+        self.records
+            .get(&msg.id)
+            .cloned()
+            .ok_or(Error::RecordNotFound)
+    }
+}
+
+// remove recording record
+
+#[derive(Message)]
+#[reply(Result<RecordingRecord, Error>)]
+pub struct RemoveRecordingRecord {
+    pub id: u32,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<RemoveRecordingRecord> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: RemoveRecordingRecord,
+        _ctx: &mut Context<Self>,
+    ) -> <RemoveRecordingRecord as Message>::Reply {
+        tracing::debug!(msg.name = "RemoveRecordingRecord", msg.id);
+        let record = self.remove_record(msg.id).await?;
+        self.save_records();
+        Ok(record)
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    async fn remove_record(&mut self, id: u32) -> Result<RecordingRecord, Error> {
+        let record = self.records.remove(&id).ok_or(Error::RecordNotFound)?;
+        tracing::info!(record.id, "Removed record");
+        // The content file is also removed.
+        if let Some(ref basedir) = self.config.recording.basedir {
+            let content_path = record.content_path(basedir);
+            match tokio::fs::remove_file(&content_path).await {
+                Ok(_) => tracing::info!(record.id, ?content_path, "Removed content"),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    tracing::warn!(record.id, ?content_path, "No content found");
+                }
+                Err(err) => {
+                    tracing::error!(%err, record.id, ?content_path, "Failed to remove content");
+                }
+            }
+        }
+        // Jobs for the record are no longer needed.  Running jobs are kept
+        // until they finish.
+        let len = self.jobs.len();
+        self.jobs
+            .retain(|_, job| job.record_id != record.id || job.is_running());
+        if self.jobs.len() != len {
+            self.save_jobs();
+        }
+        Ok(record)
+    }
+}
+
+// update recording record
+
+#[derive(Message)]
+#[reply(Result<RecordingRecord, Error>)]
+pub struct UpdateRecordingRecord {
+    pub id: u32,
+    pub protected: Option<bool>,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<UpdateRecordingRecord> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: UpdateRecordingRecord,
+        _ctx: &mut Context<Self>,
+    ) -> <UpdateRecordingRecord as Message>::Reply {
+        tracing::debug!(msg.name = "UpdateRecordingRecord", msg.id, ?msg.protected);
+        let record = self.update_record(msg.id, msg.protected)?;
+        self.save_records();
+        Ok(record)
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    fn update_record(
+        &mut self,
+        id: u32,
+        protected: Option<bool>,
+    ) -> Result<RecordingRecord, Error> {
+        let record = self.records.get_mut(&id).ok_or(Error::RecordNotFound)?;
+        if let Some(protected) = protected {
+            record.protected = protected;
+        }
+        tracing::info!(record.id, record.protected, "Updated record");
+        Ok(record.clone())
+    }
+
+    fn total_size(&self) -> u64 {
+        self.records.values().map(|record| record.size).sum()
+    }
+
+    fn needs_space(&self, required: u64) -> bool {
+        let config = &self.config.recording;
+        if let Some(max_total_size) = config.max_total_size {
+            if self.total_size() + required > max_total_size {
+                return true;
+            }
+        }
+        if let Some(min_free_space) = config.min_free_space {
+            let basedir = config.basedir.as_ref().unwrap();
+            match file_util::get_free_space(basedir) {
+                Ok(free_space) if free_space < min_free_space + required => return true,
+                Ok(_) => (),
+                Err(err) => tracing::warn!(%err, ?basedir, "Failed to get free space"),
+            }
+        }
+        false
+    }
+
+    // Removes the oldest unprotected records until the storage has enough space
+    // for `required` bytes.  Returns `true` if some records are removed.
+    async fn apply_retention(&mut self, required: u64) -> bool {
+        let mut changed = false;
+        while self.needs_space(required) {
+            let oldest = self
+                .records
+                .values()
+                .filter(|record| !record.protected)
+                .min_by_key(|record| (record.end_time, record.id))
+                .map(|record| record.id);
+            let id = match oldest {
+                Some(id) => id,
+                None => {
+                    tracing::warn!("No record can be removed");
+                    break;
+                }
+            };
+            tracing::info!(record.id = id, "Remove the oldest record for retention");
+            let _ = self.remove_record(id).await;
+            changed = true;
+        }
+        changed
+    }
+
+    // Estimates the size of the content file from records of the same service.
+    fn estimate_content_size(&self, schedule: &RecordingSchedule) -> u64 {
+        let service_id = ServiceId::from(schedule.program.id);
+        let (size, secs) = self
+            .records
+            .values()
+            .filter(|record| record.service.id == service_id)
+            .map(|record| {
+                (
+                    record.size,
+                    (record.end_time - record.start_time).num_seconds(),
+                )
+            })
+            .filter(|&(_, secs)| secs > 0)
+            .fold((0, 0), |(size, secs), (s, t)| (size + s, secs + t as u64));
+        let bytes_per_sec = size.checked_div(secs).unwrap_or(DEFAULT_BYTES_PER_SEC);
+        let config = &self.config.recording;
+        let secs = match (schedule.start_time(config), schedule.end_time(config)) {
+            (start_time, Some(end_time)) => (end_time - start_time).num_seconds().max(0) as u64,
+            _ => 0,
+        };
+        bytes_per_sec * secs
+    }
+}
+
+// query recording jobs
+
+#[derive(Message)]
+#[reply(Vec<RecordingJob>)]
+pub struct QueryRecordingJobs;
+
+#[async_trait]
+impl<T, E, O> Handler<QueryRecordingJobs> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        _msg: QueryRecordingJobs,
+        _ctx: &mut Context<Self>,
+    ) -> <QueryRecordingJobs as Message>::Reply {
+        tracing::debug!(msg.name = "QueryRecordingJobs");
+        self.jobs.values().cloned().collect()
+    }
+}
+
+// query recording job
+
+#[derive(Message)]
+#[reply(Result<RecordingJob, Error>)]
+pub struct QueryRecordingJob {
+    pub id: u32,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<QueryRecordingJob> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: QueryRecordingJob,
+        _ctx: &mut Context<Self>,
+    ) -> <QueryRecordingJob as Message>::Reply {
+        tracing::debug!(msg.name = "QueryRecordingJob", msg.id);
+        self.jobs.get(&msg.id).cloned().ok_or(Error::JobNotFound)
+    }
+}
+
+// query recording history
+
+#[derive(Message)]
+#[reply(Result<Vec<RecordingHistoryEntry>, Error>)]
+pub struct QueryRecordingHistory {
+    pub filter: RecordingHistoryFilter,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<QueryRecordingHistory> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: QueryRecordingHistory,
+        _ctx: &mut Context<Self>,
+    ) -> <QueryRecordingHistory as Message>::Reply {
+        tracing::debug!(msg.name = "QueryRecordingHistory", ?msg.filter);
+        match self.config.recording.basedir {
+            Some(ref basedir) => history::load_history(&basedir.join("history.jsonl"), &msg.filter),
+            None => Ok(vec![]),
+        }
+    }
+}
+
+// query recording conflicts
+
+#[derive(Message)]
+#[reply(Result<Vec<RecordingConflict>, Error>)]
+pub struct QueryRecordingConflicts;
+
+#[async_trait]
+impl<T, E, O> Handler<QueryRecordingConflicts> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<QueryTunerReservations>,
+    T: Call<QueryTuners>,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        _msg: QueryRecordingConflicts,
+        _ctx: &mut Context<Self>,
+    ) -> <QueryRecordingConflicts as Message>::Reply {
+        tracing::debug!(msg.name = "QueryRecordingConflicts");
+        let services = self.epg.call(QueryServices).await?;
+        let reservations = self.tuner_manager.call(QueryTunerReservations).await?;
+        let maintenance = self
+            .tuner_manager
+            .call(QueryTuners)
+            .await?
+            .into_iter()
+            .filter(|tuner| tuner.is_maintenance)
+            .map(|tuner| tuner.name)
+            .collect();
+        Ok(conflicts::predict_conflicts(
+            &self.config,
+            self.schedules.values(),
+            &services,
+            &reservations,
+            &maintenance,
+        ))
+    }
+}
+
+// query recording recorders
+
+#[derive(Message)]
+#[reply(Vec<RecorderModel>)]
+pub struct QueryRecordingRecorders;
+
+#[async_trait]
+impl<T, E, O> Handler<QueryRecordingRecorders> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        _msg: QueryRecordingRecorders,
+        _ctx: &mut Context<Self>,
+    ) -> <QueryRecordingRecorders as Message>::Reply {
+        tracing::debug!(msg.name = "QueryRecordingRecorders");
+        self.recorders
+            .iter()
+            .map(|(&program_id, recorder)| recorder.get_model(program_id))
+            .collect()
+    }
+}
+
+// query recording recorder
+
+#[derive(Message)]
+#[reply(Result<RecorderModel, Error>)]
+pub struct QueryRecordingRecorder {
+    pub program_id: ProgramId,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<QueryRecordingRecorder> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: QueryRecordingRecorder,
+        _ctx: &mut Context<Self>,
+    ) -> <QueryRecordingRecorder as Message>::Reply {
+        tracing::debug!(msg.name = "QueryRecordingRecorder", %msg.program_id);
+        self.recorders
+            .get(&msg.program_id)
+            .map(|recorder| recorder.get_model(msg.program_id))
+            .ok_or(Error::RecorderNotFound)
+    }
+}
+
+// start recording
+
+#[derive(Message)]
+#[reply(Result<(), Error>)]
+pub struct StartRecording {
+    pub schedule: RecordingSchedule,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<StartRecording> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: StartRecording,
+        ctx: &mut Context<Self>,
+    ) -> <StartRecording as Message>::Reply {
+        tracing::debug!(
+            msg.name = "StartRecording",
+            %msg.schedule.program.id,
+            ?msg.schedule.options.content_path,
+            %msg.schedule.options.priority,
+            ?msg.schedule.options.pre_filters,
+            ?msg.schedule.options.post_filters,
+        );
+        let program_id = msg.schedule.program.id;
+        self.add_schedule(msg.schedule)?;
+        self.start_recording(program_id, ctx.address().clone(), ctx)
+            .await;
+        self.save_schedules();
+        Ok(())
+    }
+}
+
+// stop recording
+
+#[derive(Message)]
+#[reply(Result<(), Error>)]
+pub struct StopRecording {
+    pub program_id: ProgramId,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<StopRecording> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: StopRecording,
+        _ctx: &mut Context<Self>,
+    ) -> <StopRecording as Message>::Reply {
+        tracing::debug!(
+            msg.name = "StopRecording",
+            %msg.program_id,
+        );
+        // This function doesn't wait for the recorder to stop completely.
+        //
+        // Dropping the stop trigger will stop the tuner pipeline and this
+        // will cause EOF in the recording pipeline.  A `RecordingStopped`
+        // signal will be emitted, and then it will be handled in the handler
+        // as usual.
+        self.recorders
+            .get_mut(&msg.program_id)
+            .ok_or(Error::RecorderNotFound)?
+            .stop_trigger
+            .take();
+        Ok(())
+    }
+}
+
+// process recording
+
+#[derive(Message)]
+struct ProcessRecording;
+
+#[async_trait]
+impl<T, E, O> Handler<ProcessRecording> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(&mut self, _msg: ProcessRecording, ctx: &mut Context<Self>) {
+        tracing::debug!(msg.name = "ProcessRecording");
+        let now = Jst::now();
+
+        let mut changed = self.maintain_schedules(now).await;
+        if changed {
+            self.rebuild_queue();
+        }
+
+        let program_ids = self.dequeue_next_schedules(now);
+        if !program_ids.is_empty() {
+            changed = true;
+        }
+
+        for program_id in program_ids.into_iter() {
+            self.start_recording(program_id, ctx.address().clone(), ctx)
+                .await;
+        }
+
+        self.set_timer(ctx);
+        if changed {
+            self.save_schedules();
+        }
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    async fn maintain_schedules(&mut self, now: DateTime<Jst>) -> bool {
+        use RecordingScheduleState::*;
+
+        let max_delay = Duration::hours(MAX_DELAY_HOURS);
+
+        let len = self.schedules.len();
+        let mut expired = vec![];
+        let mut entries = vec![];
+
+        self.schedules.retain(|_, schedule| {
+            if schedule.is_recording() {
+                return true;
+            }
+            if schedule.program.start_at.unwrap() + max_delay > now {
+                return true;
+            }
+            match schedule.state {
+                Scheduled | Tracking | Rescheduling => {
+                    tracing::error!(
+                        %schedule.program.id,
+                        "Schedule expired",
+                    );
+                    expired.push(schedule.program.id);
+                    entries.extend(RecordingHistoryEntry::removed(
+                        schedule,
+                        Some(RecordingFailedReason::ScheduleExpired),
+                    ));
+                }
+                _ => {
+                    tracing::debug!(
+                        %schedule.program.id,
+                        "Removed old schedule for maintenance"
+                    );
+                }
+            }
+            false
+        });
+
+        for entry in entries.into_iter() {
+            self.append_history(entry);
+        }
+
+        for &program_id in expired.iter() {
+            self.emit_recording_failed(program_id, RecordingFailedReason::ScheduleExpired)
+                .await;
+        }
+
+        self.schedules.len() != len
+    }
+
+    fn dequeue_next_schedules(&mut self, now: DateTime<Jst>) -> Vec<ProgramId> {
+        let mut program_ids = vec![];
+        let prep_secs = Duration::seconds(PREP_SECS);
+        while let Some(schedule) = self.queue.peek() {
+            if schedule.start_at - now <= prep_secs {
+                let schedule = self.queue.pop().unwrap();
+                debug_assert!(self.schedules.contains_key(&schedule.program_id));
+                program_ids.push(schedule.program_id);
+            } else {
+                break;
+            }
+        }
+        program_ids
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn start_recording<C: Spawn>(
+        &mut self,
+        program_id: ProgramId,
+        addr: Address<Self>,
+        ctx: &C,
+    ) {
+        let required = match self.schedules.get(&program_id) {
+            Some(schedule) => self.estimate_content_size(schedule),
+            None => 0,
+        };
+        if self.apply_retention(required).await {
+            self.save_records();
+        }
+        if self.needs_space(required) {
+            tracing::error!(
+                schedule.program.id = %program_id,
+                required,
+                "Insufficient space for recording",
+            );
+            let reason = RecordingFailedReason::InsufficientSpace { required };
+            if let Some(schedule) = self.schedules.get_mut(&program_id) {
+                schedule.state = RecordingScheduleState::Failed;
+                schedule.failed_reason = Some(reason.clone());
+            }
+            self.append_state_history(program_id);
+            self.emit_recording_failed(program_id, reason).await;
+            return;
+        }
+
+        match self.do_start_recording(program_id, addr, ctx).await {
+            Ok(_) => {
+                tracing::info!(
+                    schedule.program.id = %program_id,
+                    "Start recording",
+                );
+            }
+            Err(err) => {
+                tracing::error!(
+                    %err,
+                    schedule.program.id = %program_id,
+                    "Failed to start recording",
+                );
+                let reason = RecordingFailedReason::StartRecordingFailed {
+                    message: format!("{}", err),
+                };
+                if let Some(schedule) = self.schedules.get_mut(&program_id) {
+                    schedule.state = RecordingScheduleState::Failed;
+                    schedule.failed_reason = Some(reason.clone());
+                }
+                self.append_state_history(program_id);
+                self.emit_recording_failed(program_id, reason).await;
+            }
+        }
+    }
+
+    async fn do_start_recording<C: Spawn>(
+        &mut self,
+        program_id: ProgramId,
+        addr: Address<Self>,
+        ctx: &C,
+    ) -> Result<(), Error> {
+        if self.recorders.contains_key(&program_id) {
+            return Err(Error::AlreadyExists);
+        }
+
+        // TODO: copied from web::program_stream_g().
+
+        let service_id = program_id.into();
+        let schedule = self.schedules.get(&program_id).unwrap();
+
+        let service = self.epg.call(QueryService { service_id }).await??;
+        let clock = self.epg.call(QueryClock { service_id }).await??;
+
+        // The content path is determined only once so that the same file is
+        // used when the recording is retried.
+        let relative_path = match schedule.content_path {
+            Some(ref content_path) => content_path.clone(),
+            None => self.make_content_path(schedule, &service)?,
+        };
+
+        let user = TunerUser {
+            info: TunerUserInfo::Recorder {
+                name: format!("program#{}", program_id),
+            },
+            priority: schedule.options.priority.into(),
+        };
+
+        // Share the tuner with the recording of the preceding TV program in
+        // order to keep the tuner owned at the boundary.
+        //
+        // The tuner manager also reuses an active tuner tuned to the same
+        // channel, but it may select another one when two or more tuners are
+        // tuned to the channel.  Specifying the stream makes sure that the
+        // recording subscribes the same `Broadcaster` as the preceding one.
+        // The tuner manager applies the same checks as the normal allocation,
+        // such as tuner reservations and roles, and another tuner is used if
+        // the check fails.
+        let handover_stream_id = self.find_handover_stream(schedule);
+        let result = self
+            .tuner_manager
+            .call(StartStreaming {
+                channel: service.channel.clone(),
+                user: user.clone(),
+                stream_id: handover_stream_id,
+            })
+            .await?;
+        let stream = match result {
+            Ok(stream) => {
+                if let Some(stream_id) = handover_stream_id {
+                    tracing::info!(schedule.program.id = %program_id, stream.id = %stream_id, "Took over tuner from preceding recording");
+                }
+                stream
+            }
+            // The preceding recording may have stopped streaming.
+            Err(Error::TunerUnavailable) if handover_stream_id.is_some() => {
+                tracing::warn!(schedule.program.id = %program_id, "Failed to take over tuner, try another tuner");
+                self.tuner_manager
+                    .call(StartStreaming {
+                        channel: service.channel.clone(),
+                        user,
+                        stream_id: None,
+                    })
+                    .await??
+            }
+            Err(err) => return Err(err),
+        };
+
+        // stop_trigger must be created here in order to stop streaming when
+        // an error occurs.
+        let msg = StopStreaming { id: stream.id() };
+        let stop_trigger = self.tuner_manager.trigger(msg);
+
+        let ts_stats = stream.ts_stats();
+        let ts_stats_base = ts_stats.get();
+
+        let video_tags: Vec<u8> = schedule
+            .program
+            .video
+            .iter()
+            .map(|video| video.component_tag)
+            .collect();
+
+        let audio_tags: Vec<u8> = schedule
+            .program
+            .audios
+            .values()
+            .map(|audio| audio.component_tag)
+            .collect();
+
+        let mut builder = mustache::MapBuilder::new();
+        builder = builder
+            .insert_str("channel_name", &service.channel.name)
+            .insert("channel_type", &service.channel.channel_type)?
+            .insert_str("channel", &service.channel.channel)
+            .insert("sid", &program_id.sid().value())?
+            .insert("eid", &program_id.eid().value())?
+            .insert("clock_pid", &clock.pid)?
+            .insert("clock_pcr", &clock.pcr)?
+            .insert("clock_time", &clock.time)?
+            .insert("video_tags", &video_tags)?
+            .insert("audio_tags", &audio_tags)?
+            .insert(
+                "start_margin",
+                &schedule.options.start_margin(&self.config.recording),
+            )?
+            .insert(
+                "end_margin",
+                &schedule.options.end_margin(&self.config.recording),
+            )?;
+        let data = builder.build();
+
+        let mut builder = FilterPipelineBuilder::new(data);
+        builder.add_pre_filters(&self.config.pre_filters, &schedule.options.pre_filters)?;
+        if !stream.is_decoded() {
+            builder.add_decode_filter(&self.config.filters.decode_filter)?;
+        }
+        if schedule.is_manual() {
+            builder.add_service_filter(&self.config.filters.service_filter)?;
+        } else {
+            builder.add_program_filter(&self.config.filters.program_filter)?;
+        }
+        builder.add_post_filters(&self.config.post_filters, &schedule.options.post_filters)?;
+        let (filters, _) = builder.build();
+
+        let basedir = self.config.recording.basedir.as_ref().unwrap();
+
+        let content_path = resolve_content_path(basedir, &relative_path);
+        // We assumed that schedule.content_path has already been normalized.
+        if let Some(dir) = content_path.parent() {
+            // Create missing directories if they don't exist.
+            tokio::fs::create_dir_all(dir).await?;
+        }
+
+        let mut pipeline = spawn_pipeline(filters, stream.id(), "recording")?;
+        let (input, mut output) = pipeline.take_endpoints();
+
+        let fut = async move {
+            let _ = stream.pipe(input).await;
+        };
+        ctx.spawn_task(fut);
+
+        // Inner future in order to capture the result in an outer future.
+        let inner_fut = {
+            let content_path = content_path.clone();
+            async move {
+                let record = tokio::fs::File::create(&content_path).await?;
+                let mut writer = BufWriter::new(record);
+                // TODO: use Stdio
+                Ok::<_, std::io::Error>(tokio::io::copy(&mut output, &mut writer).await?)
+            }
+        };
+        // The service-filter doesn't stop by itself.  So, manual schedules
+        // are stopped by a timer.
+        let stop_timer = match schedule.end_time(&self.config.recording) {
+            Some(end_time) if schedule.is_manual() => {
+                let duration = (end_time - Jst::now()).to_std().unwrap_or_default();
+                let addr = addr.clone();
+                Some(async move {
+                    tokio::time::sleep(duration).await;
+                    tracing::info!(schedule.program.id = %program_id, "Stop manual recording");
+                    let _ = addr.call(StopRecording { program_id }).await;
+                })
+            }
+            _ => None,
+        };
+
+        // Outer future emits messages to observers.
+        let fut = {
+            async move {
+                addr.emit(RecordingStarted { program_id }).await;
+                let _stopped = addr.trigger(RecordingStopped { program_id });
+                let result = inner_fut.await;
+                if let Err(err) = result {
+                    addr.emit(RecordingFailed {
+                        program_id,
+                        reason: RecordingFailedReason::IoError {
+                            message: format!("{}", err),
+                            os_error: err.raw_os_error(),
+                        },
+                        ts_stats: None,
+                    })
+                    .await;
+                }
+            }
+        };
+
+        let recorder = Recorder {
+            started_at: Jst::now(),
+            service,
+            pipeline,
+            stop_trigger: Some(stop_trigger),
+            ts_stats,
+            ts_stats_base,
+        };
+        let tuner_index = recorder.pipeline.id().tuner_index();
+        self.recorders.insert(program_id, recorder);
+        let schedule = self.schedules.get_mut(&program_id).unwrap();
+        schedule.state = RecordingScheduleState::Recording;
+        schedule.content_path = Some(relative_path);
+        let mut entry = RecordingHistoryEntry::new(RecordingHistoryEvent::Updated, schedule);
+        entry.tuner_index = Some(tuner_index);
+        self.append_history(entry);
+
+        // Spawn the following task after the recorder is inserted so that
+        // actors receiving RecordingStarted messages can access the recorder.
+        ctx.spawn_task(fut);
+        if let Some(stop_timer) = stop_timer {
+            ctx.spawn_task(stop_timer);
+        }
+
+        Ok(())
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    // Returns the ID of the tuner subscription used in the recording of the
+    // preceding TV program on the same service if the recording is still
+    // running when the next recording starts.
+    fn find_handover_stream(&self, schedule: &RecordingSchedule) -> Option<TunerSubscriptionId> {
+        let service_id = ServiceId::from(schedule.program.id);
+        let start_at = schedule.program.start_at?;
+        let start_time = schedule.start_time(&self.config.recording);
+        self.recorders
+            .iter()
+            .filter(|(&program_id, _)| program_id != schedule.program.id)
+            .filter(|(&program_id, _)| ServiceId::from(program_id) == service_id)
+            .filter_map(|(program_id, recorder)| {
+                self.schedules
+                    .get(program_id)
+                    .map(|preceding| (preceding, recorder))
+            })
+            .filter(|(preceding, _)| {
+                matches!(preceding.program.end_at(), Some(end_at) if end_at <= start_at)
+            })
+            .filter(|(preceding, _)| {
+                matches!(preceding.end_time(&self.config.recording), Some(end_time) if end_time >= start_time)
+            })
+            .map(|(_, recorder)| *recorder.pipeline.id())
+            .next()
+    }
+
+    // Renders the template of the content path and resolves a collision with
+    // an existing file or a file used in another recording.
+    fn make_content_path(
+        &self,
+        schedule: &RecordingSchedule,
+        service: &EpgService,
+    ) -> Result<PathBuf, Error> {
+        let basedir = self.config.recording.basedir.as_ref().unwrap();
+        let template = schedule
+            .options
+            .content_path_template(&self.config.recording);
+        let content_path = content_path::render_content_path(template, &schedule.program, service)?;
+        if let Err(err) = content_path::validate_content_path(basedir, &content_path) {
+            tracing::error!(%err, %schedule.program.id, ?content_path, "Invalid content path");
+            return Err(err);
+        }
+        let is_used = |path: &Path| {
+            resolve_content_path(basedir, path).exists()
+                || self
+                    .schedules
+                    .values()
+                    .any(|schedule| schedule.content_path.as_deref() == Some(path))
+                || self
+                    .records
+                    .values()
+                    .any(|record| record.content_path == path)
+        };
+        match content_path::resolve_collision(&content_path, is_used) {
+            Some(resolved) => {
+                if resolved != content_path {
+                    tracing::warn!(
+                        %schedule.program.id,
+                        ?content_path,
+                        ?resolved,
+                        "Content path already used, renamed",
+                    );
+                }
+                Ok(resolved)
+            }
+            None => {
+                let err = Error::AlreadyExists;
+                tracing::error!(%err, %schedule.program.id, ?content_path);
+                Err(err)
+            }
+        }
+    }
+}
+
+// register emitter
+
+#[derive(Message)]
+#[reply(usize)]
+pub enum RegisterEmitter {
+    RecordingStarted(Emitter<RecordingStarted>),
+    RecordingStopped(Emitter<RecordingStopped>),
+    RecordingFailed(Emitter<RecordingFailed>),
+    RecordingRescheduled(Emitter<RecordingRescheduled>),
+    RecordingJobUpdated(Emitter<RecordingJobUpdated>),
+}
+
+#[async_trait]
+impl<T, E, O> Handler<RegisterEmitter> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: RegisterEmitter,
+        ctx: &mut Context<Self>,
+    ) -> <RegisterEmitter as Message>::Reply {
+        match msg {
+            RegisterEmitter::RecordingStarted(emitter) => {
+                let id = self.recording_started.register(emitter.clone());
+                tracing::debug!(msg.name = "RegisterEmitter::RecordingStarted", id);
+                if id != 0 {
+                    // Sending many messages in the message handler may cause a dead lock
+                    // when the number of messages to be sent is larger than the capacity
+                    // of the emitter's channel.  See the issue #705 for example.
+                    ctx.set_post_process(RegisterEmitterPostProcess(emitter));
+                }
+                id
+            }
+            RegisterEmitter::RecordingStopped(emitter) => {
+                let id = self.recording_stopped.register(emitter);
+                tracing::debug!(msg.name = "RegisterEmitter::RecordingStopped", id);
+                id
+            }
+            RegisterEmitter::RecordingFailed(emitter) => {
+                let id = self.recording_failed.register(emitter);
+                tracing::debug!(msg.name = "RegisterEmitter::RecordingFailed", id);
+                id
+            }
+            RegisterEmitter::RecordingRescheduled(emitter) => {
+                let id = self.recording_rescheduled.register(emitter);
+                tracing::debug!(msg.name = "RegisterEmitter::RecordingRescheduled", id);
+                id
+            }
+            RegisterEmitter::RecordingJobUpdated(emitter) => {
+                let id = self.recording_job_updated.register(emitter);
+                tracing::debug!(msg.name = "RegisterEmitter::RecordingJobUpdated", id);
+                id
+            }
+        }
+    }
+}
+
+#[derive(Message)]
+pub struct RegisterEmitterPostProcess(Emitter<RecordingStarted>);
+
+#[async_trait]
+impl<T, E, O> Handler<RegisterEmitterPostProcess> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(&mut self, msg: RegisterEmitterPostProcess, _ctx: &mut Context<Self>) {
+        let emitter = msg.0;
+        for &program_id in self.recorders.keys() {
+            let msg = RecordingStarted { program_id };
+            emitter.emit(msg).await;
+        }
+    }
+}
+
+// unregister emitter
+
+#[derive(Message)]
+pub enum UnregisterEmitter {
+    RecordingStarted(usize),
+    RecordingStopped(usize),
+    RecordingFailed(usize),
+    RecordingRescheduled(usize),
+    RecordingJobUpdated(usize),
+}
+
+#[async_trait]
+impl<T, E, O> Handler<UnregisterEmitter> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(&mut self, msg: UnregisterEmitter, _ctx: &mut Context<Self>) {
+        match msg {
+            UnregisterEmitter::RecordingStarted(id) => {
+                tracing::debug!(msg.name = "UnregisterEmitter::RecordingStarted", id);
+                self.recording_started.unregister(id);
+            }
+            UnregisterEmitter::RecordingStopped(id) => {
+                tracing::debug!(msg.name = "UnregisterEmitter::RecordingStopped", id);
+                self.recording_stopped.unregister(id);
+            }
+            UnregisterEmitter::RecordingFailed(id) => {
+                tracing::debug!(msg.name = "UnregisterEmitter::RecordingFailed", id);
+                self.recording_failed.unregister(id);
+            }
+            UnregisterEmitter::RecordingRescheduled(id) => {
+                tracing::debug!(msg.name = "UnregisterEmitter::RecordingRescheduled", id);
+                self.recording_rescheduled.unregister(id);
+            }
+            UnregisterEmitter::RecordingJobUpdated(id) => {
+                tracing::debug!(msg.name = "UnregisterEmitter::RecordingJobUpdated", id);
+                self.recording_job_updated.unregister(id);
+            }
+        }
+    }
+}
+
+// recording started
+
+#[derive(Clone, Message)]
+pub struct RecordingStarted {
+    pub program_id: ProgramId,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<RecordingStarted> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(&mut self, msg: RecordingStarted, _ctx: &mut Context<Self>) {
+        tracing::debug!(msg.name = "RecordingStarted", %msg.program_id);
+        self.handle_recording_started(msg.program_id).await;
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    async fn handle_recording_started(&self, program_id: ProgramId) {
+        tracing::info!(
+            schedule.program.id = %program_id,
+            "Recording started",
+        );
+        let msg = RecordingStarted { program_id };
+        self.recording_started.emit(msg).await;
+    }
+}
+
+// recording stopped
+
+#[derive(Clone, Message)]
+pub struct RecordingStopped {
+    pub program_id: ProgramId,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<RecordingStopped> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(&mut self, msg: RecordingStopped, ctx: &mut Context<Self>) {
+        tracing::debug!(msg.name = "RecordingStopped", %msg.program_id);
+        let changed = self.handle_recording_stopped(msg.program_id).await;
+        if changed {
+            self.save_schedules();
+            self.rebuild_queue();
+            self.set_timer(ctx);
+        }
+        self.dispatch_jobs(ctx).await;
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O>
+where
+    E: Call<QueryPrograms>,
+{
+    async fn handle_recording_stopped(&mut self, program_id: ProgramId) -> bool {
+        // The schedule is NOT removed for a while so that an external script
+        // listening recording events can access the details of the schedule.
+        //
+        // It will be removed in the `ProcessRecording` message handler.
+
+        // The schedule may have been removed before the recording stops.
+        // For example, `clear_schedules()` clears schedules before the
+        // recordings stop.
+        let maybe_schedule = self.schedules.get_mut(&program_id);
+
+        let mut changed = false;
+
+        // Unlike the schedule, the recorder should be removed after the
+        // recording stopped.
+        let recorder = self.recorders.remove(&program_id);
+        match recorder {
+            Some(mut recorder) => {
+                // Manually drop the stop trigger so that we get the exit code
+                // from the program-filter without killing its process.
+                if let Some(stop_trigger) = recorder.stop_trigger.take() {
+                    drop(stop_trigger);
+                }
+
+                let results = recorder.pipeline.wait().await;
+                let ts_stats = recorder.ts_stats();
+                tracing::info!(
+                    schedule.program.id = %program_id,
+                    ts_stats.packets,
+                    ts_stats.sync_losses,
+                    ts_stats.scrambled_packets,
+                    ts_stats.error_packets,
+                    ts_stats.cc_drops,
+                    ts_stats.bitrate,
+                    "TS packet statistics",
+                );
+                let retry = check_retry(&results);
+                if retry {
+                    tracing::error!(
+                        schedule.program.id = %program_id,
+                        "Recording stopped before the TV program starts",
+                    );
+                    if let Some(mut schedule) = maybe_schedule {
+                        tracing::warn!(
+                            %schedule.program.id,
+                            "Need rescheduling",
+                        );
+                        schedule.state = RecordingScheduleState::Rescheduling;
+                        changed = true;
+                        self.emit_recording_failed_with_ts_stats(
+                            program_id,
+                            RecordingFailedReason::NeedRescheduling,
+                            Some(ts_stats.clone()),
+                        )
+                        .await;
+                    }
+                } else if let Some(exit_code) = get_first_error(&results) {
+                    tracing::error!(
+                        %exit_code,
+                        schedule.program.id = %program_id,
+                        "The recording pipeline terminated abnormally",
+                    );
+                    let mut reason = RecordingFailedReason::PipelineError { exit_code };
+                    if let Some(mut schedule) = maybe_schedule {
+                        schedule.state = RecordingScheduleState::Failed;
+                        // Keep the preemption which is the root cause.
+                        match schedule.failed_reason {
+                            Some(ref preempted @ RecordingFailedReason::Preempted { .. }) => {
+                                reason = preempted.clone();
+                            }
+                            _ => schedule.failed_reason = Some(reason.clone()),
+                        }
+                        changed = true;
+                    }
+                    self.emit_recording_failed_with_ts_stats(
+                        program_id,
+                        reason,
+                        Some(ts_stats.clone()),
+                    )
+                    .await;
+                } else {
+                    tracing::info!(
+                        schedule.program.id = %program_id,
+                        "The recording finished successfully",
+                    );
+                    let mut preempted = None;
+                    if let Some(mut schedule) = maybe_schedule {
+                        // An I/O error may have occurred while writing the
+                        // content file.  See the `RecordingFailed` handler.
+                        if schedule.failed_reason.is_some() {
+                            schedule.state = RecordingScheduleState::Failed;
+                        } else {
+                            schedule.state = RecordingScheduleState::Finished;
+                        }
+                        // The recording pipeline exits normally when the tuner
+                        // is preempted.  See `handle_preempted()`.
+                        if let Some(ref reason @ RecordingFailedReason::Preempted { .. }) =
+                            schedule.failed_reason
+                        {
+                            preempted = Some(reason.clone());
+                        }
+                        changed = true;
+                    }
+                    if let Some(reason) = preempted {
+                        self.emit_recording_failed_with_ts_stats(
+                            program_id,
+                            reason,
+                            Some(ts_stats.clone()),
+                        )
+                        .await;
+                    }
+                }
+                if let Some(schedule) = self.schedules.get(&program_id) {
+                    let mut entry =
+                        RecordingHistoryEntry::new(RecordingHistoryEvent::Updated, schedule);
+                    if retry {
+                        entry.failed_reason = Some(RecordingFailedReason::NeedRescheduling);
+                    }
+                    entry.exit_code = get_first_error(&results);
+                    entry.tuner_index = Some(recorder.pipeline.id().tuner_index());
+                    entry.ts_stats = Some(ts_stats.clone());
+                    self.append_history(entry);
+                }
+                // No record is added if the schedule will be rescheduled.
+                if !retry {
+                    // The relay target must be linked before adding the record.
+                    if self.follow_relay(program_id).await {
+                        changed = true;
+                    }
+                    if let Some(record_id) = self.add_record(program_id, &recorder, ts_stats) {
+                        self.enqueue_jobs(record_id).await;
+                    }
+                    if self.apply_retention(0).await {
+                        self.save_records();
+                    }
+                }
+            }
+            None => {
+                tracing::debug!(
+                    schedule.program.id = %program_id,
+                    "INCONSNSTENT: The recorder stopped before the recording stopped",
+                );
+            }
+        }
+
+        // TODO: Save recording logs to a file.
+        let msg = RecordingStopped { program_id };
+        self.recording_stopped.emit(msg).await;
+
+        changed
+    }
+
+    // Adds a schedule for the continuation of a TV program relayed to another
+    // service.  The relay target is usually defined in the EIT[p/f] before the
+    // TV program ends.
+    //
+    // Returns `true` if the schedule is linked to the continuation.
+    async fn follow_relay(&mut self, program_id: ProgramId) -> bool {
+        let schedule = match self.schedules.get(&program_id) {
+            Some(schedule) if schedule.relayed_to.is_none() => schedule,
+            _ => return false,
+        };
+        let target_id = match relay::relay_target(&schedule.program) {
+            Some(target_id) => target_id,
+            None => return false,
+        };
+
+        let msg = QueryPrograms {
+            service_id: target_id.into(),
+        };
+        let program = match self.epg.call(msg).await {
+            Ok(programs) => programs.get(&target_id.eid()).cloned(),
+            Err(err) => {
+                tracing::error!(%err, %target_id, "Failed to query programs");
+                return false;
+            }
+        };
+        let program = match program {
+            Some(program) if program.start_at.is_some() => program,
+            _ => {
+                tracing::warn!(
+                    %schedule.program.id,
+                    %target_id,
+                    "No relay target found in EPG",
+                );
+                return false;
+            }
+        };
+        if let Some(end_at) = program.end_at() {
+            if end_at <= Jst::now() {
+                tracing::warn!(
+                    %schedule.program.id,
+                    %target_id,
+                    "The relay target has already ended",
+                );
+                return false;
+            }
+        }
+
+        let mut options = schedule.options.clone();
+        let template = options.content_path_template(&self.config.recording);
+        options.content_path = relay::relay_content_path(template, target_id);
+        let mut relay = RecordingSchedule::new(Arc::new(program), options, schedule.tags.clone());
+        relay.relayed_from = Some(program_id);
+        // The continuation may have been scheduled manually.
+        if self.add_schedule(relay).is_ok() {
+            tracing::info!(
+                schedule.program.id = %program_id,
+                %target_id,
+                "Follow the relay",
+            );
+        }
+        if let Some(schedule) = self.schedules.get_mut(&program_id) {
+            schedule.relayed_to = Some(target_id);
+        }
+        true
+    }
+
+    fn add_record(
+        &mut self,
+        program_id: ProgramId,
+        recorder: &Recorder,
+        ts_stats: TsStats,
+    ) -> Option<u32> {
+        let schedule = self.schedules.get(&program_id)?;
+        let basedir = self.config.recording.basedir.as_ref()?;
+        // IDs of removed records are never reused.
+        let id = self.next_record_id;
+        let relative_path = schedule
+            .content_path
+            .clone()
+            .unwrap_or_else(|| schedule.options.content_path.clone());
+        let content_path = resolve_content_path(basedir, &relative_path);
+        let size = match std::fs::metadata(&content_path) {
+            Ok(metadata) => metadata.len(),
+            Err(err) => {
+                tracing::warn!(%err, ?content_path, "Failed to get the size of the content");
+                0
+            }
+        };
+        let record = RecordingRecord {
+            id,
+            program: schedule.program.clone(),
+            service: recorder.service.clone(),
+            content_path: relative_path,
+            start_time: recorder.started_at,
+            end_time: Jst::now(),
+            size,
+            failed_reason: schedule.failed_reason.clone(),
+            tags: schedule.tags.clone(),
+            protected: false,
+            relayed_from: schedule.relayed_from,
+            relayed_to: schedule.relayed_to,
+            ts_stats: Some(ts_stats),
+        };
+        tracing::info!(record.id, %program_id, "Added record");
+        self.records.insert(id, record);
+        self.next_record_id += 1;
+        self.save_records();
+        Some(id)
+    }
+
+    // Jobs are created only for records of recordings finished successfully.
+    async fn enqueue_jobs(&mut self, record_id: u32) {
+        let record = match self.records.get(&record_id) {
+            Some(record) if record.failed_reason.is_none() => record,
+            _ => return,
+        };
+        let basedir = match self.config.recording.basedir {
+            Some(ref basedir) => basedir,
+            None => return,
+        };
+        let mut job_ids = vec![];
+        for template in self.config.recording.jobs.commands.iter() {
+            let command = match render_job_command(template, record, basedir) {
+                Ok(command) => command,
+                Err(err) => {
+                    tracing::error!(%err, template, record.id, "Failed to render the command");
+                    continue;
+                }
+            };
+            // IDs of removed jobs are never reused.
+            let id = self.next_job_id;
+            self.next_job_id += 1;
+            let job = RecordingJob::new(id, record_id, command);
+            tracing::info!(job.id, job.record_id, job.command, "Added job");
+            self.jobs.insert(id, job);
+            job_ids.push(id);
+        }
+        if job_ids.is_empty() {
+            return;
+        }
+        self.save_jobs();
+        for id in job_ids.into_iter() {
+            self.emit_recording_job_updated(id).await;
+        }
+    }
+}
+
+// recording failed
+
+#[derive(Clone, Message)]
+pub struct RecordingFailed {
+    pub program_id: ProgramId,
+    pub reason: RecordingFailedReason,
+    // Statistics of TS packets during the recording if it has started.
+    pub ts_stats: Option<TsStats>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum RecordingFailedReason {
+    #[serde(rename_all = "camelCase")]
+    StartRecordingFailed {
+        message: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    IoError {
+        message: String,
+        os_error: Option<i32>,
+    },
+    #[serde(rename_all = "camelCase")]
+    PipelineError {
+        exit_code: i32,
+    },
+    NeedRescheduling,
+    ScheduleExpired,
+    RemovedFromEpg,
+    #[serde(rename_all = "camelCase")]
+    InsufficientSpace {
+        required: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    Preempted {
+        tuner_index: usize,
+        preemptor: String,
+    },
+}
+
+#[async_trait]
+impl<T, E, O> Handler<RecordingFailed> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(&mut self, msg: RecordingFailed, _ctx: &mut Context<Self>) {
+        tracing::debug!(msg.name = "RecordingFailed", %msg.program_id, ?msg.reason);
+        // This message is sent from the recorder before it stops.  The reason
+        // is kept in the schedule and then copied to the record.
+        if let Some(schedule) = self.schedules.get_mut(&msg.program_id) {
+            if schedule.is_recording() {
+                schedule.failed_reason = Some(msg.reason.clone());
+            }
+        }
+        self.emit_recording_failed(msg.program_id, msg.reason).await;
+    }
+}
+
+// tuner event
+
+#[async_trait]
+impl<T, E, O> Handler<crate::tuner::Event> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(&mut self, msg: crate::tuner::Event, _ctx: &mut Context<Self>) {
+        if let crate::tuner::Event::Preempted { id, preemptor, .. } = msg {
+            tracing::debug!(msg.name = "tuner::Event::Preempted", %id, %preemptor.info);
+            self.handle_preempted(id, preemptor);
+        }
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    fn handle_preempted(&mut self, id: TunerSubscriptionId, preemptor: TunerUser) {
+        let found = self
+            .recorders
+            .iter()
+            .find(|(_, recorder)| *recorder.pipeline.id() == id)
+            .map(|(&program_id, _)| program_id);
+        let program_id = match found {
+            Some(program_id) => program_id,
+            None => return,
+        };
+        tracing::error!(
+            schedule.program.id = %program_id,
+            %preemptor.info,
+            %preemptor.priority,
+            "The tuner was preempted by another user",
+        );
+        let reason = RecordingFailedReason::Preempted {
+            tuner_index: id.tuner_index(),
+            preemptor: preemptor.get_mirakurun_model().id,
+        };
+        // The reason is kept in the schedule and then copied to the record.
+        // `RecordingFailed` will be emitted with it when the recording
+        // pipeline exits.
+        if let Some(schedule) = self.schedules.get_mut(&program_id) {
+            if schedule.is_recording() {
+                schedule.failed_reason = Some(reason);
+            }
+        }
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    async fn emit_recording_failed(&self, program_id: ProgramId, reason: RecordingFailedReason) {
+        let ts_stats = self
+            .recorders
+            .get(&program_id)
+            .map(|recorder| recorder.ts_stats());
+        self.emit_recording_failed_with_ts_stats(program_id, reason, ts_stats)
+            .await;
+    }
+
+    async fn emit_recording_failed_with_ts_stats(
+        &self,
+        program_id: ProgramId,
+        reason: RecordingFailedReason,
+        ts_stats: Option<TsStats>,
+    ) {
+        let msg = RecordingFailed {
+            program_id,
+            reason: reason.clone(),
+            ts_stats,
+        };
+        self.recording_failed.emit(msg).await;
+    }
+}
+
+// recording rescheduled
+
+#[derive(Clone, Message)]
+pub struct RecordingRescheduled {
+    pub program_id: ProgramId,
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    async fn emit_recording_rescheduled(&self, program_id: ProgramId) {
+        let msg = RecordingRescheduled { program_id };
+        self.recording_rescheduled.emit(msg).await;
+    }
+}
+
+// dispatch recording jobs
+
+#[derive(Message)]
+struct DispatchRecordingJobs;
+
+#[async_trait]
+impl<T, E, O> Handler<DispatchRecordingJobs> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(&mut self, _msg: DispatchRecordingJobs, ctx: &mut Context<Self>) {
+        tracing::debug!(msg.name = "DispatchRecordingJobs");
+        self.dispatch_jobs(ctx).await;
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    async fn dispatch_jobs<C>(&mut self, ctx: &C)
+    where
+        C: Spawn + EmitterFactory<RecordingJobFinished> + EmitterFactory<DispatchRecordingJobs>,
+    {
+        let now = Jst::now();
+        let job_ids = self.start_jobs(now);
+        if !job_ids.is_empty() {
+            self.save_jobs();
+        }
+        for id in job_ids.into_iter() {
+            let command = self.jobs[&id].command.clone();
+            tracing::info!(job.id = id, job.command = command, "Start job");
+            let emitter: Emitter<RecordingJobFinished> = ctx.emitter();
+            ctx.spawn_task(async move {
+                let result = run_job(&command).await;
+                emitter.emit(RecordingJobFinished { id, result }).await;
+            });
+            self.emit_recording_job_updated(id).await;
+        }
+        self.set_job_timer(now, ctx);
+    }
+
+    // Marks runnable jobs as running within the concurrency limit and returns
+    // their IDs.
+    fn start_jobs(&mut self, now: DateTime<Jst>) -> Vec<u32> {
+        let max_concurrency = self.config.recording.jobs.max_concurrency;
+        let running = self.jobs.values().filter(|job| job.is_running()).count();
+        let job_ids = self
+            .jobs
+            .values()
+            .filter(|job| job.is_runnable(now))
+            .map(|job| job.id)
+            .take(max_concurrency.saturating_sub(running))
+            .collect_vec();
+        for id in job_ids.iter() {
+            self.jobs.get_mut(id).unwrap().start();
+        }
+        job_ids
+    }
+
+    // Sets a timer to dispatch jobs waiting for retry.
+    fn set_job_timer<C>(&mut self, now: DateTime<Jst>, ctx: &C)
+    where
+        C: Spawn + EmitterFactory<DispatchRecordingJobs>,
+    {
+        if let Some(token) = self.job_timer_token.take() {
+            token.cancel();
+        }
+        let retry_at = self
+            .jobs
+            .values()
+            .filter(|job| !job.is_running())
+            .filter_map(|job| job.retry_at)
+            .filter(|&retry_at| retry_at > now)
+            .min();
+        if let Some(retry_at) = retry_at {
+            let duration = (retry_at - now).to_std().unwrap_or_default();
+            tracing::debug!(%retry_at, "Set job timer");
+            let emitter: Emitter<DispatchRecordingJobs> = ctx.emitter();
+            let token = ctx.spawn_task(async move {
+                tokio::time::sleep(duration).await;
+                emitter.emit(DispatchRecordingJobs).await;
+            });
+            self.job_timer_token = Some(token);
+        }
+    }
+}
+
+async fn run_job(command: &str) -> Result<(), String> {
+    let mut child = CommandBuilder::new(command)
+        .and_then(|mut builder| builder.stdin(Stdio::null()).stdout(Stdio::null()).spawn())
+        .map_err(|err| format!("{}", err))?;
+    let status = child.wait().await.map_err(|err| format!("{}", err))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{}", status))
+    }
+}
+
+// recording job finished
+
+#[derive(Message)]
+struct RecordingJobFinished {
+    id: u32,
+    result: Result<(), String>,
+}
+
+#[async_trait]
+impl<T, E, O> Handler<RecordingJobFinished> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(&mut self, msg: RecordingJobFinished, ctx: &mut Context<Self>) {
+        tracing::debug!(msg.name = "RecordingJobFinished", msg.id, ?msg.result);
+        if self.handle_job_finished(msg.id, msg.result, Jst::now()) {
+            self.save_jobs();
+            self.emit_recording_job_updated(msg.id).await;
+        }
+        self.dispatch_jobs(ctx).await;
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    fn handle_job_finished(
+        &mut self,
+        id: u32,
+        result: Result<(), String>,
+        now: DateTime<Jst>,
+    ) -> bool {
+        let job = match self.jobs.get_mut(&id) {
+            Some(job) => job,
+            None => {
+                tracing::warn!(
+                    job.id = id,
+                    "INCONSISTENT: The job has already been removed"
+                );
+                return false;
+            }
+        };
+        let config = &self.config.recording.jobs;
+        let retry_delay =
+            Duration::from_std(config.retry_delay).unwrap_or_else(|_| Duration::zero());
+        let retry = job.finish(result, config.max_retries, now + retry_delay);
+        match job.state {
+            RecordingJobState::Succeeded => {
+                tracing::info!(job.id, job.attempts, "The job finished successfully");
+            }
+            _ if retry => {
+                tracing::warn!(
+                    job.id,
+                    job.attempts,
+                    job.error,
+                    "The job failed, retry later"
+                );
+            }
+            _ => {
+                tracing::error!(job.id, job.attempts, job.error, "The job failed");
+            }
+        }
+        true
+    }
+}
+
+// recording job updated
+
+#[derive(Clone, Message)]
+pub struct RecordingJobUpdated {
+    pub job_id: u32,
+    pub record_id: u32,
+    pub state: RecordingJobState,
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    async fn emit_recording_job_updated(&self, job_id: u32) {
+        if let Some(job) = self.jobs.get(&job_id) {
+            let msg = RecordingJobUpdated {
+                job_id,
+                record_id: job.record_id,
+                state: job.state,
+            };
+            self.recording_job_updated.emit(msg).await;
+        }
+    }
+}
+
+// services updated
+
+#[async_trait]
+impl<T, E, O> Handler<epg::ServicesUpdated> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(&mut self, msg: epg::ServicesUpdated, ctx: &mut Context<Self>) {
+        tracing::debug!(msg.name = "ServicesUpdated");
+        let changed = self.update_schedules_by_epg_services(&msg.services).await;
+        if changed {
+            self.save_schedules();
+            self.rebuild_queue();
+            self.set_timer(ctx);
+        }
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    async fn update_schedules_by_epg_services(
+        &mut self,
+        services: &IndexMap<ServiceId, epg::EpgService>,
+    ) -> bool {
+        let mut removed = vec![];
+        let mut entries = vec![];
+        self.schedules.retain(|&program_id, schedule| {
+            if services.contains_key(&ServiceId::from(program_id)) {
+                return true;
+            }
+            tracing::warn!(%schedule.program.id, "Removed from EPG");
+            removed.push(program_id);
+            entries.extend(RecordingHistoryEntry::removed(
+                schedule,
+                Some(RecordingFailedReason::RemovedFromEpg),
+            ));
+            false
+        });
+
+        for entry in entries.into_iter() {
+            self.append_history(entry);
+        }
+
+        for &program_id in removed.iter() {
+            if self.recorders.contains_key(&program_id) {
+                let _ = self.stop_recorder(program_id);
+            }
+            self.emit_recording_failed(program_id, RecordingFailedReason::RemovedFromEpg)
+                .await;
+        }
+
+        !removed.is_empty()
+    }
+}
+
+// programs updated
+
+#[async_trait]
+impl<T, E, O> Handler<epg::ProgramsUpdated> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(&mut self, msg: epg::ProgramsUpdated, ctx: &mut Context<Self>) {
+        tracing::debug!(msg.name = "ProgramsUpdated", %msg.service_id);
+        let now = Jst::now();
+        let mut changed = self
+            .update_schedules_by_epg_programs(now, msg.service_id)
+            .await;
+        if self.apply_series_to_service(now, msg.service_id).await {
+            changed = true;
+        }
+        if self.apply_rules_to_service(now, msg.service_id).await {
+            changed = true;
+        }
+        if changed {
+            self.save_schedules();
+            self.rebuild_queue();
+            self.set_timer(ctx);
+        }
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O>
+where
+    E: Call<QueryPrograms>,
+{
+    async fn update_schedules_by_epg_programs(
+        &mut self,
+        now: DateTime<Jst>,
+        service_id: ServiceId,
+    ) -> bool {
+        let programs = match self.epg.call(QueryPrograms { service_id }).await {
+            Ok(programs) => programs,
+            Err(err) => {
+                tracing::error!(%err, %service_id, "Failed to update schedules");
+                return false;
+            }
+        };
+
+        let mut changed = false;
+        let mut removed = vec![];
+        let mut rescheduled = vec![];
+        let mut entries = vec![];
+        self.schedules.retain(|&program_id, schedule| {
+            if ServiceId::from(program_id) != service_id {
+                return true;
+            }
+            if !schedule.can_be_updated_by_epg() {
+                return true;
+            }
+            let program = match programs.get(&program_id.eid()) {
+                Some(program) => program,
+                None => {
+                    tracing::warn!(%schedule.program.id, "Removed from EPG");
+                    removed.push(program_id);
+                    entries.extend(RecordingHistoryEntry::removed(
+                        schedule,
+                        Some(RecordingFailedReason::RemovedFromEpg),
+                    ));
+                    changed = true;
+                    return false;
+                }
+            };
+            if let Some(end_time) = program.end_at() {
+                // REMARK: `programs` contains TV programs already ended.
+                if end_time > now {
+                    let start_at_changed = program.start_at != schedule.program.start_at;
+                    schedule.program = Arc::new(program.clone());
+                    if start_at_changed {
+                        schedule.state = RecordingScheduleState::Scheduled;
+                        rescheduled.push(program_id);
+                        entries.push(RecordingHistoryEntry::new(
+                            RecordingHistoryEvent::Updated,
+                            schedule,
+                        ));
+                    }
+                    changed = true;
+                }
+            }
+            true
+        });
+
+        for entry in entries.into_iter() {
+            self.append_history(entry);
+        }
+
+        for &program_id in removed.iter() {
+            let reason = RecordingFailedReason::RemovedFromEpg;
+            self.emit_recording_failed(program_id, reason).await;
+        }
+
+        for &program_id in rescheduled.iter() {
+            self.emit_recording_rescheduled(program_id).await;
+        }
+
+        changed
+    }
+}
+
+// on-air program changed
+
+#[async_trait]
+impl<T, E, O> Handler<onair::OnairProgramChanged> for RecordingManager<T, E, O>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<QueryClock>,
+    E: Call<QueryPrograms>,
+    E: Call<QueryService>,
+    E: Call<QueryServices>,
+    E: Call<epg::RegisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<onair::RegisterEmitter>,
+{
+    async fn handle(&mut self, msg: onair::OnairProgramChanged, ctx: &mut Context<Self>) {
+        tracing::debug!(msg.name = "OnairProgramChanged");
+
+        let mut changed = false;
+
+        // The start time in EIT[p/f] may be undefined.
+        if let Some(program) = msg.current {
+            if program.start_at.is_some() {
+                let program_id = program.id;
+                changed = self.update_schedule_by_onair_program(program).await;
+                // Follow the relay as early as possible so that the beginning
+                // of the continuation is recorded.
+                let recording = self
+                    .schedules
+                    .get(&program_id)
+                    .is_some_and(|schedule| schedule.is_recording());
+                if recording && self.follow_relay(program_id).await {
+                    changed = true;
+                }
+            }
+        }
+        if let Some(program) = msg.next {
+            if program.start_at.is_some() {
+                changed |= self.update_schedule_by_onair_program(program).await;
+            }
+        }
+
+        if changed {
+            self.save_schedules();
+            self.rebuild_queue();
+            self.set_timer(ctx);
+        }
+    }
+}
+
+impl<T, E, O> RecordingManager<T, E, O> {
+    async fn update_schedule_by_onair_program(&mut self, program: Arc<EpgProgram>) -> bool {
+        use RecordingScheduleState::*;
+
+        let mut rescheduled = false;
+        let mut tracking = false;
+
+        if let Some(mut schedule) = self.schedules.get_mut(&program.id) {
+            match schedule.state {
+                Scheduled | Rescheduling => {
+                    rescheduled = schedule.program.start_at != program.start_at;
+                    schedule.program = program.clone();
+                    schedule.state = Tracking;
+                    tracing::info!(
+                        %schedule.program.id,
+                        "Start tracking changes of the schedule",
+                    );
+                    tracking = true;
+                }
+                Tracking => {
+                    rescheduled = schedule.program.start_at != program.start_at;
+                    schedule.program = program.clone();
+                }
+                Recording => {
+                    schedule.program = program.clone();
+                }
+                Finished | Failed => {
+                    // Nothing to do.
+                }
+            }
+        }
+
+        if tracking {
+            self.append_state_history(program.id);
+        }
+
+        if rescheduled {
+            self.emit_recording_rescheduled(program.id).await;
+        }
+
+        rescheduled
+    }
+}
+
+// models
+
+#[derive(Debug, Eq)]
+struct QueueItem {
+    program_id: ProgramId,
+    start_at: DateTime<Jst>,
+    priority: i32,
+}
+
+impl Ord for QueueItem {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.start_at
+            .cmp(&other.start_at)
+            .reverse()
+            .then_with(|| self.priority.cmp(&other.priority))
+    }
+}
+
+impl PartialEq for QueueItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.program_id == other.program_id
+    }
+}
+
+impl PartialOrd for QueueItem {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RecordingSchedule {
+    pub state: RecordingScheduleState,
+    pub program: Arc<EpgProgram>,
+    pub options: RecordingOptions,
+    #[serde(default)]
+    pub tags: HashSet<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failed_reason: Option<RecordingFailedReason>,
+    // The TV program relayed to this TV program.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relayed_from: Option<ProgramId>,
+    // The TV program to which this TV program relays.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relayed_to: Option<ProgramId>,
+    // The path of the content file rendered from `options.content_path` when
+    // the recording starts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_path: Option<PathBuf>,
+}
+
+impl RecordingSchedule {
+    pub fn new(program: Arc<EpgProgram>, options: RecordingOptions, tags: HashSet<String>) -> Self {
+        RecordingSchedule {
+            state: RecordingScheduleState::Scheduled,
+            program,
+            options,
+            tags,
+            failed_reason: None,
+            relayed_from: None,
+            relayed_to: None,
+            content_path: None,
+        }
+    }
+
+    // The time to start recording, which includes the start margin.
+    fn start_time(&self, config: &RecordingConfig) -> DateTime<Jst> {
+        let margin = self.options.start_margin(config);
+        self.program.start_at.unwrap() - Duration::milliseconds(margin as i64)
+    }
+
+    // The time to stop recording, which includes the end margin.
+    fn end_time(&self, config: &RecordingConfig) -> Option<DateTime<Jst>> {
+        let margin = self.options.end_margin(config);
+        self.program
+            .end_at()
+            .map(|end_at| end_at + Duration::milliseconds(margin as i64))
+    }
+
+    pub fn is_manual(&self) -> bool {
+        manual::is_manual(self.program.id)
+    }
+
+    fn can_be_updated_by_epg(&self) -> bool {
+        use RecordingScheduleState::*;
+        // Manual schedules are not tied to TV programs in EPG.
+        if self.is_manual() {
+            return false;
+        }
+        match self.state {
+            Scheduled | Rescheduling => true,
+            _ => false,
+        }
+    }
+
+    fn is_ready_for_recording(&self) -> bool {
+        use RecordingScheduleState::*;
+        match self.state {
+            Scheduled | Tracking => true,
+            _ => false,
+        }
+    }
+
+    fn is_recording(&self) -> bool {
+        use RecordingScheduleState::*;
+        match self.state {
+            Recording => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+#[derive(ToSchema)]
+#[schema(title = "RecordingScheduleState")]
+pub enum RecordingScheduleState {
+    Scheduled,
+    // This state is used only when on-air program manager is available for the
+    // service of the target TV program.  In this case, the state of the
+    // schedule always transits to `recording` via `tracking`.
+    Tracking,
+    Recording,
+    // When the recording fails, the schedule transits to this state.  If on-air
+    // program tracker is available, the schedule may be rescheduled when an EIT
+    // section ([schedule] or [p/f]) for the target TV program is emitted.
+    // Otherwise, it will be expired eventually.
+    Rescheduling,
+    // The recording finished successfully.
+    Finished,
+    // The recording failed for some reason.
+    Failed,
+}
+
+/// Recording options.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[derive(ToSchema)]
+#[schema(title = "RecordingOptions")]
+pub struct RecordingOptions {
+    /// A relative path of a file to store recorded data.
+    ///
+    /// The path is a mustache template rendered when the recording starts.
+    /// `config.recording.content-path` is used if not specified.
+    #[serde(default)]
+    #[schema(value_type = String)]
+    pub content_path: PathBuf,
+    /// A priority of tuner usage.
+    #[serde(default)]
+    pub priority: i32,
+    /// A list of pre-filters to use.
+    #[serde(default)]
+    pub pre_filters: Vec<String>,
+    /// A list of post-filters to use.
+    #[serde(default)]
+    pub post_filters: Vec<String>,
+    /// A margin in milliseconds before the TV program starts.
+    ///
+    /// `config.recording.start-margin` is used if not specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_margin: Option<u64>,
+    /// A margin in milliseconds after the TV program ends.
+    ///
+    /// `config.recording.end-margin` is used if not specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_margin: Option<u64>,
+}
+
+impl RecordingOptions {
+    fn content_path_template<'a>(&'a self, config: &'a RecordingConfig) -> &'a Path {
+        if self.content_path.as_os_str().is_empty() {
+            &config.content_path
+        } else {
+            &self.content_path
+        }
+    }
+
+    // In milliseconds.
+    fn start_margin(&self, config: &RecordingConfig) -> u64 {
+        self.start_margin
+            .unwrap_or(config.start_margin.as_millis() as u64)
+    }
+
+    // In milliseconds.
+    fn end_margin(&self, config: &RecordingConfig) -> u64 {
+        self.end_margin
+            .unwrap_or(config.end_margin.as_millis() as u64)
+    }
+}
+
+struct Recorder {
+    started_at: DateTime<Jst>,
+    service: EpgService,
+    pipeline: CommandPipeline<TunerSubscriptionId>,
+    stop_trigger: Option<Trigger<StopStreaming>>,
+    ts_stats: TsStatsHandle,
+    // Statistics when the recording started.  The tuner session may have
+    // started before the recording started.
+    ts_stats_base: TsStats,
+}
+
+impl Recorder {
+    // Statistics of TS packets during the recording.
+    fn ts_stats(&self) -> TsStats {
+        let duration = (Jst::now() - self.started_at).to_std().unwrap_or_default();
+        self.ts_stats.get().since(&self.ts_stats_base, duration)
+    }
+
+    fn get_model(&self, program_id: ProgramId) -> RecorderModel {
+        RecorderModel {
+            program_id,
+            started_at: self.started_at,
+            pipeline: self.pipeline.get_model(),
+        }
+    }
+}
+
+pub struct RecorderModel {
+    pub program_id: ProgramId,
+    pub started_at: DateTime<Jst>,
+    pub pipeline: Vec<CommandPipelineProcessModel>,
+}
+
+// helpers
+
+fn resolve_content_path(basedir: &Path, content_path: &Path) -> PathBuf {
+    if content_path.is_absolute() {
+        content_path.to_owned()
+    } else {
+        basedir.join(content_path)
+    }
+}
+
+fn check_retry(results: &[std::io::Result<ExitStatus>]) -> bool {
+    results.iter().any(|result| match result {
+        Ok(status) => {
+            if let Some(EXIT_RETRY) = status.code() {
+                true
+            } else {
+                false
+            }
+        }
+        _ => false,
+    })
+}
+
+fn get_first_error(results: &[std::io::Result<ExitStatus>]) -> Option<i32> {
+    results
+        .iter()
+        .flat_map(|result| match result {
+            Ok(status) => status.code(),
+            _ => None,
+        })
+        .find(|&code| code != 0)
+}
+
+// <coverage:exclude>
+#[cfg(test)]
+#[macro_use]
+mod test_macros {
+    macro_rules! options {
+        ($content_path:expr, $priority:expr) => {
+            RecordingOptions {
+                content_path: $content_path.into(),
+                priority: $priority.into(),
+                pre_filters: vec![],
+                post_filters: vec![],
+                start_margin: None,
+                end_margin: None,
+            }
+        };
+    }
+
+    macro_rules! rule {
+        ($id:expr) => {
+            RecordingRule {
+                id: $id,
+                disabled: false,
+                condition: RecordingRuleCondition {
+                    services: vec![(0, 1).into()],
+                    ..Default::default()
+                },
+                options: options!("test.m2ts", 1),
+                tags: Default::default(),
+                excluded_programs: vec![],
+            }
+        };
+    }
+
+    macro_rules! series {
+        ($id:expr) => {
+            RecordingSeries {
+                id: $id,
+                service_id: (0, 1).into(),
+                series_id: 1,
+                name: None,
+                expire_date: None,
+                last_episode_number: 0,
+                episodes: Default::default(),
+                options: options!("test.m2ts", 1),
+                tags: Default::default(),
+            }
+        };
+    }
+
+    macro_rules! record {
+        ($id:expr) => {
+            RecordingRecord {
+                id: $id,
+                program: Arc::new(program!((0, 1, 1), Jst::now(), "1m")),
+                service: service!((0, 1), "test", channel_gr!("test", "1")),
+                content_path: "test.m2ts".into(),
+                start_time: Jst::now(),
+                end_time: Jst::now(),
+                size: 0,
+                failed_reason: None,
+                tags: Default::default(),
+                protected: false,
+                relayed_from: None,
+                relayed_to: None,
+                ts_stats: None,
+            }
+        };
+    }
+
+    macro_rules! schedule {
+        ($state:expr, $program:expr, $options:expr) => {
+            RecordingSchedule {
+                state: $state,
+                program: Arc::new($program),
+                options: $options,
+                tags: Default::default(),
+                failed_reason: None,
+                relayed_from: None,
+                relayed_to: None,
+                content_path: None,
+            }
+        };
+        ($state:expr, $program:expr, $options:expr, $tags:expr) => {
+            RecordingSchedule {
+                state: $state,
+                program: Arc::new($program),
+                options: $options,
+                tags: $tags,
+                failed_reason: None,
+                relayed_from: None,
+                relayed_to: None,
+                content_path: None,
+            }
+        };
+    }
+
+    macro_rules! manager {
+        ($config:expr) => {
+            RecordingManager::new($config, TunerManagerStub, EpgStub, OnairProgramManagerStub)
+        };
+        ($config:expr, $tuner:expr, $epg:expr, $onair:expr) => {
+            RecordingManager::new($config, $tuner, $epg, $onair)
+        };
+    }
+
+    macro_rules! recorder {
+        ($started_at:expr, $pipeline:expr) => {
+            Recorder {
+                started_at: $started_at,
+                service: service!((0, 1), "test", channel_gr!("test", "1")),
+                pipeline: $pipeline,
+                stop_trigger: None,
+                ts_stats: Default::default(),
+                ts_stats_base: Default::default(),
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epg::stub::EpgStub;
+    use crate::onair::stub::OnairProgramManagerStub;
+    use crate::tuner::stub::TunerManagerStub;
+    use assert_matches::assert_matches;
+    use indexmap::indexmap;
+    use maplit::hashset;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_save_and_load() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config.clone());
+
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 1), now, "1h"),
+            options!("1.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        let schedule = schedule!(
+            RecordingScheduleState::Tracking,
+            program!((0, 1, 2), now, "1h"),
+            options!("2.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        let schedule = schedule!(
+            RecordingScheduleState::Recording,
+            program!((0, 1, 3), now - Duration::hours(1), "2h"),
+            options!("3.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        let num_schedules = manager.schedules.len();
+
+        manager.save_schedules();
+        assert!(temp_dir.path().join("schedules.json").is_file());
+
+        let mut manager = manager!(config.clone());
+        manager.load_schedules();
+        assert_eq!(manager.schedules.len(), num_schedules);
+        assert!(manager.schedules.contains_key(&(0, 1, 1).into()));
+        assert!(manager.schedules.contains_key(&(0, 1, 2).into()));
+        assert!(manager.schedules.contains_key(&(0, 1, 3).into()));
+    }
+
+    #[test]
+    fn test_rebuild_queue() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config);
+
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 1), now + Duration::hours(1)),
+            options!("1.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 2), now),
+            options!("2.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        let schedule = schedule!(
+            RecordingScheduleState::Tracking,
+            program!((0, 1, 3), now),
+            options!("3.m2ts", 1)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        let schedule = schedule!(
+            RecordingScheduleState::Recording,
+            program!((0, 1, 4), now - Duration::minutes(30)),
+            options!("4.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        manager.rebuild_queue();
+        assert_matches!(manager.queue.pop(), Some(item) => {
+            assert_eq!(item.program_id, (0, 1, 3).into());
+        });
+        assert_matches!(manager.queue.pop(), Some(item) => {
+            assert_eq!(item.program_id, (0, 1, 2).into());
+        });
+        assert_matches!(manager.queue.pop(), Some(item) => {
+            assert_eq!(item.program_id, (0, 1, 1).into());
+        });
+        assert_matches!(manager.queue.pop(), None);
+    }
+
+    #[test]
+    fn test_query_schedules() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config);
+
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 1), now + Duration::hours(1)),
+            options!("1.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 2), now),
+            options!("2.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        let schedule = schedule!(
+            RecordingScheduleState::Tracking,
+            program!((0, 1, 3), now),
+            options!("3.m2ts", 1)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        let schedule = schedule!(
+            RecordingScheduleState::Recording,
+            program!((0, 1, 4), now - Duration::minutes(30)),
+            options!("4.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        assert_eq!(manager.schedules.len(), 4);
+
+        let schedules = manager.query_schedules();
+        assert_eq!(schedules.len(), 4);
+        assert_matches!(schedules.get(0), Some(schedule) => {
+            assert_eq!(schedule.program.id, (0, 1, 4).into());
+        });
+        assert_matches!(schedules.get(1), Some(schedule) => {
+            assert_eq!(schedule.program.id, (0, 1, 3).into());
+        });
+        assert_matches!(schedules.get(2), Some(schedule) => {
+            assert_eq!(schedule.program.id, (0, 1, 2).into());
+        });
+        assert_matches!(schedules.get(3), Some(schedule) => {
+            assert_eq!(schedule.program.id, (0, 1, 1).into());
+        });
+    }
+
+    #[test]
+    fn test_query_schedule() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config);
+
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 1), now),
+            options!("1.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        assert_matches!(manager.query_schedule((0, 1, 1).into()), Ok(schedule) => {
+            assert_eq!(schedule.program.id, (0, 1, 1).into());
+        });
+        assert_matches!(manager.query_schedule((0, 1, 2).into()), Err(err) => {
+            assert_matches!(err, Error::ScheduleNotFound);
+        });
+    }
+
+    #[test]
+    fn test_add_schedule() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config);
+
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 1), now, "1h"),
+            options!("1.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+        assert_eq!(manager.schedules.len(), 1);
+
+        // Schedule already exists.
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 1), now, "1h"),
+            options!("1.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Err(Error::AlreadyExists));
+        assert_eq!(manager.schedules.len(), 1);
+
+        // Adding a schedule for an ended program is allowed.
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 2), now - Duration::hours(1), "3h"),
+            options!("2.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+        assert_eq!(manager.schedules.len(), 2);
+
+        // Adding a schedule for a program already started is allowed.
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 3), now - Duration::hours(1), "30m"),
+            options!("3.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+        assert_eq!(manager.schedules.len(), 3);
+    }
+
+    #[test]
+    fn test_dequeue_next_schedules_with_margins() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.recording.basedir = Some(temp_dir.path().to_owned());
+        config.recording.start_margin = std::time::Duration::from_secs(60);
+        let config = Arc::new(config);
+
+        let mut manager = manager!(config);
+
+        // The start margin in the config is used.
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 1), now + Duration::seconds(PREP_SECS + 30), "1h"),
+            options!("1.m2ts", 0)
+        );
+        manager.schedules.insert((0, 1, 1).into(), schedule);
+
+        // The start margin in the options is used.
+        let mut options = options!("2.m2ts", 0);
+        options.start_margin = Some(0);
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 2), now + Duration::seconds(PREP_SECS + 30), "1h"),
+            options
+        );
+        manager.schedules.insert((0, 1, 2).into(), schedule);
+
+        let mut options = options!("3.m2ts", 0);
+        options.start_margin = Some(120_000);
+        let schedule = schedule!(
+            RecordingScheduleState::Tracking,
+            program!((0, 1, 3), now + Duration::seconds(PREP_SECS + 90), "1h"),
+            options
+        );
+        manager.schedules.insert((0, 1, 3).into(), schedule);
+
+        manager.rebuild_queue();
+        assert_eq!(manager.queue.len(), 3);
+
+        let program_ids = manager.dequeue_next_schedules(now);
+        assert_eq!(program_ids.len(), 2);
+        assert!(program_ids.contains(&(0, 1, 1).into()));
+        assert!(program_ids.contains(&(0, 1, 3).into()));
+        assert_eq!(manager.queue.len(), 1);
+    }
+
+    #[test]
+    fn test_remove_schedules() {
+        let now = Jst::now();
+
+        let config = config_for_test("/tmp");
+
+        let mut manager = manager!(config);
+
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 1), now + Duration::seconds(PREP_SECS + 1), "1h"),
+            options!("1.m2ts", 0),
+            hashset!["tag1".to_string()]
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 2), now + Duration::seconds(PREP_SECS + 1), "1h"),
+            options!("2.m2ts", 0),
+            hashset!["tag2".to_string()]
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        // Schedules which will start soon are always retained.
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 3), now + Duration::seconds(PREP_SECS - 1), "1h"),
+            options!("3.m2ts", 0),
+            hashset!["tag1".to_string()]
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        // Schedules in "Tracking" are always retained.
+        let schedule = schedule!(
+            RecordingScheduleState::Tracking,
+            program!((0, 1, 4), now, "1h"),
+            options!("4.m2ts", 0),
+            hashset!["tag2".to_string()]
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        // Schedules in "Recording" are always retained.
+        let schedule = schedule!(
+            RecordingScheduleState::Recording,
+            program!((0, 1, 5), now, "1h"),
+            options!("5.m2ts", 0),
+            hashset!["tag2".to_string()]
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        // Schedules in "Rescheduling" are always removed.
+        let schedule = schedule!(
+            RecordingScheduleState::Rescheduling,
+            program!((0, 1, 6), now, "1h"),
+            options!("6.m2ts", 0),
+            hashset!["tag2".to_string()]
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+        assert_eq!(manager.schedules.len(), 6);
+
+        manager.remove_schedules(RemovalTarget::Tag("tag2".to_string()), now);
+        assert_eq!(manager.schedules.len(), 4);
+        assert!(manager.schedules.contains_key(&(0, 1, 1).into()));
+        assert!(manager.schedules.contains_key(&(0, 1, 3).into()));
+        assert!(manager.schedules.contains_key(&(0, 1, 4).into()));
+        assert!(manager.schedules.contains_key(&(0, 1, 5).into()));
+
+        manager.remove_schedules(RemovalTarget::All, now);
+        assert!(manager.schedules.is_empty());
+    }
+
+    #[test]
+    fn test_save_and_load_rules() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config.clone());
+
+        let result = manager.add_rule(rule!(0));
+        assert_matches!(result, Ok(rule) => {
+            assert_eq!(rule.id, 1);
+        });
+
+        let mut rule = rule!(0);
+        rule.condition.keyword = Some("[".to_string());
+        rule.condition.regex = true;
+        let result = manager.add_rule(rule);
+        assert_matches!(result, Err(Error::InvalidRule));
+
+        let result = manager.add_rule(rule!(0));
+        assert_matches!(result, Ok(rule) => {
+            assert_eq!(rule.id, 2);
+        });
+
+        manager.save_rules();
+        assert!(temp_dir.path().join("rules.json").is_file());
+
+        let mut manager = manager!(config.clone());
+        manager.load_rules();
+        assert_eq!(manager.rules.len(), 2);
+        assert!(manager.rules.contains_key(&1));
+        assert!(manager.rules.contains_key(&2));
+
+        let result = manager.add_rule(rule!(0));
+        assert_matches!(result, Ok(rule) => {
+            assert_eq!(rule.id, 3);
+        });
+
+        // IDs of removed rules are never reused.
+        manager.rules.remove(&3);
+        manager.save_rules();
+        let mut manager = manager!(config.clone());
+        manager.load_rules();
+        assert_eq!(manager.rules.len(), 2);
+        let result = manager.add_rule(rule!(0));
+        assert_matches!(result, Ok(rule) => {
+            assert_eq!(rule.id, 4);
+        });
+
+        // Rules saved in an array by older versions.
+        let rules = manager.rules.values().collect_vec();
+        assert!(file_util::save_json(
+            &rules,
+            temp_dir.path().join("rules.json")
+        ));
+        let mut manager = manager!(config);
+        manager.load_rules();
+        assert_eq!(manager.rules.len(), 3);
+        let result = manager.add_rule(rule!(0));
+        assert_matches!(result, Ok(rule) => {
+            assert_eq!(rule.id, 5);
+        });
+    }
+
+    #[test]
+    fn test_add_and_update_rule_without_filters() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config);
+
+        let mut rule = rule!(0);
+        rule.condition = Default::default();
+        let result = manager.add_rule(rule);
+        assert_matches!(result, Err(Error::InvalidRule));
+
+        let mut rule = rule!(0);
+        rule.condition = RecordingRuleCondition {
+            keyword: Some(" ".to_string()),
+            excluded_keyword: Some("news".to_string()),
+            weekdays: vec![chrono::Weekday::Mon],
+            ..Default::default()
+        };
+        let result = manager.add_rule(rule);
+        assert_matches!(result, Err(Error::InvalidRule));
+        assert!(manager.rules.is_empty());
+
+        let mut rule = rule!(0);
+        rule.condition.services.clear();
+        rule.condition.keyword = Some("news".to_string());
+        assert_matches!(manager.add_rule(rule), Ok(_));
+
+        let mut rule = rule!(0);
+        rule.condition.services.clear();
+        rule.condition.genres = vec![RecordingRuleGenre { lv1: 1, lv2: None }];
+        assert_matches!(manager.add_rule(rule), Ok(_));
+
+        let mut rule = rule!(1);
+        rule.condition = Default::default();
+        let result = manager.update_rule(rule);
+        assert_matches!(result, Err(Error::InvalidRule));
+        assert_matches!(manager.rules.get(&1), Some(rule) => {
+            assert_eq!(rule.condition.keyword, Some("news".to_string()));
+        });
+    }
+
+    #[test]
+    fn test_apply_rules() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config);
+
+        let mut rule = rule!(0);
+        rule.condition.keyword = Some("news".to_string());
+        rule.options = options!("{{{program_id}}}.m2ts", 0);
+        rule.tags = hashset!["rule".to_string()];
+        assert_matches!(manager.add_rule(rule), Ok(_));
+
+        let mut rule = rule!(0);
+        rule.disabled = true;
+        rule.condition.keyword = Some("drama".to_string());
+        assert_matches!(manager.add_rule(rule), Ok(_));
+
+        let mut programs = [
+            program!((0, 1, 1), now + Duration::hours(1), "1h"),
+            program!((0, 1, 2), now + Duration::hours(2), "1h"),
+            program!((0, 1, 3), now - Duration::minutes(10), "1h"),
+            program!((0, 1, 4), now + Duration::hours(3), "1h"),
+        ];
+        programs[0].name = Some("news".to_string());
+        programs[1].name = Some("drama".to_string());
+        programs[2].name = Some("news".to_string());
+        programs[3].name = Some("news".to_string());
+
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            programs[3].clone(),
+            options!("4.m2ts", 0)
+        );
+        assert_matches!(manager.add_schedule(schedule), Ok(()));
+
+        let changed = manager.apply_rules(now, programs.iter());
+        assert!(changed);
+        assert_eq!(manager.schedules.len(), 2);
+        assert_matches!(manager.schedules.get(&(0, 1, 1).into()), Some(schedule) => {
+            // Rendered when the recording starts.
+            assert_eq!(schedule.options.content_path, Path::new("{{{program_id}}}.m2ts"));
+            assert!(schedule.tags.contains("rule"));
+        });
+        assert_matches!(manager.schedules.get(&(0, 1, 4).into()), Some(schedule) => {
+            assert_eq!(schedule.options.content_path, Path::new("4.m2ts"));
+        });
+
+        let changed = manager.apply_rules(now, programs.iter());
+        assert!(!changed);
+    }
+
+    #[test]
+    fn test_apply_rules_after_removal() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config.clone());
+
+        let mut rule = rule!(0);
+        rule.condition.keyword = Some("news".to_string());
+        assert_matches!(manager.add_rule(rule), Ok(_));
+
+        let mut rule = rule!(0);
+        rule.condition.keyword = Some("drama".to_string());
+        assert_matches!(manager.add_rule(rule), Ok(_));
+
+        let mut programs = [
+            program!((0, 1, 1), now + Duration::hours(1), "1h"),
+            program!((0, 1, 2), now + Duration::hours(2), "1h"),
+        ];
+        programs[0].name = Some("news drama".to_string());
+        programs[1].name = Some("news".to_string());
+
+        let changed = manager.apply_rules(now, programs.iter());
+        assert!(changed);
+        assert_eq!(manager.schedules.len(), 2);
+
+        let result = manager.remove_schedule((0, 1, 1).into(), now);
+        assert_matches!(result, Ok((schedule, rules_changed)) => {
+            assert_eq!(schedule.program.id, (0, 1, 1).into());
+            assert!(rules_changed);
+        });
+        // Excluded from all the matched rules.
+        assert!(manager.rules[&1].is_excluded((0, 1, 1).into()));
+        assert!(manager.rules[&2].is_excluded((0, 1, 1).into()));
+
+        let changed = manager.apply_rules(now, programs.iter());
+        assert!(!changed);
+        assert_eq!(manager.schedules.len(), 1);
+        assert!(!manager.schedules.contains_key(&(0, 1, 1).into()));
+
+        // Exclusions are kept when the rule is updated.
+        let mut rule = rule!(1);
+        rule.condition.keyword = Some("news".to_string());
+        assert_matches!(manager.update_rule(rule), Ok(_));
+        assert!(manager.rules[&1].is_excluded((0, 1, 1).into()));
+
+        manager.save_rules();
+        let mut manager = manager!(config);
+        manager.load_rules();
+        assert!(manager.rules[&1].is_excluded((0, 1, 1).into()));
+        let changed = manager.apply_rules(now, programs.iter());
+        assert!(changed);
+        assert_eq!(manager.schedules.len(), 1);
+        assert!(manager.schedules.contains_key(&(0, 1, 2).into()));
+
+        // Exclusions are purged after the TV program ends.
+        assert!(manager
+            .rules
+            .get_mut(&1)
+            .unwrap()
+            .purge_exclusions(now + Duration::hours(2)));
+        assert!(!manager.rules[&1].is_excluded((0, 1, 1).into()));
+    }
+
+    #[test]
+    fn test_save_and_load_series() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config.clone());
+
+        let mut program = program!((0, 1, 1));
+        program.series = Some(series_desc(1, 1, 0));
+        let result = manager.add_series(&program, options!("1.m2ts", 0), Default::default());
+        assert_matches!(result, Ok(series) => {
+            assert_eq!(series.id, 1);
+        });
+
+        // The series already exists.
+        let mut program = program!((0, 1, 2));
+        program.series = Some(series_desc(1, 2, 0));
+        let result = manager.add_series(&program, options!("2.m2ts", 0), Default::default());
+        assert_matches!(result, Err(Error::AlreadyExists));
+
+        // Another service.
+        let mut program = program!((0, 2, 1));
+        program.series = Some(series_desc(1, 1, 0));
+        let result = manager.add_series(&program, options!("3.m2ts", 0), Default::default());
+        assert_matches!(result, Ok(series) => {
+            assert_eq!(series.id, 2);
+        });
+
+        // No series.
+        let program = program!((0, 1, 3));
+        let result = manager.add_series(&program, options!("4.m2ts", 0), Default::default());
+        assert_matches!(result, Err(Error::NoSeries));
+
+        manager.save_series();
+        assert!(temp_dir.path().join("series.json").is_file());
+
+        let mut manager = manager!(config.clone());
+        manager.load_series();
+        assert_eq!(manager.series.len(), 2);
+        assert!(manager.series.contains_key(&1));
+        assert!(manager.series.contains_key(&2));
+
+        // IDs of removed series are never reused.
+        manager.series.remove(&2);
+        manager.save_series();
+        let mut manager = manager!(config.clone());
+        manager.load_series();
+        assert_eq!(manager.series.len(), 1);
+        let mut program = program!((0, 1, 4));
+        program.series = Some(series_desc(3, 1, 0));
+        let result = manager.add_series(&program, options!("5.m2ts", 0), Default::default());
+        assert_matches!(result, Ok(series) => {
+            assert_eq!(series.id, 3);
+        });
+
+        // Series saved in an array by older versions.
+        let series = manager.series.values().collect_vec();
+        assert!(file_util::save_json(
+            &series,
+            temp_dir.path().join("series.json")
+        ));
+        let mut manager = manager!(config);
+        manager.load_series();
+        assert_eq!(manager.series.len(), 2);
+        let mut program = program!((0, 1, 5));
+        program.series = Some(series_desc(4, 1, 0));
+        let result = manager.add_series(&program, options!("6.m2ts", 0), Default::default());
+        assert_matches!(result, Ok(series) => {
+            assert_eq!(series.id, 4);
+        });
+    }
+
+    #[test]
+    fn test_save_and_load_records() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config.clone());
+        manager.records.insert(1, record!(1));
+        manager.records.insert(2, record!(2));
+        manager.save_records();
+        assert!(temp_dir.path().join("records.json").is_file());
+
+        let mut manager = manager!(config.clone());
+        manager.load_records();
+        assert_eq!(manager.records.len(), 2);
+        assert!(manager.records.contains_key(&1));
+        assert!(manager.records.contains_key(&2));
+        assert_eq!(manager.next_record_id, 3);
+
+        // IDs of removed records are never reused.
+        manager.records.remove(&2);
+        manager.save_records();
+        let mut manager = manager!(config.clone());
+        manager.load_records();
+        assert_eq!(manager.records.len(), 1);
+        assert_eq!(manager.next_record_id, 3);
+
+        // Records saved in an array by older versions.
+        let records = manager.records.values().collect_vec();
+        assert!(file_util::save_json(
+            &records,
+            temp_dir.path().join("records.json")
+        ));
+        let mut manager = manager!(config);
+        manager.load_records();
+        assert_eq!(manager.records.len(), 1);
+        assert_eq!(manager.next_record_id, 2);
+    }
+
+    #[tokio::test]
+    async fn test_remove_record() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let content_path = temp_dir.path().join("test.m2ts");
+        std::fs::write(&content_path, b"test").unwrap();
+
+        let mut manager = manager!(config);
+        manager.records.insert(1, record!(1));
+        manager.records.insert(2, record!(2));
+        manager
+            .jobs
+            .insert(1, RecordingJob::new(1, 1, "true".to_string()));
+        manager
+            .jobs
+            .insert(2, RecordingJob::new(2, 2, "true".to_string()));
+
+        let result = manager.remove_record(1).await;
+        assert_matches!(result, Ok(record) => {
+            assert_eq!(record.id, 1);
+        });
+        assert!(!content_path.exists());
+        // Jobs for the record are also removed.
+        assert!(!manager.jobs.contains_key(&1));
+        assert!(manager.jobs.contains_key(&2));
+
+        // No error even if the content file doesn't exist.
+        let result = manager.remove_record(2).await;
+        assert_matches!(result, Ok(record) => {
+            assert_eq!(record.id, 2);
+        });
+        assert!(manager.records.is_empty());
+
+        let result = manager.remove_record(1).await;
+        assert_matches!(result, Err(Error::RecordNotFound));
+    }
+
+    #[test]
+    fn test_save_and_load_jobs() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config.clone());
+        manager
+            .jobs
+            .insert(1, RecordingJob::new(1, 1, "true".to_string()));
+        manager
+            .jobs
+            .insert(2, RecordingJob::new(2, 1, "true".to_string()));
+        manager.jobs.get_mut(&2).unwrap().start();
+        manager.save_jobs();
+        assert!(temp_dir.path().join("jobs.json").is_file());
+
+        let mut manager = manager!(config.clone());
+        manager.load_jobs();
+        assert_eq!(manager.jobs.len(), 2);
+        // Jobs running when the process was terminated will be run again.
+        assert_matches!(manager.jobs.get(&2), Some(job) => {
+            assert_eq!(job.state, RecordingJobState::Queued);
+        });
+        assert_eq!(manager.next_job_id, 3);
+
+        // IDs of removed jobs are never reused.
+        manager.jobs.remove(&2);
+        manager.save_jobs();
+        let mut manager = manager!(config.clone());
+        manager.load_jobs();
+        assert_eq!(manager.jobs.len(), 1);
+        assert_eq!(manager.next_job_id, 3);
+
+        // Jobs saved in an array by older versions.
+        let jobs = manager.jobs.values().collect_vec();
+        assert!(file_util::save_json(
+            &jobs,
+            temp_dir.path().join("jobs.json")
+        ));
+        let mut manager = manager!(config);
+        manager.load_jobs();
+        assert_eq!(manager.jobs.len(), 1);
+        assert_eq!(manager.next_job_id, 2);
+    }
+
+    #[tokio::test]
+    async fn test_enqueue_jobs() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.recording.basedir = Some(temp_dir.path().to_owned());
+        config.recording.jobs.commands = vec![
+            "echo {{{record_id}}}".to_string(),
+            "echo {{{content_path}}}".to_string(),
+        ];
+        let config = Arc::new(config);
+
+        let mut manager = manager!(config);
+
+        let mut updated = MockRecordingJobUpdatedValidator::new();
+        updated.expect_emit().times(2).returning(|msg| {
+            assert_eq!(msg.record_id, 1);
+            assert_eq!(msg.state, RecordingJobState::Queued);
+        });
+        manager
+            .recording_job_updated
+            .register(Emitter::new(updated));
+
+        manager.records.insert(1, record!(1));
+        manager.enqueue_jobs(1).await;
+        assert_eq!(manager.jobs.len(), 2);
+        assert_matches!(manager.jobs.get(&1), Some(job) => {
+            assert_eq!(job.record_id, 1);
+            assert_eq!(job.command, "echo 1");
+        });
+        assert_matches!(manager.jobs.get(&2), Some(job) => {
+            assert_eq!(job.record_id, 1);
+            assert_eq!(
+                job.command,
+                format!("echo {}", temp_dir.path().join("test.m2ts").display())
+            );
+        });
+
+        // No job is created for a failed recording.
+        let mut record = record!(2);
+        record.failed_reason = Some(RecordingFailedReason::NeedRescheduling);
+        manager.records.insert(2, record);
+        manager.enqueue_jobs(2).await;
+        assert_eq!(manager.jobs.len(), 2);
+    }
+
+    #[test]
+    fn test_start_jobs() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.recording.basedir = Some(temp_dir.path().to_owned());
+        config.recording.jobs.max_concurrency = 2;
+        let config = Arc::new(config);
+
+        let mut manager = manager!(config);
+        for id in 1..=4 {
+            manager
+                .jobs
+                .insert(id, RecordingJob::new(id, 1, "true".to_string()));
+        }
+        // Waiting for retry.
+        manager.jobs.get_mut(&1).unwrap().retry_at = Some(now + Duration::minutes(1));
+
+        assert_eq!(manager.start_jobs(now), [2, 3]);
+        assert!(manager.start_jobs(now).is_empty());
+
+        let changed = manager.handle_job_finished(2, Ok(()), now);
+        assert!(changed);
+        assert_eq!(manager.start_jobs(now), [4]);
+        assert!(manager.start_jobs(now).is_empty());
+
+        let changed = manager.handle_job_finished(3, Ok(()), now);
+        assert!(changed);
+        assert!(manager.start_jobs(now).is_empty());
+        assert_eq!(manager.start_jobs(now + Duration::minutes(1)), [1]);
+    }
+
+    #[test]
+    fn test_handle_job_finished() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.recording.basedir = Some(temp_dir.path().to_owned());
+        config.recording.jobs.max_retries = 1;
+        config.recording.jobs.retry_delay = std::time::Duration::from_secs(60);
+        let config = Arc::new(config);
+
+        let mut manager = manager!(config);
+        manager
+            .jobs
+            .insert(1, RecordingJob::new(1, 1, "false".to_string()));
+
+        assert_eq!(manager.start_jobs(now), [1]);
+        let changed = manager.handle_job_finished(1, Err("error".to_string()), now);
+        assert!(changed);
+        assert_matches!(manager.jobs.get(&1), Some(job) => {
+            assert_eq!(job.state, RecordingJobState::Queued);
+            assert_eq!(job.attempts, 1);
+            assert_eq!(job.retry_at, Some(now + Duration::minutes(1)));
+        });
+
+        let now = now + Duration::minutes(1);
+        assert_eq!(manager.start_jobs(now), [1]);
+        let changed = manager.handle_job_finished(1, Err("error".to_string()), now);
+        assert!(changed);
+        assert_matches!(manager.jobs.get(&1), Some(job) => {
+            assert_eq!(job.state, RecordingJobState::Failed);
+            assert_eq!(job.attempts, 2);
+            assert_eq!(job.error, Some("error".to_string()));
+        });
+
+        let changed = manager.handle_job_finished(2, Ok(()), now);
+        assert!(!changed);
+    }
+
+    #[tokio::test]
+    async fn test_run_job() {
+        assert_matches!(run_job("true").await, Ok(()));
+        assert_matches!(run_job("false").await, Err(_));
+        assert_matches!(run_job("command-not-found").await, Err(_));
+        assert_matches!(run_job("'").await, Err(_));
+    }
+
+    #[test]
+    fn test_update_record() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config);
+        manager.records.insert(1, record!(1));
+
+        let result = manager.update_record(1, None);
+        assert_matches!(result, Ok(record) => {
+            assert!(!record.protected);
+        });
+
+        let result = manager.update_record(1, Some(true));
+        assert_matches!(result, Ok(record) => {
+            assert!(record.protected);
+        });
+        assert!(manager.records.get(&1).unwrap().protected);
+
+        let result = manager.update_record(2, Some(true));
+        assert_matches!(result, Err(Error::RecordNotFound));
+    }
+
+    #[test]
+    fn test_make_content_path() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+        let service = service!((0, 1), "test", channel_gr!("test", "1"));
+
+        let mut manager = manager!(config);
+
+        let mut program = program!((0, 1, 1), now, "1h");
+        program.name = Some("a/b".to_string());
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program,
+            options!("{{{service_name}}}_{{{title}}}.m2ts", 0)
+        );
+        let result = manager.make_content_path(&schedule, &service);
+        assert_matches!(result, Ok(path) => {
+            assert_eq!(path, Path::new("test_a_b.m2ts"));
+        });
+
+        // The default template.
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 1), now, "1h"),
+            options!("", 0)
+        );
+        let result = manager.make_content_path(&schedule, &service);
+        assert_matches!(result, Ok(path) => {
+            assert_eq!(path, Path::new("100001.m2ts"));
+        });
+
+        // Collision with an existing file.
+        std::fs::write(temp_dir.path().join("100001.m2ts"), b"").unwrap();
+        let result = manager.make_content_path(&schedule, &service);
+        assert_matches!(result, Ok(path) => {
+            assert_eq!(path, Path::new("100001-1.m2ts"));
+        });
+
+        // Collision with another recording.
+        let mut other = schedule!(
+            RecordingScheduleState::Recording,
+            program!((0, 1, 2), now, "1h"),
+            options!("test.m2ts", 0)
+        );
+        other.content_path = Some("100001-1.m2ts".into());
+        manager.schedules.insert((0, 1, 2).into(), other);
+        let result = manager.make_content_path(&schedule, &service);
+        assert_matches!(result, Ok(path) => {
+            assert_eq!(path, Path::new("100001-2.m2ts"));
+        });
+
+        // Collision with a record.
+        let mut record = record!(1);
+        record.content_path = "100001-2.m2ts".into();
+        manager.records.insert(1, record);
+        let result = manager.make_content_path(&schedule, &service);
+        assert_matches!(result, Ok(path) => {
+            assert_eq!(path, Path::new("100001-3.m2ts"));
+        });
+
+        // A leading variable rendered into an empty string.
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 3), now, "1h"),
+            options!("{{{title}}}/test.m2ts", 0)
+        );
+        let result = manager.make_content_path(&schedule, &service);
+        assert_matches!(result, Err(Error::InvalidPath));
+    }
+
+    #[test]
+    fn test_add_manual_schedule() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config);
+
+        macro_rules! msg {
+            ($start_time:expr, $end_time:expr) => {
+                AddManualRecordingSchedule {
+                    service_id: (0, 1).into(),
+                    start_time: $start_time,
+                    end_time: $end_time,
+                    name: Some("manual".to_string()),
+                    options: options!("manual.m2ts", 0),
+                    tags: Default::default(),
+                }
+            };
+        }
+
+        let result = manager.add_manual_schedule(msg!(now, now + Duration::hours(1)), now);
+        let first = assert_matches!(result, Ok(schedule) => {
+            assert!(schedule.is_manual());
+            assert_eq!(ServiceId::from(schedule.program.id), (0, 1).into());
+            assert_eq!(schedule.program.start_at, Some(now));
+            assert_eq!(schedule.program.end_at(), Some(now + Duration::hours(1)));
+            assert_eq!(schedule.program.name.as_deref(), Some("manual"));
+            schedule.program.id
+        });
+        assert!(manager.schedules.contains_key(&first));
+
+        let result = manager.add_manual_schedule(msg!(now, now + Duration::hours(1)), now);
+        assert_matches!(result, Ok(schedule) => {
+            assert_ne!(schedule.program.id, first);
+        });
+        assert_eq!(manager.schedules.len(), 2);
+
+        let result = manager.add_manual_schedule(msg!(now, now), now);
+        assert_matches!(result, Err(Error::InvalidTimeRange));
+
+        let result = manager.add_manual_schedule(
+            msg!(now - Duration::hours(2), now - Duration::hours(1)),
+            now,
+        );
+        assert_matches!(result, Err(Error::ProgramEnded));
+
+        // Manual schedules are dequeued at the start time.
+        manager.rebuild_queue();
+        assert_matches!(manager.queue.peek(), Some(item) => {
+            assert_eq!(item.start_at, now + Duration::seconds(PREP_SECS));
+        });
+    }
+
+    #[tokio::test]
+    async fn test_update_schedules_by_epg_programs_manual() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut epg = MockEpg::new();
+        epg.expect_call()
+            .returning(|_| Ok(Arc::new(Default::default())));
+
+        let mut manager = manager!(config, TunerManagerStub, epg, OnairProgramManagerStub);
+        let msg = AddManualRecordingSchedule {
+            service_id: (0, 1).into(),
+            start_time: now + Duration::hours(1),
+            end_time: now + Duration::hours(2),
+            name: None,
+            options: options!("manual.m2ts", 0),
+            tags: Default::default(),
+        };
+        assert_matches!(manager.add_manual_schedule(msg, now), Ok(_));
+
+        // Manual schedules are never removed even if they are not in EPG.
+        let changed = manager
+            .update_schedules_by_epg_programs(now, (0, 1).into())
+            .await;
+        assert!(!changed);
+        assert_eq!(manager.schedules.len(), 1);
+    }
+
+    #[test]
+    fn test_update_schedule() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config);
+
+        let states = [
+            RecordingScheduleState::Scheduled,
+            RecordingScheduleState::Tracking,
+            RecordingScheduleState::Rescheduling,
+        ];
+        for state in states {
+            let schedule = schedule!(state, program!((0, 1, 1), now, "1h"), options!("1.m2ts", 0));
+            manager.schedules.insert((0, 1, 1).into(), schedule);
+            let result = manager.update_schedule(
+                (0, 1, 1).into(),
+                Some(options!("2.m2ts", 1)),
+                Some(hashset!["tag".to_string()]),
+            );
+            assert_matches!(result, Ok(schedule) => {
+                assert_eq!(schedule.options.content_path, Path::new("2.m2ts"));
+                assert_eq!(schedule.options.priority, 1);
+                assert!(schedule.tags.contains("tag"));
+            });
+            assert_matches!(manager.schedules.get(&(0, 1, 1).into()), Some(schedule) => {
+                assert_eq!(schedule.options.priority, 1);
+            });
+        }
+
+        let schedule = schedule!(
+            RecordingScheduleState::Recording,
+            program!((0, 1, 1), now, "1h"),
+            options!("1.m2ts", 0)
+        );
+        manager.schedules.insert((0, 1, 1).into(), schedule);
+        let result = manager.update_schedule((0, 1, 1).into(), Some(options!("2.m2ts", 1)), None);
+        assert_matches!(result, Err(Error::InvalidScheduleState));
+        let result =
+            manager.update_schedule((0, 1, 1).into(), None, Some(hashset!["tag".to_string()]));
+        assert_matches!(result, Ok(schedule) => {
+            assert_eq!(schedule.options.content_path, Path::new("1.m2ts"));
+            assert!(schedule.tags.contains("tag"));
+        });
+
+        let states = [
+            RecordingScheduleState::Finished,
+            RecordingScheduleState::Failed,
+        ];
+        for state in states {
+            let schedule = schedule!(state, program!((0, 1, 1), now, "1h"), options!("1.m2ts", 0));
+            manager.schedules.insert((0, 1, 1).into(), schedule);
+            let result =
+                manager.update_schedule((0, 1, 1).into(), None, Some(hashset!["tag".to_string()]));
+            assert_matches!(result, Err(Error::InvalidScheduleState));
+        }
+
+        let result = manager.update_schedule((0, 1, 2).into(), None, None);
+        assert_matches!(result, Err(Error::ScheduleNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_apply_retention() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.recording.basedir = Some(temp_dir.path().to_owned());
+        config.recording.max_total_size = Some(250);
+        let config = Arc::new(config);
+
+        let mut manager = manager!(config);
+
+        let mut record = record!(1);
+        record.end_time = now - Duration::hours(3);
+        record.size = 100;
+        manager.records.insert(1, record);
+
+        let mut record = record!(2);
+        record.end_time = now - Duration::hours(2);
+        record.size = 100;
+        record.protected = true;
+        manager.records.insert(2, record);
+
+        let mut record = record!(3);
+        record.end_time = now - Duration::hours(1);
+        record.size = 100;
+        manager.records.insert(3, record);
+
+        // The oldest record will be removed.
+        assert!(manager.apply_retention(0).await);
+        assert_eq!(manager.records.len(), 2);
+        assert!(!manager.records.contains_key(&1));
+        assert!(!manager.apply_retention(0).await);
+
+        // Protected records won't be removed.
+        assert!(manager.apply_retention(100).await);
+        assert_eq!(manager.records.len(), 1);
+        assert!(manager.records.contains_key(&2));
+        assert!(!manager.needs_space(100));
+
+        assert!(!manager.apply_retention(200).await);
+        assert_eq!(manager.records.len(), 1);
+        assert!(manager.needs_space(200));
+    }
+
+    #[test]
+    fn test_needs_space_min_free_space() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.recording.basedir = Some(temp_dir.path().to_owned());
+        config.recording.min_free_space = Some(0);
+        let manager = manager!(Arc::new(config.clone()));
+        assert!(!manager.needs_space(0));
+
+        config.recording.min_free_space = Some(u64::MAX / 2);
+        let manager = manager!(Arc::new(config));
+        assert!(manager.needs_space(0));
+    }
+
+    #[test]
+    fn test_estimate_content_size() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.recording.basedir = Some(temp_dir.path().to_owned());
+        config.recording.end_margin = std::time::Duration::ZERO;
+        let config = Arc::new(config);
+
+        let mut manager = manager!(config);
+
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 1), now, "1m"),
+            options!("1.m2ts", 0)
+        );
+
+        // No record for the service.
+        assert_eq!(
+            manager.estimate_content_size(&schedule),
+            DEFAULT_BYTES_PER_SEC * 60
+        );
+
+        let mut record = record!(1);
+        record.start_time = now;
+        record.end_time = now + Duration::seconds(10);
+        record.size = 1000;
+        manager.records.insert(1, record);
+        assert_eq!(manager.estimate_content_size(&schedule), 100 * 60);
+    }
+
+    #[test]
+    fn test_apply_series() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config);
+
+        let mut programs = [
+            program!((0, 1, 1), now - Duration::minutes(10), "1h"),
+            program!((0, 1, 2), now + Duration::days(1), "1h"),
+            program!((0, 1, 3), now + Duration::days(2), "1h"),
+            program!((0, 1, 4), now + Duration::days(7), "1h"),
+            program!((0, 1, 5), now + Duration::days(3), "1h"),
+            program!((0, 1, 6), now + Duration::days(4), "1h"),
+        ];
+        programs[0].series = Some(series_desc(1, 1, 3));
+        programs[1].series = Some(series_desc(1, 2, 3));
+        // A rebroadcast.
+        programs[2].series = Some(series_desc(1, 2, 3));
+        programs[3].series = Some(series_desc(1, 3, 3));
+        // Another series.
+        programs[4].series = Some(series_desc(2, 1, 3));
+
+        let result = manager.add_series(
+            &programs[0],
+            options!("{{{program_id}}}.m2ts", 0),
+            hashset!["series".to_string()],
+        );
+        assert_matches!(result, Ok(_));
+
+        let changed = manager.apply_series(now, programs.iter());
+        assert!(changed);
+        assert_eq!(manager.schedules.len(), 2);
+        assert_matches!(manager.schedules.get(&(0, 1, 2).into()), Some(schedule) => {
+            // Rendered when the recording starts.
+            assert_eq!(schedule.options.content_path, Path::new("{{{program_id}}}.m2ts"));
+            assert!(schedule.tags.contains("series"));
+        });
+        assert!(manager.schedules.contains_key(&(0, 1, 4).into()));
+        // The series has been removed because the last episode was scheduled.
+        assert!(manager.series.is_empty());
+    }
+
+    #[test]
+    fn test_apply_series_expired() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config);
+
+        let mut program = program!((0, 1, 1), now + Duration::hours(1), "1h");
+        program.series = Some(series_desc(1, 1, 0));
+        let result = manager.add_series(&program, options!("1.m2ts", 0), Default::default());
+        assert_matches!(result, Ok(_));
+
+        let changed = manager.apply_series(now, [program.clone()].iter());
+        assert!(changed);
+        assert_eq!(manager.schedules.len(), 1);
+        assert_eq!(manager.series.len(), 1);
+
+        let mut program = program!((0, 1, 2), now + Duration::days(1), "1h");
+        let mut desc = series_desc(1, 2, 0);
+        desc.expire_date = Some((now - Duration::hours(1)).timestamp_millis());
+        program.series = Some(desc);
+        let changed = manager.apply_series(now, [program].iter());
+        assert!(!changed);
+        assert_eq!(manager.schedules.len(), 1);
+        assert!(manager.series.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_maintain_schedules() {
+        let now = Jst::now();
+        let config = config_for_test("/tmp");
+
+        let max_delay = Duration::hours(MAX_DELAY_HOURS);
+
+        let mut manager = manager!(config.clone());
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 1), now + Duration::hours(1), "1h"),
+            options!("1.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+        let changed = manager.maintain_schedules(now + max_delay).await;
+        assert!(!changed);
+        assert_eq!(manager.schedules.len(), 1);
+        manager.schedules.clear();
+
+        let mut manager = manager!(config.clone());
+        let schedule = schedule!(
+            RecordingScheduleState::Recording,
+            program!((0, 1, 1), now, "1h"),
+            options!("1.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+        let changed = manager.maintain_schedules(now + max_delay).await;
+        assert!(!changed);
+        assert_eq!(manager.schedules.len(), 1);
+        manager.schedules.clear();
+
+        let states = [
+            RecordingScheduleState::Scheduled,
+            RecordingScheduleState::Tracking,
+            RecordingScheduleState::Rescheduling,
+        ];
+        for state in states {
+            let mut manager = manager!(config.clone());
+            let mut failed = MockRecordingFailedValidator::new();
+            failed.expect_emit().times(1).returning(|msg| {
+                assert_eq!(msg.program_id, (0, 1, 1).into());
+            });
+            manager.recording_failed.register(Emitter::new(failed));
+            let schedule = schedule!(state, program!((0, 1, 1), now, "1h"), options!("1.m2ts", 0));
+            let result = manager.add_schedule(schedule);
+            assert_matches!(result, Ok(()));
+            let changed = manager.maintain_schedules(now + max_delay).await;
+            assert!(changed);
+            assert!(manager.schedules.is_empty());
+        }
+
+        let states = [
+            RecordingScheduleState::Finished,
+            RecordingScheduleState::Failed,
+        ];
+        for state in states {
+            let mut manager = manager!(config.clone());
+            let mut failed = MockRecordingFailedValidator::new();
+            failed.expect_emit().never();
+            manager.recording_failed.register(Emitter::new(failed));
+            let schedule = schedule!(state, program!((0, 1, 1), now, "1h"), options!("1.m2ts", 0));
+            let result = manager.add_schedule(schedule);
+            assert_matches!(result, Ok(()));
+            let changed = manager.maintain_schedules(now + max_delay).await;
+            assert!(changed);
+            assert!(manager.schedules.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_handover_stream() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config);
+
+        let preceding = schedule!(
+            RecordingScheduleState::Recording,
+            program!((0, 1, 1), now - Duration::hours(1), "1h"),
+            options!("1.m2ts", 0)
+        );
+        manager.schedules.insert((0, 1, 1).into(), preceding);
+        let recorder = recorder!(now - Duration::hours(1), pipeline!["true"]);
+        manager.recorders.insert((0, 1, 1).into(), recorder);
+
+        let other = schedule!(
+            RecordingScheduleState::Recording,
+            program!((0, 2, 1), now - Duration::hours(1), "1h"),
+            options!("2.m2ts", 0)
+        );
+        manager.schedules.insert((0, 2, 1).into(), other);
+        let recorder = recorder!(now - Duration::hours(1), pipeline!["true"]);
+        manager.recorders.insert((0, 2, 1).into(), recorder);
+
+        // The next TV program on the same service.
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 2), now, "1h"),
+            options!("3.m2ts", 0)
+        );
+        assert!(manager.find_handover_stream(&schedule).is_some());
+
+        // A TV program on another service.
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 3, 2), now, "1h"),
+            options!("3.m2ts", 0)
+        );
+        assert!(manager.find_handover_stream(&schedule).is_none());
+
+        // Not back-to-back.
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 2), now + Duration::minutes(10), "1h"),
+            options!("3.m2ts", 0)
+        );
+        assert!(manager.find_handover_stream(&schedule).is_none());
+
+        // Overlapped.
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 2), now - Duration::minutes(10), "1h"),
+            options!("3.m2ts", 0)
+        );
+        assert!(manager.find_handover_stream(&schedule).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_handle_preempted() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config);
+        let mut failed = MockRecordingFailedValidator::new();
+        failed.expect_emit().times(1).returning(|msg| {
+            assert_eq!(msg.program_id, (0, 1, 1).into());
+            assert_matches!(msg.reason, RecordingFailedReason::Preempted { tuner_index, preemptor } => {
+                assert_eq!(tuner_index, 0);
+                assert_eq!(preemptor, "web");
+            });
+        });
+        manager.recording_failed.register(Emitter::new(failed));
+
+        let start_time = now - Duration::minutes(30);
+
+        let schedule = schedule!(
+            RecordingScheduleState::Recording,
+            program!((0, 1, 1), start_time, "1h"),
+            options!("1.m2ts", 0)
+        );
+        manager.schedules.insert((0, 1, 1).into(), schedule);
+        let recorder = recorder!(start_time, pipeline!["true"]);
+        manager.recorders.insert((0, 1, 1).into(), recorder);
+
+        let preemptor = tuner_user!(128, web; "web");
+        manager.handle_preempted(Default::default(), preemptor.clone());
+        assert_matches!(manager.schedules.get(&(0, 1, 1).into()), Some(schedule) => {
+            assert_matches!(
+                schedule.failed_reason,
+                Some(RecordingFailedReason::Preempted { .. })
+            );
+        });
+
+        // `RecordingFailed` is emitted only once when the recording pipeline
+        // exits.
+        let changed = manager.handle_recording_stopped((0, 1, 1).into()).await;
+        assert!(changed);
+        assert_matches!(manager.schedules.get(&(0, 1, 1).into()), Some(schedule) => {
+            assert_matches!(schedule.state, RecordingScheduleState::Failed);
+        });
+        assert_matches!(manager.records.get(&1), Some(record) => {
+            assert_matches!(
+                record.failed_reason,
+                Some(RecordingFailedReason::Preempted { .. })
+            );
+        });
+
+        // Ignore streams not used for recording.
+        manager.handle_preempted(Default::default(), preemptor);
+    }
+
+    #[tokio::test]
+    async fn test_handle_preempted_pipeline_error() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config);
+        let mut failed = MockRecordingFailedValidator::new();
+        failed.expect_emit().times(1).returning(|msg| {
+            assert_eq!(msg.program_id, (0, 1, 1).into());
+            assert_matches!(msg.reason, RecordingFailedReason::Preempted { .. });
+        });
+        manager.recording_failed.register(Emitter::new(failed));
+
+        let start_time = now - Duration::minutes(30);
+
+        let schedule = schedule!(
+            RecordingScheduleState::Recording,
+            program!((0, 1, 1), start_time, "1h"),
+            options!("1.m2ts", 0)
+        );
+        manager.schedules.insert((0, 1, 1).into(), schedule);
+        let recorder = recorder!(start_time, pipeline!["false"]);
+        manager.recorders.insert((0, 1, 1).into(), recorder);
+
+        let preemptor = tuner_user!(128, web; "web");
+        manager.handle_preempted(Default::default(), preemptor);
+
+        let changed = manager.handle_recording_stopped((0, 1, 1).into()).await;
+        assert!(changed);
+        assert_matches!(manager.schedules.get(&(0, 1, 1).into()), Some(schedule) => {
+            assert_matches!(schedule.state, RecordingScheduleState::Failed);
+            assert_matches!(
+                schedule.failed_reason,
+                Some(RecordingFailedReason::Preempted { .. })
+            );
+        });
+    }
+
+    #[tokio::test]
+    async fn test_handle_recording_stopped() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config);
+
+        let mut stopped = MockRecordingStoppedValidator::new();
+        stopped.expect_emit().times(1).returning(|msg| {
+            assert_eq!(msg.program_id, (0, 1, 1).into());
+        });
+        manager.recording_stopped.register(Emitter::new(stopped));
+
+        let mut failed = MockRecordingFailedValidator::new();
+        failed.expect_emit().never();
+        manager.recording_failed.register(Emitter::new(failed));
+
+        let start_time = now - Duration::minutes(30);
+
+        let schedule = schedule!(
+            RecordingScheduleState::Recording,
+            program!((0, 1, 1), start_time, "1h"),
+            options!("1.m2ts", 0),
+            hashset!["tag1".to_string()]
+        );
+        manager.schedules.insert((0, 1, 1).into(), schedule);
+
+        let recorder = recorder!(start_time, pipeline!["true"]);
+        manager.recorders.insert((0, 1, 1).into(), recorder);
+
+        let changed = manager.handle_recording_stopped((0, 1, 1).into()).await;
+        assert!(changed);
+        assert!(manager.recorders.get(&(0, 1, 1).into()).is_none());
+        assert_matches!(manager.schedules.get(&(0, 1, 1).into()), Some(schedule) => {
+            assert_matches!(schedule.state, RecordingScheduleState::Finished);
+        });
+        assert_eq!(manager.records.len(), 1);
+        assert_matches!(manager.records.get(&1), Some(record) => {
+            assert_eq!(record.program.id, (0, 1, 1).into());
+            assert_eq!(record.start_time, start_time);
+            assert!(record.failed_reason.is_none());
+            assert!(record.tags.contains("tag1"));
+        });
+    }
+
+    #[tokio::test]
+    async fn test_handle_recording_stopped_relay() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut epg = MockEpg::new();
+        epg.expect_call().times(1).returning(move |msg| {
+            assert_eq!(msg.service_id, (0, 2).into());
+            Ok(Arc::new(indexmap! {
+                2.into() => program!((0, 2, 2), now, "1h"),
+            }))
+        });
+
+        let mut manager = manager!(config, TunerManagerStub, epg, OnairProgramManagerStub);
+
+        let start_time = now - Duration::minutes(30);
+        let mut program = program!((0, 1, 1), start_time, "30m");
+        program.event_group = Some(epg::EventGroupDescriptor {
+            group_type: 2,
+            events: vec![epg::EventGroupEvent {
+                original_network_id: None,
+                transport_stream_id: None,
+                service_id: 2.into(),
+                event_id: 2.into(),
+            }],
+        });
+        let schedule = schedule!(
+            RecordingScheduleState::Recording,
+            program,
+            options!("1.m2ts", 0),
+            hashset!["tag1".to_string()]
+        );
+        manager.schedules.insert((0, 1, 1).into(), schedule);
+
+        let recorder = recorder!(start_time, pipeline!["true"]);
+        manager.recorders.insert((0, 1, 1).into(), recorder);
+
+        let changed = manager.handle_recording_stopped((0, 1, 1).into()).await;
+        assert!(changed);
+        assert_matches!(manager.schedules.get(&(0, 1, 1).into()), Some(schedule) => {
+            assert_eq!(schedule.relayed_to, Some((0, 2, 2).into()));
+        });
+        assert_matches!(manager.schedules.get(&(0, 2, 2).into()), Some(schedule) => {
+            assert_matches!(schedule.state, RecordingScheduleState::Scheduled);
+            assert_eq!(schedule.relayed_from, Some((0, 1, 1).into()));
+            assert!(schedule.tags.contains("tag1"));
+            assert_ne!(schedule.options.content_path, Path::new("1.m2ts"));
+        });
+        assert_matches!(manager.records.get(&1), Some(record) => {
+            assert_eq!(record.relayed_to, Some((0, 2, 2).into()));
+        });
+
+        // The relay is followed only once.
+        let changed = manager.follow_relay((0, 1, 1).into()).await;
+        assert!(!changed);
+    }
+
+    #[tokio::test]
+    async fn test_handle_recording_stopped_retry() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config);
+
+        let mut stopped = MockRecordingStoppedValidator::new();
+        stopped.expect_emit().times(1).returning(|msg| {
+            assert_eq!(msg.program_id, (0, 1, 1).into());
+        });
+        manager.recording_stopped.register(Emitter::new(stopped));
+
+        let mut failed = MockRecordingFailedValidator::new();
+        failed.expect_emit().times(1).returning(|msg| {
+            assert_eq!(msg.program_id, (0, 1, 1).into());
+            assert_matches!(msg.reason, RecordingFailedReason::NeedRescheduling);
+        });
+        manager.recording_failed.register(Emitter::new(failed));
+
+        let start_time = now - Duration::minutes(30);
+
+        let schedule = schedule!(
+            RecordingScheduleState::Recording,
+            program!((0, 1, 1), start_time, "1h"),
+            options!("1.m2ts", 0),
+            hashset!["tag1".to_string()]
+        );
+        manager.schedules.insert((0, 1, 1).into(), schedule);
+
+        let recorder = recorder!(
+            start_time,
+            pipeline![format!("sh -c 'exit {}'", EXIT_RETRY)]
+        );
+        manager.recorders.insert((0, 1, 1).into(), recorder);
+
+        let changed = manager.handle_recording_stopped((0, 1, 1).into()).await;
+        assert!(changed);
+        assert!(manager.recorders.get(&(0, 1, 1).into()).is_none());
+        assert_matches!(manager.schedules.get(&(0, 1, 1).into()), Some(schedule) => {
+            assert_matches!(schedule.state, RecordingScheduleState::Rescheduling);
+        });
+        assert!(manager.records.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_handle_recording_stopped_pipeline_error() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config);
+
+        let mut stopped = MockRecordingStoppedValidator::new();
+        stopped.expect_emit().times(1).returning(|msg| {
+            assert_eq!(msg.program_id, (0, 1, 1).into());
+        });
+        manager.recording_stopped.register(Emitter::new(stopped));
+
+        let mut failed = MockRecordingFailedValidator::new();
+        failed.expect_emit().times(1).returning(|msg| {
+            assert_eq!(msg.program_id, (0, 1, 1).into());
+            assert_matches!(
+                msg.reason,
+                RecordingFailedReason::PipelineError { exit_code: 1 }
+            );
+        });
+        manager.recording_failed.register(Emitter::new(failed));
+
+        let start_time = now - Duration::minutes(30);
+
+        let schedule = schedule!(
+            RecordingScheduleState::Recording,
+            program!((0, 1, 1), start_time, "1h"),
+            options!("1.m2ts", 0),
+            hashset!["tag1".to_string()]
+        );
+        manager.schedules.insert((0, 1, 1).into(), schedule);
+
+        let recorder = recorder!(start_time, pipeline!["false"]);
+        manager.recorders.insert((0, 1, 1).into(), recorder);
+
+        let changed = manager.handle_recording_stopped((0, 1, 1).into()).await;
+        assert!(changed);
+        assert!(manager.recorders.get(&(0, 1, 1).into()).is_none());
+        assert_matches!(manager.schedules.get(&(0, 1, 1).into()), Some(schedule) => {
+            assert_matches!(schedule.state, RecordingScheduleState::Failed);
+        });
+        assert_matches!(manager.records.get(&1), Some(record) => {
+            assert_matches!(
+                record.failed_reason,
+                Some(RecordingFailedReason::PipelineError { exit_code: 1 })
+            );
+        });
+        let path = temp_dir.path().join("history.jsonl");
+        let result = history::load_history(&path, &Default::default());
+        assert_matches!(result, Ok(entries) => {
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].event, RecordingHistoryEvent::Updated);
+            assert_eq!(entries[0].program_id, (0, 1, 1).into());
+            assert_matches!(entries[0].state, RecordingScheduleState::Failed);
+            assert_eq!(entries[0].exit_code, Some(1));
+            assert_eq!(entries[0].tuner_index, Some(0));
+            assert!(entries[0].tags.contains("tag1"));
+        });
+    }
+
+    #[test]
+    fn test_history_of_removed_schedules() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config);
+
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 1), now + Duration::hours(1), "1h"),
+            options!("1.m2ts", 0),
+            hashset!["tag1".to_string()]
+        );
+        assert_matches!(manager.add_schedule(schedule), Ok(()));
+
+        let schedule = schedule!(
+            RecordingScheduleState::Finished,
+            program!((0, 1, 2), now - Duration::hours(1), "1h"),
+            options!("2.m2ts", 0),
+            hashset!["tag1".to_string()]
+        );
+        assert_matches!(manager.add_schedule(schedule), Ok(()));
+
+        manager.remove_schedules(RemovalTarget::Tag("tag1".to_string()), now);
+        assert!(manager.schedules.is_empty());
+
+        // No entry is added for finished schedules when they are removed.
+        let path = temp_dir.path().join("history.jsonl");
+        let result = history::load_history(&path, &Default::default());
+        assert_matches!(result, Ok(entries) => {
+            assert_eq!(entries.len(), 3);
+            assert_eq!(entries[0].event, RecordingHistoryEvent::Added);
+            assert_eq!(entries[1].event, RecordingHistoryEvent::Added);
+            assert_eq!(entries[2].event, RecordingHistoryEvent::Removed);
+            assert_eq!(entries[2].program_id, (0, 1, 1).into());
+        });
+    }
+
+    #[tokio::test]
+    async fn test_update_schedules_by_epg_services() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut manager = manager!(config);
+        let mut mock = MockRecordingFailedValidator::new();
+        mock.expect_emit().times(1).returning(|msg| {
+            assert_eq!(msg.program_id, (0, 2, 1).into());
+            assert_matches!(msg.reason, RecordingFailedReason::RemovedFromEpg);
+        });
+        manager.recording_failed.register(Emitter::new(mock));
+
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 1), now),
+            options!("1.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 2, 1), now),
+            options!("2.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        let services = indexmap! {
+            (0, 1).into() => service!((0, 1), "test", channel_gr!("gr", "1")),
+        };
+        let changed = manager.update_schedules_by_epg_services(&services).await;
+        assert!(changed);
+        assert_eq!(manager.schedules.len(), 1);
+        assert!(manager.schedules.contains_key(&(0, 1, 1).into()));
+        assert!(!manager.schedules.contains_key(&(0, 2, 1).into()));
+
+        let changed = manager.update_schedules_by_epg_services(&services).await;
+        assert!(!changed);
+    }
+
+    #[tokio::test]
+    async fn test_update_schedules_by_epg_programs() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut epg = MockEpg::new();
+        {
+            let now = now;
+            epg.expect_call().returning(move |_| {
+                Ok(Arc::new(indexmap! {
+                    1.into() => program!((0, 1, 1), now, "1h"),
+                }))
+            });
+        }
+
+        let mut manager = manager!(config, TunerManagerStub, epg, OnairProgramManagerStub);
+        let mut failed_mock = MockRecordingFailedValidator::new();
+        failed_mock.expect_emit().times(1).returning(|msg| {
+            assert_eq!(msg.program_id, (0, 1, 2).into());
+            assert_matches!(msg.reason, RecordingFailedReason::RemovedFromEpg);
+        });
+        manager.recording_failed.register(Emitter::new(failed_mock));
+
+        let mut rescheduled_mock = MockRecordingRescheduledValidator::new();
+        rescheduled_mock.expect_emit().never();
+        manager
+            .recording_rescheduled
+            .register(Emitter::new(rescheduled_mock));
+
+        let schedule = schedule!(
+            RecordingScheduleState::Rescheduling,
+            program!((0, 1, 1), now),
+            options!("1.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        let schedule = schedule!(
+            RecordingScheduleState::Scheduled,
+            program!((0, 1, 2), now),
+            options!("2.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        let changed = manager
+            .update_schedules_by_epg_programs(now, (0, 1).into())
+            .await;
+        assert!(changed);
+        assert_eq!(manager.schedules.len(), 1);
+        assert!(manager.schedules.contains_key(&(0, 1, 1).into()));
+        assert!(!manager.schedules.contains_key(&(0, 1, 2).into()));
+
+        let changed = manager
+            .update_schedules_by_epg_programs(now, (0, 0).into())
+            .await;
+        assert!(!changed);
+    }
+
+    #[tokio::test]
+    async fn test_update_schedules_by_epg_programs_rescheduled() {
+        let now = Jst::now();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_for_test(temp_dir.path());
+
+        let mut epg = MockEpg::new();
+        {
+            let now = now;
+            epg.expect_call().returning(move |_| {
+                Ok(Arc::new(indexmap! {
+                    1.into() => program!((0, 1, 1), now, "1h"),
+                }))
+            });
+        }
+
+        let mut manager = manager!(config, TunerManagerStub, epg, OnairProgramManagerStub);
+
+        let mut mock = MockRecordingRescheduledValidator::new();
+        mock.expect_emit().times(1).returning(|msg| {
+            assert_eq!(msg.program_id, (0, 1, 1).into());
+        });
+        manager.recording_rescheduled.register(Emitter::new(mock));
+
+        let schedule = schedule!(
+            RecordingScheduleState::Rescheduling,
+            program!((0, 1, 1), now - Duration::minutes(30), "1h"),
+            options!("1.m2ts", 0)
+        );
+        let result = manager.add_schedule(schedule);
+        assert_matches!(result, Ok(()));
+
+        let changed = manager
+            .update_schedules_by_epg_programs(now, (0, 1).into())
+            .await;
+        assert!(changed);
+        assert_eq!(manager.schedules.len(), 1);
+        assert_matches!(manager.schedules.get(&(0, 1, 1).into()), Some(schedule) => {
+            assert_matches!(schedule.state, RecordingScheduleState::Scheduled);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_check_retry() {
+        // exit(0)
+        let mut pipeline: CommandPipeline<u8> = pipeline!["true"];
+        let results = pipeline.wait().await;
+        assert!(!check_retry(&results));
+
+        // exit(1)
+        let mut pipeline: CommandPipeline<u8> = pipeline!["false"];
+        let results = pipeline.wait().await;
+        assert!(!check_retry(&results));
+
+        // no such command
+        let mut pipeline: CommandPipeline<u8> = pipeline!["sh -c 'command_not_fond'"];
+        let results = pipeline.wait().await;
+        assert!(!check_retry(&results));
+
+        // retry
+        let mut pipeline: CommandPipeline<u8> = pipeline![format!("sh -c 'exit {}'", EXIT_RETRY)];
+        let results = pipeline.wait().await;
+        assert!(check_retry(&results));
+    }
+
+    #[tokio::test]
+    async fn test_recorder_get_first_error() {
+        // exit(0)
+        let mut pipeline: CommandPipeline<u8> = pipeline!["true"];
+        let results = pipeline.wait().await;
+        assert_matches!(get_first_error(&results), None);
+
+        // exit(1)
+        let mut pipeline: CommandPipeline<u8> = pipeline!["false"];
+        let results = pipeline.wait().await;
+        assert_matches!(get_first_error(&results), Some(1));
+    }
+
+    fn config_for_test<P: AsRef<Path>>(dir: P) -> Arc<Config> {
+        let mut config = Config::default();
+        config.recording.basedir = Some(dir.as_ref().to_owned());
+        Arc::new(config)
+    }
+
+    fn series_desc(series_id: u16, episode: u16, last_episode: u16) -> epg::SeriesDescriptor {
+        epg::SeriesDescriptor {
+            series_id,
+            repeat_label: 0,
+            program_pattern: 0,
+            expire_date: None,
+            episode_number: episode,
+            last_episode_number: last_episode,
+            series_name: None,
+        }
+    }
+
+    mockall::mock! {
+        Epg {}
+
+        #[async_trait]
+        impl Call<QueryPrograms> for Epg {
+            async fn call(&self, msg: QueryPrograms) -> actlet::Result<<QueryPrograms as Message>::Reply>;
+        }
+    }
+
+    mockall::mock! {
+        RecordingStoppedValidator {}
+
+        #[async_trait]
+        impl Emit<RecordingStopped> for RecordingStoppedValidator {
+            async fn emit(&self, msg: RecordingStopped);
+        }
+    }
+
+    mockall::mock! {
+        RecordingFailedValidator {}
+
+        #[async_trait]
+        impl Emit<RecordingFailed> for RecordingFailedValidator {
+            async fn emit(&self, msg: RecordingFailed);
+        }
+    }
+
+    mockall::mock! {
+        RecordingRescheduledValidator {}
+
+        #[async_trait]
+        impl Emit<RecordingRescheduled> for RecordingRescheduledValidator {
+            async fn emit(&self, msg: RecordingRescheduled);
+        }
+    }
+
+    mockall::mock! {
+        RecordingJobUpdatedValidator {}
+
+        #[async_trait]
+        impl Emit<RecordingJobUpdated> for RecordingJobUpdatedValidator {
+            async fn emit(&self, msg: RecordingJobUpdated);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod stub {
+    use super::*;
+
+    #[derive(Clone)]
+    pub(crate) struct RecordingManagerStub;
+
+    #[async_trait]
+    impl Call<AddRecordingSchedule> for RecordingManagerStub {
+        async fn call(
+            &self,
+            msg: AddRecordingSchedule,
+        ) -> actlet::Result<<AddRecordingSchedule as Message>::Reply> {
+            match msg.schedule.program.id.eid().value() {
+                // 0 is reserved for Error::ProgramNotFound
+                1 => Ok(Err(Error::AlreadyExists)),
+                2 => Ok(Err(Error::ProgramEnded)),
+                _ => Ok(Ok(msg.schedule)),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Call<AddManualRecordingSchedule> for RecordingManagerStub {
+        async fn call(
+            &self,
+            msg: AddManualRecordingSchedule,
+        ) -> actlet::Result<<AddManualRecordingSchedule as Message>::Reply> {
+            if msg.start_time >= msg.end_time {
+                return Ok(Err(Error::InvalidTimeRange));
+            }
+            let program_id = manual::manual_program_id(msg.service_id, |_| false).unwrap();
+            let program =
+                manual::manual_program(program_id, msg.start_time, msg.end_time, msg.name);
+            Ok(Ok(RecordingSchedule::new(
+                Arc::new(program),
+                msg.options,
+                msg.tags,
+            )))
+        }
+    }
+
+    #[async_trait]
+    impl Call<QueryRecordingSchedule> for RecordingManagerStub {
+        async fn call(
+            &self,
+            msg: QueryRecordingSchedule,
+        ) -> actlet::Result<<QueryRecordingSchedule as Message>::Reply> {
+            let mut program = EpgProgram::new(msg.program_id);
+            program.start_at = Some(Jst::now());
+            program.duration = Some(Duration::minutes(1));
+            match msg.program_id.eid().value() {
+                0 => Ok(Err(Error::ProgramNotFound)),
+                _ => Ok(Ok(schedule!(
+                    RecordingScheduleState::Scheduled,
+                    program!(msg.program_id, Jst::now(), "1m"),
+                    options!("test.m2ts", 1)
+                ))),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Call<QueryRecordingConflicts> for RecordingManagerStub {
+        async fn call(
+            &self,
+            _msg: QueryRecordingConflicts,
+        ) -> actlet::Result<<QueryRecordingConflicts as Message>::Reply> {
+            Ok(Ok(vec![RecordingConflict {
+                program_id: 1.into(),
+                reason: RecordingConflictReason::NoTunerAvailable,
+                competitors: vec![],
+            }]))
+        }
+    }
+
+    #[async_trait]
+    impl Call<QueryRecordingSchedules> for RecordingManagerStub {
+        async fn call(
+            &self,
+            _msg: QueryRecordingSchedules,
+        ) -> actlet::Result<<QueryRecordingSchedules as Message>::Reply> {
+            Ok(vec![])
+        }
+    }
+
+    #[async_trait]
+    impl Call<ReloadConfig> for RecordingManagerStub {
+        async fn call(
+            &self,
+            _msg: ReloadConfig,
+        ) -> actlet::Result<<ReloadConfig as Message>::Reply> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl Call<RegisterEmitter> for RecordingManagerStub {
+        async fn call(
+            &self,
+            _msg: RegisterEmitter,
+        ) -> actlet::Result<<RegisterEmitter as Message>::Reply> {
+            Ok(0)
+        }
+    }
+
+    stub_impl_fire! {RecordingManagerStub, UnregisterEmitter}
+
+    #[async_trait]
+    impl Call<RemoveRecordingSchedule> for RecordingManagerStub {
+        async fn call(
+            &self,
+            msg: RemoveRecordingSchedule,
+        ) -> actlet::Result<<RemoveRecordingSchedule as Message>::Reply> {
+            let mut program = EpgProgram::new(msg.program_id);
+            program.start_at = Some(Jst::now());
+            program.duration = Some(Duration::minutes(1));
+            match msg.program_id.eid().value() {
+                0 => Ok(Err(Error::ScheduleNotFound)),
+                _ => Ok(Ok(schedule!(
+                    RecordingScheduleState::Scheduled,
+                    program!(msg.program_id, Jst::now(), "1m"),
+                    options!("test.m2ts", 1)
+                ))),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Call<UpdateRecordingSchedule> for RecordingManagerStub {
+        async fn call(
+            &self,
+            msg: UpdateRecordingSchedule,
+        ) -> actlet::Result<<UpdateRecordingSchedule as Message>::Reply> {
+            match msg.program_id.eid().value() {
+                0 => Ok(Err(Error::ScheduleNotFound)),
+                1 => Ok(Err(Error::InvalidScheduleState)),
+                _ => {
+                    let mut schedule = schedule!(
+                        RecordingScheduleState::Scheduled,
+                        program!(msg.program_id, Jst::now(), "1m"),
+                        options!("test.m2ts", 1)
+                    );
+                    if let Some(options) = msg.options {
+                        schedule.options = options;
+                    }
+                    if let Some(tags) = msg.tags {
+                        schedule.tags = tags;
+                    }
+                    Ok(Ok(schedule))
+                }
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Call<RemoveRecordingSchedules> for RecordingManagerStub {
+        async fn call(
+            &self,
+            msg: RemoveRecordingSchedules,
+        ) -> actlet::Result<<RemoveRecordingSchedules as Message>::Reply> {
+            match msg.target {
+                RemovalTarget::All => Ok(()),
+                RemovalTarget::Tag(tag) => {
+                    assert_eq!(tag, "tag");
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Call<QueryRecordingRules> for RecordingManagerStub {
+        async fn call(
+            &self,
+            _msg: QueryRecordingRules,
+        ) -> actlet::Result<<QueryRecordingRules as Message>::Reply> {
+            Ok(vec![])
+        }
+    }
+
+    #[async_trait]
+    impl Call<QueryRecordingRule> for RecordingManagerStub {
+        async fn call(
+            &self,
+            msg: QueryRecordingRule,
+        ) -> actlet::Result<<QueryRecordingRule as Message>::Reply> {
+            match msg.id {
+                0 => Ok(Err(Error::RuleNotFound)),
+                id => Ok(Ok(rule!(id))),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Call<AddRecordingRule> for RecordingManagerStub {
+        async fn call(
+            &self,
+            msg: AddRecordingRule,
+        ) -> actlet::Result<<AddRecordingRule as Message>::Reply> {
+            Ok(Ok(RecordingRule { id: 1, ..msg.rule }))
+        }
+    }
+
+    #[async_trait]
+    impl Call<UpdateRecordingRule> for RecordingManagerStub {
+        async fn call(
+            &self,
+            msg: UpdateRecordingRule,
+        ) -> actlet::Result<<UpdateRecordingRule as Message>::Reply> {
+            match msg.rule.id {
+                0 => Ok(Err(Error::RuleNotFound)),
+                _ => Ok(Ok(msg.rule)),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Call<RemoveRecordingRule> for RecordingManagerStub {
+        async fn call(
+            &self,
+            msg: RemoveRecordingRule,
+        ) -> actlet::Result<<RemoveRecordingRule as Message>::Reply> {
+            match msg.id {
+                0 => Ok(Err(Error::RuleNotFound)),
+                id => Ok(Ok(rule!(id))),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Call<QueryRecordingSeriesList> for RecordingManagerStub {
+        async fn call(
+            &self,
+            _msg: QueryRecordingSeriesList,
+        ) -> actlet::Result<<QueryRecordingSeriesList as Message>::Reply> {
+            Ok(vec![])
+        }
+    }
+
+    #[async_trait]
+    impl Call<QueryRecordingSeries> for RecordingManagerStub {
+        async fn call(
+            &self,
+            msg: QueryRecordingSeries,
+        ) -> actlet::Result<<QueryRecordingSeries as Message>::Reply> {
+            match msg.id {
+                0 => Ok(Err(Error::SeriesNotFound)),
+                id => Ok(Ok(series!(id))),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Call<AddRecordingSeries> for RecordingManagerStub {
+        async fn call(
+            &self,
+            msg: AddRecordingSeries,
+        ) -> actlet::Result<<AddRecordingSeries as Message>::Reply> {
+            match msg.program_id.eid().value() {
+                0 => Ok(Err(Error::ProgramNotFound)),
+                1 => Ok(Err(Error::NoSeries)),
+                _ => Ok(Ok(series!(1))),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Call<RemoveRecordingSeries> for RecordingManagerStub {
+        async fn call(
+            &self,
+            msg: RemoveRecordingSeries,
+        ) -> actlet::Result<<RemoveRecordingSeries as Message>::Reply> {
+            match msg.id {
+                0 => Ok(Err(Error::SeriesNotFound)),
+                id => Ok(Ok(series!(id))),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Call<QueryRecordingRecords> for RecordingManagerStub {
+        async fn call(
+            &self,
+            _msg: QueryRecordingRecords,
+        ) -> actlet::Result<<QueryRecordingRecords as Message>::Reply> {
+            Ok(vec![])
+        }
+    }
+
+    #[async_trait]
+    impl Call<QueryRecordingRecord> for RecordingManagerStub {
+        async fn call(
+            &self,
+            msg: QueryRecordingRecord,
+        ) -> actlet::Result<<QueryRecordingRecord as Message>::Reply> {
+            match msg.id {
+                0 => Ok(Err(Error::RecordNotFound)),
+                id => {
+                    let mut record = record!(id);
+                    // Used for testing the streaming.
+                    record.content_path = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml").into();
+                    Ok(Ok(record))
+                }
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Call<UpdateRecordingRecord> for RecordingManagerStub {
+        async fn call(
+            &self,
+            msg: UpdateRecordingRecord,
+        ) -> actlet::Result<<UpdateRecordingRecord as Message>::Reply> {
+            match msg.id {
+                0 => Ok(Err(Error::RecordNotFound)),
+                id => {
+                    let mut record = record!(id);
+                    record.protected = msg.protected.unwrap_or_default();
+                    Ok(Ok(record))
+                }
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Call<RemoveRecordingRecord> for RecordingManagerStub {
+        async fn call(
+            &self,
+            msg: RemoveRecordingRecord,
+        ) -> actlet::Result<<RemoveRecordingRecord as Message>::Reply> {
+            match msg.id {
+                0 => Ok(Err(Error::RecordNotFound)),
+                id => Ok(Ok(record!(id))),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Call<QueryRecordingJobs> for RecordingManagerStub {
+        async fn call(
+            &self,
+            _msg: QueryRecordingJobs,
+        ) -> actlet::Result<<QueryRecordingJobs as Message>::Reply> {
+            Ok(vec![])
+        }
+    }
+
+    #[async_trait]
+    impl Call<QueryRecordingJob> for RecordingManagerStub {
+        async fn call(
+            &self,
+            msg: QueryRecordingJob,
+        ) -> actlet::Result<<QueryRecordingJob as Message>::Reply> {
+            match msg.id {
+                0 => Ok(Err(Error::JobNotFound)),
+                id => Ok(Ok(RecordingJob::new(id, 1, "true".to_string()))),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Call<QueryRecordingHistory> for RecordingManagerStub {
+        async fn call(
+            &self,
+            _msg: QueryRecordingHistory,
+        ) -> actlet::Result<<QueryRecordingHistory as Message>::Reply> {
+            Ok(Ok(vec![]))
+        }
+    }
+
+    #[async_trait]
+    impl Call<QueryRecordingRecorder> for RecordingManagerStub {
+        async fn call(
+            &self,
+            msg: QueryRecordingRecorder,
+        ) -> actlet::Result<<QueryRecordingRecorder as Message>::Reply> {
+            match msg.program_id.eid().value() {
+                0 => Ok(Err(Error::RecorderNotFound)),
+                _ => Ok(Ok(RecorderModel {
+                    program_id: msg.program_id,
+                    started_at: Jst::now(),
+                    pipeline: vec![],
+                })),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Call<QueryRecordingRecorders> for RecordingManagerStub {
+        async fn call(
+            &self,
+            _msg: QueryRecordingRecorders,
+        ) -> actlet::Result<<QueryRecordingRecorders as Message>::Reply> {
+            Ok(vec![])
+        }
+    }
+
+    #[async_trait]
+    impl Call<StartRecording> for RecordingManagerStub {
+        async fn call(
+            &self,
+            msg: StartRecording,
+        ) -> actlet::Result<<StartRecording as Message>::Reply> {
+            match msg.schedule.program.id.eid().value() {
+                0 => Ok(Err(Error::RecorderNotFound)),
+                _ => Ok(Ok(())),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Call<StopRecording> for RecordingManagerStub {
+        async fn call(
+            &self,
+            msg: StopRecording,
+        ) -> actlet::Result<<StopRecording as Message>::Reply> {
+            match msg.program_id.eid().value() {
+                0 => Ok(Err(Error::RecorderNotFound)),
+                _ => Ok(Ok(())),
+            }
+        }
+    }
+}
+// </coverage:exclude>
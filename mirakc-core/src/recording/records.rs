@@ -0,0 +1,174 @@
+use std::collections::HashSet;
+use std::io::SeekFrom;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use chrono::DateTime;
+use chrono_jst::serde::ts_milliseconds;
+use chrono_jst::Jst;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncSeekExt;
+use tokio::io::Take;
+use tokio_util::io::ReaderStream;
+
+use crate::epg::EpgProgram;
+use crate::epg::EpgService;
+use crate::error::Error;
+use crate::models::ProgramId;
+use crate::mpeg_ts_stream::MpegTsStream;
+use crate::mpeg_ts_stream::MpegTsStreamRange;
+use crate::ts_stats::TsStats;
+
+use super::resolve_content_path;
+use super::RecordingFailedReason;
+
+const CHUNK_SIZE: usize = 4096 * 8;
+
+pub type RecordingRecordStream = MpegTsStream<String, ReaderStream<Take<File>>>;
+
+/// A record of a TV program recorded.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingRecord {
+    pub id: u32,
+    pub program: Arc<EpgProgram>,
+    pub service: EpgService,
+    pub content_path: PathBuf,
+    #[serde(with = "ts_milliseconds")]
+    pub start_time: DateTime<Jst>,
+    #[serde(with = "ts_milliseconds")]
+    pub end_time: DateTime<Jst>,
+    // The size of the content file when the recording stopped.
+    pub size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failed_reason: Option<RecordingFailedReason>,
+    #[serde(default)]
+    pub tags: HashSet<String>,
+    // Protected records are never removed automatically.
+    #[serde(default)]
+    pub protected: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relayed_from: Option<ProgramId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relayed_to: Option<ProgramId>,
+    // Statistics of TS packets during the recording.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ts_stats: Option<TsStats>,
+}
+
+impl RecordingRecord {
+    pub fn content_path(&self, basedir: &Path) -> PathBuf {
+        resolve_content_path(basedir, &self.content_path)
+    }
+
+    pub async fn create_stream(
+        &self,
+        basedir: &Path,
+        start_pos: Option<u64>,
+        seekable: bool,
+    ) -> Result<RecordingRecordStream, Error> {
+        let path = self.content_path(basedir);
+        let mut file = File::open(&path).await?;
+        // The content file may be modified by post-processing after the
+        // recording stopped.
+        let size = file.metadata().await?.len();
+        let range = MpegTsStreamRange::bound(start_pos.unwrap_or(0), size)?;
+        tracing::debug!(
+            record.id = self.id,
+            ?path,
+            range.first,
+            range.bytes = range.bytes(),
+            "Start streaming"
+        );
+        file.seek(SeekFrom::Start(range.first)).await?;
+        let stream = ReaderStream::with_capacity(file.take(range.bytes()), CHUNK_SIZE);
+        let id = format!("recording/records/{}", self.id);
+        if seekable {
+            Ok(MpegTsStream::with_range(id, stream, range).decoded())
+        } else {
+            Ok(MpegTsStream::new(id, stream).decoded())
+        }
+    }
+}
+
+// <coverage:exclude>
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use tempfile::TempDir;
+    use tokio_stream::StreamExt;
+
+    #[tokio::test]
+    async fn test_create_stream() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("1.m2ts"), b"0123456789").unwrap();
+
+        let record = record_for_test("1.m2ts");
+
+        let result = record.create_stream(temp_dir.path(), None, true).await;
+        assert_matches!(result, Ok(stream) => {
+            assert_matches!(stream.range(), Some(range) => {
+                assert_eq!(range.first, 0);
+                assert_eq!(range.last, 9);
+                assert_eq!(range.size, Some(10));
+            });
+            assert_eq!(read_all(stream).await, b"0123456789");
+        });
+
+        let result = record.create_stream(temp_dir.path(), Some(4), true).await;
+        assert_matches!(result, Ok(stream) => {
+            assert_matches!(stream.range(), Some(range) => {
+                assert_eq!(range.first, 4);
+                assert_eq!(range.last, 9);
+                assert!(range.is_partial());
+            });
+            assert_eq!(read_all(stream).await, b"456789");
+        });
+
+        let result = record.create_stream(temp_dir.path(), Some(4), false).await;
+        assert_matches!(result, Ok(stream) => {
+            assert!(stream.range().is_none());
+            assert_eq!(read_all(stream).await, b"456789");
+        });
+
+        let result = record.create_stream(temp_dir.path(), Some(10), true).await;
+        assert_matches!(result, Err(Error::OutOfRange));
+
+        let record = record_for_test("2.m2ts");
+        let result = record.create_stream(temp_dir.path(), None, true).await;
+        assert_matches!(result, Err(Error::IoError(_)));
+    }
+
+    fn record_for_test(content_path: &str) -> RecordingRecord {
+        let now = Jst::now();
+        RecordingRecord {
+            id: 1,
+            program: Arc::new(program!((0, 1, 1), now, "1h")),
+            service: service!((0, 1), "test", channel_gr!("test", "1")),
+            content_path: content_path.into(),
+            start_time: now,
+            end_time: now,
+            size: 0,
+            failed_reason: None,
+            tags: Default::default(),
+            protected: false,
+            relayed_from: None,
+            relayed_to: None,
+            ts_stats: None,
+        }
+    }
+
+    async fn read_all(mut stream: RecordingRecordStream) -> Vec<u8> {
+        let mut data = vec![];
+        while let Some(Ok(chunk)) = stream.next().await {
+            data.extend_from_slice(&chunk);
+        }
+        data
+    }
+}
+// </coverage:exclude>
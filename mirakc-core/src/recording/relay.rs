@@ -0,0 +1,114 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::epg::EpgProgram;
+use crate::models::ProgramId;
+
+// Group types of EventGroupDescriptor defined in ARIB STD-B10.
+const RELAY_TO_INTERNAL_EVENT: u8 = 2;
+const RELAY_TO_EXTERNAL_EVENT: u8 = 4;
+
+// Returns the ID of the TV program to which the TV program relays.
+//
+// Only the first event in the descriptor is used.  Broadcasters don't relay a
+// TV program to multiple services at the same time in practice.
+pub(super) fn relay_target(program: &EpgProgram) -> Option<ProgramId> {
+    let event_group = program.event_group.as_ref()?;
+    let event = match event_group.group_type {
+        RELAY_TO_INTERNAL_EVENT | RELAY_TO_EXTERNAL_EVENT => event_group.events.first()?,
+        _ => return None,
+    };
+    // The network ID is omitted in relays to events in the same network.
+    let nid = event
+        .original_network_id
+        .unwrap_or_else(|| program.id.nid());
+    let program_id = ProgramId::new(nid, event.service_id, event.event_id);
+    if program_id == program.id {
+        return None;
+    }
+    Some(program_id)
+}
+
+// Returns a path of the content file for the continuation.
+//
+// The program ID is inserted before the extension so that each part is saved
+// into a different file.
+pub(super) fn relay_content_path(content_path: &Path, program_id: ProgramId) -> PathBuf {
+    let stem = content_path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let filename = match content_path.extension() {
+        Some(ext) => format!(
+            "{}.relay-{}.{}",
+            stem,
+            program_id.value(),
+            ext.to_string_lossy()
+        ),
+        None => format!("{}.relay-{}", stem, program_id.value()),
+    };
+    content_path.with_file_name(filename)
+}
+
+// <coverage:exclude>
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epg::EventGroupDescriptor;
+    use crate::epg::EventGroupEvent;
+
+    macro_rules! event_group {
+        ($group_type:expr, $nid:expr, $sid:expr, $eid:expr) => {
+            EventGroupDescriptor {
+                group_type: $group_type,
+                events: vec![EventGroupEvent {
+                    original_network_id: $nid.map(|nid: u16| nid.into()),
+                    transport_stream_id: None,
+                    service_id: $sid.into(),
+                    event_id: $eid.into(),
+                }],
+            }
+        };
+    }
+
+    #[test]
+    fn test_relay_target() {
+        let mut program = program!((1, 1, 1));
+        assert_eq!(relay_target(&program), None);
+
+        // Shared events are not relays.
+        program.event_group = Some(event_group!(1, None, 2, 2));
+        assert_eq!(relay_target(&program), None);
+
+        program.event_group = Some(event_group!(2, None, 2, 2));
+        assert_eq!(relay_target(&program), Some((1, 2, 2).into()));
+
+        program.event_group = Some(event_group!(4, Some(3), 2, 2));
+        assert_eq!(relay_target(&program), Some((3, 2, 2).into()));
+
+        // Relay to itself.
+        program.event_group = Some(event_group!(2, None, 1, 1));
+        assert_eq!(relay_target(&program), None);
+
+        program.event_group = Some(EventGroupDescriptor {
+            group_type: 2,
+            events: vec![],
+        });
+        assert_eq!(relay_target(&program), None);
+    }
+
+    #[test]
+    fn test_relay_content_path() {
+        let program_id = ProgramId::from((1, 2, 3));
+        let value = program_id.value();
+        assert_eq!(
+            relay_content_path(Path::new("dir/a.m2ts"), program_id),
+            PathBuf::from(format!("dir/a.relay-{}.m2ts", value))
+        );
+        assert_eq!(
+            relay_content_path(Path::new("a"), program_id),
+            PathBuf::from(format!("a.relay-{}", value))
+        );
+    }
+}
+// </coverage:exclude>
@@ -0,0 +1,537 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use chrono::DateTime;
+use chrono::Datelike;
+use chrono::NaiveTime;
+use chrono::Weekday;
+use chrono_jst::serde::ts_milliseconds;
+use chrono_jst::Jst;
+use regex::Regex;
+use serde::Deserialize;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::epg::EpgProgram;
+use crate::error::Error;
+use crate::models::ProgramId;
+use crate::models::ServiceId;
+
+use super::RecordingOptions;
+use super::RecordingSchedule;
+
+/// A rule for the automatic recording.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingRule {
+    pub id: u32,
+    #[serde(default)]
+    pub disabled: bool,
+    pub condition: RecordingRuleCondition,
+    pub options: RecordingOptions,
+    #[serde(default)]
+    pub tags: HashSet<String>,
+    // TV programs whose schedules have been removed by users.  The rule never
+    // adds schedules for them again.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded_programs: Vec<RecordingRuleExclusion>,
+}
+
+impl RecordingRule {
+    pub(super) fn validate(&self) -> Result<(), Error> {
+        // A rule without any of these filters matches almost all TV programs.
+        let condition = &self.condition;
+        let has_keyword = condition
+            .keyword
+            .as_deref()
+            .is_some_and(|keyword| !keyword.trim().is_empty());
+        if !has_keyword && condition.genres.is_empty() && condition.services.is_empty() {
+            tracing::error!(
+                rule.id = self.id,
+                "No keyword, genres nor services specified"
+            );
+            return Err(Error::InvalidRule);
+        }
+        RecordingRuleMatcher::new(condition)?;
+        Ok(())
+    }
+
+    pub(super) fn is_excluded(&self, program_id: ProgramId) -> bool {
+        self.excluded_programs
+            .iter()
+            .any(|exclusion| exclusion.program_id == program_id)
+    }
+
+    // Returns `true` if the TV program has been newly excluded.
+    pub(super) fn exclude(&mut self, program: &EpgProgram) -> bool {
+        let end_at = match program.end_at() {
+            Some(end_at) => end_at,
+            None => return false,
+        };
+        if self.is_excluded(program.id) {
+            return false;
+        }
+        self.excluded_programs.push(RecordingRuleExclusion {
+            program_id: program.id,
+            end_at,
+        });
+        true
+    }
+
+    // Returns `true` if some of the exclusions have been purged.
+    pub(super) fn purge_exclusions(&mut self, now: DateTime<Jst>) -> bool {
+        let len = self.excluded_programs.len();
+        self.excluded_programs
+            .retain(|exclusion| exclusion.end_at > now);
+        self.excluded_programs.len() != len
+    }
+
+    pub(super) fn create_schedule(&self, program: &EpgProgram) -> RecordingSchedule {
+        // `options.contentPath` is rendered when the recording starts.
+        RecordingSchedule::new(
+            Arc::new(program.clone()),
+            self.options.clone(),
+            self.tags.clone(),
+        )
+    }
+}
+
+// A TV program excluded from a rule.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingRuleExclusion {
+    pub program_id: ProgramId,
+    // Used for purging the exclusion after the TV program ends.
+    #[serde(with = "ts_milliseconds")]
+    pub end_at: DateTime<Jst>,
+}
+
+/// Conditions to be satisfied by TV programs to be recorded.
+///
+/// Every condition specified must be satisfied.  Conditions omitted are
+/// ignored.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[derive(ToSchema)]
+#[schema(title = "RecordingRuleCondition")]
+pub struct RecordingRuleCondition {
+    /// Keywords separated by whitespaces.
+    ///
+    /// The condition is satisfied when all the keywords are contained in the
+    /// name of a TV program.  Treated as a regular expression if `regex` is
+    /// `true`.
+    #[serde(default)]
+    pub keyword: Option<String>,
+    /// Keywords separated by whitespaces.
+    ///
+    /// The condition is NOT satisfied when any of the keywords is contained in
+    /// the name of a TV program.  Treated as a regular expression if `regex` is
+    /// `true`.
+    #[serde(default)]
+    pub excluded_keyword: Option<String>,
+    /// Use `keyword` and `excludedKeyword` as regular expressions.
+    #[serde(default)]
+    pub regex: bool,
+    /// Match keywords also with the description of a TV program.
+    #[serde(default)]
+    pub description: bool,
+    /// A list of genres.
+    ///
+    /// The condition is satisfied when any of the genres matches one of the
+    /// genres of a TV program.
+    #[serde(default)]
+    pub genres: Vec<RecordingRuleGenre>,
+    /// A list of Mirakurun service IDs.
+    #[serde(default)]
+    #[schema(value_type = Vec<u64>)]
+    pub services: Vec<ServiceId>,
+    /// A list of days of the week such as `Mon` and `Sun`.
+    ///
+    /// Compared with the start time of a TV program.
+    #[serde(default)]
+    #[schema(value_type = Vec<String>)]
+    pub weekdays: Vec<Weekday>,
+    /// A time range in which a TV program starts.
+    #[serde(default)]
+    pub time_range: Option<RecordingRuleTimeRange>,
+}
+
+/// A genre used in recording rules.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[derive(ToSchema)]
+#[schema(title = "RecordingRuleGenre")]
+pub struct RecordingRuleGenre {
+    /// The `lv1` value of a genre.
+    pub lv1: u8,
+    /// The `lv2` value of a genre.
+    ///
+    /// Any `lv2` value matches if omitted.
+    #[serde(default)]
+    pub lv2: Option<u8>,
+}
+
+/// A time range of the day in JST.
+///
+/// `end` can be smaller than `start`.  In this case, the time range crosses
+/// midnight.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[derive(ToSchema)]
+#[schema(title = "RecordingRuleTimeRange")]
+pub struct RecordingRuleTimeRange {
+    /// The start time of the range such as `21:00:00` (inclusive).
+    #[schema(value_type = String)]
+    pub start: NaiveTime,
+    /// The end time of the range such as `23:30:00` (exclusive).
+    #[schema(value_type = String)]
+    pub end: NaiveTime,
+}
+
+impl RecordingRuleTimeRange {
+    fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+}
+
+// A compiled form of `RecordingRuleCondition`.
+#[derive(Debug)]
+pub(super) struct RecordingRuleMatcher<'a> {
+    condition: &'a RecordingRuleCondition,
+    keywords: Vec<KeywordMatcher>,
+    excluded_keywords: Vec<KeywordMatcher>,
+}
+
+impl<'a> RecordingRuleMatcher<'a> {
+    pub(super) fn new(condition: &'a RecordingRuleCondition) -> Result<Self, Error> {
+        Ok(RecordingRuleMatcher {
+            condition,
+            keywords: KeywordMatcher::parse(condition.keyword.as_deref(), condition.regex)?,
+            excluded_keywords: KeywordMatcher::parse(
+                condition.excluded_keyword.as_deref(),
+                condition.regex,
+            )?,
+        })
+    }
+
+    pub(super) fn matches(&self, program: &EpgProgram) -> bool {
+        let start_at = match program.start_at {
+            Some(start_at) => start_at,
+            None => return false,
+        };
+
+        let condition = self.condition;
+
+        if !condition.services.is_empty()
+            && !condition.services.contains(&ServiceId::from(program.id))
+        {
+            return false;
+        }
+
+        if !condition.weekdays.is_empty() && !condition.weekdays.contains(&start_at.weekday()) {
+            return false;
+        }
+
+        if let Some(ref time_range) = condition.time_range {
+            if !time_range.contains(start_at.time()) {
+                return false;
+            }
+        }
+
+        if !condition.genres.is_empty() {
+            let genres = match program.genres {
+                Some(ref genres) => genres,
+                None => return false,
+            };
+            let found = genres.iter().any(|genre| {
+                condition.genres.iter().any(|cond| {
+                    cond.lv1 == genre.lv1 && (cond.lv2.is_none() || cond.lv2 == Some(genre.lv2))
+                })
+            });
+            if !found {
+                return false;
+            }
+        }
+
+        let name = program.name.as_deref().unwrap_or("");
+        let description = if condition.description {
+            program.description.as_deref()
+        } else {
+            None
+        };
+        let contains = |matcher: &KeywordMatcher| {
+            matcher.is_match(name) || description.is_some_and(|text| matcher.is_match(text))
+        };
+
+        if !self.keywords.iter().all(contains) {
+            return false;
+        }
+
+        if self.excluded_keywords.iter().any(contains) {
+            return false;
+        }
+
+        true
+    }
+}
+
+#[derive(Debug)]
+enum KeywordMatcher {
+    Text(String),
+    Regex(Regex),
+}
+
+impl KeywordMatcher {
+    fn parse(keyword: Option<&str>, regex: bool) -> Result<Vec<Self>, Error> {
+        let keyword = match keyword {
+            Some(keyword) => keyword,
+            None => return Ok(vec![]),
+        };
+        if regex {
+            // Whitespaces may be used in a regular expression.
+            match Regex::new(keyword) {
+                Ok(regex) => Ok(vec![KeywordMatcher::Regex(regex)]),
+                Err(err) => {
+                    tracing::error!(%err, keyword, "Invalid regular expression");
+                    Err(Error::InvalidRule)
+                }
+            }
+        } else {
+            Ok(keyword
+                .split_whitespace()
+                .map(|s| KeywordMatcher::Text(s.to_string()))
+                .collect())
+        }
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        match self {
+            KeywordMatcher::Text(keyword) => text.contains(keyword.as_str()),
+            KeywordMatcher::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+// <coverage:exclude>
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EpgGenre;
+    use assert_matches::assert_matches;
+    use chrono_jst::Jst;
+
+    #[test]
+    fn test_recording_rule_condition() {
+        assert_matches!(
+            serde_json::from_str::<RecordingRuleCondition>("{}"),
+            Ok(condition) => {
+                assert_eq!(condition.keyword, None);
+                assert_eq!(condition.excluded_keyword, None);
+                assert!(!condition.regex);
+                assert!(!condition.description);
+                assert!(condition.genres.is_empty());
+                assert!(condition.services.is_empty());
+                assert!(condition.weekdays.is_empty());
+                assert!(condition.time_range.is_none());
+            }
+        );
+
+        assert_matches!(
+            serde_json::from_str::<RecordingRuleCondition>(r#"{
+                "keyword": "a b",
+                "excludedKeyword": "c",
+                "regex": true,
+                "description": true,
+                "genres": [{ "lv1": 1 }, { "lv1": 2, "lv2": 3 }],
+                "services": [1],
+                "weekdays": ["Mon", "Sun"],
+                "timeRange": { "start": "21:00:00", "end": "02:00:00" }
+            }"#),
+            Ok(condition) => {
+                assert_eq!(condition.keyword, Some("a b".to_string()));
+                assert_eq!(condition.excluded_keyword, Some("c".to_string()));
+                assert!(condition.regex);
+                assert!(condition.description);
+                assert_eq!(condition.genres.len(), 2);
+                assert_eq!(condition.services, vec![1.into()]);
+                assert_eq!(condition.weekdays, vec![Weekday::Mon, Weekday::Sun]);
+                assert_matches!(condition.time_range, Some(range) => {
+                    assert_eq!(range.start, NaiveTime::from_hms_opt(21, 0, 0).unwrap());
+                    assert_eq!(range.end, NaiveTime::from_hms_opt(2, 0, 0).unwrap());
+                });
+            }
+        );
+    }
+
+    #[test]
+    fn test_matcher_keyword() {
+        let mut program = program!((0, 1, 1), jst!("2023-07-31T21:00:00+09:00"), "1h");
+        program.name = Some("ニュース 7".to_string());
+        program.description = Some("今日のニュース".to_string());
+
+        let condition = RecordingRuleCondition::default();
+        assert!(RecordingRuleMatcher::new(&condition)
+            .unwrap()
+            .matches(&program));
+
+        let condition = RecordingRuleCondition {
+            keyword: Some("ニュース 7".to_string()),
+            ..Default::default()
+        };
+        assert!(RecordingRuleMatcher::new(&condition)
+            .unwrap()
+            .matches(&program));
+
+        let condition = RecordingRuleCondition {
+            keyword: Some("ニュース 9".to_string()),
+            ..Default::default()
+        };
+        assert!(!RecordingRuleMatcher::new(&condition)
+            .unwrap()
+            .matches(&program));
+
+        let condition = RecordingRuleCondition {
+            keyword: Some("今日".to_string()),
+            ..Default::default()
+        };
+        assert!(!RecordingRuleMatcher::new(&condition)
+            .unwrap()
+            .matches(&program));
+
+        let condition = RecordingRuleCondition {
+            keyword: Some("今日".to_string()),
+            description: true,
+            ..Default::default()
+        };
+        assert!(RecordingRuleMatcher::new(&condition)
+            .unwrap()
+            .matches(&program));
+
+        let condition = RecordingRuleCondition {
+            keyword: Some("ニュース".to_string()),
+            excluded_keyword: Some("再 7".to_string()),
+            ..Default::default()
+        };
+        assert!(!RecordingRuleMatcher::new(&condition)
+            .unwrap()
+            .matches(&program));
+
+        let condition = RecordingRuleCondition {
+            keyword: Some("^ニュース [0-9]$".to_string()),
+            regex: true,
+            ..Default::default()
+        };
+        assert!(RecordingRuleMatcher::new(&condition)
+            .unwrap()
+            .matches(&program));
+
+        let condition = RecordingRuleCondition {
+            keyword: Some("(".to_string()),
+            regex: true,
+            ..Default::default()
+        };
+        assert_matches!(
+            RecordingRuleMatcher::new(&condition),
+            Err(Error::InvalidRule)
+        );
+    }
+
+    #[test]
+    fn test_matcher_genres() {
+        let mut program = program!((0, 1, 1), jst!("2023-07-31T21:00:00+09:00"), "1h");
+
+        let condition = RecordingRuleCondition {
+            genres: vec![RecordingRuleGenre { lv1: 1, lv2: None }],
+            ..Default::default()
+        };
+        assert!(!RecordingRuleMatcher::new(&condition)
+            .unwrap()
+            .matches(&program));
+
+        program.genres = Some(vec![EpgGenre::new((1, 2, 0, 0))]);
+        assert!(RecordingRuleMatcher::new(&condition)
+            .unwrap()
+            .matches(&program));
+
+        let condition = RecordingRuleCondition {
+            genres: vec![RecordingRuleGenre {
+                lv1: 1,
+                lv2: Some(3),
+            }],
+            ..Default::default()
+        };
+        assert!(!RecordingRuleMatcher::new(&condition)
+            .unwrap()
+            .matches(&program));
+    }
+
+    #[test]
+    fn test_matcher_services() {
+        let program = program!((0, 1, 1), Jst::now(), "1h");
+
+        let condition = RecordingRuleCondition {
+            services: vec![(0, 1).into()],
+            ..Default::default()
+        };
+        assert!(RecordingRuleMatcher::new(&condition)
+            .unwrap()
+            .matches(&program));
+
+        let condition = RecordingRuleCondition {
+            services: vec![(0, 2).into()],
+            ..Default::default()
+        };
+        assert!(!RecordingRuleMatcher::new(&condition)
+            .unwrap()
+            .matches(&program));
+    }
+
+    #[test]
+    fn test_matcher_weekdays_and_time_range() {
+        // Monday
+        let program = program!((0, 1, 1), jst!("2023-07-31T01:00:00+09:00"), "1h");
+
+        let condition = RecordingRuleCondition {
+            weekdays: vec![Weekday::Mon],
+            ..Default::default()
+        };
+        assert!(RecordingRuleMatcher::new(&condition)
+            .unwrap()
+            .matches(&program));
+
+        let condition = RecordingRuleCondition {
+            weekdays: vec![Weekday::Sun],
+            ..Default::default()
+        };
+        assert!(!RecordingRuleMatcher::new(&condition)
+            .unwrap()
+            .matches(&program));
+
+        let condition = RecordingRuleCondition {
+            time_range: Some(RecordingRuleTimeRange {
+                start: NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(1, 0, 0).unwrap(),
+            }),
+            ..Default::default()
+        };
+        assert!(!RecordingRuleMatcher::new(&condition)
+            .unwrap()
+            .matches(&program));
+
+        let condition = RecordingRuleCondition {
+            time_range: Some(RecordingRuleTimeRange {
+                start: NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(2, 0, 0).unwrap(),
+            }),
+            ..Default::default()
+        };
+        assert!(RecordingRuleMatcher::new(&condition)
+            .unwrap()
+            .matches(&program));
+    }
+}
+// </coverage:exclude>
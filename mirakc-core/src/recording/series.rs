@@ -0,0 +1,243 @@
+use std::collections::BTreeSet;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use chrono::DateTime;
+use chrono_jst::Jst;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::epg::EpgProgram;
+use crate::epg::SeriesDescriptor;
+use crate::error::Error;
+use crate::models::ServiceId;
+
+use super::RecordingOptions;
+use super::RecordingSchedule;
+
+/// A series of TV programs to be recorded.
+///
+/// A series is identified by a pair of a service ID and a series ID in the
+/// SeriesDescriptor of each episode.  Episodes broadcasted on other services
+/// are not recorded.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingSeries {
+    pub id: u32,
+    pub service_id: ServiceId,
+    pub series_id: u16,
+    #[serde(default)]
+    pub name: Option<String>,
+    // UNIX time in milliseconds.
+    #[serde(default)]
+    pub expire_date: Option<i64>,
+    // 0 means that the number of episodes is unknown.
+    #[serde(default)]
+    pub last_episode_number: u16,
+    // Episode numbers of episodes already scheduled.
+    #[serde(default)]
+    pub episodes: BTreeSet<u16>,
+    pub options: RecordingOptions,
+    #[serde(default)]
+    pub tags: HashSet<String>,
+}
+
+impl RecordingSeries {
+    pub(super) fn new(
+        id: u32,
+        program: &EpgProgram,
+        options: RecordingOptions,
+        tags: HashSet<String>,
+    ) -> Result<Self, Error> {
+        let desc = program.series.as_ref().ok_or(Error::NoSeries)?;
+        Ok(RecordingSeries {
+            id,
+            service_id: program.id.into(),
+            series_id: desc.series_id,
+            name: desc.series_name.clone().or_else(|| program.name.clone()),
+            expire_date: desc.expire_date,
+            last_episode_number: desc.last_episode_number,
+            episodes: Default::default(),
+            options,
+            tags,
+        })
+    }
+
+    pub(super) fn matches(&self, program: &EpgProgram) -> bool {
+        if ServiceId::from(program.id) != self.service_id {
+            return false;
+        }
+        match program.series {
+            Some(ref desc) => desc.series_id == self.series_id,
+            None => false,
+        }
+    }
+
+    // Broadcasters may change the expiration date and the number of episodes
+    // while the series is on the air.
+    pub(super) fn update(&mut self, desc: &SeriesDescriptor) -> bool {
+        let mut changed = false;
+        if self.expire_date != desc.expire_date {
+            self.expire_date = desc.expire_date;
+            changed = true;
+        }
+        if self.last_episode_number != desc.last_episode_number {
+            self.last_episode_number = desc.last_episode_number;
+            changed = true;
+        }
+        changed
+    }
+
+    // The series ID may be reused for another series after the expiration
+    // date.
+    pub(super) fn is_expired(&self, now: DateTime<Jst>) -> bool {
+        match self.expire_date {
+            Some(expire_date) => expire_date < now.timestamp_millis(),
+            None => false,
+        }
+    }
+
+    pub(super) fn is_completed(&self) -> bool {
+        self.last_episode_number != 0 && self.episodes.contains(&self.last_episode_number)
+    }
+
+    // Returns `false` if the episode has already been scheduled.
+    pub(super) fn add_episode(&mut self, program: &EpgProgram) -> bool {
+        match program.series {
+            // The episode number is 0 if it's unknown.  In this case, every
+            // program in the series will be scheduled.
+            Some(ref desc) if desc.episode_number != 0 => self.episodes.insert(desc.episode_number),
+            _ => true,
+        }
+    }
+
+    pub(super) fn create_schedule(&self, program: &EpgProgram) -> RecordingSchedule {
+        // `options.contentPath` is rendered when the recording starts.
+        RecordingSchedule::new(
+            Arc::new(program.clone()),
+            self.options.clone(),
+            self.tags.clone(),
+        )
+    }
+}
+
+// <coverage:exclude>
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use chrono::Duration;
+
+    macro_rules! series {
+        ($series_id:expr, $episode:expr, $last_episode:expr) => {
+            SeriesDescriptor {
+                series_id: $series_id,
+                repeat_label: 0,
+                program_pattern: 0,
+                expire_date: None,
+                episode_number: $episode,
+                last_episode_number: $last_episode,
+                series_name: Some("series".to_string()),
+            }
+        };
+    }
+
+    fn options() -> RecordingOptions {
+        RecordingOptions {
+            content_path: "{{{program_id}}}.m2ts".into(),
+            priority: 0,
+            pre_filters: vec![],
+            post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
+        }
+    }
+
+    #[test]
+    fn test_new() {
+        let program = program!((0, 1, 1));
+        let result = RecordingSeries::new(1, &program, options(), Default::default());
+        assert_matches!(result, Err(Error::NoSeries));
+
+        let mut program = program!((0, 1, 1));
+        program.series = Some(series!(1, 1, 10));
+        let result = RecordingSeries::new(1, &program, options(), Default::default());
+        assert_matches!(result, Ok(series) => {
+            assert_eq!(series.service_id, (0, 1).into());
+            assert_eq!(series.series_id, 1);
+            assert_eq!(series.name, Some("series".to_string()));
+            assert_eq!(series.last_episode_number, 10);
+            assert!(series.episodes.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_matches() {
+        let mut program = program!((0, 1, 1));
+        program.series = Some(series!(1, 1, 10));
+        let series = RecordingSeries::new(1, &program, options(), Default::default()).unwrap();
+
+        let mut program = program!((0, 1, 2));
+        program.series = Some(series!(1, 2, 10));
+        assert!(series.matches(&program));
+
+        // Another series.
+        let mut program = program!((0, 1, 3));
+        program.series = Some(series!(2, 1, 10));
+        assert!(!series.matches(&program));
+
+        // Another service.
+        let mut program = program!((0, 2, 1));
+        program.series = Some(series!(1, 2, 10));
+        assert!(!series.matches(&program));
+
+        // No series.
+        let program = program!((0, 1, 4));
+        assert!(!series.matches(&program));
+    }
+
+    #[test]
+    fn test_episodes() {
+        let mut program = program!((0, 1, 1));
+        program.series = Some(series!(1, 1, 2));
+        let mut series = RecordingSeries::new(1, &program, options(), Default::default()).unwrap();
+        assert!(!series.is_completed());
+
+        assert!(series.add_episode(&program));
+        assert!(!series.add_episode(&program));
+        assert!(!series.is_completed());
+
+        // Unknown episode numbers.
+        let mut program = program!((0, 1, 2));
+        program.series = Some(series!(1, 0, 2));
+        assert!(series.add_episode(&program));
+        assert!(series.add_episode(&program));
+        assert!(!series.is_completed());
+
+        let mut program = program!((0, 1, 3));
+        program.series = Some(series!(1, 2, 2));
+        assert!(series.add_episode(&program));
+        assert!(series.is_completed());
+    }
+
+    #[test]
+    fn test_is_expired() {
+        let now = Jst::now();
+
+        let mut program = program!((0, 1, 1));
+        program.series = Some(series!(1, 1, 0));
+        let mut series = RecordingSeries::new(1, &program, options(), Default::default()).unwrap();
+        assert!(!series.is_expired(now));
+
+        let mut desc = series!(1, 1, 0);
+        desc.expire_date = Some((now + Duration::days(1)).timestamp_millis());
+        assert!(series.update(&desc));
+        assert!(!series.update(&desc));
+        assert!(!series.is_expired(now));
+
+        desc.expire_date = Some((now - Duration::days(1)).timestamp_millis());
+        assert!(series.update(&desc));
+        assert!(series.is_expired(now));
+    }
+}
+// </coverage:exclude>
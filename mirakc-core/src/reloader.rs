@@ -0,0 +1,140 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use actlet::prelude::*;
+use tokio::sync::watch;
+use tokio::sync::Mutex;
+
+use crate::config::Config;
+use crate::error::Error;
+
+/// Reloads the config file at runtime and applies the new config to actors.
+///
+/// Only the following parts of the config are applied:
+///
+/// * `channels`
+/// * `tuners`, `tuner-selection` and `tuner-reservations`
+/// * `filters`, `pre-filters` and `post-filters`
+/// * `server.mounts`
+/// * `timeshift`
+///
+/// Other parts are ignored until mirakc restarts.
+pub struct ConfigReloader {
+    config_path: PathBuf,
+    sender: watch::Sender<Arc<Config>>,
+    // Serializes reload operations.
+    lock: Mutex<()>,
+}
+
+impl ConfigReloader {
+    pub fn new<P: Into<PathBuf>>(config_path: P, config: Arc<Config>) -> Self {
+        let (sender, _) = watch::channel(config);
+        ConfigReloader {
+            config_path: config_path.into(),
+            sender,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Returns the current config.
+    pub fn config(&self) -> Arc<Config> {
+        self.sender.borrow().clone()
+    }
+
+    /// Returns a receiver to be notified of the reloaded config.
+    pub fn subscribe(&self) -> watch::Receiver<Arc<Config>> {
+        self.sender.subscribe()
+    }
+
+    pub async fn reload<T, E, R, S>(
+        &self,
+        tuner_manager: &T,
+        epg: &E,
+        recording_manager: &R,
+        timeshift_manager: &S,
+    ) -> Result<Arc<Config>, Error>
+    where
+        T: Call<crate::tuner::ReloadConfig>,
+        E: Call<crate::epg::ReloadConfig>,
+        R: Call<crate::recording::ReloadConfig>,
+        S: Call<crate::timeshift::ReloadConfig>,
+    {
+        let _guard = self.lock.lock().await;
+
+        let config = crate::config::reload(&self.config_path)?;
+
+        let old_config = self.config();
+        if config.server.addrs != old_config.server.addrs {
+            tracing::warn!("Changes in server.addrs will be applied after restart");
+        }
+        if config.recording != old_config.recording {
+            tracing::warn!("Changes in recording will be applied after restart");
+        }
+        if config.maintenance != old_config.maintenance {
+            tracing::warn!("Changes in maintenance will be applied after restart");
+        }
+
+        // The tuner manager must be reloaded before the EPG because the EPG
+        // may start scanning services with the new tuners.
+        tuner_manager
+            .call(crate::tuner::ReloadConfig(config.clone()))
+            .await?;
+        epg.call(crate::epg::ReloadConfig(config.clone())).await?;
+        recording_manager
+            .call(crate::recording::ReloadConfig(config.clone()))
+            .await?;
+        timeshift_manager
+            .call(crate::timeshift::ReloadConfig(config.clone()))
+            .await?;
+
+        // Rebuild the web app with the new config.
+        self.sender.send_replace(config.clone());
+
+        tracing::info!("Applied the new config");
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epg::stub::EpgStub;
+    use crate::recording::stub::RecordingManagerStub;
+    use crate::timeshift::stub::TimeshiftManagerStub;
+    use crate::tuner::stub::TunerManagerStub;
+    use assert_matches::assert_matches;
+    use std::io::Write;
+
+    #[tokio::test]
+    async fn test_reload() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        let reloader = ConfigReloader::new(file.path(), Arc::new(Default::default()));
+        let mut rx = reloader.subscribe();
+
+        // Use an existing file in order to pass the validation.
+        writeln!(file, "resource:\n  strings-yaml: /bin/sh").unwrap();
+        let result = reloader
+            .reload(
+                &TunerManagerStub,
+                &EpgStub,
+                &RecordingManagerStub,
+                &TimeshiftManagerStub,
+            )
+            .await;
+        assert!(result.is_ok());
+        assert!(rx.has_changed().unwrap());
+        rx.borrow_and_update();
+
+        writeln!(file, "unknown: property").unwrap();
+        let result = reloader
+            .reload(
+                &TunerManagerStub,
+                &EpgStub,
+                &RecordingManagerStub,
+                &TimeshiftManagerStub,
+            )
+            .await;
+        assert_matches!(result, Err(Error::InvalidConfig(_)));
+        assert!(!rx.has_changed().unwrap());
+    }
+}
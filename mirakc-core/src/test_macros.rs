@@ -20,6 +20,11 @@ macro_rules! tuner {
             is_free: true,
             is_using: false,
             is_fault: false,
+            is_maintenance: false,
+            weight: 1,
+            role: crate::models::TunerRole::Any,
+            selection_policy: crate::models::TunerSelectionPolicy::First,
+            ts_stats: None,
         }
     };
 }
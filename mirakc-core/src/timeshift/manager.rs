@@ -20,6 +20,8 @@ pub struct TimeshiftManager<T, E> {
     epg: E,
     recorders: IndexMap<String, RecorderHolder<T>>,
     event_emitters: EmitterRegistry<TimeshiftEvent>,
+    // `true` if the health check has been started.
+    started: bool,
 }
 
 impl<T, E> TimeshiftManager<T, E> {
@@ -30,14 +32,12 @@ impl<T, E> TimeshiftManager<T, E> {
             epg,
             recorders: IndexMap::new(),
             event_emitters: Default::default(),
+            started: false,
         }
     }
 }
 
-// actor
-
-#[async_trait]
-impl<T, E> Actor for TimeshiftManager<T, E>
+impl<T, E> TimeshiftManager<T, E>
 where
     T: Clone + Send + Sync + 'static,
     T: Call<StartStreaming>,
@@ -45,14 +45,7 @@ where
     E: Send + Sync + 'static,
     E: Call<epg::RegisterEmitter>,
 {
-    async fn started(&mut self, ctx: &mut Context<Self>) {
-        tracing::debug!("Started");
-
-        if !self.config.timeshift.is_enabled() {
-            tracing::info!("Timeshift recording is disabled");
-            return;
-        }
-
+    async fn start(&mut self, ctx: &mut Context<Self>) {
         self.epg
             .call(epg::RegisterEmitter::ServicesUpdated(ctx.emitter()))
             .await
@@ -61,16 +54,7 @@ where
         // Spawn recorders regardless of whether its service is available or not.
         // Records should be accessible even if the service is unavailable.
         for (index, name) in self.config.timeshift.recorders.keys().enumerate() {
-            let addr = ctx
-                .spawn_actor(TimeshiftRecorder::new(
-                    index,
-                    name.clone(),
-                    self.config.clone(),
-                    self.tuner_manager.clone(),
-                    ctx.emitter(),
-                ))
-                .await;
-            let holder = RecorderHolder::new(addr);
+            let holder = RecorderHolder::new(self.spawn_recorder(index, name, ctx).await);
             self.recorders.insert(name.clone(), holder);
         }
 
@@ -98,6 +82,73 @@ where
             }
         };
         ctx.spawn_task(task);
+        self.started = true;
+    }
+
+    async fn spawn_recorder(
+        &self,
+        index: usize,
+        name: &str,
+        ctx: &mut Context<Self>,
+    ) -> Address<TimeshiftRecorder<T>> {
+        ctx.spawn_actor(TimeshiftRecorder::new(
+            index,
+            name.to_string(),
+            self.config.clone(),
+            self.tuner_manager.clone(),
+            ctx.emitter(),
+        ))
+        .await
+    }
+
+    // Recorders not changed keep recording.  Changed recorders are restarted
+    // in order to apply the changes.
+    async fn reload_recorders(&mut self, old_config: &Config, ctx: &mut Context<Self>) {
+        let mut recorders = IndexMap::new();
+        for (index, (name, config)) in self.config.timeshift.recorders.iter().enumerate() {
+            let holder = match self.recorders.shift_remove(name) {
+                Some(holder) if old_config.timeshift.recorders.get(name) == Some(config) => holder,
+                Some(holder) => {
+                    tracing::info!(recorder.name = name, "Changed, restart the recorder");
+                    holder.addr.emit(actlet::Stop).await;
+                    holder.addr.wait().await;
+                    RecorderHolder::new(self.spawn_recorder(index, name, ctx).await)
+                }
+                None => {
+                    tracing::info!(recorder.name = name, "Added");
+                    RecorderHolder::new(self.spawn_recorder(index, name, ctx).await)
+                }
+            };
+            recorders.insert(name.clone(), holder);
+        }
+        for (name, holder) in self.recorders.drain(..) {
+            tracing::info!(recorder.name = name, "Removed");
+            holder.addr.emit(actlet::Stop).await;
+        }
+        self.recorders = recorders;
+    }
+}
+
+// actor
+
+#[async_trait]
+impl<T, E> Actor for TimeshiftManager<T, E>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<epg::RegisterEmitter>,
+{
+    async fn started(&mut self, ctx: &mut Context<Self>) {
+        tracing::debug!("Started");
+
+        if !self.config.timeshift.is_enabled() {
+            tracing::info!("Timeshift recording is disabled");
+            return;
+        }
+
+        self.start(ctx).await;
     }
 
     async fn stopped(&mut self, _ctx: &mut Context<Self>) {
@@ -214,12 +265,77 @@ where
             let msg = QueryTimeshiftRecorder {
                 recorder: TimeshiftRecorderQuery::ByIndex(index),
             };
-            models.push(holder.addr.call(msg).await??);
+            let mut model = holder.addr.call(msg).await??;
+            // The index may change when the config is reloaded.
+            model.index = index;
+            models.push(model);
         }
         Ok(models)
     }
 }
 
+// query timeshift recorder
+
+#[async_trait]
+impl<T, E> Handler<QueryTimeshiftRecorder> for TimeshiftManager<T, E>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<epg::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: QueryTimeshiftRecorder,
+        _ctx: &mut Context<Self>,
+    ) -> <QueryTimeshiftRecorder as Message>::Reply {
+        let (index, holder) = match msg.recorder {
+            TimeshiftRecorderQuery::ByIndex(index) => self
+                .recorders
+                .get_index(index)
+                .map(|(_, holder)| (index, holder)),
+            TimeshiftRecorderQuery::ByName(ref name) => self
+                .recorders
+                .get_full(name)
+                .map(|(index, _, holder)| (index, holder)),
+        }
+        .ok_or(Error::RecordNotFound)?;
+        let mut model = holder.addr.call(msg).await??;
+        // The index may change when the config is reloaded.
+        model.index = index;
+        Ok(model)
+    }
+}
+
+// reload config
+
+#[async_trait]
+impl<T, E> Handler<ReloadConfig> for TimeshiftManager<T, E>
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<StartStreaming>,
+    T: TriggerFactory<StopStreaming>,
+    E: Send + Sync + 'static,
+    E: Call<epg::RegisterEmitter>,
+{
+    async fn handle(
+        &mut self,
+        msg: ReloadConfig,
+        ctx: &mut Context<Self>,
+    ) -> <ReloadConfig as Message>::Reply {
+        tracing::debug!(msg.name = "ReloadConfig");
+        let old_config = std::mem::replace(&mut self.config, msg.0);
+        if !self.started {
+            if self.config.timeshift.is_enabled() {
+                self.start(ctx).await;
+            }
+            return;
+        }
+        self.reload_recorders(&old_config, ctx).await;
+    }
+}
+
 // forward messages to a specified recorder
 
 macro_rules! impl_proxy_handler {
@@ -256,7 +372,6 @@ macro_rules! impl_proxy_handler {
     };
 }
 
-impl_proxy_handler!(QueryTimeshiftRecorder);
 impl_proxy_handler!(QueryTimeshiftRecords);
 impl_proxy_handler!(QueryTimeshiftRecord);
 impl_proxy_handler!(CreateTimeshiftLiveStreamSource);
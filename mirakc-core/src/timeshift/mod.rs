@@ -10,11 +10,14 @@ mod recorder;
 #[cfg(test)]
 pub(crate) mod stub;
 
+use std::sync::Arc;
+
 use actlet::prelude::*;
 use chrono::DateTime;
 use chrono::Duration;
 use chrono_jst::Jst;
 
+use crate::config::Config;
 use crate::epg::EpgService;
 use crate::error::Error;
 use crate::models::TimeshiftRecordId;
@@ -39,6 +42,10 @@ pub struct RegisterEmitter(pub Emitter<TimeshiftEvent>);
 #[derive(Message)]
 pub struct UnregisterEmitter(pub usize);
 
+#[derive(Message)]
+#[reply()]
+pub struct ReloadConfig(pub Arc<Config>);
+
 #[derive(Message)]
 #[reply(Result<Vec<TimeshiftRecorderModel>, Error>)]
 pub struct QueryTimeshiftRecorders;
@@ -15,6 +15,13 @@ impl Call<QueryTimeshiftRecorders> for TimeshiftManagerStub {
     }
 }
 
+#[async_trait]
+impl Call<ReloadConfig> for TimeshiftManagerStub {
+    async fn call(&self, _msg: ReloadConfig) -> actlet::Result<<ReloadConfig as Message>::Reply> {
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl Call<RegisterEmitter> for TimeshiftManagerStub {
     async fn call(
@@ -0,0 +1,338 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use serde::Deserialize;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+const TS_PACKET_SIZE: usize = 188;
+const TS_SYNC_BYTE: u8 = 0x47;
+const NULL_PID: u16 = 0x1FFF;
+
+// The bitrate is updated at this interval.
+const BITRATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Statistics of TS packets in a stream.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TsStats {
+    /// The number of TS packets inspected.
+    pub packets: u64,
+
+    /// The number of bytes inspected.
+    pub bytes: u64,
+
+    /// The number of times the sync byte was lost.
+    pub sync_losses: u64,
+
+    /// The number of TS packets scrambled.
+    pub scrambled_packets: u64,
+
+    /// The number of TS packets with the transport error indicator.
+    pub error_packets: u64,
+
+    /// The number of TS packets dropped, detected by the continuity counter.
+    pub cc_drops: u64,
+
+    /// The number of TS packets dropped in each PID.
+    ///
+    /// PIDs without drops are not included.
+    #[schema(value_type = Object)]
+    pub cc_drops_per_pid: BTreeMap<u16, u64>,
+
+    /// The bitrate in bits per second.
+    pub bitrate: u64,
+}
+
+impl TsStats {
+    /// Computes statistics of TS packets inspected after `base` was taken.
+    ///
+    /// The bitrate is computed as the average bitrate in `duration`.
+    pub fn since(&self, base: &TsStats, duration: Duration) -> TsStats {
+        let bytes = self.bytes.saturating_sub(base.bytes);
+        let mut cc_drops_per_pid = BTreeMap::new();
+        for (&pid, &n) in self.cc_drops_per_pid.iter() {
+            let n = n.saturating_sub(base.cc_drops_per_pid.get(&pid).cloned().unwrap_or(0));
+            if n > 0 {
+                cc_drops_per_pid.insert(pid, n);
+            }
+        }
+        TsStats {
+            packets: self.packets.saturating_sub(base.packets),
+            bytes,
+            sync_losses: self.sync_losses.saturating_sub(base.sync_losses),
+            scrambled_packets: self
+                .scrambled_packets
+                .saturating_sub(base.scrambled_packets),
+            error_packets: self.error_packets.saturating_sub(base.error_packets),
+            cc_drops: self.cc_drops.saturating_sub(base.cc_drops),
+            cc_drops_per_pid,
+            bitrate: compute_bitrate(bytes, duration),
+        }
+    }
+}
+
+/// A handle to share statistics updated by a `TsPacketInspector`.
+#[derive(Clone, Debug, Default)]
+pub struct TsStatsHandle(Arc<Mutex<TsStats>>);
+
+impl TsStatsHandle {
+    pub fn get(&self) -> TsStats {
+        self.0.lock().unwrap().clone()
+    }
+
+    fn set(&self, stats: &TsStats) {
+        self.0.lock().unwrap().clone_from(stats);
+    }
+}
+
+/// A lightweight inspector of TS packets.
+///
+/// Chunks given to the inspector don't need to be aligned to TS packets.
+pub struct TsPacketInspector {
+    stats: TsStats,
+    handle: TsStatsHandle,
+    // A TS packet split across chunks.
+    partial: Vec<u8>,
+    synced: bool,
+    last_cc: HashMap<u16, u8>,
+    window_start: Instant,
+    window_bytes: u64,
+}
+
+impl TsPacketInspector {
+    pub fn new(handle: TsStatsHandle) -> Self {
+        TsPacketInspector {
+            stats: Default::default(),
+            handle,
+            partial: Vec::with_capacity(TS_PACKET_SIZE),
+            synced: true,
+            last_cc: HashMap::new(),
+            window_start: Instant::now(),
+            window_bytes: 0,
+        }
+    }
+
+    pub fn inspect(&mut self, mut data: &[u8]) {
+        self.stats.bytes += data.len() as u64;
+        self.window_bytes += data.len() as u64;
+
+        if !self.partial.is_empty() {
+            let n = (TS_PACKET_SIZE - self.partial.len()).min(data.len());
+            self.partial.extend_from_slice(&data[..n]);
+            data = &data[n..];
+            if self.partial.len() < TS_PACKET_SIZE {
+                self.update_bitrate();
+                return;
+            }
+            let packet = std::mem::take(&mut self.partial);
+            self.inspect_packet(&packet);
+            self.partial = packet;
+            self.partial.clear();
+        }
+
+        while !data.is_empty() {
+            if data[0] != TS_SYNC_BYTE {
+                if self.synced {
+                    self.stats.sync_losses += 1;
+                    self.synced = false;
+                }
+                match data.iter().position(|&b| b == TS_SYNC_BYTE) {
+                    Some(pos) => data = &data[pos..],
+                    None => break,
+                }
+            }
+            if data.len() < TS_PACKET_SIZE {
+                self.partial.extend_from_slice(data);
+                break;
+            }
+            self.inspect_packet(&data[..TS_PACKET_SIZE]);
+            data = &data[TS_PACKET_SIZE..];
+        }
+
+        self.update_bitrate();
+    }
+
+    fn inspect_packet(&mut self, packet: &[u8]) {
+        self.synced = true;
+        self.stats.packets += 1;
+
+        let tei = packet[1] & 0x80 != 0;
+        if tei {
+            // Other fields in the header are not reliable.
+            self.stats.error_packets += 1;
+            return;
+        }
+
+        let pid = (((packet[1] & 0x1F) as u16) << 8) | packet[2] as u16;
+        if pid == NULL_PID {
+            return;
+        }
+
+        if packet[3] & 0xC0 != 0 {
+            self.stats.scrambled_packets += 1;
+        }
+
+        let has_adaptation = packet[3] & 0x20 != 0;
+        let has_payload = packet[3] & 0x10 != 0;
+        let cc = packet[3] & 0x0F;
+        let discontinuity = has_adaptation && packet[4] > 0 && packet[5] & 0x80 != 0;
+
+        let last_cc = self.last_cc.insert(pid, cc);
+        if discontinuity || !has_payload {
+            // The continuity counter doesn't increment.
+            return;
+        }
+        if let Some(last_cc) = last_cc {
+            // A duplicate packet has the same continuity counter.
+            if cc != last_cc && cc != (last_cc + 1) & 0x0F {
+                self.stats.cc_drops += 1;
+                *self.stats.cc_drops_per_pid.entry(pid).or_default() += 1;
+            }
+        }
+    }
+
+    fn update_bitrate(&mut self) {
+        let elapsed = self.window_start.elapsed();
+        if elapsed >= BITRATE_INTERVAL {
+            self.stats.bitrate = compute_bitrate(self.window_bytes, elapsed);
+            self.window_start = Instant::now();
+            self.window_bytes = 0;
+        }
+        self.handle.set(&self.stats);
+    }
+}
+
+fn compute_bitrate(bytes: u64, duration: Duration) -> u64 {
+    let secs = duration.as_secs_f64();
+    if secs > 0.0 {
+        (bytes as f64 * 8.0 / secs) as u64
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(pid: u16, cc: u8) -> Vec<u8> {
+        let mut packet = vec![0xFF; TS_PACKET_SIZE];
+        packet[0] = TS_SYNC_BYTE;
+        packet[1] = (pid >> 8) as u8 & 0x1F;
+        packet[2] = pid as u8;
+        packet[3] = 0x10 | (cc & 0x0F);
+        packet
+    }
+
+    #[test]
+    fn test_inspect() {
+        let handle = TsStatsHandle::default();
+        let mut inspector = TsPacketInspector::new(handle.clone());
+
+        let mut data = vec![];
+        data.extend(packet(0x100, 0));
+        data.extend(packet(0x100, 1));
+        data.extend(packet(0x100, 1)); // duplicate
+        data.extend(packet(0x100, 3)); // drop
+        data.extend(packet(0x101, 15));
+        data.extend(packet(0x101, 0));
+        data.extend(packet(NULL_PID, 5));
+        inspector.inspect(&data);
+
+        let stats = handle.get();
+        assert_eq!(stats.packets, 7);
+        assert_eq!(stats.bytes, 7 * TS_PACKET_SIZE as u64);
+        assert_eq!(stats.sync_losses, 0);
+        assert_eq!(stats.cc_drops, 1);
+        assert_eq!(stats.cc_drops_per_pid.get(&0x100), Some(&1));
+        assert_eq!(stats.cc_drops_per_pid.get(&0x101), None);
+    }
+
+    #[test]
+    fn test_inspect_split_packets() {
+        let handle = TsStatsHandle::default();
+        let mut inspector = TsPacketInspector::new(handle.clone());
+
+        let mut data = vec![];
+        data.extend(packet(0x100, 0));
+        data.extend(packet(0x100, 1));
+        data.extend(packet(0x100, 2));
+        for chunk in data.chunks(100) {
+            inspector.inspect(chunk);
+        }
+
+        let stats = handle.get();
+        assert_eq!(stats.packets, 3);
+        assert_eq!(stats.sync_losses, 0);
+        assert_eq!(stats.cc_drops, 0);
+    }
+
+    #[test]
+    fn test_inspect_sync_loss() {
+        let handle = TsStatsHandle::default();
+        let mut inspector = TsPacketInspector::new(handle.clone());
+
+        let mut data = vec![];
+        data.extend(packet(0x100, 0));
+        data.extend([0; 10]);
+        data.extend(packet(0x100, 1));
+        data.extend([0; 10]);
+        data.extend(packet(0x100, 2));
+        inspector.inspect(&data);
+
+        let stats = handle.get();
+        assert_eq!(stats.packets, 3);
+        assert_eq!(stats.sync_losses, 2);
+        assert_eq!(stats.cc_drops, 0);
+    }
+
+    #[test]
+    fn test_inspect_error_and_scrambled() {
+        let handle = TsStatsHandle::default();
+        let mut inspector = TsPacketInspector::new(handle.clone());
+
+        let mut data = vec![];
+        let mut error = packet(0x100, 0);
+        error[1] |= 0x80;
+        data.extend(error);
+        let mut scrambled = packet(0x100, 1);
+        scrambled[3] |= 0x80;
+        data.extend(scrambled);
+        inspector.inspect(&data);
+
+        let stats = handle.get();
+        assert_eq!(stats.packets, 2);
+        assert_eq!(stats.error_packets, 1);
+        assert_eq!(stats.scrambled_packets, 1);
+        assert_eq!(stats.cc_drops, 0);
+    }
+
+    #[test]
+    fn test_since() {
+        let base = TsStats {
+            packets: 10,
+            bytes: 1000,
+            cc_drops: 1,
+            cc_drops_per_pid: [(0x100, 1)].into(),
+            ..Default::default()
+        };
+        let stats = TsStats {
+            packets: 30,
+            bytes: 2000,
+            cc_drops: 3,
+            cc_drops_per_pid: [(0x100, 1), (0x101, 2)].into(),
+            ..Default::default()
+        };
+        let stats = stats.since(&base, Duration::from_secs(2));
+        assert_eq!(stats.packets, 20);
+        assert_eq!(stats.bytes, 1000);
+        assert_eq!(stats.cc_drops, 2);
+        assert_eq!(stats.cc_drops_per_pid, [(0x101, 2)].into());
+        assert_eq!(stats.bitrate, 4000);
+    }
+}
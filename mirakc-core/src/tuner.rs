@@ -1,10 +1,22 @@
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::atomic::AtomicU32;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
 
 use actlet::prelude::*;
+use chrono::DateTime;
+use chrono_jst::Jst;
+use futures::stream::AbortHandle;
+use once_cell::sync::Lazy;
+use tokio_util::io::StreamReader;
+use tokio_util::sync::CancellationToken;
+use url::Url;
 
 use crate::broadcaster::*;
 use crate::command_util::spawn_pipeline;
@@ -12,16 +24,35 @@ use crate::command_util::CommandPipeline;
 use crate::config::Config;
 use crate::config::FilterConfig;
 use crate::config::TunerConfig;
+use crate::config::TunerSelectionConfig;
 use crate::epg::EpgChannel;
 use crate::error::Error;
+use crate::file_util;
 use crate::models::*;
 use crate::mpeg_ts_stream::MpegTsStream;
+use crate::ts_stats::TsStats;
+use crate::ts_stats::TsStatsHandle;
+use crate::tuner_reservation::assign_tuners;
+use crate::tuner_reservation::TunerReservation;
+use crate::tuner_reservation::TunerReservationTarget;
+
+// A short timeout is used for connecting to upstream servers so that the
+// tuner manager is not blocked for a long time by an unreachable server.
+const UPSTREAM_CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+// Shared by all remote tuners in order to reuse connections.
+static UPSTREAM_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .connect_timeout(UPSTREAM_CONNECT_TIMEOUT)
+        .build()
+        .expect("Failed to build an HTTP client for upstream servers")
+});
 
 // identifiers
 
 type TunerStream = MpegTsStream<TunerSubscriptionId, BroadcasterStream>;
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
 #[cfg_attr(test, derive(Debug, Default))]
 pub struct TunerSessionId {
     tuner_index: usize,
@@ -37,6 +68,10 @@ impl TunerSessionId {
             session_number,
         }
     }
+
+    pub fn tuner_index(&self) -> usize {
+        self.tuner_index
+    }
 }
 
 impl fmt::Display for TunerSessionId {
@@ -59,6 +94,10 @@ impl TunerSubscriptionId {
             serial_number,
         }
     }
+
+    pub fn tuner_index(&self) -> usize {
+        self.session_id.tuner_index
+    }
 }
 
 impl fmt::Display for TunerSubscriptionId {
@@ -73,6 +112,17 @@ pub struct TunerManager {
     config: Arc<Config>,
     tuners: Vec<Tuner>,
     event_emitters: EmitterRegistry<Event>,
+    // Used for the round-robin selection.
+    last_selected: Option<usize>,
+    reservations: Vec<TunerReservation>,
+    next_reservation_id: u32,
+    reservation_timer_token: Option<CancellationToken>,
+    // The path to a file to save names of tuners in maintenance.
+    //
+    // Changes of `config.maintenance` are applied after restart.
+    maintenance_path: Option<PathBuf>,
+    // The path to a file to save reservations added via Web API.
+    reservations_path: Option<PathBuf>,
 }
 
 struct TunerSubscription {
@@ -99,10 +149,26 @@ impl TunerSubscription {
 
 impl TunerManager {
     pub fn new(config: Arc<Config>) -> Self {
+        let maintenance_path = config
+            .maintenance
+            .state_dir
+            .as_ref()
+            .map(|dir| dir.join("tuners.json"));
+        let reservations_path = config
+            .maintenance
+            .state_dir
+            .as_ref()
+            .map(|dir| dir.join("tuner-reservations.json"));
         TunerManager {
             config,
             tuners: Vec::new(),
             event_emitters: Default::default(),
+            last_selected: None,
+            reservations: vec![],
+            next_reservation_id: 1,
+            reservation_timer_token: None,
+            maintenance_path,
+            reservations_path,
         }
     }
 
@@ -113,23 +179,345 @@ impl TunerManager {
             .iter()
             .filter(|config| !config.disabled)
             .enumerate()
-            .map(|(i, config)| match config.dedicated_for {
-                Some(ref name) => {
-                    let dedicated_for = self
-                        .config
-                        .onair_program_trackers
-                        .get(name)
-                        .map(|_| TunerUserInfo::OnairProgramTracker(name.to_string()));
-                    (i, config, dedicated_for)
-                }
-                None => (i, config, None),
+            .map(|(i, config)| {
+                let mut tuner = Tuner::new(i, config, self.dedicated_for(config));
+                tuner.selection_policy = self.config.tuner_selection.policy;
+                tuner
             })
-            .map(|(i, config, dedicated_for)| Tuner::new(i, config, dedicated_for))
             .collect();
         tracing::info!(tuners.len = tuners.len(), "Loaded tuners");
         self.tuners = tuners;
     }
 
+    // Applies changes of `config.tuners` to the tuners.
+    //
+    // Indexes of the tuners never change in order to keep IDs of running
+    // sessions valid.  A tuner removed from `config.tuners` is hidden and no
+    // longer used, but it keeps working until all users of the tuner stop
+    // streaming.  New tuners are appended.
+    async fn reload_tuners(&mut self, ctx: &Context<Self>) {
+        let emitter = Emitter::new(ctx.address().clone());
+        for tuner in self.tuners.iter_mut() {
+            tuner.removed = true;
+        }
+        for config in self.config.tuners.iter().filter(|config| !config.disabled) {
+            let dedicated_for = self.dedicated_for(config);
+            let found = self
+                .tuners
+                .iter_mut()
+                .find(|tuner| tuner.name == config.name);
+            let tuner = match found {
+                Some(tuner) => {
+                    if tuner.removed {
+                        tracing::info!(tuner.index, tuner.name, "Restored");
+                    }
+                    tuner.removed = false;
+                    tuner.reconfigure(config, dedicated_for);
+                    tuner
+                }
+                None => {
+                    let index = self.tuners.len();
+                    let mut tuner = Tuner::new(index, config, dedicated_for);
+                    tuner.health_emitter = Some(emitter.clone());
+                    tracing::info!(tuner.index, tuner.name, "Added");
+                    self.tuners.push(tuner);
+                    self.tuners.last_mut().unwrap()
+                }
+            };
+            tuner.selection_policy = self.config.tuner_selection.policy;
+        }
+        for tuner in self.tuners.iter().filter(|tuner| tuner.removed) {
+            tracing::info!(tuner.index, tuner.name, "Removed");
+        }
+        self.last_selected = None;
+        for tuner in self.tuners.iter() {
+            self.event_emitters
+                .emit(Event::StatusChanged(tuner.index))
+                .await;
+        }
+    }
+
+    fn load_maintenance(&mut self) {
+        fn do_load(path: &Path) -> Result<Vec<String>, Error> {
+            let file = std::fs::File::open(path)?;
+            Ok(serde_json::from_reader(file)?)
+        }
+
+        let path = match self.maintenance_path {
+            Some(ref path) => path,
+            None => return,
+        };
+
+        if !path.exists() {
+            return;
+        }
+
+        match do_load(path) {
+            Ok(names) => {
+                tracing::info!(?path, "Loaded");
+                for tuner in self.tuners.iter_mut() {
+                    if names.contains(&tuner.name) {
+                        tracing::info!(tuner.index, tuner.name, "In maintenance");
+                        tuner.maintenance = true;
+                    }
+                }
+            }
+            Err(err) => {
+                tracing::warn!(%err, ?path, "Failed to load");
+            }
+        }
+    }
+
+    fn save_maintenance(&self) {
+        let path = match self.maintenance_path {
+            Some(ref path) => path,
+            None => return,
+        };
+
+        // Tuners removed from `config.tuners` are also saved so that they're
+        // kept in maintenance when restored.
+        let names: Vec<&str> = self
+            .tuners
+            .iter()
+            .filter(|tuner| tuner.maintenance)
+            .map(|tuner| tuner.name.as_str())
+            .collect();
+        if file_util::save_json(&names, path) {
+            tracing::info!(tuners.len = names.len(), "Saved tuners in maintenance");
+        } else {
+            tracing::error!("Failed to save tuners in maintenance");
+        }
+    }
+
+    // Loads reservations added via Web API before restart.
+    fn load_saved_reservations(&mut self) {
+        fn do_load(path: &Path) -> Result<Vec<TunerReservation>, Error> {
+            let file = std::fs::File::open(path)?;
+            Ok(serde_json::from_reader(file)?)
+        }
+
+        let path = match self.reservations_path {
+            Some(ref path) => path,
+            None => return,
+        };
+
+        if !path.exists() {
+            return;
+        }
+
+        match do_load(path) {
+            Ok(reservations) => {
+                tracing::info!(?path, "Loaded");
+                let now = Jst::now();
+                for reservation in reservations.into_iter() {
+                    if reservation.from_config || reservation.window.is_expired(now) {
+                        continue;
+                    }
+                    self.next_reservation_id = self.next_reservation_id.max(reservation.id + 1);
+                    self.reservations.push(reservation);
+                }
+            }
+            Err(err) => {
+                tracing::warn!(%err, ?path, "Failed to load");
+            }
+        }
+    }
+
+    fn save_reservations(&self) {
+        let path = match self.reservations_path {
+            Some(ref path) => path,
+            None => return,
+        };
+
+        // Reservations defined in `config.tuner-reservations` are not saved.
+        let reservations: Vec<&TunerReservation> = self
+            .reservations
+            .iter()
+            .filter(|reservation| !reservation.from_config)
+            .collect();
+        if file_util::save_json(&reservations, path) {
+            tracing::info!(reservations.len = reservations.len(), "Saved reservations");
+        } else {
+            tracing::error!("Failed to save reservations");
+        }
+    }
+
+    // Replaces reservations defined in `config.tuner-reservations`.
+    //
+    // Reservations added via Web API are kept.
+    fn load_reservations(&mut self) {
+        self.reservations
+            .retain(|reservation| !reservation.from_config);
+        for config in self.config.tuner_reservations.iter() {
+            let id = self.next_reservation_id;
+            self.next_reservation_id += 1;
+            self.reservations
+                .push(TunerReservation::from_config(id, config));
+        }
+        tracing::info!(
+            reservations.len = self.reservations.len(),
+            "Loaded reservations"
+        );
+    }
+
+    // Returns `true` if reservations added via Web API have been removed.
+    fn remove_expired_reservations(&mut self) -> bool {
+        let now = Jst::now();
+        let mut changed = false;
+        self.reservations.retain(|reservation| {
+            if reservation.window.is_expired(now) {
+                tracing::debug!(reservation.id, "Expired");
+                changed |= !reservation.from_config;
+                false
+            } else {
+                true
+            }
+        });
+        changed
+    }
+
+    // Returns reservations in effect at `now` for each tuner index.
+    //
+    // Tuners dedicated for on-air program trackers are never reserved.
+    fn reservations_at(&self, now: DateTime<Jst>) -> HashMap<usize, Vec<&TunerReservation>> {
+        let tuners: Vec<&Tuner> = self
+            .tuners
+            .iter()
+            .filter(|tuner| !tuner.removed)
+            .filter(|tuner| tuner.dedicated_for.is_none())
+            .collect();
+        let targets: Vec<(&str, &[ChannelType])> = tuners
+            .iter()
+            .map(|tuner| (tuner.name.as_str(), tuner.channel_types.as_slice()))
+            .collect();
+        let reservations = self
+            .reservations
+            .iter()
+            .filter(|reservation| reservation.window.contains(now));
+        assign_tuners(reservations, &targets)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, reservations)| !reservations.is_empty())
+            .map(|(i, reservations)| (tuners[i].index, reservations))
+            .collect()
+    }
+
+    // Returns indexes of tuners which cannot be used by the user at the
+    // moment due to reservations.
+    fn reserved_tuners(&self, user: &TunerUser) -> HashSet<usize> {
+        self.reservations_at(Jst::now())
+            .into_iter()
+            .filter(|(_, reservations)| {
+                reservations
+                    .iter()
+                    .any(|reservation| !reservation.permits(user))
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    // Stops streaming for users who are not permitted to use reserved tuners.
+    //
+    // Other users keep using the tuners.
+    async fn enforce_reservations<C>(&mut self, ctx: &C)
+    where
+        C: Spawn,
+    {
+        let reservations: Vec<(usize, Vec<TunerReservation>)> = self
+            .reservations_at(Jst::now())
+            .into_iter()
+            .map(|(index, reservations)| (index, reservations.into_iter().cloned().collect()))
+            .collect();
+        for (index, reservations) in reservations.into_iter() {
+            let tuner = &mut self.tuners[index];
+            let mut changed = false;
+            for (id, user) in tuner.activity.subscribers() {
+                let found = reservations
+                    .iter()
+                    .find(|reservation| !reservation.permits(&user));
+                let reservation = match found {
+                    Some(reservation) => reservation,
+                    None => continue,
+                };
+                let preemptor = reservation.user();
+                tracing::info!(
+                    tuner.index,
+                    stream.id = %id,
+                    user.info = %user.info,
+                    user.priority = %user.priority,
+                    reservation.id,
+                    "Preempted by reservation"
+                );
+                Self::emit_preempted(
+                    &self.event_emitters,
+                    Event::Preempted {
+                        id,
+                        user,
+                        preemptor,
+                    },
+                    ctx,
+                );
+                let _ = tuner.stop_streaming(id).await;
+                changed = true;
+            }
+            if changed {
+                self.event_emitters
+                    .emit(Event::StatusChanged(tuner.index))
+                    .await;
+            }
+        }
+    }
+
+    // Emits `Event::Preempted` from a separate task.
+    //
+    // A preempted user such as `RecordingManager` may be waiting for a reply
+    // from this actor.  Waiting for the emission here causes a deadlock when
+    // the message queue of the user is full.
+    fn emit_preempted<C>(emitters: &EmitterRegistry<Event>, event: Event, ctx: &C)
+    where
+        C: Spawn,
+    {
+        let emitters = emitters.clone();
+        ctx.spawn_task(async move {
+            emitters.emit(event).await;
+        });
+    }
+
+    // Sets a timer to enforce reservations when the next one starts.
+    fn set_reservation_timer<C>(&mut self, ctx: &C)
+    where
+        C: Spawn + EmitterFactory<CheckReservations>,
+    {
+        if let Some(token) = self.reservation_timer_token.take() {
+            token.cancel();
+        }
+        let now = Jst::now();
+        let next_start = self
+            .reservations
+            .iter()
+            .filter_map(|reservation| reservation.window.next_start(now))
+            .min();
+        if let Some(next_start) = next_start {
+            tracing::debug!(%next_start, "Set reservation timer");
+            let duration = (next_start - now).to_std().unwrap_or_default();
+            let emitter = ctx.emitter();
+            let token = ctx.spawn_task(async move {
+                tokio::time::sleep(duration).await;
+                emitter.emit(CheckReservations).await;
+            });
+            self.reservation_timer_token = Some(token);
+        }
+    }
+
+    fn dedicated_for(&self, config: &TunerConfig) -> Option<TunerUserInfo> {
+        config.dedicated_for.as_ref().and_then(|name| {
+            self.config
+                .onair_program_trackers
+                .get(name)
+                .map(|_| TunerUserInfo::OnairProgramTracker(name.to_string()))
+        })
+    }
+
     async fn activate_tuner<C>(
         &mut self,
         channel: &EpgChannel,
@@ -141,8 +529,14 @@ impl TunerManager {
         C: Spawn,
     {
         if let Some(stream_id) = stream_id {
+            // The same checks as the normal allocation below are applied.
+            let reserved = self.reserved_tuners(user);
             let tuner = &mut self.tuners[stream_id.session_id.tuner_index];
-            if tuner.is_subscribed(&stream_id) {
+            if tuner.is_subscribed(stream_id)
+                && !tuner.maintenance
+                && tuner.role.accepts(&user.info)
+                && !reserved.contains(&tuner.index)
+            {
                 tracing::debug!(tuner.index, %channel, %user.info, stream.id = %stream_id, "Reuse specified tuner");
                 self.event_emitters
                     .emit(Event::StatusChanged(tuner.index))
@@ -156,6 +550,9 @@ impl TunerManager {
         let found = self
             .tuners
             .iter_mut()
+            .filter(|tuner| !tuner.removed)
+            .filter(|tuner| !tuner.is_fault())
+            .filter(|tuner| !tuner.maintenance)
             .find(|tuner| tuner.is_dedicated_for(&user));
         if let Some(tuner) = found {
             tracing::debug!(tuner.index, %channel, %user.info, "Use dedicated tuner");
@@ -165,7 +562,7 @@ impl TunerManager {
                     &channel,
                     &self.config.filters.tuner_filter,
                 )?;
-                tuner.activate(channel, filters, ctx).await?;
+                tuner.activate(channel, user.priority, filters, ctx).await?;
             }
             self.event_emitters
                 .emit(Event::StatusChanged(tuner.index))
@@ -173,10 +570,17 @@ impl TunerManager {
             return Ok(tuner.subscribe(user));
         }
 
+        let reserved = self.reserved_tuners(user);
+
         let found = self
             .tuners
             .iter_mut()
+            .filter(|tuner| !tuner.removed)
             .filter(|tuner| tuner.dedicated_for.is_none())
+            .filter(|tuner| !tuner.is_fault())
+            .filter(|tuner| !tuner.maintenance)
+            .filter(|tuner| tuner.role.accepts(&user.info))
+            .filter(|tuner| !reserved.contains(&tuner.index))
             .find(|tuner| tuner.is_reuseable(&channel));
         if let Some(tuner) = found {
             tracing::debug!(tuner.index, %channel, %user.info, "Reuse active tuner");
@@ -186,39 +590,81 @@ impl TunerManager {
             return Ok(tuner.subscribe(user));
         }
 
-        let found = self
-            .tuners
-            .iter_mut()
-            .filter(|tuner| tuner.dedicated_for.is_none())
-            .find(|tuner| tuner.is_available_for(&channel));
-        if let Some(tuner) = found {
+        let candidates = self.select_tuners(channel, |tuner| {
+            tuner.dedicated_for.is_none()
+                && tuner.role.accepts(&user.info)
+                && !reserved.contains(&tuner.index)
+                && tuner.is_available_for(channel)
+        });
+        for index in candidates {
+            self.last_selected = Some(index);
+            let tuner = &mut self.tuners[index];
             tracing::debug!(tuner.index, %channel, %user.info, "Use tuner");
             let filters =
                 Self::make_filter_commands(&tuner, &channel, &self.config.filters.tuner_filter)?;
-            tuner.activate(channel, filters, ctx).await?;
-            self.event_emitters
-                .emit(Event::StatusChanged(tuner.index))
-                .await;
-            return Ok(tuner.subscribe(user));
+            match tuner.activate(channel, user.priority, filters, ctx).await {
+                Ok(_) => {
+                    self.event_emitters
+                        .emit(Event::StatusChanged(tuner.index))
+                        .await;
+                    return Ok(tuner.subscribe(user));
+                }
+                // Fail over to the next tuner if the upstream server is unavailable.
+                Err(err) if tuner.is_remote() => {
+                    tracing::warn!(%err, tuner.index, %channel, %user.info, "Upstream unavailable, try next tuner");
+                    self.event_emitters
+                        .emit(Event::StatusChanged(tuner.index))
+                        .await;
+                }
+                Err(err) => return Err(err),
+            }
         }
 
         // No available tuner at this point.
         // Grab a tuner used by lower priority users.
         let found = self
-            .tuners
-            .iter_mut()
-            .filter(|tuner| tuner.dedicated_for.is_none())
-            .filter(|tuner| tuner.is_supported_type(&channel))
-            .find(|tuner| tuner.can_grab(user.priority));
-        if let Some(tuner) = found {
+            .select_tuners(channel, |tuner| {
+                tuner.dedicated_for.is_none()
+                    && !tuner.is_fault()
+                    && !tuner.maintenance
+                    && tuner.role.accepts(&user.info)
+                    && !reserved.contains(&tuner.index)
+                    && tuner.is_supported_type(channel)
+                    && tuner.can_grab(user.priority)
+            })
+            .first()
+            .cloned();
+        if let Some(index) = found {
+            self.last_selected = Some(index);
+            let tuner = &mut self.tuners[index];
             tracing::debug!(tuner.index, %channel, %user.info, %user.priority, "Grab tuner");
             let filters =
                 Self::make_filter_commands(&tuner, &channel, &self.config.filters.tuner_filter)?;
+            for (id, preempted) in tuner.activity.subscribers() {
+                tracing::info!(
+                    tuner.index,
+                    stream.id = %id,
+                    user.info = %preempted.info,
+                    user.priority = %preempted.priority,
+                    preemptor.info = %user.info,
+                    preemptor.priority = %user.priority,
+                    "Preempted"
+                );
+                Self::emit_preempted(
+                    &self.event_emitters,
+                    Event::Preempted {
+                        id,
+                        user: preempted,
+                        preemptor: user.clone(),
+                    },
+                    ctx,
+                );
+            }
             tuner.deactivate();
             self.event_emitters
                 .emit(Event::StatusChanged(tuner.index))
                 .await;
-            tuner.activate(channel, filters, ctx).await?;
+            tuner.activate(channel, user.priority, filters, ctx).await?;
             return Ok(tuner.subscribe(user));
         }
 
@@ -226,6 +672,75 @@ impl TunerManager {
         Err(Error::TunerUnavailable)
     }
 
+    // Returns indexes of tuners satisfying the predicate in the order of the
+    // selection.
+    //
+    // Tuners are sorted by `selection_order()`.  Tuners with the same order
+    // are sorted according to the selection policy.
+    fn select_tuners<F>(&self, channel: &EpgChannel, pred: F) -> Vec<usize>
+    where
+        F: Fn(&Tuner) -> bool,
+    {
+        let order = |tuner: &Tuner| {
+            selection_order(
+                &self.config.tuner_selection,
+                channel.channel_type,
+                &tuner.name,
+                tuner.weight,
+            )
+        };
+        // The position of a tuner in the round-robin order.
+        let start = self.last_selected.map(|i| i + 1).unwrap_or(0);
+        let turn = |tuner: &Tuner| (tuner.index + self.tuners.len() - start) % self.tuners.len();
+
+        let mut tuners: Vec<&Tuner> = self
+            .tuners
+            .iter()
+            .filter(|tuner| !tuner.removed)
+            .filter(|tuner| pred(tuner))
+            .collect();
+        // `sort_by()` is stable.  Tuners are kept in the order of `config.tuners`
+        // when they are equal.
+        tuners.sort_by(|a, b| {
+            order(a)
+                .cmp(&order(b))
+                .then_with(|| match self.config.tuner_selection.policy {
+                    TunerSelectionPolicy::First => std::cmp::Ordering::Equal,
+                    TunerSelectionPolicy::RoundRobin => turn(a).cmp(&turn(b)),
+                    TunerSelectionPolicy::LeastRecentlyUsed => a.last_used.cmp(&b.last_used),
+                })
+        });
+        tuners.iter().map(|tuner| tuner.index).collect()
+    }
+
+    // Emits events for tuners whose fault status changed, and schedules the
+    // release of tuners newly quarantined.
+    async fn handle_fault_changes(&mut self, ctx: &Context<Self>) {
+        let now = Instant::now();
+        for tuner in self.tuners.iter_mut() {
+            if !tuner.fault.take_changed() {
+                continue;
+            }
+            if let Some(until) = tuner.fault.quarantined_until {
+                let duration = until.saturating_duration_since(now);
+                tracing::warn!(
+                    tuner.index,
+                    retry_interval = %humantime::format_duration(duration),
+                    "Quarantined due to consecutive failures"
+                );
+                let index = tuner.index;
+                let addr = ctx.address().clone();
+                ctx.spawn_task(async move {
+                    tokio::time::sleep(duration).await;
+                    addr.emit(ReleaseTuner(index)).await;
+                });
+            }
+            self.event_emitters
+                .emit(Event::StatusChanged(tuner.index))
+                .await;
+        }
+    }
+
     fn deactivate_tuner(&mut self, id: TunerSubscriptionId) {
         self.tuners[id.session_id.tuner_index].deactivate();
     }
@@ -285,10 +800,18 @@ impl TunerManager {
 
 #[async_trait]
 impl Actor for TunerManager {
-    async fn started(&mut self, _ctx: &mut Context<Self>) {
+    async fn started(&mut self, ctx: &mut Context<Self>) {
         // It's guaranteed that no response is sent before tuners are loaded.
         tracing::debug!("Started");
         self.load_tuners();
+        let emitter = Emitter::new(ctx.address().clone());
+        for tuner in self.tuners.iter_mut() {
+            tuner.health_emitter = Some(emitter.clone());
+        }
+        self.load_maintenance();
+        self.load_saved_reservations();
+        self.load_reservations();
+        self.set_reservation_timer(ctx);
     }
 
     async fn stopped(&mut self, _ctx: &mut Context<Self>) {
@@ -366,6 +889,7 @@ impl Handler<QueryTuners> for TunerManager {
         tracing::debug!(msg.name = "QueryTuners");
         self.tuners
             .iter()
+            .filter(|tuner| !tuner.removed)
             .map(|tuner| tuner.get_mirakurun_model())
             .collect()
     }
@@ -387,11 +911,52 @@ impl Handler<QueryTuner> for TunerManager {
         tracing::debug!(msg.name = "QueryTuner", msg.index = msg.0);
         self.tuners
             .get(msg.0)
+            .filter(|tuner| !tuner.removed)
             .map(|tuner| tuner.get_mirakurun_model())
             .ok_or(Error::TunerNotFound)
     }
 }
 
+// set tuner maintenance
+
+#[derive(Message)]
+#[reply(Result<MirakurunTuner, Error>)]
+pub struct SetTunerMaintenance {
+    pub index: usize,
+    pub maintenance: bool,
+}
+
+#[async_trait]
+impl Handler<SetTunerMaintenance> for TunerManager {
+    async fn handle(
+        &mut self,
+        msg: SetTunerMaintenance,
+        _ctx: &mut Context<Self>,
+    ) -> <SetTunerMaintenance as Message>::Reply {
+        tracing::debug!(msg.name = "SetTunerMaintenance", msg.index, msg.maintenance);
+        let tuner = self
+            .tuners
+            .get_mut(msg.index)
+            .filter(|tuner| !tuner.removed)
+            .ok_or(Error::TunerNotFound)?;
+        if tuner.maintenance != msg.maintenance {
+            tuner.maintenance = msg.maintenance;
+            if tuner.maintenance {
+                // Users using the tuner are not stopped.  The tuner will be
+                // drained when all of them stop streaming.
+                tracing::info!(tuner.index, tuner.name, "Entered maintenance");
+            } else {
+                tracing::info!(tuner.index, tuner.name, "Left maintenance");
+            }
+            self.save_maintenance();
+            self.event_emitters
+                .emit(Event::StatusChanged(msg.index))
+                .await;
+        }
+        Ok(self.tuners[msg.index].get_mirakurun_model())
+    }
+}
+
 // start streaming
 
 #[derive(Message)]
@@ -411,9 +976,11 @@ impl Handler<StartStreaming> for TunerManager {
     ) -> <StartStreaming as Message>::Reply {
         tracing::debug!(msg.name = "StartStreaming", %msg.channel, %msg.user.info, %msg.user.priority);
 
-        let subscription = self
+        let result = self
             .activate_tuner(&msg.channel, &msg.user, &msg.stream_id, ctx)
-            .await?;
+            .await;
+        self.handle_fault_changes(ctx).await;
+        let subscription = result?;
 
         let result = subscription
             .broadcaster
@@ -469,11 +1036,215 @@ impl Handler<StopStreaming> for TunerManager {
     }
 }
 
+// stream health checked
+
+#[async_trait]
+impl Handler<StreamHealthChecked> for TunerManager {
+    async fn handle(&mut self, msg: StreamHealthChecked, ctx: &mut Context<Self>) {
+        tracing::debug!(msg.name = "StreamHealthChecked", %msg.id, msg.healthy);
+        let tuner = match self.tuners.get_mut(msg.id.tuner_index) {
+            Some(tuner) => tuner,
+            None => return,
+        };
+        // The stream of a deactivated session ends without any chunk when the
+        // tuner command is killed before outputting TS packets.  Such a
+        // session must be ignored.
+        if !tuner.activity.is_session(&msg.id) {
+            return;
+        }
+        if msg.healthy {
+            tuner.record_success();
+        } else {
+            tracing::warn!(tuner.index, session.id = %msg.id, "No packet came from the tuner");
+            tuner.record_no_packet();
+        }
+        self.handle_fault_changes(ctx).await;
+    }
+}
+
+// release tuner
+
+#[derive(Message)]
+struct ReleaseTuner(usize);
+
+#[async_trait]
+impl Handler<ReleaseTuner> for TunerManager {
+    async fn handle(&mut self, msg: ReleaseTuner, ctx: &mut Context<Self>) {
+        tracing::debug!(msg.name = "ReleaseTuner", msg.index = msg.0);
+        if let Some(tuner) = self.tuners.get_mut(msg.0) {
+            if tuner.fault.release(Instant::now()) {
+                tracing::info!(tuner.index, "Released from quarantine");
+            }
+        }
+        self.handle_fault_changes(ctx).await;
+    }
+}
+
+// reload config
+
+#[derive(Message)]
+#[reply()]
+pub struct ReloadConfig(pub Arc<Config>);
+
+#[async_trait]
+impl Handler<ReloadConfig> for TunerManager {
+    async fn handle(
+        &mut self,
+        msg: ReloadConfig,
+        ctx: &mut Context<Self>,
+    ) -> <ReloadConfig as Message>::Reply {
+        tracing::debug!(msg.name = "ReloadConfig");
+        self.config = msg.0;
+        self.reload_tuners(ctx).await;
+        self.load_reservations();
+        self.enforce_reservations(ctx).await;
+        self.set_reservation_timer(ctx);
+    }
+}
+
+// query tuner reservations
+
+#[derive(Message)]
+#[reply(Vec<TunerReservation>)]
+pub struct QueryTunerReservations;
+
+#[async_trait]
+impl Handler<QueryTunerReservations> for TunerManager {
+    async fn handle(
+        &mut self,
+        _msg: QueryTunerReservations,
+        _ctx: &mut Context<Self>,
+    ) -> <QueryTunerReservations as Message>::Reply {
+        tracing::debug!(msg.name = "QueryTunerReservations");
+        if self.remove_expired_reservations() {
+            self.save_reservations();
+        }
+        self.reservations.clone()
+    }
+}
+
+// add tuner reservation
+
+#[derive(Message)]
+#[reply(Result<TunerReservation, Error>)]
+pub struct AddTunerReservation {
+    pub reservation: TunerReservation,
+}
+
+#[async_trait]
+impl Handler<AddTunerReservation> for TunerManager {
+    async fn handle(
+        &mut self,
+        msg: AddTunerReservation,
+        ctx: &mut Context<Self>,
+    ) -> <AddTunerReservation as Message>::Reply {
+        tracing::debug!(msg.name = "AddTunerReservation", %msg.reservation.owner);
+        let mut reservation = msg.reservation;
+        reservation.validate()?;
+        if reservation.window.is_expired(Jst::now()) {
+            return Err(Error::InvalidTimeRange);
+        }
+        if let TunerReservationTarget::Tuner { ref name } = reservation.target {
+            let found = self
+                .tuners
+                .iter()
+                .filter(|tuner| !tuner.removed)
+                .filter(|tuner| tuner.dedicated_for.is_none())
+                .any(|tuner| tuner.name == *name);
+            if !found {
+                return Err(Error::TunerNotFound);
+            }
+        }
+        reservation.id = self.next_reservation_id;
+        reservation.from_config = false;
+        self.next_reservation_id += 1;
+        tracing::info!(reservation.id, %reservation.owner, reservation.priority, "Added reservation");
+        self.reservations.push(reservation.clone());
+        self.save_reservations();
+        self.enforce_reservations(ctx).await;
+        self.set_reservation_timer(ctx);
+        Ok(reservation)
+    }
+}
+
+// remove tuner reservation
+
+#[derive(Message)]
+#[reply(Result<TunerReservation, Error>)]
+pub struct RemoveTunerReservation {
+    pub id: u32,
+}
+
+#[async_trait]
+impl Handler<RemoveTunerReservation> for TunerManager {
+    async fn handle(
+        &mut self,
+        msg: RemoveTunerReservation,
+        ctx: &mut Context<Self>,
+    ) -> <RemoveTunerReservation as Message>::Reply {
+        tracing::debug!(msg.name = "RemoveTunerReservation", msg.id);
+        let index = self
+            .reservations
+            .iter()
+            .position(|reservation| reservation.id == msg.id)
+            .ok_or(Error::ReservationNotFound)?;
+        if self.reservations[index].from_config {
+            return Err(Error::InvalidReservation);
+        }
+        let reservation = self.reservations.remove(index);
+        tracing::info!(reservation.id, "Removed reservation");
+        self.save_reservations();
+        self.set_reservation_timer(ctx);
+        Ok(reservation)
+    }
+}
+
+// check reservations
+
+#[derive(Message)]
+struct CheckReservations;
+
+#[async_trait]
+impl Handler<CheckReservations> for TunerManager {
+    async fn handle(&mut self, _msg: CheckReservations, ctx: &mut Context<Self>) {
+        tracing::debug!(msg.name = "CheckReservations");
+        if self.remove_expired_reservations() {
+            self.save_reservations();
+        }
+        self.enforce_reservations(ctx).await;
+        self.set_reservation_timer(ctx);
+    }
+}
+
+// The order of tuners in the selection which doesn't depend on the state of
+// the tuners.
+//
+// Tuners listed in `config.tuner-selection.preferred` come first, then tuners
+// with higher weights.
+pub(crate) fn selection_order(
+    config: &TunerSelectionConfig,
+    channel_type: ChannelType,
+    name: &str,
+    weight: u32,
+) -> (usize, std::cmp::Reverse<u32>) {
+    let rank = config
+        .preferred
+        .get(&channel_type)
+        .and_then(|names| names.iter().position(|preferred| preferred == name))
+        .unwrap_or(usize::MAX);
+    (rank, std::cmp::Reverse(weight))
+}
+
 // event
 
 #[derive(Clone, Message)]
 pub enum Event {
     StatusChanged(usize),
+    Preempted {
+        id: TunerSubscriptionId,
+        user: TunerUser,
+        preemptor: TunerUser,
+    },
 }
 
 // tuner
@@ -487,6 +1258,25 @@ struct Tuner {
     decoded: bool,
     dedicated_for: Option<TunerUserInfo>,
     activity: TunerActivity,
+    fault: TunerFault,
+    fault_threshold: usize,
+    fault_retry_interval: Duration,
+    health_emitter: Option<Emitter<StreamHealthChecked>>,
+    upstream: Option<Url>,
+    // `false` if the upstream server refused the last request or it was not
+    // reachable.
+    upstream_available: bool,
+    weight: u32,
+    role: TunerRole,
+    selection_policy: TunerSelectionPolicy,
+    last_used: Option<Instant>,
+    // `true` if the tuner has been removed from `config.tuners`.
+    removed: bool,
+    // `true` while the tuner is in maintenance.
+    maintenance: bool,
+    // Changes of the config which will be applied when the tuner is activated
+    // next time.
+    pending_config: Option<(TunerConfig, Option<TunerUserInfo>)>,
 }
 
 impl Tuner {
@@ -500,7 +1290,44 @@ impl Tuner {
             decoded: config.decoded,
             dedicated_for,
             activity: TunerActivity::Inactive,
+            fault: Default::default(),
+            fault_threshold: config.fault_threshold,
+            fault_retry_interval: Duration::from_millis(config.fault_retry_interval),
+            health_emitter: None,
+            upstream: config.upstream.clone(),
+            upstream_available: true,
+            weight: config.weight,
+            role: config.role,
+            selection_policy: Default::default(),
+            last_used: None,
+            removed: false,
+            maintenance: false,
+            pending_config: None,
+        }
+    }
+
+    // Changes of the config are applied immediately if the tuner is inactive.
+    // Otherwise, they are applied when the tuner is deactivated in order to
+    // keep the running session consistent with the config.
+    fn reconfigure(&mut self, config: &TunerConfig, dedicated_for: Option<TunerUserInfo>) {
+        if self.is_active() {
+            self.pending_config = Some((config.clone(), dedicated_for));
+            return;
+        }
+        self.pending_config = None;
+        self.channel_types = config.channel_types.clone();
+        self.command = config.command.clone();
+        self.time_limit = config.time_limit;
+        self.decoded = config.decoded;
+        self.dedicated_for = dedicated_for;
+        self.fault_threshold = config.fault_threshold;
+        self.fault_retry_interval = Duration::from_millis(config.fault_retry_interval);
+        if self.upstream != config.upstream {
+            self.upstream = config.upstream.clone();
+            self.upstream_available = true;
         }
+        self.weight = config.weight;
+        self.role = config.role;
     }
 
     fn is_subscribed(&self, id: &TunerSubscriptionId) -> bool {
@@ -511,8 +1338,43 @@ impl Tuner {
         self.activity.is_active()
     }
 
+    fn is_remote(&self) -> bool {
+        self.upstream.is_some()
+    }
+
     fn is_available(&self) -> bool {
-        self.activity.is_inactive()
+        self.activity.is_inactive() && !self.is_fault() && !self.maintenance
+    }
+
+    fn is_fault(&self) -> bool {
+        self.fault.is_fault()
+    }
+
+    fn record_failure(&mut self) {
+        self.fault.record_failure(
+            self.fault_threshold,
+            self.fault_retry_interval,
+            Instant::now(),
+        );
+    }
+
+    // No packet came from the tuner.  This may be caused by the channel (e.g.
+    // the broadcast of the channel has been stopped) rather than the tuner.
+    fn record_no_packet(&mut self) {
+        let channel = match self.activity.channel() {
+            Some(channel) => (channel.channel_type, channel.channel.clone()),
+            None => return,
+        };
+        self.fault.record_channel_failure(
+            channel,
+            self.fault_threshold,
+            self.fault_retry_interval,
+            Instant::now(),
+        );
+    }
+
+    fn record_success(&mut self) {
+        self.fault.record_success();
     }
 
     fn is_supported_type(&self, channel: &EpgChannel) -> bool {
@@ -542,26 +1404,68 @@ impl Tuner {
     async fn activate<C>(
         &mut self,
         channel: &EpgChannel,
+        priority: TunerUserPriority,
         filters: Vec<String>,
         ctx: &C,
     ) -> Result<(), Error>
     where
         C: Spawn,
     {
-        let command = match self.make_command(&channel) {
-            Ok(command) => command,
-            Err(err) => {
-                tracing::error!(%err, tuner.index = self.index, %channel, "Failed to render the tuner command");
-                return Err(err.into());
+        assert!(
+            self.activity.is_inactive(),
+            "Must be deactivated before activating"
+        );
+        let id = TunerSessionId::new(self.index);
+        let mut broadcaster = Broadcaster::new(id, self.time_limit);
+        if let Some(ref emitter) = self.health_emitter {
+            broadcaster = broadcaster.with_health_emitter(emitter.clone());
+        }
+        let result = if self.is_remote() {
+            let url = self.make_upstream_url(channel);
+            let result =
+                TunerSession::connect(id, channel, url, priority, filters, broadcaster, ctx).await;
+            self.upstream_available = !matches!(
+                result,
+                Err(Error::TunerUnavailable | Error::ReqwestError(_) | Error::StreamingTimedOut)
+            );
+            // The upstream server refuses the request when no tuner is available
+            // in it.  This is not a fault of the tuner.
+            if let Err(Error::TunerUnavailable) = result {
+                return Err(Error::TunerUnavailable);
             }
+            result
+        } else {
+            let command = match self.make_command(channel) {
+                Ok(command) => command,
+                Err(err) => {
+                    tracing::error!(%err, tuner.index = self.index, %channel, "Failed to render the tuner command");
+                    return Err(err);
+                }
+            };
+            TunerSession::new(id, channel, command, filters, broadcaster, ctx).await
         };
-        self.activity
-            .activate(self.index, channel, command, filters, self.time_limit, ctx)
-            .await
+        match result {
+            Ok(session) => {
+                self.activity.activate(session);
+                self.last_used = Some(Instant::now());
+                Ok(())
+            }
+            Err(err) => {
+                self.record_failure();
+                Err(err)
+            }
+        }
     }
 
     fn deactivate(&mut self) {
         self.activity.deactivate();
+        self.apply_pending_config();
+    }
+
+    fn apply_pending_config(&mut self) {
+        if let Some((config, dedicated_for)) = self.pending_config.take() {
+            self.reconfigure(&config, dedicated_for);
+        }
     }
 
     fn subscribe(&mut self, user: &TunerUser) -> TunerSubscription {
@@ -574,7 +1478,14 @@ impl Tuner {
         &mut self,
         id: TunerSubscriptionId,
     ) -> Result<Option<TunerUser>, Error> {
-        self.activity.unsubscript(id).await
+        let result = self.activity.unsubscript(id).await;
+        if !self.is_active() {
+            self.apply_pending_config();
+            if self.maintenance && result.is_ok() {
+                tracing::info!(tuner.index = self.index, "Drained");
+            }
+        }
+        result
     }
 
     fn get_mirakurun_model(&self) -> MirakurunTuner {
@@ -587,11 +1498,16 @@ impl Tuner {
             command,
             pid,
             users,
-            is_available: true,
-            is_remote: false,
+            is_available: self.upstream_available,
+            is_remote: self.is_remote(),
             is_free: self.is_available(),
-            is_using: !self.is_available(),
-            is_fault: false,
+            is_using: self.is_active(),
+            is_fault: self.is_fault(),
+            is_maintenance: self.maintenance,
+            weight: self.weight,
+            role: self.role,
+            selection_policy: self.selection_policy,
+            ts_stats: self.activity.ts_stats(),
         }
     }
 
@@ -605,6 +1521,104 @@ impl Tuner {
             .build();
         Ok(template.render_data_to_string(&data)?)
     }
+
+    fn make_upstream_url(&self, channel: &EpgChannel) -> Url {
+        let mut url = self.upstream.clone().unwrap();
+        // `config.tuners[].upstream` is validated as a URL which can be a base.
+        url.path_segments_mut().unwrap().pop_if_empty().extend(&[
+            "api",
+            "channels",
+            &channel.channel_type.to_string(),
+            &channel.channel,
+            "stream",
+        ]);
+        url.query_pairs_mut()
+            .append_pair("decode", if self.decoded { "1" } else { "0" });
+        url
+    }
+}
+
+// fault
+
+// Consecutive failures of a tuner.
+//
+// A tuner is quarantined when the number of consecutive failures reaches the
+// threshold.  The quarantined tuner is released after the retry interval and
+// it's quarantined again if it fails again.  The retry interval is doubled
+// each time until the tuner works.
+//
+// Failures depending on channels are counted only once for each channel so
+// that a tuner is not quarantined due to a channel which is not on the air.
+#[derive(Default)]
+struct TunerFault {
+    failures: usize,
+    // Channels which caused the consecutive failures.
+    failed_channels: Vec<(ChannelType, String)>,
+    quarantines: u32,
+    quarantined_until: Option<Instant>,
+    changed: bool,
+}
+
+impl TunerFault {
+    const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(60 * 60); // 1h
+
+    fn is_fault(&self) -> bool {
+        self.quarantined_until.is_some()
+    }
+
+    fn record_failure(&mut self, threshold: usize, interval: Duration, now: Instant) {
+        self.failures += 1;
+        // A threshold of 0 disables the quarantine.
+        if threshold == 0 || self.failures < threshold || self.is_fault() {
+            return;
+        }
+        let interval = interval
+            .saturating_mul(2u32.saturating_pow(self.quarantines))
+            .min(Self::MAX_RETRY_INTERVAL);
+        self.quarantines = self.quarantines.saturating_add(1);
+        self.quarantined_until = Some(now + interval);
+        self.changed = true;
+    }
+
+    fn record_channel_failure(
+        &mut self,
+        channel: (ChannelType, String),
+        threshold: usize,
+        interval: Duration,
+        now: Instant,
+    ) {
+        if self.failed_channels.contains(&channel) {
+            return;
+        }
+        self.failed_channels.push(channel);
+        self.record_failure(threshold, interval, now);
+    }
+
+    fn record_success(&mut self) {
+        if self.is_fault() {
+            self.changed = true;
+        }
+        self.failures = 0;
+        self.failed_channels.clear();
+        self.quarantines = 0;
+        self.quarantined_until = None;
+    }
+
+    // Returns `true` if the tuner has been released.
+    fn release(&mut self, now: Instant) -> bool {
+        match self.quarantined_until {
+            Some(until) if until <= now => {
+                self.quarantined_until = None;
+                self.changed = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
 }
 
 // activity
@@ -615,26 +1629,9 @@ enum TunerActivity {
 }
 
 impl TunerActivity {
-    async fn activate<C>(
-        &mut self,
-        tuner_index: usize,
-        channel: &EpgChannel,
-        command: String,
-        filters: Vec<String>,
-        time_limit: u64,
-        ctx: &C,
-    ) -> Result<(), Error>
-    where
-        C: Spawn,
-    {
+    fn activate(&mut self, session: TunerSession) {
         match self {
-            Self::Inactive => {
-                let session =
-                    TunerSession::new(tuner_index, channel, command, filters, time_limit, ctx)
-                        .await?;
-                *self = Self::Active(session);
-                Ok(())
-            }
+            Self::Inactive => *self = Self::Active(session),
             Self::Active(_) => panic!("Must be deactivated before activating"),
         }
     }
@@ -657,6 +1654,20 @@ impl TunerActivity {
         }
     }
 
+    fn channel(&self) -> Option<&EpgChannel> {
+        match self {
+            Self::Inactive => None,
+            Self::Active(session) => Some(&session.channel),
+        }
+    }
+
+    fn is_session(&self, id: &TunerSessionId) -> bool {
+        match self {
+            Self::Inactive => false,
+            Self::Active(session) => session.id == *id,
+        }
+    }
+
     fn is_inactive(&self) -> bool {
         !self.is_active()
     }
@@ -698,12 +1709,26 @@ impl TunerActivity {
         }
     }
 
+    fn subscribers(&self) -> Vec<(TunerSubscriptionId, TunerUser)> {
+        match self {
+            Self::Inactive => vec![],
+            Self::Active(session) => session.subscribers(),
+        }
+    }
+
     fn get_mirakurun_models(&self) -> (Option<String>, Option<u32>, Vec<MirakurunTunerUser>) {
         match self {
             Self::Inactive => (None, None, Vec::new()),
             Self::Active(session) => session.get_mirakurun_models(),
         }
     }
+
+    fn ts_stats(&self) -> Option<TsStats> {
+        match self {
+            Self::Inactive => None,
+            Self::Active(session) => Some(session.ts_stats.get()),
+        }
+    }
 }
 
 // session
@@ -711,26 +1736,36 @@ impl TunerActivity {
 struct TunerSession {
     id: TunerSessionId,
     channel: EpgChannel,
-    // Used for closing the tuner in order to take over the right to use it.
-    pipeline: CommandPipeline<TunerSessionId>,
+    source: TunerSessionSource,
     broadcaster: Address<Broadcaster>,
+    ts_stats: TsStatsHandle,
     subscribers: HashMap<u32, TunerUser>,
     next_serial_number: u32,
 }
 
+enum TunerSessionSource {
+    // Used for closing the tuner in order to take over the right to use it.
+    Command(CommandPipeline<TunerSessionId>),
+    Upstream {
+        url: Url,
+        // Used for closing the connection to the upstream server.
+        abort_handle: AbortHandle,
+        _filters: Option<CommandPipeline<TunerSessionId>>,
+    },
+}
+
 impl TunerSession {
     async fn new<C>(
-        tuner_index: usize,
+        id: TunerSessionId,
         channel: &EpgChannel,
         command: String,
         mut filters: Vec<String>,
-        time_limit: u64,
+        broadcaster: Broadcaster,
         ctx: &C,
     ) -> Result<TunerSession, Error>
     where
         C: Spawn,
     {
-        let id = TunerSessionId::new(tuner_index);
         let mut commands = vec![command];
         commands.append(&mut filters);
         let mut pipeline = match spawn_pipeline(commands, id, "tuner") {
@@ -741,17 +1776,100 @@ impl TunerSession {
             }
         };
         let (_, output) = pipeline.take_endpoints();
-        let broadcaster = ctx
-            .spawn_actor(Broadcaster::new(id.clone(), time_limit))
-            .await;
+        let ts_stats = broadcaster.ts_stats();
+        let broadcaster = ctx.spawn_actor(broadcaster).await;
         broadcaster.emit(BindStream(output)).await;
         tracing::debug!(session.id = %id, %channel, "Activated");
 
         Ok(TunerSession {
             id,
             channel: channel.clone(),
-            pipeline,
+            source: TunerSessionSource::Command(pipeline),
+            broadcaster,
+            ts_stats,
+            subscribers: HashMap::new(),
+            next_serial_number: 1,
+        })
+    }
+
+    async fn connect<C>(
+        id: TunerSessionId,
+        channel: &EpgChannel,
+        url: Url,
+        priority: TunerUserPriority,
+        filters: Vec<String>,
+        broadcaster: Broadcaster,
+        ctx: &C,
+    ) -> Result<TunerSession, Error>
+    where
+        C: Spawn,
+    {
+        let request = UPSTREAM_CLIENT
+            .get(url.clone())
+            .header("X-Mirakurun-Priority", priority.to_string())
+            .send();
+        // The time limit is also applied to waiting for the response from the
+        // upstream server.
+        let response = match tokio::time::timeout(broadcaster.time_limit(), request).await {
+            Ok(Ok(response)) if response.status().is_success() => response,
+            Ok(Ok(response)) => {
+                tracing::warn!(session.id = %id, %channel, %url, status = %response.status(), "Refused by the upstream server");
+                return Err(Error::TunerUnavailable);
+            }
+            Ok(Err(err)) => {
+                tracing::error!(%err, session.id = %id, %channel, %url, "Failed to connect to the upstream server");
+                return Err(err.into());
+            }
+            Err(_) => {
+                tracing::error!(session.id = %id, %channel, %url, "Timed out waiting for the upstream server");
+                return Err(Error::StreamingTimedOut);
+            }
+        };
+
+        let stream = futures::stream::unfold(response, |mut response| async move {
+            match response.chunk().await {
+                Ok(Some(chunk)) => Some((Ok(chunk), response)),
+                Ok(None) => None,
+                Err(err) => Some((Err(std::io::Error::other(err)), response)),
+            }
+        });
+        let (stream, abort_handle) = futures::stream::abortable(Box::pin(stream));
+        let mut reader = StreamReader::new(stream);
+
+        let ts_stats = broadcaster.ts_stats();
+
+        let (filters, broadcaster) = if filters.is_empty() {
+            let broadcaster = ctx.spawn_actor(broadcaster).await;
+            broadcaster.emit(BindStream(reader)).await;
+            (None, broadcaster)
+        } else {
+            let mut pipeline = match spawn_pipeline(filters, id, "tuner") {
+                Ok(pipeline) => pipeline,
+                Err(err) => {
+                    tracing::error!(%err, session.id = %id, %channel, "Failed to spawn a tuner pipeline");
+                    return Err(err.into());
+                }
+            };
+            let (mut input, output) = pipeline.take_endpoints();
+            ctx.spawn_task(async move {
+                let _ = tokio::io::copy(&mut reader, &mut input).await;
+            });
+            let broadcaster = ctx.spawn_actor(broadcaster).await;
+            broadcaster.emit(BindStream(output)).await;
+            (Some(pipeline), broadcaster)
+        };
+        tracing::debug!(session.id = %id, %channel, %url, "Activated");
+
+        Ok(TunerSession {
+            id,
+            channel: channel.clone(),
+            source: TunerSessionSource::Upstream {
+                url,
+                abort_handle,
+                _filters: filters,
+            },
             broadcaster,
+            ts_stats,
             subscribers: HashMap::new(),
             next_serial_number: 1,
         })
@@ -800,9 +1918,26 @@ impl TunerSession {
         self.subscribers.is_empty()
     }
 
+    fn subscribers(&self) -> Vec<(TunerSubscriptionId, TunerUser)> {
+        self.subscribers
+            .iter()
+            .map(|(&serial_number, user)| {
+                (
+                    TunerSubscriptionId::new(self.id, serial_number),
+                    user.clone(),
+                )
+            })
+            .collect()
+    }
+
     fn get_mirakurun_models(&self) -> (Option<String>, Option<u32>, Vec<MirakurunTunerUser>) {
-        let command = self.pipeline.get_command(0).map(|s| s.to_string());
-        let pids = self.pipeline.pids().iter().cloned().next().flatten();
+        let (command, pids) = match self.source {
+            TunerSessionSource::Command(ref pipeline) => (
+                pipeline.get_command(0).map(|s| s.to_string()),
+                pipeline.pids().iter().cloned().next().flatten(),
+            ),
+            TunerSessionSource::Upstream { ref url, .. } => (Some(url.to_string()), None),
+        };
         let users = self
             .subscribers
             .values()
@@ -814,6 +1949,12 @@ impl TunerSession {
 
 impl Drop for TunerSession {
     fn drop(&mut self) {
+        if let TunerSessionSource::Upstream {
+            ref abort_handle, ..
+        } = self.source
+        {
+            abort_handle.abort();
+        }
         tracing::debug!(session.id = %self.id, "Deactivated");
     }
 }
@@ -823,7 +1964,9 @@ impl Drop for TunerSession {
 mod tests {
     use super::*;
     use crate::command_util::Error as CommandUtilError;
+    use crate::tuner_reservation::TunerReservationWindow;
     use assert_matches::assert_matches;
+    use maplit::hashmap;
 
     #[test]
     fn test_make_filter_command() {
@@ -949,89 +2092,882 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_tuner_is_subscribed() {
+    async fn test_start_streaming_upstream() {
+        use axum::extract::Path;
+        use axum::http::HeaderMap;
+        use axum::http::StatusCode;
+        use axum::routing::get;
+        use std::sync::Mutex;
+
+        let priorities = Arc::new(Mutex::new(vec![]));
+        let app = axum::Router::new().route(
+            "/api/channels/:type/:channel/stream",
+            get({
+                let priorities = priorities.clone();
+                move |Path((_, channel)): Path<(String, String)>, headers: HeaderMap| async move {
+                    let priority = headers
+                        .get("x-mirakurun-priority")
+                        .map(|value| value.to_str().unwrap().to_string());
+                    priorities.lock().unwrap().push(priority);
+                    match channel.as_str() {
+                        "0" => Ok("data"),
+                        _ => Err(StatusCode::SERVICE_UNAVAILABLE),
+                    }
+                }
+            }),
+        );
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(app.into_make_service());
+        tokio::spawn(server);
+
         let system = System::new();
         {
-            let config = create_config("true".to_string());
-            let mut tuner = Tuner::new(0, &config, None);
-
-            let dummy_id = TunerSubscriptionId::new(TunerSessionId::new(0), 1);
-
-            assert!(!tuner.is_subscribed(&dummy_id));
+            let config: Arc<Config> = Arc::new(
+                serde_yaml::from_str(&format!(
+                    r#"
+                tuners:
+                  - name: upstream
+                    types: [GR]
+                    upstream: http://{addr}/
+                  - name: gr
+                    types: [GR]
+                    command: >-
+                      sleep 1
+                "#
+                ))
+                .unwrap(),
+            );
 
-            let result = tuner.activate(&create_channel("1"), vec![], &system).await;
-            assert!(result.is_ok());
-            assert!(!tuner.is_subscribed(&dummy_id));
+            let manager = system.spawn_actor(TunerManager::new(config)).await;
 
-            let subscription = tuner.subscribe(&TunerUser {
-                info: TunerUserInfo::Web {
-                    id: "".to_string(),
-                    agent: None,
-                },
-                priority: 0.into(),
+            // Fail over to the next tuner.
+            let result = manager
+                .call(StartStreaming {
+                    channel: create_channel("1"),
+                    user: create_user(1.into()),
+                    stream_id: None,
+                })
+                .await;
+            assert_matches!(result, Ok(Ok(stream)) => {
+                assert_eq!(stream.id().session_id.tuner_index, 1);
             });
-            assert!(tuner.is_subscribed(&subscription.id));
+            let tuner = manager.call(QueryTuner(0)).await.unwrap().unwrap();
+            assert!(tuner.is_remote);
+            assert!(!tuner.is_available);
+            assert!(!tuner.is_fault);
 
-            let result = tuner.stop_streaming(subscription.id.clone()).await;
-            assert!(result.is_ok());
-            assert!(!tuner.is_subscribed(&subscription.id));
+            let result = manager
+                .call(StartStreaming {
+                    channel: create_channel("0"),
+                    user: create_user(2.into()),
+                    stream_id: None,
+                })
+                .await;
+            assert_matches!(result, Ok(Ok(stream)) => {
+                assert_eq!(stream.id().session_id.tuner_index, 0);
+            });
+            let tuner = manager.call(QueryTuner(0)).await.unwrap().unwrap();
+            assert!(tuner.is_available);
+            assert!(tuner.is_using);
+            assert_eq!(
+                tuner.command,
+                Some(format!("http://{addr}/api/channels/GR/0/stream?decode=0"))
+            );
         }
         system.stop();
+
+        assert_eq!(
+            *priorities.lock().unwrap(),
+            vec![Some("1".to_string()), Some("2".to_string())]
+        );
     }
 
     #[tokio::test]
-    async fn test_tuner_is_active() {
+    async fn test_start_streaming_upstream_unreachable() {
+        // Nothing listens on the port after the listener is dropped.
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
         let system = System::new();
         {
-            let config = create_config("true".to_string());
-            let mut tuner = Tuner::new(0, &config, None);
+            let config: Arc<Config> = Arc::new(
+                serde_yaml::from_str(&format!(
+                    r#"
+                tuners:
+                  - name: upstream
+                    types: [GR]
+                    upstream: http://{addr}/
+                  - name: gr
+                    types: [GR]
+                    command: >-
+                      sleep 1
+                "#
+                ))
+                .unwrap(),
+            );
 
-            assert!(!tuner.is_active());
+            let manager = system.spawn_actor(TunerManager::new(config)).await;
 
-            let result = tuner.activate(&create_channel("1"), vec![], &system).await;
-            assert!(result.is_ok());
-            assert!(tuner.is_active());
+            // Fail over to the next tuner.
+            let result = manager
+                .call(StartStreaming {
+                    channel: create_channel("0"),
+                    user: create_user(1.into()),
+                    stream_id: None,
+                })
+                .await;
+            assert_matches!(result, Ok(Ok(stream)) => {
+                assert_eq!(stream.id().session_id.tuner_index, 1);
+            });
+            let tuner = manager.call(QueryTuner(0)).await.unwrap().unwrap();
+            assert!(tuner.is_remote);
+            assert!(!tuner.is_available);
+            assert!(!tuner.is_using);
         }
         system.stop();
     }
 
+    #[test]
+    fn test_tuner_make_upstream_url() {
+        let mut config = create_config("".to_string());
+        config.upstream = Some(Url::parse("http://upstream:40772/mirakurun/").unwrap());
+        config.decoded = true;
+        let tuner = Tuner::new(0, &config, None);
+        assert_eq!(
+            tuner.make_upstream_url(&create_channel("BS01_0")).as_str(),
+            "http://upstream:40772/mirakurun/api/channels/GR/BS01_0/stream?decode=1"
+        );
+    }
+
+    #[test]
+    fn test_select_tuners() {
+        let mut config: Config = serde_yaml::from_str(
+            r#"
+            tuners:
+              - name: gr0
+                types: [GR]
+                command: >-
+                  true
+              - name: gr1
+                types: [GR]
+                command: >-
+                  true
+              - name: gr2
+                types: [GR]
+                command: >-
+                  true
+                weight: 2
+              - name: bs
+                types: [BS]
+                command: >-
+                  true
+        "#,
+        )
+        .unwrap();
+        let any = |_: &Tuner| true;
+        let gr = create_channel("0");
+
+        let mut manager = TunerManager::new(Arc::new(config.clone()));
+        manager.load_tuners();
+        assert_eq!(manager.select_tuners(&gr, any), vec![2, 0, 1, 3]);
+
+        config.tuner_selection.preferred = hashmap! {
+            ChannelType::GR => vec!["gr1".to_string()],
+        };
+        let mut manager = TunerManager::new(Arc::new(config.clone()));
+        manager.load_tuners();
+        assert_eq!(manager.select_tuners(&gr, any), vec![1, 2, 0, 3]);
+        assert_eq!(
+            manager.select_tuners(&gr, |tuner| tuner.is_supported_type(&gr)),
+            vec![1, 2, 0]
+        );
+
+        config.tuner_selection.preferred.clear();
+        config.tuners[2].weight = 1;
+        config.tuner_selection.policy = TunerSelectionPolicy::RoundRobin;
+        let mut manager = TunerManager::new(Arc::new(config.clone()));
+        manager.load_tuners();
+        assert_eq!(manager.select_tuners(&gr, any), vec![0, 1, 2, 3]);
+        manager.last_selected = Some(1);
+        assert_eq!(manager.select_tuners(&gr, any), vec![2, 3, 0, 1]);
+
+        config.tuner_selection.policy = TunerSelectionPolicy::LeastRecentlyUsed;
+        let mut manager = TunerManager::new(Arc::new(config.clone()));
+        manager.load_tuners();
+        let now = Instant::now();
+        manager.tuners[0].last_used = Some(now + Duration::from_secs(1));
+        manager.tuners[2].last_used = Some(now);
+        assert_eq!(manager.select_tuners(&gr, any), vec![1, 3, 2, 0]);
+        assert_eq!(
+            manager.tuners[0].get_mirakurun_model().selection_policy,
+            TunerSelectionPolicy::LeastRecentlyUsed
+        );
+    }
+
     #[tokio::test]
-    async fn test_tuner_activate() {
+    async fn test_start_streaming_role() {
         let system = System::new();
-        {
-            let config = create_config("true".to_string());
-            let mut tuner = Tuner::new(0, &config, None);
-            let result = tuner.activate(&create_channel("1"), vec![], &system).await;
-            assert!(result.is_ok());
-            tokio::task::yield_now().await;
-        }
 
         {
-            let config = create_config("cmd '".to_string());
-            let mut tuner = Tuner::new(0, &config, None);
-            let result = tuner.activate(&create_channel("1"), vec![], &system).await;
-            assert_matches!(
-                result,
-                Err(Error::CommandFailed(CommandUtilError::UnableToParse(_)))
+            let config: Arc<Config> = Arc::new(
+                serde_yaml::from_str(
+                    r#"
+                tuners:
+                  - name: recording
+                    types: [GR]
+                    role: recording
+                    command: >-
+                      sleep 1
+                  - name: streaming
+                    types: [GR]
+                    role: streaming
+                    command: >-
+                      sleep 1
+                "#,
+                )
+                .unwrap(),
             );
-            tokio::task::yield_now().await;
-        }
 
-        {
-            let config = create_config("no-such-command".to_string());
-            let mut tuner = Tuner::new(0, &config, None);
-            let result = tuner.activate(&create_channel("1"), vec![], &system).await;
-            assert_matches!(
-                result,
-                Err(Error::CommandFailed(CommandUtilError::UnableToSpawn(..)))
-            );
-            tokio::task::yield_now().await;
-        }
-        system.stop();
-    }
+            let manager = system.spawn_actor(TunerManager::new(config)).await;
 
-    #[tokio::test]
-    async fn test_tuner_stop_streaming() {
+            let result = manager
+                .call(StartStreaming {
+                    channel: create_channel("0"),
+                    user: tuner_user!(0, web; "web"),
+                    stream_id: None,
+                })
+                .await;
+            assert_matches!(result, Ok(Ok(stream)) => {
+                assert_eq!(stream.id().session_id.tuner_index, 1);
+            });
+
+            // The tuner used for streaming cannot be reused for recording.
+            let result = manager
+                .call(StartStreaming {
+                    channel: create_channel("0"),
+                    user: tuner_user!(0, recorder; "recorder"),
+                    stream_id: None,
+                })
+                .await;
+            assert_matches!(result, Ok(Ok(stream)) => {
+                assert_eq!(stream.id().session_id.tuner_index, 0);
+            });
+
+            let result = manager
+                .call(StartStreaming {
+                    channel: create_channel("1"),
+                    user: tuner_user!(TunerUserPriority::GRAB, web; "web"),
+                    stream_id: None,
+                })
+                .await;
+            assert_matches!(result, Ok(Ok(stream)) => {
+                assert_eq!(stream.id().session_id.tuner_index, 1);
+            });
+        }
+        system.stop();
+    }
+
+    #[tokio::test]
+    async fn test_start_streaming_preempted() {
+        let system = System::new();
+
+        {
+            let config: Arc<Config> = Arc::new(
+                serde_yaml::from_str(
+                    r#"
+                tuners:
+                  - name: gr
+                    types: [GR]
+                    command: >-
+                      sleep 1
+                "#,
+                )
+                .unwrap(),
+            );
+
+            let manager = system.spawn_actor(TunerManager::new(config)).await;
+
+            let result = manager
+                .call(StartStreaming {
+                    channel: create_channel("0"),
+                    user: create_user(0.into()),
+                    stream_id: None,
+                })
+                .await;
+            let stream = assert_matches!(result, Ok(Ok(stream)) => stream);
+            let stream_id = stream.id();
+
+            let mut mock = MockEventEmitter::new();
+            mock.expect_emit().returning(move |msg| {
+                if let Event::Preempted {
+                    id,
+                    user,
+                    preemptor,
+                } = msg
+                {
+                    assert_eq!(id, stream_id);
+                    assert_eq!(user.priority, 0.into());
+                    assert_eq!(preemptor.priority, 1.into());
+                }
+            });
+            let result = manager.call(RegisterEmitter(Emitter::new(mock))).await;
+            assert_matches!(result, Ok(_));
+
+            let result = manager
+                .call(StartStreaming {
+                    channel: create_channel("1"),
+                    user: create_user(1.into()),
+                    stream_id: None,
+                })
+                .await;
+            assert_matches!(result, Ok(Ok(_)));
+        }
+        system.stop();
+    }
+
+    #[tokio::test]
+    async fn test_reload_config() {
+        let system = System::new();
+
+        {
+            let config: Arc<Config> = Arc::new(
+                serde_yaml::from_str(
+                    r#"
+                tuners:
+                  - name: a
+                    types: [GR]
+                    command: >-
+                      sleep 1
+                  - name: b
+                    types: [GR]
+                    command: >-
+                      sleep 1
+                "#,
+                )
+                .unwrap(),
+            );
+
+            let manager = system.spawn_actor(TunerManager::new(config)).await;
+
+            let result = manager
+                .call(StartStreaming {
+                    channel: create_channel("0"),
+                    user: create_user(0.into()),
+                    stream_id: None,
+                })
+                .await;
+            let stream = assert_matches!(result, Ok(Ok(stream)) => {
+                assert_eq!(stream.id().session_id.tuner_index, 0);
+                stream
+            });
+
+            let config: Arc<Config> = Arc::new(
+                serde_yaml::from_str(
+                    r#"
+                tuners:
+                  - name: a
+                    types: [BS]
+                    command: >-
+                      sleep 1
+                  - name: c
+                    types: [GR]
+                    command: >-
+                      sleep 1
+                "#,
+                )
+                .unwrap(),
+            );
+
+            let result = manager.call(ReloadConfig(config)).await;
+            assert_matches!(result, Ok(()));
+
+            // The removed tuner is hidden and the added tuner has a new index.
+            let result = manager.call(QueryTuners).await;
+            assert_matches!(result, Ok(tuners) => {
+                assert_eq!(tuners.len(), 2);
+                assert_eq!(tuners[0].index, 0);
+                assert_eq!(tuners[0].name, "a");
+                assert_eq!(tuners[1].index, 2);
+                assert_eq!(tuners[1].name, "c");
+            });
+            let result = manager.call(QueryTuner(1)).await;
+            assert_matches!(result, Ok(Err(Error::TunerNotFound)));
+
+            // The active tuner keeps running with the old config.
+            let result = manager.call(QueryTuner(0)).await;
+            assert_matches!(result, Ok(Ok(tuner)) => {
+                assert!(tuner.is_using);
+                assert_eq!(tuner.channel_types, vec![ChannelType::GR]);
+            });
+
+            // The new config is applied after the tuner is deactivated.
+            manager.emit(StopStreaming { id: stream.id() }).await;
+            let result = manager
+                .call(StartStreaming {
+                    channel: create_channel("0"),
+                    user: create_user(0.into()),
+                    stream_id: None,
+                })
+                .await;
+            assert_matches!(result, Ok(Ok(stream)) => {
+                assert_eq!(stream.id().session_id.tuner_index, 2);
+            });
+        }
+        system.stop();
+    }
+
+    #[tokio::test]
+    async fn test_start_streaming_reserved() {
+        let system = System::new();
+
+        {
+            let now = Jst::now();
+            let config: Arc<Config> = Arc::new(
+                serde_yaml::from_str(&format!(
+                    r#"
+                tuners:
+                  - name: a
+                    types: [GR]
+                    command: >-
+                      sleep 1
+                  - name: b
+                    types: [GR]
+                    command: >-
+                      sleep 1
+                tuner-reservations:
+                  - tuner: a
+                    start-at: '{}'
+                    end-at: '{}'
+                    owner: owner
+                    priority: 0
+                "#,
+                    (now - chrono::Duration::hours(1)).to_rfc3339(),
+                    (now + chrono::Duration::hours(1)).to_rfc3339(),
+                ))
+                .unwrap(),
+            );
+
+            let manager = system.spawn_actor(TunerManager::new(config)).await;
+
+            let result = manager.call(QueryTunerReservations).await;
+            let config_reservation_id = assert_matches!(result, Ok(reservations) => {
+                assert_eq!(reservations.len(), 1);
+                assert!(reservations[0].from_config);
+                reservations[0].id
+            });
+
+            // The reserved tuner is skipped.
+            let result = manager
+                .call(StartStreaming {
+                    channel: create_channel("0"),
+                    user: create_user(0.into()),
+                    stream_id: None,
+                })
+                .await;
+            assert_matches!(result, Ok(Ok(stream)) => {
+                assert_eq!(stream.id().session_id.tuner_index, 1);
+            });
+
+            let result = manager
+                .call(StartStreaming {
+                    channel: create_channel("1"),
+                    user: create_user(0.into()),
+                    stream_id: None,
+                })
+                .await;
+            assert_matches!(result, Ok(Err(Error::TunerUnavailable)));
+
+            // The owner can use the reserved tuner.
+            let result = manager
+                .call(StartStreaming {
+                    channel: create_channel("1"),
+                    user: tuner_user!(0, web; "owner"),
+                    stream_id: None,
+                })
+                .await;
+            let stream = assert_matches!(result, Ok(Ok(stream)) => {
+                assert_eq!(stream.id().session_id.tuner_index, 0);
+                stream
+            });
+
+            // Other users cannot take over the reserved tuner.
+            let result = manager
+                .call(StartStreaming {
+                    channel: create_channel("1"),
+                    user: create_user(0.into()),
+                    stream_id: Some(stream.id()),
+                })
+                .await;
+            assert_matches!(result, Ok(Err(Error::TunerUnavailable)));
+
+            // Reservations defined in the config cannot be removed.
+            let result = manager
+                .call(RemoveTunerReservation {
+                    id: config_reservation_id,
+                })
+                .await;
+            assert_matches!(result, Ok(Err(Error::InvalidReservation)));
+
+            // A reservation in effect stops streaming for users who are not
+            // permitted to use the reserved tuner.
+            let result = manager
+                .call(AddTunerReservation {
+                    reservation: TunerReservation {
+                        id: 0,
+                        target: TunerReservationTarget::ChannelType {
+                            channel_type: ChannelType::GR,
+                        },
+                        window: TunerReservationWindow::Once {
+                            start_at: now,
+                            end_at: now + chrono::Duration::hours(1),
+                        },
+                        owner: "other".to_string(),
+                        priority: 1,
+                        from_config: false,
+                    },
+                })
+                .await;
+            let reservation_id = assert_matches!(result, Ok(Ok(reservation)) => {
+                assert_ne!(reservation.id, config_reservation_id);
+                assert!(!reservation.from_config);
+                reservation.id
+            });
+            let result = manager.call(QueryTuner(1)).await;
+            assert_matches!(result, Ok(Ok(tuner)) => {
+                assert!(!tuner.is_using);
+            });
+            let result = manager.call(QueryTuner(0)).await;
+            assert_matches!(result, Ok(Ok(tuner)) => {
+                assert!(tuner.is_using);
+            });
+
+            let result = manager
+                .call(RemoveTunerReservation { id: reservation_id })
+                .await;
+            assert_matches!(result, Ok(Ok(_)));
+            let result = manager
+                .call(RemoveTunerReservation { id: reservation_id })
+                .await;
+            assert_matches!(result, Ok(Err(Error::ReservationNotFound)));
+
+            // Unknown tuners cannot be reserved.
+            let result = manager
+                .call(AddTunerReservation {
+                    reservation: TunerReservation {
+                        id: 0,
+                        target: TunerReservationTarget::Tuner {
+                            name: "unknown".to_string(),
+                        },
+                        window: TunerReservationWindow::Once {
+                            start_at: now,
+                            end_at: now + chrono::Duration::hours(1),
+                        },
+                        owner: "other".to_string(),
+                        priority: 1,
+                        from_config: false,
+                    },
+                })
+                .await;
+            assert_matches!(result, Ok(Err(Error::TunerNotFound)));
+        }
+        system.stop();
+    }
+
+    #[tokio::test]
+    async fn test_tuner_reservations_persistence() {
+        let system = System::new();
+
+        {
+            let now = Jst::now();
+            let dir = tempfile::tempdir().unwrap();
+            let config: Arc<Config> = Arc::new(
+                serde_yaml::from_str(&format!(
+                    r#"
+                tuners:
+                  - name: a
+                    types: [GR]
+                    command: >-
+                      sleep 1
+                tuner-reservations:
+                  - tuner: a
+                    start: '00:00:00'
+                    end: '01:00:00'
+                    owner: config
+                maintenance:
+                  state-dir: {}
+                "#,
+                    dir.path().display(),
+                ))
+                .unwrap(),
+            );
+
+            let manager = system.spawn_actor(TunerManager::new(config.clone())).await;
+            let result = manager
+                .call(AddTunerReservation {
+                    reservation: TunerReservation {
+                        id: 0,
+                        target: TunerReservationTarget::Tuner {
+                            name: "a".to_string(),
+                        },
+                        window: TunerReservationWindow::Once {
+                            start_at: now + chrono::Duration::hours(1),
+                            end_at: now + chrono::Duration::hours(2),
+                        },
+                        owner: "api".to_string(),
+                        priority: 1,
+                        from_config: false,
+                    },
+                })
+                .await;
+            let added = assert_matches!(result, Ok(Ok(reservation)) => reservation);
+            assert!(dir.path().join("tuner-reservations.json").exists());
+
+            // Restored after restart.
+            let manager = system.spawn_actor(TunerManager::new(config.clone())).await;
+            let result = manager.call(QueryTunerReservations).await;
+            assert_matches!(result, Ok(reservations) => {
+                assert_eq!(reservations.len(), 2);
+                let restored = reservations
+                    .iter()
+                    .filter(|reservation| reservation.id == added.id)
+                    .collect::<Vec<_>>();
+                // IDs are not reused.
+                assert_eq!(restored.len(), 1);
+                assert_eq!(restored[0].owner, "api");
+                assert!(!restored[0].from_config);
+            });
+
+            let result = manager.call(RemoveTunerReservation { id: added.id }).await;
+            assert_matches!(result, Ok(Ok(_)));
+
+            // Removed after restart.
+            let manager = system.spawn_actor(TunerManager::new(config.clone())).await;
+            let result = manager.call(QueryTunerReservations).await;
+            assert_matches!(result, Ok(reservations) => {
+                assert_eq!(reservations.len(), 1);
+                assert!(reservations[0].from_config);
+            });
+        }
+        system.stop();
+    }
+
+    #[tokio::test]
+    async fn test_start_streaming_maintenance() {
+        let system = System::new();
+
+        {
+            let dir = tempfile::tempdir().unwrap();
+            let config: Arc<Config> = Arc::new(
+                serde_yaml::from_str(&format!(
+                    r#"
+                tuners:
+                  - name: a
+                    types: [GR]
+                    command: >-
+                      sleep 1
+                  - name: b
+                    types: [GR]
+                    command: >-
+                      sleep 1
+                maintenance:
+                  state-dir: {}
+                "#,
+                    dir.path().display(),
+                ))
+                .unwrap(),
+            );
+
+            let manager = system.spawn_actor(TunerManager::new(config.clone())).await;
+
+            let result = manager
+                .call(StartStreaming {
+                    channel: create_channel("0"),
+                    user: create_user(0.into()),
+                    stream_id: None,
+                })
+                .await;
+            let stream = assert_matches!(result, Ok(Ok(stream)) => {
+                assert_eq!(stream.id().session_id.tuner_index, 0);
+                stream
+            });
+
+            let result = manager
+                .call(SetTunerMaintenance {
+                    index: 0,
+                    maintenance: true,
+                })
+                .await;
+            assert_matches!(result, Ok(Ok(tuner)) => {
+                assert!(tuner.is_maintenance);
+                assert!(!tuner.is_free);
+                // The current user keeps using the tuner.
+                assert!(tuner.is_using);
+            });
+            assert!(dir.path().join("tuners.json").exists());
+
+            // No new user can use the tuner in maintenance even if it's
+            // streaming the same channel.
+            let result = manager
+                .call(StartStreaming {
+                    channel: create_channel("0"),
+                    user: create_user(0.into()),
+                    stream_id: None,
+                })
+                .await;
+            assert_matches!(result, Ok(Ok(stream)) => {
+                assert_eq!(stream.id().session_id.tuner_index, 1);
+            });
+            let result = manager
+                .call(StartStreaming {
+                    channel: create_channel("0"),
+                    user: create_user(0.into()),
+                    stream_id: Some(stream.id()),
+                })
+                .await;
+            assert_matches!(result, Ok(Err(Error::TunerUnavailable)));
+            let result = manager
+                .call(StartStreaming {
+                    channel: create_channel("1"),
+                    user: create_user(TunerUserPriority::GRAB),
+                    stream_id: None,
+                })
+                .await;
+            assert_matches!(result, Ok(Ok(stream)) => {
+                assert_eq!(stream.id().session_id.tuner_index, 1);
+            });
+
+            let result = manager
+                .call(SetTunerMaintenance {
+                    index: 2,
+                    maintenance: true,
+                })
+                .await;
+            assert_matches!(result, Ok(Err(Error::TunerNotFound)));
+
+            // The state is restored after restart.
+            let manager = system.spawn_actor(TunerManager::new(config.clone())).await;
+            let result = manager.call(QueryTuner(0)).await;
+            assert_matches!(result, Ok(Ok(tuner)) => {
+                assert!(tuner.is_maintenance);
+            });
+            let result = manager.call(QueryTuner(1)).await;
+            assert_matches!(result, Ok(Ok(tuner)) => {
+                assert!(!tuner.is_maintenance);
+            });
+
+            let result = manager
+                .call(SetTunerMaintenance {
+                    index: 0,
+                    maintenance: false,
+                })
+                .await;
+            assert_matches!(result, Ok(Ok(tuner)) => {
+                assert!(!tuner.is_maintenance);
+                assert!(tuner.is_free);
+            });
+        }
+        system.stop();
+    }
+
+    #[tokio::test]
+    async fn test_tuner_is_subscribed() {
+        let system = System::new();
+        {
+            let config = create_config("true".to_string());
+            let mut tuner = Tuner::new(0, &config, None);
+
+            let dummy_id = TunerSubscriptionId::new(TunerSessionId::new(0), 1);
+
+            assert!(!tuner.is_subscribed(&dummy_id));
+
+            let result = tuner
+                .activate(&create_channel("1"), 0.into(), vec![], &system)
+                .await;
+            assert!(result.is_ok());
+            assert!(!tuner.is_subscribed(&dummy_id));
+
+            let subscription = tuner.subscribe(&TunerUser {
+                info: TunerUserInfo::Web {
+                    id: "".to_string(),
+                    agent: None,
+                },
+                priority: 0.into(),
+            });
+            assert!(tuner.is_subscribed(&subscription.id));
+
+            let result = tuner.stop_streaming(subscription.id.clone()).await;
+            assert!(result.is_ok());
+            assert!(!tuner.is_subscribed(&subscription.id));
+        }
+        system.stop();
+    }
+
+    #[tokio::test]
+    async fn test_tuner_is_active() {
+        let system = System::new();
+        {
+            let config = create_config("true".to_string());
+            let mut tuner = Tuner::new(0, &config, None);
+
+            assert!(!tuner.is_active());
+
+            let result = tuner
+                .activate(&create_channel("1"), 0.into(), vec![], &system)
+                .await;
+            assert!(result.is_ok());
+            assert!(tuner.is_active());
+        }
+        system.stop();
+    }
+
+    #[tokio::test]
+    async fn test_tuner_activate() {
+        let system = System::new();
+        {
+            let config = create_config("true".to_string());
+            let mut tuner = Tuner::new(0, &config, None);
+            let result = tuner
+                .activate(&create_channel("1"), 0.into(), vec![], &system)
+                .await;
+            assert!(result.is_ok());
+            tokio::task::yield_now().await;
+        }
+
+        {
+            let config = create_config("cmd '".to_string());
+            let mut tuner = Tuner::new(0, &config, None);
+            let result = tuner
+                .activate(&create_channel("1"), 0.into(), vec![], &system)
+                .await;
+            assert_matches!(
+                result,
+                Err(Error::CommandFailed(CommandUtilError::UnableToParse(_)))
+            );
+            tokio::task::yield_now().await;
+        }
+
+        {
+            let config = create_config("no-such-command".to_string());
+            let mut tuner = Tuner::new(0, &config, None);
+            let result = tuner
+                .activate(&create_channel("1"), 0.into(), vec![], &system)
+                .await;
+            assert_matches!(
+                result,
+                Err(Error::CommandFailed(CommandUtilError::UnableToSpawn(..)))
+            );
+            tokio::task::yield_now().await;
+        }
+        system.stop();
+    }
+
+    #[tokio::test]
+    async fn test_tuner_stop_streaming() {
         let system = System::new();
         {
             let config = create_config("true".to_string());
@@ -1039,7 +2975,9 @@ mod tests {
             let result = tuner.stop_streaming(Default::default()).await;
             assert_matches!(result, Err(Error::SessionNotFound));
 
-            let result = tuner.activate(&create_channel("1"), vec![], &system).await;
+            let result = tuner
+                .activate(&create_channel("1"), 0.into(), vec![], &system)
+                .await;
             assert!(result.is_ok());
             let subscription = tuner.subscribe(&TunerUser {
                 info: TunerUserInfo::Web {
@@ -1071,7 +3009,7 @@ mod tests {
             assert!(tuner.can_grab(0.into()));
 
             tuner
-                .activate(&create_channel("1"), vec![], &system)
+                .activate(&create_channel("1"), 0.into(), vec![], &system)
                 .await
                 .unwrap();
             tuner.subscribe(&create_user(0.into()));
@@ -1107,14 +3045,16 @@ mod tests {
             let config = create_config("true".to_string());
             let mut tuner = Tuner::new(0, &config, None);
             tuner
-                .activate(&create_channel("1"), vec![], &system)
+                .activate(&create_channel("1"), 0.into(), vec![], &system)
                 .await
                 .ok();
 
             tokio::task::yield_now().await;
 
             tuner.deactivate();
-            let result = tuner.activate(&create_channel("2"), vec![], &system).await;
+            let result = tuner
+                .activate(&create_channel("2"), 0.into(), vec![], &system)
+                .await;
             assert!(result.is_ok());
 
             tokio::task::yield_now().await;
@@ -1122,6 +3062,155 @@ mod tests {
         system.stop();
     }
 
+    #[tokio::test]
+    async fn test_start_streaming_fault() {
+        let system = System::new();
+
+        {
+            let config: Arc<Config> = Arc::new(
+                serde_yaml::from_str(
+                    r#"
+                tuners:
+                  - name: gr
+                    types: [GR]
+                    command: >-
+                      true
+                    fault-threshold: 1
+                "#,
+                )
+                .unwrap(),
+            );
+
+            let manager = system.spawn_actor(TunerManager::new(config)).await;
+
+            let result = manager
+                .call(StartStreaming {
+                    channel: create_channel("0"),
+                    user: create_user(0.into()),
+                    stream_id: None,
+                })
+                .await;
+            let _stream = assert_matches!(result, Ok(Ok(stream)) => stream);
+
+            // The tuner command exits without outputting any TS packet.
+            let mut is_fault = false;
+            for _ in 0..100 {
+                let tuner = manager.call(QueryTuner(0)).await.unwrap().unwrap();
+                if tuner.is_fault {
+                    is_fault = true;
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+            assert!(is_fault);
+
+            let result = manager
+                .call(StartStreaming {
+                    channel: create_channel("1"),
+                    user: create_user(TunerUserPriority::GRAB),
+                    stream_id: None,
+                })
+                .await;
+            assert_matches!(result, Ok(Err(Error::TunerUnavailable)));
+        }
+        system.stop();
+    }
+
+    #[tokio::test]
+    async fn test_tuner_fault_on_spawn_error() {
+        let system = System::new();
+        {
+            let config = create_config("no-such-command".to_string());
+            let mut tuner = Tuner::new(0, &config, None);
+            for _ in 0..config.fault_threshold {
+                assert!(!tuner.is_fault());
+                let result = tuner
+                    .activate(&create_channel("1"), 0.into(), vec![], &system)
+                    .await;
+                assert!(result.is_err());
+            }
+            assert!(tuner.is_fault());
+            assert!(!tuner.is_available());
+            let model = tuner.get_mirakurun_model();
+            assert!(model.is_fault);
+            assert!(!model.is_free);
+        }
+        system.stop();
+    }
+
+    #[test]
+    fn test_tuner_fault() {
+        let interval = Duration::from_secs(60);
+        let now = Instant::now();
+        let mut fault = TunerFault::default();
+
+        fault.record_failure(2, interval, now);
+        assert!(!fault.is_fault());
+        assert!(!fault.take_changed());
+
+        fault.record_failure(2, interval, now);
+        assert!(fault.is_fault());
+        assert!(fault.take_changed());
+        assert_eq!(fault.quarantined_until, Some(now + interval));
+
+        assert!(!fault.release(now));
+        assert!(fault.release(now + interval));
+        assert!(!fault.is_fault());
+        assert!(fault.take_changed());
+
+        // Quarantined again with a doubled interval.
+        fault.record_failure(2, interval, now);
+        assert!(fault.is_fault());
+        assert_eq!(fault.quarantined_until, Some(now + interval * 2));
+        assert!(fault.take_changed());
+
+        fault.record_success();
+        assert!(!fault.is_fault());
+        assert!(fault.take_changed());
+        assert_eq!(fault.failures, 0);
+        assert_eq!(fault.quarantines, 0);
+
+        // The interval is limited.
+        fault.quarantines = 100;
+        fault.failures = 1;
+        fault.record_failure(2, interval, now);
+        assert_eq!(
+            fault.quarantined_until,
+            Some(now + TunerFault::MAX_RETRY_INTERVAL)
+        );
+
+        // Disabled.
+        let mut fault = TunerFault::default();
+        for _ in 0..10 {
+            fault.record_failure(0, interval, now);
+        }
+        assert!(!fault.is_fault());
+
+        // Failures on the same channel are counted only once.
+        let mut fault = TunerFault::default();
+        for _ in 0..10 {
+            fault.record_channel_failure((ChannelType::GR, "1".to_string()), 2, interval, now);
+        }
+        assert!(!fault.is_fault());
+        fault.record_channel_failure((ChannelType::GR, "2".to_string()), 2, interval, now);
+        assert!(fault.is_fault());
+
+        fault.record_success();
+        assert!(fault.failed_channels.is_empty());
+        fault.record_channel_failure((ChannelType::GR, "1".to_string()), 2, interval, now);
+        fault.record_failure(2, interval, now);
+        assert!(fault.is_fault());
+    }
+
+    mockall::mock! {
+        EventEmitter {}
+
+        #[async_trait]
+        impl Emit<Event> for EventEmitter {
+            async fn emit(&self, msg: Event);
+        }
+    }
+
     fn create_config(command: String) -> TunerConfig {
         TunerConfig {
             name: "tuner".to_string(),
@@ -1131,6 +3220,11 @@ mod tests {
             disabled: false,
             decoded: false,
             dedicated_for: None,
+            fault_threshold: 3,
+            fault_retry_interval: 60 * 1000,
+            upstream: None,
+            weight: 1,
+            role: TunerRole::Any,
         }
     }
 
@@ -1180,6 +3274,84 @@ pub(crate) mod stub {
         }
     }
 
+    #[async_trait]
+    impl Call<SetTunerMaintenance> for TunerManagerStub {
+        async fn call(
+            &self,
+            msg: SetTunerMaintenance,
+        ) -> actlet::Result<<SetTunerMaintenance as Message>::Reply> {
+            match msg.index {
+                0 => Ok(Err(Error::TunerNotFound)),
+                _ => {
+                    let mut tuner = tuner!(msg.index);
+                    tuner.is_free = !msg.maintenance;
+                    tuner.is_maintenance = msg.maintenance;
+                    Ok(Ok(tuner))
+                }
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Call<ReloadConfig> for TunerManagerStub {
+        async fn call(
+            &self,
+            _msg: ReloadConfig,
+        ) -> actlet::Result<<ReloadConfig as Message>::Reply> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl Call<QueryTunerReservations> for TunerManagerStub {
+        async fn call(
+            &self,
+            _msg: QueryTunerReservations,
+        ) -> actlet::Result<<QueryTunerReservations as Message>::Reply> {
+            Ok(vec![])
+        }
+    }
+
+    #[async_trait]
+    impl Call<AddTunerReservation> for TunerManagerStub {
+        async fn call(
+            &self,
+            msg: AddTunerReservation,
+        ) -> actlet::Result<<AddTunerReservation as Message>::Reply> {
+            if let Err(err) = msg.reservation.validate() {
+                return Ok(Err(err));
+            }
+            let mut reservation = msg.reservation;
+            reservation.id = 1;
+            Ok(Ok(reservation))
+        }
+    }
+
+    #[async_trait]
+    impl Call<RemoveTunerReservation> for TunerManagerStub {
+        async fn call(
+            &self,
+            msg: RemoveTunerReservation,
+        ) -> actlet::Result<<RemoveTunerReservation as Message>::Reply> {
+            match msg.id {
+                0 => Ok(Err(Error::ReservationNotFound)),
+                id => Ok(Ok(TunerReservation {
+                    id,
+                    target: TunerReservationTarget::Tuner {
+                        name: "tuner".to_string(),
+                    },
+                    window: crate::tuner_reservation::TunerReservationWindow::Daily {
+                        start: chrono::NaiveTime::from_hms_opt(19, 0, 0).unwrap(),
+                        end: chrono::NaiveTime::from_hms_opt(21, 0, 0).unwrap(),
+                    },
+                    owner: "owner".to_string(),
+                    priority: 0,
+                    from_config: false,
+                })),
+            }
+        }
+    }
+
     #[async_trait]
     impl Call<StartStreaming> for TunerManagerStub {
         async fn call(
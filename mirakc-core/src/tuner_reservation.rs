@@ -0,0 +1,430 @@
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::NaiveDate;
+use chrono::NaiveTime;
+use chrono_jst::serde::ts_milliseconds;
+use chrono_jst::Jst;
+use serde::Deserialize;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::config::TunerReservationConfig;
+use crate::error::Error;
+use crate::models::ChannelType;
+use crate::models::TunerUser;
+use crate::models::TunerUserInfo;
+use crate::models::TunerUserPriority;
+
+/// A reservation of a tuner for a time window.
+///
+/// While the reservation is in effect, the reserved tuner can be used only by
+/// the owner and users having a higher priority than the reservation.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(title = "TunerReservation")]
+pub struct TunerReservation {
+    /// The ID of the reservation.
+    pub id: u32,
+    /// The target of the reservation.
+    pub target: TunerReservationTarget,
+    /// The time window of the reservation.
+    pub window: TunerReservationWindow,
+    /// The owner of the reservation.
+    ///
+    /// A tuner user having the same ID as the owner can use the reserved tuner
+    /// regardless of its priority.
+    pub owner: String,
+    /// The priority of the reservation.
+    pub priority: i32,
+    /// `true` if the reservation is defined in `config.tuner-reservations`.
+    ///
+    /// Such a reservation cannot be removed via Web API.
+    pub from_config: bool,
+}
+
+impl TunerReservation {
+    pub(crate) fn from_config(id: u32, config: &TunerReservationConfig) -> Self {
+        let target = match (&config.tuner, config.channel_type) {
+            (Some(name), _) => TunerReservationTarget::Tuner { name: name.clone() },
+            (None, Some(channel_type)) => TunerReservationTarget::ChannelType { channel_type },
+            // Checked in `TunerReservationConfig::validate()`.
+            (None, None) => unreachable!(),
+        };
+        let window = match (config.start, config.end, config.start_at, config.end_at) {
+            (Some(start), Some(end), _, _) => TunerReservationWindow::Daily { start, end },
+            (_, _, Some(start_at), Some(end_at)) => TunerReservationWindow::Once {
+                start_at: start_at.with_timezone(&Jst),
+                end_at: end_at.with_timezone(&Jst),
+            },
+            // Checked in `TunerReservationConfig::validate()`.
+            _ => unreachable!(),
+        };
+        TunerReservation {
+            id,
+            target,
+            window,
+            owner: config.owner.clone(),
+            priority: config.priority,
+            from_config: true,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if !(TunerUserPriority::MIN..=TunerUserPriority::MAX).contains(&self.priority) {
+            return Err(Error::InvalidReservation);
+        }
+        if let TunerReservationTarget::Tuner { ref name } = self.target {
+            if name.is_empty() {
+                return Err(Error::InvalidReservation);
+            }
+        }
+        if !self.window.is_valid() {
+            return Err(Error::InvalidTimeRange);
+        }
+        Ok(())
+    }
+
+    pub fn priority(&self) -> TunerUserPriority {
+        self.priority.into()
+    }
+
+    pub fn permits(&self, user: &TunerUser) -> bool {
+        user.priority > self.priority() || user.get_mirakurun_model().id == self.owner
+    }
+
+    // A pseudo tuner user used when the reservation preempts a tuner.
+    pub(crate) fn user(&self) -> TunerUser {
+        TunerUser {
+            info: TunerUserInfo::Reservation {
+                owner: self.owner.clone(),
+            },
+            priority: self.priority(),
+        }
+    }
+}
+
+/// The target of a tuner reservation.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum TunerReservationTarget {
+    /// A tuner specified by its name.
+    #[serde(rename_all = "camelCase")]
+    Tuner {
+        /// The name of the tuner.
+        name: String,
+    },
+    /// A tuner supporting the channel type.
+    ///
+    /// The last tuner in `config.tuners` which supports the channel type and
+    /// is not reserved by other reservations is reserved.
+    #[serde(rename_all = "camelCase")]
+    ChannelType {
+        /// The channel type.
+        channel_type: ChannelType,
+    },
+}
+
+/// The time window of a tuner reservation.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum TunerReservationWindow {
+    /// A time window in a specific period.
+    #[serde(rename_all = "camelCase")]
+    Once {
+        /// The start time of the window in UNIX time (milliseconds).
+        #[serde(with = "ts_milliseconds")]
+        #[schema(value_type = i64)]
+        start_at: DateTime<Jst>,
+        /// The end time of the window in UNIX time (milliseconds).
+        #[serde(with = "ts_milliseconds")]
+        #[schema(value_type = i64)]
+        end_at: DateTime<Jst>,
+    },
+    /// A time window repeated every day in JST.
+    ///
+    /// `end` can be smaller than `start`.  In this case, the time window
+    /// crosses midnight.
+    #[serde(rename_all = "camelCase")]
+    Daily {
+        /// The start time of the window such as `19:00:00` (inclusive).
+        #[schema(value_type = String)]
+        start: NaiveTime,
+        /// The end time of the window such as `22:00:00` (exclusive).
+        #[schema(value_type = String)]
+        end: NaiveTime,
+    },
+}
+
+impl TunerReservationWindow {
+    fn is_valid(&self) -> bool {
+        match self {
+            Self::Once { start_at, end_at } => start_at < end_at,
+            Self::Daily { start, end } => start != end,
+        }
+    }
+
+    pub fn is_expired(&self, now: DateTime<Jst>) -> bool {
+        match self {
+            Self::Once { end_at, .. } => *end_at <= now,
+            Self::Daily { .. } => false,
+        }
+    }
+
+    pub fn contains(&self, time: DateTime<Jst>) -> bool {
+        self.overlaps(time, time + Duration::milliseconds(1))
+    }
+
+    /// Returns `true` if the window overlaps with the range from `start`
+    /// (inclusive) to `end` (exclusive).
+    pub fn overlaps(&self, start: DateTime<Jst>, end: DateTime<Jst>) -> bool {
+        match self {
+            Self::Once { start_at, end_at } => *start_at < end && start < *end_at,
+            Self::Daily {
+                start: daily_start,
+                end: daily_end,
+            } => {
+                // The window which started on the previous day may still
+                // continue.
+                let mut date = start.date_naive() - Duration::days(1);
+                while date <= end.date_naive() {
+                    let (window_start, window_end) = daily_window(*daily_start, *daily_end, date);
+                    if window_start < end && start < window_end {
+                        return true;
+                    }
+                    date += Duration::days(1);
+                }
+                false
+            }
+        }
+    }
+
+    /// Returns the time when the window starts next time after `now`.
+    pub fn next_start(&self, now: DateTime<Jst>) -> Option<DateTime<Jst>> {
+        match self {
+            Self::Once { start_at, .. } if *start_at > now => Some(*start_at),
+            Self::Once { .. } => None,
+            Self::Daily { start, end } => {
+                let (start, _) = daily_window(*start, *end, now.date_naive());
+                if start > now {
+                    Some(start)
+                } else {
+                    Some(start + Duration::days(1))
+                }
+            }
+        }
+    }
+}
+
+// Returns the start and end times of a daily window on `date`.
+fn daily_window(
+    start: NaiveTime,
+    end: NaiveTime,
+    date: NaiveDate,
+) -> (DateTime<Jst>, DateTime<Jst>) {
+    // JST has no DST.  So, the conversion never fails.
+    let start_at = date.and_time(start).and_local_timezone(Jst).unwrap();
+    let mut end_at = date.and_time(end).and_local_timezone(Jst).unwrap();
+    if end <= start {
+        end_at += Duration::days(1);
+    }
+    (start_at, end_at)
+}
+
+// Assigns tuners to reservations.
+//
+// `tuners` is a list of names and channel types of tuners which can be
+// reserved.  The returned list holds reservations assigned to each tuner in
+// `tuners`.
+//
+// A reservation for a channel type takes the last tuner which supports the
+// channel type and has not been taken by other reservations yet, so that
+// tuners listed earlier in `config.tuners` are kept available as long as
+// possible.  The reservation is ignored if there is no such tuner.
+pub(crate) fn assign_tuners<'a, I>(
+    reservations: I,
+    tuners: &[(&str, &[ChannelType])],
+) -> Vec<Vec<&'a TunerReservation>>
+where
+    I: Iterator<Item = &'a TunerReservation>,
+{
+    let mut assigned: Vec<Vec<&'a TunerReservation>> = vec![vec![]; tuners.len()];
+    let (by_name, by_type): (Vec<_>, Vec<_>) = reservations.partition(|reservation| {
+        matches!(reservation.target, TunerReservationTarget::Tuner { .. })
+    });
+    for reservation in by_name.into_iter() {
+        if let TunerReservationTarget::Tuner { ref name } = reservation.target {
+            if let Some(i) = tuners.iter().position(|(tuner, _)| *tuner == name) {
+                assigned[i].push(reservation);
+            }
+        }
+    }
+    for reservation in by_type.into_iter() {
+        if let TunerReservationTarget::ChannelType { channel_type } = reservation.target {
+            let found = tuners
+                .iter()
+                .enumerate()
+                .rev()
+                .filter(|(i, _)| assigned[*i].is_empty())
+                .find(|(_, (_, channel_types))| channel_types.contains(&channel_type))
+                .map(|(i, _)| i);
+            if let Some(i) = found {
+                assigned[i].push(reservation);
+            }
+        }
+    }
+    assigned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_window_once() {
+        let start_at = Jst.with_ymd_and_hms(2026, 10, 17, 19, 0, 0).unwrap();
+        let end_at = Jst.with_ymd_and_hms(2026, 10, 17, 21, 0, 0).unwrap();
+        let window = TunerReservationWindow::Once { start_at, end_at };
+
+        assert!(!window.contains(start_at - Duration::seconds(1)));
+        assert!(window.contains(start_at));
+        assert!(!window.contains(end_at));
+
+        assert!(window.overlaps(
+            start_at - Duration::hours(1),
+            start_at + Duration::seconds(1)
+        ));
+        assert!(!window.overlaps(start_at - Duration::hours(1), start_at));
+        assert!(!window.overlaps(end_at, end_at + Duration::hours(1)));
+
+        assert_eq!(
+            window.next_start(start_at - Duration::hours(1)),
+            Some(start_at)
+        );
+        assert_eq!(window.next_start(start_at), None);
+        assert!(!window.is_expired(start_at));
+        assert!(window.is_expired(end_at));
+    }
+
+    #[test]
+    fn test_window_daily() {
+        let window = TunerReservationWindow::Daily {
+            start: NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(1, 0, 0).unwrap(),
+        };
+        let time = |d, h, m| Jst.with_ymd_and_hms(2026, 10, d, h, m, 0).unwrap();
+
+        assert!(window.contains(time(17, 23, 0)));
+        assert!(window.contains(time(18, 0, 30)));
+        assert!(!window.contains(time(18, 1, 0)));
+        assert!(!window.contains(time(18, 22, 59)));
+
+        assert!(window.overlaps(time(18, 0, 0), time(18, 2, 0)));
+        assert!(window.overlaps(time(18, 22, 0), time(18, 23, 30)));
+        assert!(!window.overlaps(time(18, 1, 0), time(18, 23, 0)));
+
+        assert_eq!(window.next_start(time(17, 12, 0)), Some(time(17, 23, 0)));
+        assert_eq!(window.next_start(time(17, 23, 0)), Some(time(18, 23, 0)));
+        assert!(!window.is_expired(time(17, 12, 0)));
+    }
+
+    #[test]
+    fn test_validate() {
+        let now = Jst::now();
+        let mut reservation = reservation_for_test(
+            1,
+            TunerReservationTarget::Tuner {
+                name: "tuner".to_string(),
+            },
+        );
+        assert!(reservation.validate().is_ok());
+
+        reservation.priority = TunerUserPriority::MAX + 1;
+        assert_matches!(reservation.validate(), Err(Error::InvalidReservation));
+
+        reservation.priority = 0;
+        reservation.window = TunerReservationWindow::Once {
+            start_at: now,
+            end_at: now,
+        };
+        assert_matches!(reservation.validate(), Err(Error::InvalidTimeRange));
+    }
+
+    #[test]
+    fn test_permits() {
+        let reservation = reservation_for_test(
+            1,
+            TunerReservationTarget::ChannelType {
+                channel_type: ChannelType::GR,
+            },
+        );
+        assert!(!reservation.permits(&user_for_test("user", 0)));
+        assert!(reservation.permits(&user_for_test("user", 1)));
+        assert!(reservation.permits(&user_for_test("owner", 0)));
+    }
+
+    #[test]
+    fn test_assign_tuners() {
+        let gr = [ChannelType::GR];
+        let gr_bs = [ChannelType::GR, ChannelType::BS];
+        let tuners: [(&str, &[ChannelType]); 3] = [("a", &gr_bs), ("b", &gr), ("c", &gr)];
+
+        let reservations = [
+            reservation_for_test(
+                1,
+                TunerReservationTarget::ChannelType {
+                    channel_type: ChannelType::GR,
+                },
+            ),
+            reservation_for_test(
+                2,
+                TunerReservationTarget::Tuner {
+                    name: "c".to_string(),
+                },
+            ),
+            reservation_for_test(
+                3,
+                TunerReservationTarget::ChannelType {
+                    channel_type: ChannelType::BS,
+                },
+            ),
+            reservation_for_test(
+                4,
+                TunerReservationTarget::ChannelType {
+                    channel_type: ChannelType::BS,
+                },
+            ),
+        ];
+        let assigned = assign_tuners(reservations.iter(), &tuners);
+        let ids: Vec<Vec<u32>> = assigned
+            .iter()
+            .map(|reservations| reservations.iter().map(|r| r.id).collect())
+            .collect();
+        assert_eq!(ids, vec![vec![3], vec![1], vec![2]]);
+    }
+
+    fn reservation_for_test(id: u32, target: TunerReservationTarget) -> TunerReservation {
+        TunerReservation {
+            id,
+            target,
+            window: TunerReservationWindow::Daily {
+                start: NaiveTime::from_hms_opt(19, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(21, 0, 0).unwrap(),
+            },
+            owner: "owner".to_string(),
+            priority: 0,
+            from_config: false,
+        }
+    }
+
+    fn user_for_test(id: &str, priority: i32) -> TunerUser {
+        TunerUser {
+            info: TunerUserInfo::Web {
+                id: id.to_string(),
+                agent: None,
+            },
+            priority: priority.into(),
+        }
+    }
+}
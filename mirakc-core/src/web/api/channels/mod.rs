@@ -26,3 +26,71 @@ where
         .map(Json::from)
         .map_err(Error::from)
 }
+
+/// Enables a channel disabled at runtime.
+///
+/// Services in the channel will be available after scanning services.
+#[utoipa::path(
+    post,
+    path = "/channels/{type}/{channel}/enable",
+    params(
+        ("type" = ChannelType, Path, description = "Channel type"),
+        ("channel" = String, Path, description = "Channel number"),
+    ),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "enableChannel",
+)]
+pub(super) async fn enable<E>(
+    State(EpgExtractor(epg)): State<EpgExtractor<E>>,
+    Path(path): Path<ChannelPath>,
+) -> Result<(), Error>
+where
+    E: Call<epg::SetChannelEnabled>,
+{
+    epg.call(epg::SetChannelEnabled {
+        channel_type: path.channel_type,
+        channel: path.channel,
+        enabled: true,
+    })
+    .await??;
+    Ok(())
+}
+
+/// Disables a channel at runtime.
+///
+/// The channel is hidden and excluded from EPG jobs until it's enabled.  The
+/// state is kept across restarts if `config.maintenance.state-dir` is
+/// specified.
+#[utoipa::path(
+    post,
+    path = "/channels/{type}/{channel}/disable",
+    params(
+        ("type" = ChannelType, Path, description = "Channel type"),
+        ("channel" = String, Path, description = "Channel number"),
+    ),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "disableChannel",
+)]
+pub(super) async fn disable<E>(
+    State(EpgExtractor(epg)): State<EpgExtractor<E>>,
+    Path(path): Path<ChannelPath>,
+) -> Result<(), Error>
+where
+    E: Call<epg::SetChannelEnabled>,
+{
+    epg.call(epg::SetChannelEnabled {
+        channel_type: path.channel_type,
+        channel: path.channel,
+        enabled: false,
+    })
+    .await??;
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use super::*;
+
+/// Reloads the config file.
+///
+/// Only `channels`, `tuners`, `tuner-selection`, `filters`, `pre-filters`,
+/// `post-filters`, `server.mounts` and `timeshift` are applied at runtime.
+/// Running streams won't be affected.
+///
+/// The config is also reloaded when mirakc receives SIGHUP.
+#[utoipa::path(
+    post,
+    path = "/config/reload",
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Bad Request"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "reloadConfig",
+)]
+pub(super) async fn reload<T, E, R, S>(
+    State(ConfigReloaderExtractor(config_reloader)): State<ConfigReloaderExtractor>,
+    State(TunerManagerExtractor(tuner_manager)): State<TunerManagerExtractor<T>>,
+    State(EpgExtractor(epg)): State<EpgExtractor<E>>,
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+    State(TimeshiftManagerExtractor(timeshift_manager)): State<TimeshiftManagerExtractor<S>>,
+) -> Result<(), Error>
+where
+    T: Call<crate::tuner::ReloadConfig>,
+    E: Call<crate::epg::ReloadConfig>,
+    R: Call<crate::recording::ReloadConfig>,
+    S: Call<crate::timeshift::ReloadConfig>,
+{
+    config_reloader
+        .reload(&tuner_manager, &epg, &recording_manager, &timeshift_manager)
+        .await?;
+    Ok(())
+}
@@ -14,12 +14,26 @@ use utoipa::openapi::Server;
 use utoipa::OpenApi;
 
 use crate::epg;
+use crate::epg::ProgramSearchGenre;
+use crate::epg::ProgramSearchSort;
 use crate::filter::FilterPipelineBuilder;
 use crate::models::*;
+use crate::recording::RecordingConflict;
+use crate::recording::RecordingConflictCompetitor;
+use crate::recording::RecordingConflictReason;
 use crate::recording::RecordingFailedReason;
+use crate::recording::RecordingHistoryEvent;
+use crate::recording::RecordingJobState;
 use crate::recording::RecordingOptions;
+use crate::recording::RecordingRuleCondition;
+use crate::recording::RecordingRuleGenre;
+use crate::recording::RecordingRuleTimeRange;
 use crate::recording::RecordingScheduleState;
+use crate::ts_stats::TsStats;
 use crate::tuner;
+use crate::tuner_reservation::TunerReservation;
+use crate::tuner_reservation::TunerReservationTarget;
+use crate::tuner_reservation::TunerReservationWindow;
 
 use super::body::StaticFileBody;
 use super::qs::Qs;
@@ -28,6 +42,7 @@ use super::AppState;
 use super::X_MIRAKURUN_TUNER_USER_ID;
 
 mod channels;
+mod config;
 mod iptv;
 mod onair;
 mod programs;
@@ -45,8 +60,13 @@ use models::*;
 pub(super) fn build_api<T, E, R, S, O>(config: &Config) -> Router<Arc<AppState<T, E, R, S, O>>>
 where
     T: Clone + Send + Sync + 'static,
+    T: Call<crate::tuner::AddTunerReservation>,
     T: Call<crate::tuner::QueryTuner>,
+    T: Call<crate::tuner::QueryTunerReservations>,
     T: Call<crate::tuner::QueryTuners>,
+    T: Call<crate::tuner::ReloadConfig>,
+    T: Call<crate::tuner::RemoveTunerReservation>,
+    T: Call<crate::tuner::SetTunerMaintenance>,
     T: Call<crate::tuner::StartStreaming>,
     T: TriggerFactory<crate::tuner::StopStreaming>,
     E: Clone + Send + Sync + 'static,
@@ -57,16 +77,40 @@ where
     E: Call<crate::epg::QueryPrograms>,
     E: Call<crate::epg::QueryService>,
     E: Call<crate::epg::QueryServices>,
+    E: Call<crate::epg::ReloadConfig>,
+    E: Call<crate::epg::SearchPrograms>,
+    E: Call<crate::epg::SetChannelEnabled>,
+    E: Call<crate::epg::SetServiceEnabled>,
     R: Clone + Send + Sync + 'static,
+    R: Call<crate::recording::AddManualRecordingSchedule>,
+    R: Call<crate::recording::AddRecordingRule>,
     R: Call<crate::recording::AddRecordingSchedule>,
+    R: Call<crate::recording::AddRecordingSeries>,
+    R: Call<crate::recording::QueryRecordingConflicts>,
+    R: Call<crate::recording::QueryRecordingHistory>,
+    R: Call<crate::recording::QueryRecordingJob>,
+    R: Call<crate::recording::QueryRecordingJobs>,
+    R: Call<crate::recording::QueryRecordingRecord>,
     R: Call<crate::recording::QueryRecordingRecorder>,
     R: Call<crate::recording::QueryRecordingRecorders>,
+    R: Call<crate::recording::QueryRecordingRecords>,
+    R: Call<crate::recording::QueryRecordingRule>,
+    R: Call<crate::recording::QueryRecordingRules>,
     R: Call<crate::recording::QueryRecordingSchedule>,
     R: Call<crate::recording::QueryRecordingSchedules>,
+    R: Call<crate::recording::QueryRecordingSeries>,
+    R: Call<crate::recording::QueryRecordingSeriesList>,
+    R: Call<crate::recording::ReloadConfig>,
+    R: Call<crate::recording::RemoveRecordingRecord>,
+    R: Call<crate::recording::RemoveRecordingRule>,
     R: Call<crate::recording::RemoveRecordingSchedule>,
     R: Call<crate::recording::RemoveRecordingSchedules>,
+    R: Call<crate::recording::RemoveRecordingSeries>,
     R: Call<crate::recording::StartRecording>,
     R: Call<crate::recording::StopRecording>,
+    R: Call<crate::recording::UpdateRecordingRecord>,
+    R: Call<crate::recording::UpdateRecordingRule>,
+    R: Call<crate::recording::UpdateRecordingSchedule>,
     S: Clone + Send + Sync + 'static,
     S: Call<crate::timeshift::CreateTimeshiftLiveStreamSource>,
     S: Call<crate::timeshift::CreateTimeshiftRecordStreamSource>,
@@ -74,6 +118,7 @@ where
     S: Call<crate::timeshift::QueryTimeshiftRecords>,
     S: Call<crate::timeshift::QueryTimeshiftRecorder>,
     S: Call<crate::timeshift::QueryTimeshiftRecorders>,
+    S: Call<crate::timeshift::ReloadConfig>,
     O: Clone + Send + Sync + 'static,
     O: Call<crate::onair::QueryOnairProgram>,
     O: Call<crate::onair::QueryOnairPrograms>,
@@ -90,11 +135,31 @@ where
         .route("/status", routing::get(status::get))
         .route("/tuners", routing::get(tuners::list))
         .route("/tuners/:index", routing::get(tuners::get))
+        .route(
+            "/tuners/:index/maintenance",
+            routing::put(tuners::enter_maintenance).delete(tuners::leave_maintenance),
+        )
+        .route(
+            "/tuners/reservations",
+            routing::get(tuners::list_reservations).post(tuners::create_reservation),
+        )
+        .route(
+            "/tuners/reservations/:id",
+            routing::delete(tuners::delete_reservation),
+        )
         .route("/channels", routing::get(channels::list))
         .route(
             "/channels/:channel_type/:channel/stream",
             routing::get(channels::stream::get).head(channels::stream::head),
         )
+        .route(
+            "/channels/:channel_type/:channel/enable",
+            routing::post(channels::enable),
+        )
+        .route(
+            "/channels/:channel_type/:channel/disable",
+            routing::post(channels::disable),
+        )
         .route(
             "/channels/:channel_type/:channel/services/:sid/stream",
             routing::get(channels::services::stream::get).head(channels::services::stream::head),
@@ -103,11 +168,18 @@ where
         .route("/services/:id", routing::get(services::get))
         .route("/services/:id/logo", routing::get(services::logo))
         .route("/services/:id/programs", routing::get(services::programs))
+        .route(
+            "/services/:id/record-now",
+            routing::post(services::record_now),
+        )
+        .route("/services/:id/enable", routing::post(services::enable))
+        .route("/services/:id/disable", routing::post(services::disable))
         .route(
             "/services/:id/stream",
             routing::get(services::stream::get).head(services::stream::head),
         )
         .route("/programs", routing::get(programs::list))
+        .route("/programs/search", routing::get(programs::search))
         .route("/programs/:id", routing::get(programs::get))
         .route(
             "/programs/:id/stream",
@@ -120,7 +192,8 @@ where
         // For compatibility with Mirakurun
         .route("/iptv/xmltv", routing::get(iptv::xmltv))
         .route("/onair", routing::get(onair::list))
-        .route("/onair/:service_id", routing::get(onair::get));
+        .route("/onair/:service_id", routing::get(onair::get))
+        .route("/config/reload", routing::post(config::reload));
 
     if config.recording.is_enabled() {
         tracing::info!("Enable endpoints for recording");
@@ -133,6 +206,10 @@ where
                 "/recording/schedules",
                 routing::post(recording::schedules::create),
             )
+            .route(
+                "/recording/schedules/manual",
+                routing::post(recording::schedules::create_manual),
+            )
             .route(
                 "/recording/schedules",
                 routing::delete(recording::schedules::clear),
@@ -141,10 +218,66 @@ where
                 "/recording/schedules/:id",
                 routing::get(recording::schedules::get),
             )
+            .route(
+                "/recording/schedules/:id",
+                routing::patch(recording::schedules::update),
+            )
             .route(
                 "/recording/schedules/:id",
                 routing::delete(recording::schedules::delete),
             )
+            .route("/recording/rules", routing::get(recording::rules::list))
+            .route("/recording/rules", routing::post(recording::rules::create))
+            .route("/recording/rules/:id", routing::get(recording::rules::get))
+            .route(
+                "/recording/rules/:id",
+                routing::put(recording::rules::update),
+            )
+            .route(
+                "/recording/rules/:id",
+                routing::delete(recording::rules::delete),
+            )
+            .route("/recording/series", routing::get(recording::series::list))
+            .route(
+                "/recording/series",
+                routing::post(recording::series::create),
+            )
+            .route(
+                "/recording/series/:id",
+                routing::get(recording::series::get),
+            )
+            .route(
+                "/recording/series/:id",
+                routing::delete(recording::series::delete),
+            )
+            .route(
+                "/recording/conflicts",
+                routing::get(recording::conflicts::list),
+            )
+            .route("/recording/records", routing::get(recording::records::list))
+            .route(
+                "/recording/records/:id",
+                routing::get(recording::records::get),
+            )
+            .route(
+                "/recording/records/:id",
+                routing::patch(recording::records::update),
+            )
+            .route(
+                "/recording/records/:id",
+                routing::delete(recording::records::delete),
+            )
+            .route(
+                "/recording/records/:id/stream",
+                routing::get(recording::records::stream),
+            )
+            .route("/recording/jobs", routing::get(recording::jobs::list))
+            .route("/recording/history", routing::get(recording::history::list))
+            .route(
+                "/recording/import/epgstation",
+                routing::post(recording::import::epgstation),
+            )
+            .route("/recording/jobs/:id", routing::get(recording::jobs::get))
             .route(
                 "/recording/recorders",
                 routing::get(recording::recorders::list),
@@ -199,18 +332,29 @@ where
         status::get,
         tuners::list,
         tuners::get,
+        tuners::enter_maintenance,
+        tuners::leave_maintenance,
+        tuners::list_reservations,
+        tuners::create_reservation,
+        tuners::delete_reservation,
         channels::list,
         channels::stream::get,
         channels::stream::head,
+        channels::enable,
+        channels::disable,
         channels::services::stream::get,
         channels::services::stream::head,
         services::list,
         services::get,
         services::logo,
         services::programs,
+        services::record_now,
+        services::enable,
+        services::disable,
         services::stream::get,
         services::stream::head,
         programs::list,
+        programs::search,
         programs::get,
         programs::stream::get,
         programs::stream::head,
@@ -219,11 +363,33 @@ where
         iptv::xmltv,
         onair::list,
         onair::get,
+        config::reload,
         recording::schedules::list,
         recording::schedules::get,
         recording::schedules::create,
+        recording::schedules::create_manual,
+        recording::schedules::update,
         recording::schedules::delete,
         recording::schedules::clear,
+        recording::rules::list,
+        recording::rules::get,
+        recording::rules::create,
+        recording::rules::update,
+        recording::rules::delete,
+        recording::series::list,
+        recording::series::get,
+        recording::series::create,
+        recording::series::delete,
+        recording::conflicts::list,
+        recording::records::list,
+        recording::records::get,
+        recording::records::update,
+        recording::records::delete,
+        recording::records::stream,
+        recording::jobs::list,
+        recording::jobs::get,
+        recording::history::list,
+        recording::import::epgstation,
         recording::recorders::list,
         recording::recorders::get,
         recording::recorders::create,
@@ -239,21 +405,53 @@ where
         schemas(
             models::Status,
             models::Version,
+            models::WebEpgstationImportInput,
+            models::WebImportItem,
+            models::WebImportResult,
+            models::WebManualRecordingScheduleInput,
+            models::WebRecordNowInput,
             models::WebOnairProgram,
+            models::WebProgramSearchResult,
             models::WebProcessModel,
+            models::WebRecordingHistoryEntry,
+            models::WebRecordingJob,
+            models::WebRecordingRecord,
+            models::WebRecordingRecordPatch,
             models::WebRecordingRecorder,
+            models::WebRecordingRule,
+            models::WebRecordingRuleInput,
+            models::WebRecordingSeries,
+            models::WebRecordingSeriesInput,
             models::WebRecordingSchedule,
             models::WebRecordingScheduleInput,
+            models::WebRecordingSchedulePatch,
             models::WebTimeshiftRecord,
             models::WebTimeshiftRecorder,
+            models::WebTunerReservationInput,
             ChannelType,
             MirakurunChannel,
             MirakurunProgram,
             MirakurunService,
             MirakurunTuner,
+            ProgramSearchGenre,
+            ProgramSearchSort,
+            RecordingConflict,
+            RecordingConflictCompetitor,
+            RecordingConflictReason,
             RecordingFailedReason,
+            RecordingHistoryEvent,
+            RecordingJobState,
             RecordingOptions,
+            RecordingRuleCondition,
+            RecordingRuleGenre,
+            RecordingRuleTimeRange,
             RecordingScheduleState,
+            TsStats,
+            TunerReservation,
+            TunerReservationTarget,
+            TunerReservationWindow,
+            TunerRole,
+            TunerSelectionPolicy,
         ),
     ),
     modifiers(
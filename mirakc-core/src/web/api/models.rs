@@ -1,6 +1,8 @@
 use std::collections::HashSet;
 use std::convert::Infallible;
 use std::net::SocketAddr;
+use std::path::Path;
+use std::path::PathBuf;
 
 use async_trait::async_trait;
 use axum::extract::FromRequestParts;
@@ -9,6 +11,7 @@ use axum::http::request::Parts;
 use chrono::DateTime;
 use chrono::Duration;
 use chrono_jst::serde::duration_milliseconds;
+use chrono_jst::serde::duration_milliseconds_option;
 use chrono_jst::serde::ts_milliseconds;
 use chrono_jst::serde::ts_milliseconds_option;
 use chrono_jst::Jst;
@@ -20,6 +23,10 @@ use utoipa::ToSchema;
 
 use crate::command_util::CommandPipelineProcessModel;
 use crate::config::Config;
+use crate::config::RecordingConfig;
+use crate::epg;
+use crate::epg::ProgramSearchGenre;
+use crate::epg::ProgramSearchSort;
 use crate::error::Error;
 use crate::models::ChannelType;
 use crate::models::MirakurunProgram;
@@ -33,11 +40,19 @@ use crate::models::TunerUserInfo;
 use crate::models::TunerUserPriority;
 use crate::onair::OnairProgram;
 use crate::recording;
+use crate::recording::RecordingConflict;
 use crate::recording::RecordingFailedReason;
+use crate::recording::RecordingHistoryEvent;
+use crate::recording::RecordingJobState;
 use crate::recording::RecordingOptions;
+use crate::recording::RecordingRuleCondition;
 use crate::recording::RecordingScheduleState;
 use crate::timeshift::TimeshiftRecordModel;
 use crate::timeshift::TimeshiftRecorderModel;
+use crate::ts_stats::TsStats;
+use crate::tuner_reservation::TunerReservation;
+use crate::tuner_reservation::TunerReservationTarget;
+use crate::tuner_reservation::TunerReservationWindow;
 
 /// Version information of mirakc currently running.
 #[derive(Serialize, ToSchema)]
@@ -72,6 +87,31 @@ pub(in crate::web) struct WebRecordingSchedule {
     /// This property exists only when the recording failed.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub failed_reason: Option<RecordingFailedReason>,
+    /// A conflict predicted for the recording schedule.
+    ///
+    /// This property exists only when the recording will fail due to lack of
+    /// tuners.  See [/recording/conflicts](#/recording/getRecordingConflicts).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflict: Option<RecordingConflict>,
+    /// The ID of the TV program relayed to the target TV program.
+    ///
+    /// This property exists only when the schedule was added automatically
+    /// for the continuation of a relay broadcast.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<u64>)]
+    pub relayed_from: Option<ProgramId>,
+    /// The ID of the TV program to which the target TV program relays.
+    ///
+    /// This property exists only when the relay has been followed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<u64>)]
+    pub relayed_to: Option<ProgramId>,
+    /// A path of the content file rendered from `options.contentPath`.
+    ///
+    /// This property exists only after the recording started.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub content_path: Option<PathBuf>,
 }
 
 impl From<recording::RecordingSchedule> for WebRecordingSchedule {
@@ -82,11 +122,35 @@ impl From<recording::RecordingSchedule> for WebRecordingSchedule {
             options: value.options,
             tags: value.tags,
             failed_reason: value.failed_reason,
+            conflict: None,
+            relayed_from: value.relayed_from,
+            relayed_to: value.relayed_to,
+            content_path: value.content_path,
         }
     }
 }
 
 /// Input data used when creating a recording schedule.
+///
+/// `options.contentPath` is a mustache template and the following variables
+/// are available:
+///
+///   * program_id
+///   * service_id
+///   * sid
+///   * eid
+///   * start_time (formatted in `YYYYMMDDhhmm`)
+///   * start_date (formatted in `YYYYMMDD`)
+///   * title
+///   * episode (empty if unknown)
+///   * service_name
+///   * channel_name
+///   * channel_type
+///
+/// The template is rendered when the recording starts.  Characters which
+/// cannot be used in filenames are replaced with `_` in string values.  A
+/// suffix like `-1` is appended to the filename if the rendered path is
+/// already used.
 #[derive(Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 #[schema(title = "RecordingScheduleInput")]
@@ -104,30 +168,695 @@ pub(in crate::web) struct WebRecordingScheduleInput {
 
 impl WebRecordingScheduleInput {
     pub fn validate(&self, config: &Config) -> Result<(), Error> {
-        if self.options.content_path.is_absolute() {
-            let err = Error::InvalidPath;
-            tracing::error!(
-                %err,
-                input.options.content_path = ?self.options.content_path
-            );
-            return Err(err);
+        validate_options(config, &self.options)
+    }
+}
+
+/// Input data used when creating a manual recording schedule.
+///
+/// `options.contentPath` is a mustache template like `RecordingScheduleInput`.
+/// Variables for a TV program such as `eid` are meaningless.
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(title = "ManualRecordingScheduleInput")]
+pub(in crate::web) struct WebManualRecordingScheduleInput {
+    /// A Mirakurun service ID of the service to record.
+    #[schema(value_type = u64)]
+    pub service_id: ServiceId,
+    /// A time to start recording in UNIX time (milliseconds).
+    #[serde(with = "ts_milliseconds")]
+    #[schema(value_type = i64)]
+    pub start_time: DateTime<Jst>,
+    /// A time to stop recording in UNIX time (milliseconds).
+    #[serde(with = "ts_milliseconds")]
+    #[schema(value_type = i64)]
+    pub end_time: DateTime<Jst>,
+    /// A name of the recording used as the name of the pseudo TV program.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Recording options.
+    pub options: RecordingOptions,
+    /// A list of tags.
+    #[serde(default)]
+    #[schema(value_type = Vec<String>)]
+    pub tags: HashSet<String>,
+}
+
+impl WebManualRecordingScheduleInput {
+    pub fn validate(&self, config: &Config) -> Result<(), Error> {
+        validate_options(config, &self.options)
+    }
+}
+
+/// Query parameters used for searching TV programs.
+///
+/// Every condition specified must be satisfied.  Conditions omitted are
+/// ignored.  Lists are specified in the form of `services[]=1&services[]=2`.
+#[derive(Debug, Default, Deserialize, Serialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub(in crate::web) struct WebProgramSearchQuery {
+    /// Keywords separated by whitespaces.
+    ///
+    /// Every keyword must be contained in the name, the description or the
+    /// extended information of a TV program.  Full-width and half-width
+    /// characters are not distinguished.  Uppercase and lowercase ASCII
+    /// characters are not distinguished.
+    #[serde(default)]
+    pub keyword: Option<String>,
+    /// A list of genres such as `genres[0][lv1]=7&genres[0][lv2]=0`.
+    ///
+    /// Any of the genres must match one of the genres of a TV program.
+    #[serde(default)]
+    pub genres: Vec<ProgramSearchGenre>,
+    /// A list of Mirakurun service IDs.
+    #[serde(default)]
+    #[param(value_type = Vec<u64>)]
+    pub services: Vec<ServiceId>,
+    /// A list of channel types.
+    #[serde(default)]
+    pub channel_types: Vec<ChannelType>,
+    /// TV programs ending after this time in UNIX time (milliseconds).
+    #[serde(default)]
+    #[serde(with = "ts_milliseconds_option")]
+    #[param(value_type = Option<i64>)]
+    pub since: Option<DateTime<Jst>>,
+    /// TV programs starting before this time in UNIX time (milliseconds).
+    #[serde(default)]
+    #[serde(with = "ts_milliseconds_option")]
+    #[param(value_type = Option<i64>)]
+    pub until: Option<DateTime<Jst>>,
+    /// The minimum duration of TV programs in milliseconds (inclusive).
+    #[serde(default)]
+    #[serde(with = "duration_milliseconds_option")]
+    #[param(value_type = Option<i64>)]
+    pub min_duration: Option<Duration>,
+    /// The maximum duration of TV programs in milliseconds (inclusive).
+    #[serde(default)]
+    #[serde(with = "duration_milliseconds_option")]
+    #[param(value_type = Option<i64>)]
+    pub max_duration: Option<Duration>,
+    /// `true` for free TV programs, `false` for scrambled TV programs.
+    #[serde(default)]
+    pub free: Option<bool>,
+    /// A key to sort TV programs found.
+    #[serde(default)]
+    pub sort: ProgramSearchSort,
+    /// Sort in descending order.
+    #[serde(default)]
+    pub desc: bool,
+    /// The number of TV programs to skip.
+    #[serde(default)]
+    pub offset: usize,
+    /// The maximum number of TV programs to return.
+    #[serde(default = "WebProgramSearchQuery::default_limit")]
+    pub limit: usize,
+}
+
+impl WebProgramSearchQuery {
+    fn default_limit() -> usize {
+        100
+    }
+}
+
+impl From<WebProgramSearchQuery> for epg::ProgramSearchQuery {
+    fn from(value: WebProgramSearchQuery) -> Self {
+        epg::ProgramSearchQuery {
+            keyword: value.keyword,
+            genres: value.genres,
+            services: value.services,
+            channel_types: value.channel_types,
+            since: value.since,
+            until: value.until,
+            min_duration: value.min_duration,
+            max_duration: value.max_duration,
+            free: value.free,
+            sort: value.sort,
+            desc: value.desc,
+            offset: value.offset,
+            limit: Some(value.limit),
+        }
+    }
+}
+
+/// TV programs found.
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(title = "ProgramSearchResult")]
+pub(in crate::web) struct WebProgramSearchResult {
+    /// The number of TV programs matched before the pagination.
+    pub total: usize,
+    /// TV programs in the requested page.
+    pub programs: Vec<MirakurunProgram>,
+}
+
+impl From<epg::ProgramSearchResult> for WebProgramSearchResult {
+    fn from(value: epg::ProgramSearchResult) -> Self {
+        WebProgramSearchResult {
+            total: value.total,
+            programs: value
+                .programs
+                .into_iter()
+                .map(MirakurunProgram::from)
+                .collect(),
+        }
+    }
+}
+
+/// Input data used when recording a TV program currently on air.
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(title = "RecordNowInput")]
+pub(in crate::web) struct WebRecordNowInput {
+    /// Recording options.
+    ///
+    /// The same options are applied to the next TV program if `untilNext` is
+    /// `true`.
+    pub options: RecordingOptions,
+    /// A list of tags.
+    #[serde(default)]
+    #[schema(value_type = Vec<String>)]
+    pub tags: HashSet<String>,
+    /// Also records the next TV program if `true`.
+    #[serde(default)]
+    pub until_next: bool,
+}
+
+impl WebRecordNowInput {
+    pub fn validate(&self, config: &Config) -> Result<(), Error> {
+        validate_options(config, &self.options)
+    }
+}
+
+/// Input data used when updating a recording schedule.
+///
+/// Properties not specified are not changed.
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(title = "RecordingSchedulePatch")]
+pub(in crate::web) struct WebRecordingSchedulePatch {
+    /// Recording options.
+    ///
+    /// Options cannot be changed once the recording starts.
+    #[serde(default)]
+    pub options: Option<RecordingOptions>,
+    /// A list of tags.
+    #[serde(default)]
+    #[schema(value_type = Option<Vec<String>>)]
+    pub tags: Option<HashSet<String>>,
+}
+
+impl WebRecordingSchedulePatch {
+    pub fn validate(&self, config: &Config) -> Result<(), Error> {
+        match self.options {
+            Some(ref options) => validate_options(config, options),
+            None => Ok(()),
+        }
+    }
+}
+
+fn validate_options(config: &Config, options: &RecordingOptions) -> Result<(), Error> {
+    validate_content_path(config, &options.content_path)?;
+    validate_margin(options.start_margin)?;
+    validate_margin(options.end_margin)?;
+    Ok(())
+}
+
+fn validate_margin(margin: Option<u64>) -> Result<(), Error> {
+    match margin {
+        Some(margin) if margin > RecordingConfig::MAX_MARGIN.as_millis() as u64 => {
+            let err = Error::InvalidMargin;
+            tracing::error!(%err, margin);
+            Err(err)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_content_path(config: &Config, content_path: &Path) -> Result<(), Error> {
+    if content_path.is_absolute() {
+        let err = Error::InvalidPath;
+        tracing::error!(%err, input.options.content_path = ?content_path);
+        return Err(err);
+    }
+
+    let basedir = config.recording.basedir.as_ref().unwrap();
+    if !basedir.join(content_path).parse_dot()?.starts_with(basedir) {
+        let err = Error::InvalidPath;
+        tracing::error!(%err, input.options.content_path = ?content_path);
+        return Err(err);
+    }
+
+    // The content path is a mustache template.
+    if let Err(err) = mustache::compile_str(&content_path.to_string_lossy()) {
+        tracing::error!(%err, input.options.content_path = ?content_path);
+        return Err(Error::InvalidPath);
+    }
+
+    Ok(())
+}
+
+/// A recording rule model.
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(title = "RecordingRule")]
+pub(in crate::web) struct WebRecordingRule {
+    /// The ID of the recording rule.
+    pub id: u32,
+    /// `true` if the recording rule is disabled.
+    pub disabled: bool,
+    /// Conditions to be satisfied by TV programs to be recorded.
+    pub condition: RecordingRuleCondition,
+    /// Recording options used for recording schedules added by the rule.
+    ///
+    /// `contentPath` is a mustache template.  See the description of
+    /// `RecordingRuleInput` for details.
+    pub options: RecordingOptions,
+    /// A list of tags added to recording schedules.
+    #[schema(value_type = Vec<String>)]
+    pub tags: HashSet<String>,
+}
+
+impl From<recording::RecordingRule> for WebRecordingRule {
+    fn from(value: recording::RecordingRule) -> Self {
+        WebRecordingRule {
+            id: value.id,
+            disabled: value.disabled,
+            condition: value.condition,
+            options: value.options,
+            tags: value.tags,
+        }
+    }
+}
+
+/// Input data used when creating or updating a recording rule.
+///
+/// `options.contentPath` is a mustache template like `RecordingScheduleInput`.
+/// The template is copied to each recording schedule added by the rule.
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(title = "RecordingRuleInput")]
+pub(in crate::web) struct WebRecordingRuleInput {
+    /// `true` if the recording rule is disabled.
+    #[serde(default)]
+    pub disabled: bool,
+    /// Conditions to be satisfied by TV programs to be recorded.
+    pub condition: RecordingRuleCondition,
+    /// Recording options used for recording schedules added by the rule.
+    pub options: RecordingOptions,
+    /// A list of tags added to recording schedules.
+    #[serde(default)]
+    #[schema(value_type = Vec<String>)]
+    pub tags: HashSet<String>,
+}
+
+impl WebRecordingRuleInput {
+    pub fn validate(&self, config: &Config) -> Result<(), Error> {
+        validate_options(config, &self.options)
+    }
+
+    pub fn into_rule(self, id: u32) -> recording::RecordingRule {
+        recording::RecordingRule {
+            id,
+            disabled: self.disabled,
+            condition: self.condition,
+            options: self.options,
+            tags: self.tags,
+            excluded_programs: vec![],
+        }
+    }
+}
+
+/// Input data used when importing rules and reserves from EPGStation.
+///
+/// Recording rules and recording schedules are created with `options` and
+/// `tags`.  Recording options of EPGStation are not imported.
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(title = "EpgstationImportInput")]
+pub(in crate::web) struct WebEpgstationImportInput {
+    /// A list of rules contained in the response of `GET /api/rules` of
+    /// EPGStation.
+    #[serde(default)]
+    #[schema(value_type = Vec<Object>)]
+    pub rules: Vec<serde_json::Value>,
+    /// A list of reserves contained in the response of `GET /api/reserves` of
+    /// EPGStation.
+    #[serde(default)]
+    #[schema(value_type = Vec<Object>)]
+    pub reserves: Vec<serde_json::Value>,
+    /// Recording options.
+    #[serde(default)]
+    pub options: RecordingOptions,
+    /// A list of tags.
+    #[serde(default)]
+    #[schema(value_type = Vec<String>)]
+    pub tags: HashSet<String>,
+}
+
+impl WebEpgstationImportInput {
+    pub fn validate(&self, config: &Config) -> Result<(), Error> {
+        validate_options(config, &self.options)
+    }
+}
+
+/// A result of an import.
+#[derive(Debug, Default, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(title = "ImportResult")]
+pub(in crate::web) struct WebImportResult {
+    /// Results of rules.
+    pub rules: Vec<WebImportItem>,
+    /// Results of reserves.
+    pub reserves: Vec<WebImportItem>,
+}
+
+/// A result of an item to be imported.
+#[derive(Debug, Default, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(title = "ImportItem")]
+pub(in crate::web) struct WebImportItem {
+    /// The ID of the item in the source.
+    ///
+    /// `null` if the item cannot be parsed.
+    pub source_id: Option<u32>,
+    /// The ID of the recording rule created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule_id: Option<u32>,
+    /// The Mirakurun program ID of the recording schedule created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<u64>)]
+    pub program_id: Option<ProgramId>,
+    /// The reason why the item was not imported.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl WebImportItem {
+    pub fn failed(source_id: Option<u32>, error: String) -> Self {
+        WebImportItem {
+            source_id,
+            error: Some(error),
+            ..Default::default()
+        }
+    }
+}
+
+/// A recording series model.
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(title = "RecordingSeries")]
+pub(in crate::web) struct WebRecordingSeries {
+    /// The ID of the recording series.
+    pub id: u32,
+    /// The Mirakurun service ID of the service where the series is broadcasted.
+    #[schema(value_type = u64)]
+    pub service_id: ServiceId,
+    /// The series ID in the SeriesDescriptor of each episode.
+    pub series_id: u16,
+    /// The name of the series.
+    pub name: Option<String>,
+    /// The expiration date of the series in UNIX time (milliseconds).
+    ///
+    /// The series will be removed automatically after this date.
+    pub expire_at: Option<i64>,
+    /// The episode number of the last episode.
+    ///
+    /// `0` means that the number of episodes is unknown.  The series will be
+    /// removed automatically after the last episode is scheduled.
+    pub last_episode: u16,
+    /// A list of episode numbers already scheduled.
+    pub episodes: Vec<u16>,
+    /// Recording options used for recording schedules added for episodes.
+    ///
+    /// `contentPath` is a mustache template.  See the description of
+    /// `RecordingRuleInput` for details.
+    pub options: RecordingOptions,
+    /// A list of tags added to recording schedules.
+    #[schema(value_type = Vec<String>)]
+    pub tags: HashSet<String>,
+}
+
+impl From<recording::RecordingSeries> for WebRecordingSeries {
+    fn from(value: recording::RecordingSeries) -> Self {
+        WebRecordingSeries {
+            id: value.id,
+            service_id: value.service_id,
+            series_id: value.series_id,
+            name: value.name,
+            expire_at: value.expire_date,
+            last_episode: value.last_episode_number,
+            episodes: value.episodes.into_iter().collect(),
+            options: value.options,
+            tags: value.tags,
+        }
+    }
+}
+
+/// Input data used when creating a recording series.
+///
+/// `options.contentPath` is a mustache template like `RecordingRuleInput`.
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(title = "RecordingSeriesInput")]
+pub(in crate::web) struct WebRecordingSeriesInput {
+    /// A Mirakurun program ID of a TV program in the series.
+    #[schema(value_type = u64)]
+    pub program_id: ProgramId,
+    /// Recording options used for recording schedules added for episodes.
+    pub options: RecordingOptions,
+    /// A list of tags added to recording schedules.
+    #[serde(default)]
+    #[schema(value_type = Vec<String>)]
+    pub tags: HashSet<String>,
+}
+
+impl WebRecordingSeriesInput {
+    pub fn validate(&self, config: &Config) -> Result<(), Error> {
+        validate_options(config, &self.options)
+    }
+}
+
+/// A record of a TV program recorded.
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(title = "RecordingRecord")]
+pub(in crate::web) struct WebRecordingRecord {
+    /// The ID of the record.
+    pub id: u32,
+    /// Metadata of the TV program when the recording stopped.
+    pub program: MirakurunProgram,
+    /// Metadata of the service where the TV program was broadcasted.
+    pub service: MirakurunService,
+    /// A path of the content file.
+    ///
+    /// A relative path is relative to `config.recording.basedir`.
+    #[schema(value_type = String)]
+    pub content_path: PathBuf,
+    /// A time when the recording started in UNIX time (milliseconds).
+    #[serde(with = "ts_milliseconds")]
+    #[schema(value_type = i64)]
+    pub start_time: DateTime<Jst>,
+    /// A time when the recording stopped in UNIX time (milliseconds).
+    #[serde(with = "ts_milliseconds")]
+    #[schema(value_type = i64)]
+    pub end_time: DateTime<Jst>,
+    /// The size of the content file in bytes when the recording stopped.
+    pub size: u64,
+    /// Reason of the recording failure.
+    ///
+    /// This property exists only when the recording failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failed_reason: Option<RecordingFailedReason>,
+    /// A list of tags copied from the recording schedule.
+    #[schema(value_type = Vec<String>)]
+    pub tags: HashSet<String>,
+    /// `true` if the record is protected from the automatic removal.
+    pub protected: bool,
+    /// The ID of the TV program relayed to the recorded TV program.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<u64>)]
+    pub relayed_from: Option<ProgramId>,
+    /// The ID of the TV program to which the recorded TV program relays.
+    ///
+    /// The continuation is recorded in another record.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<u64>)]
+    pub relayed_to: Option<ProgramId>,
+    /// Statistics of TS packets during the recording.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ts_stats: Option<TsStats>,
+}
+
+impl From<recording::RecordingRecord> for WebRecordingRecord {
+    fn from(value: recording::RecordingRecord) -> Self {
+        WebRecordingRecord {
+            id: value.id,
+            program: value.program.as_ref().clone().into(),
+            service: value.service.into(),
+            content_path: value.content_path,
+            start_time: value.start_time,
+            end_time: value.end_time,
+            size: value.size,
+            failed_reason: value.failed_reason,
+            tags: value.tags,
+            protected: value.protected,
+            relayed_from: value.relayed_from,
+            relayed_to: value.relayed_to,
+            ts_stats: value.ts_stats,
         }
+    }
+}
+
+/// Input data used when updating a record.
+///
+/// Properties not specified won't be changed.
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(title = "RecordingRecordPatch")]
+pub(in crate::web) struct WebRecordingRecordPatch {
+    /// `true` if the record should be protected from the automatic removal.
+    #[serde(default)]
+    pub protected: Option<bool>,
+}
+
+/// An entry of the recording history.
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(title = "RecordingHistoryEntry")]
+pub(in crate::web) struct WebRecordingHistoryEntry {
+    /// A time when the event occurred in UNIX time (milliseconds).
+    #[serde(with = "ts_milliseconds")]
+    #[schema(value_type = i64)]
+    pub time: DateTime<Jst>,
+    /// The type of the event.
+    pub event: RecordingHistoryEvent,
+    /// A Mirakurun program ID of the TV program.
+    #[schema(value_type = u64)]
+    pub program_id: ProgramId,
+    /// The name of the TV program at the time.
+    pub program_name: Option<String>,
+    /// The start time of the TV program at the time in UNIX time
+    /// (milliseconds).
+    #[serde(with = "ts_milliseconds_option")]
+    #[schema(value_type = Option<i64>)]
+    pub program_start_at: Option<DateTime<Jst>>,
+    /// The state of the recording schedule after the event.
+    pub state: RecordingScheduleState,
+    /// Reason of the recording failure.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failed_reason: Option<RecordingFailedReason>,
+    /// The first non-zero exit code of processes in the recording pipeline.
+    ///
+    /// This property exists only when the recording pipeline terminated
+    /// abnormally.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// The index of the tuner used for the recording.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tuner_index: Option<usize>,
+    /// Statistics of TS packets during the recording.
+    ///
+    /// This property exists only when the recording stopped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ts_stats: Option<TsStats>,
+    /// A list of tags of the recording schedule.
+    #[schema(value_type = Vec<String>)]
+    pub tags: HashSet<String>,
+}
 
-        let basedir = config.recording.basedir.as_ref().unwrap();
-        if !basedir
-            .join(&self.options.content_path)
-            .parse_dot()?
-            .starts_with(basedir)
-        {
-            let err = Error::InvalidPath;
-            tracing::error!(
-                %err,
-                input.options.content_path = ?self.options.content_path
-            );
-            return Err(err);
+impl From<recording::RecordingHistoryEntry> for WebRecordingHistoryEntry {
+    fn from(value: recording::RecordingHistoryEntry) -> Self {
+        WebRecordingHistoryEntry {
+            time: value.time,
+            event: value.event,
+            program_id: value.program_id,
+            program_name: value.program_name,
+            program_start_at: value.program_start_at,
+            state: value.state,
+            failed_reason: value.failed_reason,
+            exit_code: value.exit_code,
+            tuner_index: value.tuner_index,
+            ts_stats: value.ts_stats,
+            tags: value.tags,
         }
+    }
+}
 
-        Ok(())
+/// Query parameters used for filtering entries of the recording history.
+#[derive(Debug, Default, Deserialize, Serialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub(in crate::web) struct WebRecordingHistoryQuery {
+    /// Entries added at or after this time in UNIX time (milliseconds).
+    #[serde(default)]
+    #[serde(with = "ts_milliseconds_option")]
+    #[param(value_type = Option<i64>)]
+    pub since: Option<DateTime<Jst>>,
+    /// Entries added before this time in UNIX time (milliseconds).
+    #[serde(default)]
+    #[serde(with = "ts_milliseconds_option")]
+    #[param(value_type = Option<i64>)]
+    pub until: Option<DateTime<Jst>>,
+    /// Entries having this tag.
+    #[serde(default)]
+    pub tag: Option<String>,
+}
+
+impl From<WebRecordingHistoryQuery> for recording::RecordingHistoryFilter {
+    fn from(value: WebRecordingHistoryQuery) -> Self {
+        recording::RecordingHistoryFilter {
+            since: value.since,
+            until: value.until,
+            tag: value.tag,
+        }
+    }
+}
+
+/// A post-processing job for a record.
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(title = "RecordingJob")]
+pub(in crate::web) struct WebRecordingJob {
+    /// The ID of the job.
+    pub id: u32,
+    /// The ID of the record to be processed.
+    pub record_id: u32,
+    /// The command rendered with the record.
+    pub command: String,
+    /// The current state of the job.
+    pub state: RecordingJobState,
+    /// The number of attempts finished.
+    pub attempts: u32,
+    /// A message describing the last failure.
+    ///
+    /// This property exists only when the last attempt failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// A time when the job was created in UNIX time (milliseconds).
+    #[serde(with = "ts_milliseconds")]
+    #[schema(value_type = i64)]
+    pub created_at: DateTime<Jst>,
+    /// A time when the job will be retried in UNIX time (milliseconds).
+    ///
+    /// `null` when the job is not waiting for retry.
+    #[serde(with = "ts_milliseconds_option")]
+    #[schema(value_type = Option<i64>)]
+    pub retry_at: Option<DateTime<Jst>>,
+}
+
+impl From<recording::RecordingJob> for WebRecordingJob {
+    fn from(value: recording::RecordingJob) -> Self {
+        WebRecordingJob {
+            id: value.id,
+            record_id: value.record_id,
+            command: value.command,
+            state: value.state,
+            attempts: value.attempts,
+            error: value.error,
+            created_at: value.created_at,
+            retry_at: value.retry_at,
+        }
     }
 }
 
@@ -163,6 +892,45 @@ impl From<recording::RecorderModel> for WebRecordingRecorder {
     }
 }
 
+/// Input data used when creating a tuner reservation.
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(title = "TunerReservationInput")]
+pub(in crate::web) struct WebTunerReservationInput {
+    /// The target of the reservation.
+    pub target: TunerReservationTarget,
+    /// The time window of the reservation.
+    pub window: TunerReservationWindow,
+    /// The owner of the reservation.
+    ///
+    /// A tuner user having the same ID as the owner can use the reserved tuner
+    /// regardless of its priority.
+    pub owner: String,
+    /// The priority of the reservation.
+    ///
+    /// Only users having a higher priority can use the reserved tuner.  The
+    /// default value blocks all users other than the owner.
+    #[serde(default = "WebTunerReservationInput::default_priority")]
+    pub priority: i32,
+}
+
+impl WebTunerReservationInput {
+    fn default_priority() -> i32 {
+        TunerUserPriority::MAX
+    }
+
+    pub fn into_reservation(self) -> TunerReservation {
+        TunerReservation {
+            id: 0,
+            target: self.target,
+            window: self.window,
+            owner: self.owner,
+            priority: self.priority,
+            from_config: false,
+        }
+    }
+}
+
 /// A timeshift recorder model.
 #[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
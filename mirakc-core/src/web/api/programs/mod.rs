@@ -39,6 +39,36 @@ where
     Ok(result.into())
 }
 
+/// Searches TV programs.
+///
+/// Unlike [/programs](#/programs/getPrograms), TV programs are filtered,
+/// sorted and paginated in mirakc.  TV programs of services disabled at
+/// runtime are not included.
+#[utoipa::path(
+    get,
+    path = "/programs/search",
+    params(WebProgramSearchQuery),
+    responses(
+        (status = 200, description = "OK", body = WebProgramSearchResult),
+        (status = 400, description = "Bad Request"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "searchPrograms",
+)]
+pub(super) async fn search<E>(
+    State(EpgExtractor(epg)): State<EpgExtractor<E>>,
+    Qs(query): Qs<WebProgramSearchQuery>,
+) -> Result<Json<WebProgramSearchResult>, Error>
+where
+    E: Call<epg::SearchPrograms>,
+{
+    let msg = epg::SearchPrograms {
+        query: query.into(),
+    };
+    let result = epg.call(msg).await?;
+    Ok(Json(result.into()))
+}
+
 /// Gets a TV program.
 ///
 /// ### A special hack for EPGStation
@@ -3,6 +3,10 @@ use super::*;
 use crate::web::api::stream::do_head_stream;
 use crate::web::api::stream::streaming;
 
+// A margin in milliseconds passed to the program-filter so that the end of the
+// TV program is streamed reliably.
+const END_MARGIN: u64 = 2000;
+
 /// Gets a media stream of a program.
 ///
 /// ### A special hack for EPGStation
@@ -101,7 +105,8 @@ where
         .insert("clock_pcr", &clock.pcr)?
         .insert("clock_time", &clock.time)?
         .insert("video_tags", &video_tags)?
-        .insert("audio_tags", &audio_tags)?;
+        .insert("audio_tags", &audio_tags)?
+        .insert("end_margin", &END_MARGIN)?;
     if let Some(max_start_delay) = config.server.program_stream_max_start_delay {
         // Round off the fractional (nanosecond) part of the duration.
         //
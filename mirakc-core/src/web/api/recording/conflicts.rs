@@ -0,0 +1,33 @@
+use super::*;
+
+use crate::recording::RecordingConflict;
+
+/// Lists conflicts of recording schedules.
+///
+/// Conflicts are predicted by simulating the tuner allocation over recording
+/// schedules, taking into account channel types supported by each tuner,
+/// tuners dedicated for on-air program trackers, priorities of recording
+/// schedules and tuners held by timeshift recorders.
+///
+/// The prediction doesn't take into account tuners used by other users such as
+/// streaming and background jobs.
+#[utoipa::path(
+    get,
+    path = "/recording/conflicts",
+    responses(
+        (status = 200, description = "OK", body = [RecordingConflict]),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "getRecordingConflicts",
+)]
+pub(in crate::web::api) async fn list<R>(
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+) -> Result<Json<Vec<RecordingConflict>>, Error>
+where
+    R: Call<recording::QueryRecordingConflicts>,
+{
+    let conflicts = recording_manager
+        .call(recording::QueryRecordingConflicts)
+        .await??;
+    Ok(Json(conflicts))
+}
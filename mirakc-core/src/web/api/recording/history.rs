@@ -0,0 +1,35 @@
+use super::*;
+
+/// Lists entries of the recording history.
+///
+/// An entry is appended to the history when a recording schedule is added,
+/// its state changes, or it's removed before the recording finishes.  Entries
+/// are listed in chronological order.
+#[utoipa::path(
+    get,
+    path = "/recording/history",
+    params(WebRecordingHistoryQuery),
+    responses(
+        (status = 200, description = "OK", body = [WebRecordingHistoryEntry]),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "getRecordingHistory",
+)]
+pub(in crate::web::api) async fn list<R>(
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+    Query(query): Query<WebRecordingHistoryQuery>,
+) -> Result<Json<Vec<WebRecordingHistoryEntry>>, Error>
+where
+    R: Call<recording::QueryRecordingHistory>,
+{
+    let msg = recording::QueryRecordingHistory {
+        filter: query.into(),
+    };
+    let entries = recording_manager.call(msg).await??;
+    Ok(Json(
+        entries
+            .into_iter()
+            .map(WebRecordingHistoryEntry::from)
+            .collect(),
+    ))
+}
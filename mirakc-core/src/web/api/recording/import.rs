@@ -0,0 +1,140 @@
+use super::*;
+
+use crate::recording::EpgstationReserve;
+use crate::recording::EpgstationRule;
+use crate::recording::RecordingSchedule;
+
+/// Imports rules and reserves from EPGStation.
+///
+/// Rules are imported as recording rules.  Reserves are imported as recording
+/// schedules.  Program IDs of reserves are resolved against the current EPG
+/// data.  Time-specified reserves are imported as manual recording schedules.
+///
+/// Each item is imported independently.  Items which cannot be imported are
+/// reported with the reason in the response.
+#[utoipa::path(
+    post,
+    path = "/recording/import/epgstation",
+    request_body = WebEpgstationImportInput,
+    responses(
+        (status = 200, description = "OK", body = WebImportResult),
+        (status = 400, description = "Bad Request"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "importFromEpgstation",
+)]
+pub(in crate::web::api) async fn epgstation<E, R>(
+    State(ConfigExtractor(config)): State<ConfigExtractor>,
+    State(EpgExtractor(epg)): State<EpgExtractor<E>>,
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+    Json(input): Json<WebEpgstationImportInput>,
+) -> Result<Json<WebImportResult>, Error>
+where
+    E: Call<epg::QueryProgram>,
+    E: Call<epg::QueryServices>,
+    R: Call<recording::AddManualRecordingSchedule>,
+    R: Call<recording::AddRecordingRule>,
+    R: Call<recording::AddRecordingSchedule>,
+{
+    input.validate(&config)?;
+
+    let services = epg.call(epg::QueryServices).await?;
+    let mut result = WebImportResult::default();
+
+    for value in input.rules.into_iter() {
+        let source_id = source_id(&value);
+        let rule: EpgstationRule = match serde_json::from_value(value) {
+            Ok(rule) => rule,
+            Err(err) => {
+                result
+                    .rules
+                    .push(WebImportItem::failed(source_id, err.to_string()));
+                continue;
+            }
+        };
+        let rule = match rule.to_rule(&input.options, &input.tags, &services) {
+            Ok(rule) => rule,
+            Err(reason) => {
+                result.rules.push(WebImportItem::failed(source_id, reason));
+                continue;
+            }
+        };
+        let item = match recording_manager
+            .call(recording::AddRecordingRule { rule })
+            .await?
+        {
+            Ok(rule) => WebImportItem {
+                source_id,
+                rule_id: Some(rule.id),
+                ..Default::default()
+            },
+            Err(err) => WebImportItem::failed(source_id, err.to_string()),
+        };
+        result.rules.push(item);
+    }
+
+    for value in input.reserves.into_iter() {
+        let source_id = source_id(&value);
+        let reserve: EpgstationReserve = match serde_json::from_value(value) {
+            Ok(reserve) => reserve,
+            Err(err) => {
+                result
+                    .reserves
+                    .push(WebImportItem::failed(source_id, err.to_string()));
+                continue;
+            }
+        };
+        if reserve.is_skip {
+            result.reserves.push(WebImportItem::failed(
+                source_id,
+                "skipped in EPGStation".to_string(),
+            ));
+            continue;
+        }
+        let schedule = match reserve.program_id() {
+            Some(program_id) => match epg.call(epg::QueryProgram { program_id }).await? {
+                Ok(program) => {
+                    let schedule = RecordingSchedule::new(
+                        Arc::new(program),
+                        input.options.clone(),
+                        input.tags.clone(),
+                    );
+                    let msg = recording::AddRecordingSchedule { schedule };
+                    recording_manager.call(msg).await?
+                }
+                Err(err) => Err(err),
+            },
+            None => {
+                let msg = recording::AddManualRecordingSchedule {
+                    service_id: reserve.service_id(),
+                    start_time: reserve.start_at,
+                    end_time: reserve.end_at,
+                    name: reserve.name,
+                    options: input.options.clone(),
+                    tags: input.tags.clone(),
+                };
+                recording_manager.call(msg).await?
+            }
+        };
+        let item = match schedule {
+            Ok(schedule) => WebImportItem {
+                source_id,
+                program_id: Some(schedule.program.id),
+                ..Default::default()
+            },
+            Err(err) => WebImportItem::failed(source_id, err.to_string()),
+        };
+        result.reserves.push(item);
+    }
+
+    Ok(Json(result))
+}
+
+// The ID is extracted before parsing so that it can be reported even if the
+// item cannot be parsed.
+fn source_id(value: &serde_json::Value) -> Option<u32> {
+    value
+        .get("id")
+        .and_then(serde_json::Value::as_u64)
+        .and_then(|id| u32::try_from(id).ok())
+}
@@ -0,0 +1,50 @@
+use super::*;
+
+/// Lists post-processing jobs.
+#[utoipa::path(
+    get,
+    path = "/recording/jobs",
+    responses(
+        (status = 200, description = "OK", body = [WebRecordingJob]),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "getRecordingJobs",
+)]
+pub(in crate::web::api) async fn list<R>(
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+) -> Result<Json<Vec<WebRecordingJob>>, Error>
+where
+    R: Call<recording::QueryRecordingJobs>,
+{
+    let jobs = recording_manager
+        .call(recording::QueryRecordingJobs)
+        .await?;
+    Ok(Json(jobs.into_iter().map(WebRecordingJob::from).collect()))
+}
+
+/// Gets a post-processing job.
+#[utoipa::path(
+    get,
+    path = "/recording/jobs/{id}",
+    params(
+        ("id" = u32, Path, description = "Job ID"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = WebRecordingJob),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "getRecordingJob",
+)]
+pub(in crate::web::api) async fn get<R>(
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+    Path(id): Path<u32>,
+) -> Result<Json<WebRecordingJob>, Error>
+where
+    R: Call<recording::QueryRecordingJob>,
+{
+    let job = recording_manager
+        .call(recording::QueryRecordingJob { id })
+        .await??;
+    Ok(Json(job.into()))
+}
@@ -2,5 +2,12 @@ use super::*;
 
 use crate::recording;
 
+pub(super) mod conflicts;
+pub(super) mod history;
+pub(super) mod import;
+pub(super) mod jobs;
 pub(super) mod recorders;
+pub(super) mod records;
+pub(super) mod rules;
 pub(super) mod schedules;
+pub(super) mod series;
@@ -0,0 +1,209 @@
+use super::*;
+
+use std::ops::Bound;
+
+use crate::models::TunerUser;
+use crate::web::api::stream::streaming;
+
+/// Lists records of TV programs recorded.
+#[utoipa::path(
+    get,
+    path = "/recording/records",
+    responses(
+        (status = 200, description = "OK", body = [WebRecordingRecord]),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "getRecordingRecords",
+)]
+pub(in crate::web::api) async fn list<R>(
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+) -> Result<Json<Vec<WebRecordingRecord>>, Error>
+where
+    R: Call<recording::QueryRecordingRecords>,
+{
+    let records = recording_manager
+        .call(recording::QueryRecordingRecords)
+        .await?;
+    Ok(Json(
+        records.into_iter().map(WebRecordingRecord::from).collect(),
+    ))
+}
+
+/// Gets a record of a TV program recorded.
+#[utoipa::path(
+    get,
+    path = "/recording/records/{id}",
+    params(
+        ("id" = u32, Path, description = "Record ID"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = WebRecordingRecord),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "getRecordingRecord",
+)]
+pub(in crate::web::api) async fn get<R>(
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+    Path(id): Path<u32>,
+) -> Result<Json<WebRecordingRecord>, Error>
+where
+    R: Call<recording::QueryRecordingRecord>,
+{
+    let record = recording_manager
+        .call(recording::QueryRecordingRecord { id })
+        .await??;
+    Ok(Json(record.into()))
+}
+
+/// Updates a record of a TV program recorded.
+///
+/// Protected records are never removed automatically by the retention based on
+/// `config.recording.max-total-size` and `config.recording.min-free-space`.
+#[utoipa::path(
+    patch,
+    path = "/recording/records/{id}",
+    params(
+        ("id" = u32, Path, description = "Record ID"),
+    ),
+    request_body = WebRecordingRecordPatch,
+    responses(
+        (status = 200, description = "OK", body = WebRecordingRecord),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "updateRecordingRecord",
+)]
+pub(in crate::web::api) async fn update<R>(
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+    Path(id): Path<u32>,
+    Json(patch): Json<WebRecordingRecordPatch>,
+) -> Result<Json<WebRecordingRecord>, Error>
+where
+    R: Call<recording::UpdateRecordingRecord>,
+{
+    let msg = recording::UpdateRecordingRecord {
+        id,
+        protected: patch.protected,
+    };
+    let record = recording_manager.call(msg).await??;
+    Ok(Json(record.into()))
+}
+
+/// Deletes a record of a TV program recorded.
+///
+/// The content file of the record will also be deleted.
+#[utoipa::path(
+    delete,
+    path = "/recording/records/{id}",
+    params(
+        ("id" = u32, Path, description = "Record ID"),
+    ),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "deleteRecordingRecord",
+)]
+pub(in crate::web::api) async fn delete<R>(
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+    Path(id): Path<u32>,
+) -> Result<(), Error>
+where
+    R: Call<recording::RemoveRecordingRecord>,
+{
+    recording_manager
+        .call(recording::RemoveRecordingRecord { id })
+        .await??;
+    Ok(())
+}
+
+/// Gets a media stream of the content of a record.
+///
+/// The `Range` header is supported unless post-filters are specified.
+#[utoipa::path(
+    get,
+    path = "/recording/records/{id}/stream",
+    params(
+        ("id" = u32, Path, description = "Record ID"),
+        ("pre-filters" = Option<[String]>, Query, description = "Pre-filters"),
+        ("post-filters" = Option<[String]>, Query, description = "post-filters"),
+    ),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 206, description = "Partial Content"),
+        (status = 404, description = "Not Found"),
+        (status = 416, description = "Range Not Satisfiable"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "getRecordingRecordStream",
+)]
+pub(in crate::web::api) async fn stream<R>(
+    State(ConfigExtractor(config)): State<ConfigExtractor>,
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+    Path(id): Path<u32>,
+    ranges: Option<TypedHeader<axum::headers::Range>>,
+    user: TunerUser,
+    Qs(filter_setting): Qs<FilterSetting>,
+) -> Result<Response, Error>
+where
+    R: Call<recording::QueryRecordingRecord>,
+{
+    let record = recording_manager
+        .call(recording::QueryRecordingRecord { id })
+        .await??;
+
+    let start_pos = if let Some(TypedHeader(ranges)) = ranges {
+        ranges.iter().next().and_then(|(start, _)| match start {
+            Bound::Included(n) => Some(n),
+            Bound::Excluded(n) => Some(n + 1),
+            _ => None,
+        })
+    } else {
+        None
+    };
+
+    // We assume that pre-filters don't change TS packets.
+    let seekable = filter_setting.post_filters.is_empty();
+
+    let basedir = config.recording.basedir.as_ref().unwrap();
+    let stream = record.create_stream(basedir, start_pos, seekable).await?;
+
+    let video_tags: Vec<u8> = record
+        .program
+        .video
+        .iter()
+        .map(|video| video.component_tag)
+        .collect();
+
+    let audio_tags: Vec<u8> = record
+        .program
+        .audios
+        .values()
+        .map(|audio| audio.component_tag)
+        .collect();
+
+    let duration = record.end_time - record.start_time;
+
+    let data = mustache::MapBuilder::new()
+        .insert_str("channel_name", &record.service.channel.name)
+        .insert("channel_type", &record.service.channel.channel_type)?
+        .insert_str("channel", &record.service.channel.channel)
+        .insert("sid", &record.service.sid())?
+        .insert("eid", &record.program.eid())?
+        .insert("video_tags", &video_tags)?
+        .insert("audio_tags", &audio_tags)?
+        .insert("id", &record.id)?
+        .insert("duration", &duration.num_seconds())?
+        .insert("size", &record.size)?
+        .build();
+
+    let mut builder = FilterPipelineBuilder::new(data);
+    builder.add_pre_filters(&config.pre_filters, &filter_setting.pre_filters)?;
+    // The content has already been decoded.
+    builder.add_post_filters(&config.post_filters, &filter_setting.post_filters)?;
+    let (filters, content_type) = builder.build();
+
+    streaming(&config, user, stream, filters, content_type, ()).await
+}
@@ -0,0 +1,150 @@
+use super::*;
+
+/// Lists recording rules.
+#[utoipa::path(
+    get,
+    path = "/recording/rules",
+    responses(
+        (status = 200, description = "OK", body = [WebRecordingRule]),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "getRecordingRules",
+)]
+pub(in crate::web::api) async fn list<R>(
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+) -> Result<Json<Vec<WebRecordingRule>>, Error>
+where
+    R: Call<recording::QueryRecordingRules>,
+{
+    let rules = recording_manager
+        .call(recording::QueryRecordingRules)
+        .await?;
+    Ok(Json(
+        rules.into_iter().map(WebRecordingRule::from).collect(),
+    ))
+}
+
+/// Gets a recording rule.
+#[utoipa::path(
+    get,
+    path = "/recording/rules/{id}",
+    params(
+        ("id" = u32, Path, description = "Recording rule ID"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = WebRecordingRule),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "getRecordingRule",
+)]
+pub(in crate::web::api) async fn get<R>(
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+    Path(id): Path<u32>,
+) -> Result<Json<WebRecordingRule>, Error>
+where
+    R: Call<recording::QueryRecordingRule>,
+{
+    let rule = recording_manager
+        .call(recording::QueryRecordingRule { id })
+        .await??;
+    Ok(Json(rule.into()))
+}
+
+/// Creates a recording rule.
+///
+/// Recording schedules will be added for TV programs matching the rule when
+/// the rule is created and when EPG programs are updated.
+///
+/// Removing a recording schedule added by a rule doesn't prevent the rule from
+/// adding it again.  Update the conditions of the rule in this case.
+#[utoipa::path(
+    post,
+    path = "/recording/rules",
+    request_body = WebRecordingRuleInput,
+    responses(
+        (status = 201, description = "Created", body = WebRecordingRule),
+        (status = 400, description = "Bad Request"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "createRecordingRule",
+)]
+pub(in crate::web::api) async fn create<R>(
+    State(ConfigExtractor(config)): State<ConfigExtractor>,
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+    Json(input): Json<WebRecordingRuleInput>,
+) -> Result<(StatusCode, Json<WebRecordingRule>), Error>
+where
+    R: Call<recording::AddRecordingRule>,
+{
+    input.validate(&config)?;
+    let msg = recording::AddRecordingRule {
+        rule: input.into_rule(0),
+    };
+    let rule = recording_manager.call(msg).await??;
+    Ok((StatusCode::CREATED, Json(rule.into())))
+}
+
+/// Updates a recording rule.
+///
+/// Recording schedules already added by the rule won't be changed.
+#[utoipa::path(
+    put,
+    path = "/recording/rules/{id}",
+    params(
+        ("id" = u32, Path, description = "Recording rule ID"),
+    ),
+    request_body = WebRecordingRuleInput,
+    responses(
+        (status = 200, description = "OK", body = WebRecordingRule),
+        (status = 400, description = "Bad Request"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "updateRecordingRule",
+)]
+pub(in crate::web::api) async fn update<R>(
+    State(ConfigExtractor(config)): State<ConfigExtractor>,
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+    Path(id): Path<u32>,
+    Json(input): Json<WebRecordingRuleInput>,
+) -> Result<Json<WebRecordingRule>, Error>
+where
+    R: Call<recording::UpdateRecordingRule>,
+{
+    input.validate(&config)?;
+    let msg = recording::UpdateRecordingRule {
+        rule: input.into_rule(id),
+    };
+    let rule = recording_manager.call(msg).await??;
+    Ok(Json(rule.into()))
+}
+
+/// Deletes a recording rule.
+///
+/// Recording schedules already added by the rule won't be deleted.
+#[utoipa::path(
+    delete,
+    path = "/recording/rules/{id}",
+    params(
+        ("id" = u32, Path, description = "Recording rule ID"),
+    ),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "deleteRecordingRule",
+)]
+pub(in crate::web::api) async fn delete<R>(
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+    Path(id): Path<u32>,
+) -> Result<(), Error>
+where
+    R: Call<recording::RemoveRecordingRule>,
+{
+    recording_manager
+        .call(recording::RemoveRecordingRule { id })
+        .await??;
+    Ok(())
+}
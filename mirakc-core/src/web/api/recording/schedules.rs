@@ -2,6 +2,7 @@ use super::*;
 
 use std::collections::HashMap;
 
+use crate::recording::RecordingConflict;
 use crate::recording::RecordingSchedule;
 
 /// Lists recording schedules.
@@ -18,14 +19,19 @@ pub(in crate::web::api) async fn list<R>(
     State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
 ) -> Result<Json<Vec<WebRecordingSchedule>>, Error>
 where
+    R: Call<recording::QueryRecordingConflicts>,
     R: Call<recording::QueryRecordingSchedules>,
 {
+    let mut conflicts = query_conflicts(&recording_manager).await?;
     let mut results = vec![];
     let schedules = recording_manager
         .call(recording::QueryRecordingSchedules)
         .await?;
     for schedule in schedules.into_iter() {
-        results.push(schedule.into());
+        let program_id = schedule.program.id;
+        let mut result: WebRecordingSchedule = schedule.into();
+        result.conflict = conflicts.remove(&program_id);
+        results.push(result);
     }
 
     Ok(Json(results))
@@ -50,12 +56,16 @@ pub(in crate::web::api) async fn get<R>(
     Path(program_id): Path<ProgramId>,
 ) -> Result<Json<WebRecordingSchedule>, Error>
 where
+    R: Call<recording::QueryRecordingConflicts>,
     R: Call<recording::QueryRecordingSchedule>,
 {
     let schedule = recording_manager
         .call(recording::QueryRecordingSchedule { program_id })
         .await??;
-    Ok(Json(schedule.into()))
+    let mut conflicts = query_conflicts(&recording_manager).await?;
+    let mut result: WebRecordingSchedule = schedule.into();
+    result.conflict = conflicts.remove(&program_id);
+    Ok(Json(result))
 }
 
 /// Books a recording schedule.
@@ -93,6 +103,93 @@ where
     Ok((StatusCode::CREATED, Json(schedule.into())))
 }
 
+/// Books a manual recording schedule.
+///
+/// A manual recording schedule records a service between two timestamps
+/// regardless of TV programs in EPG.  A pseudo TV program with a generated ID
+/// is created for the recording schedule.
+#[utoipa::path(
+    post,
+    path = "/recording/schedules/manual",
+    request_body = WebManualRecordingScheduleInput,
+    responses(
+        (status = 201, description = "Created", body = WebRecordingSchedule),
+        (status = 400, description = "Bad Request"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "createManualRecordingSchedule",
+)]
+pub(in crate::web::api) async fn create_manual<E, R>(
+    State(ConfigExtractor(config)): State<ConfigExtractor>,
+    State(EpgExtractor(epg)): State<EpgExtractor<E>>,
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+    Json(input): Json<WebManualRecordingScheduleInput>,
+) -> Result<(StatusCode, Json<WebRecordingSchedule>), Error>
+where
+    E: Call<epg::QueryService>,
+    R: Call<recording::AddManualRecordingSchedule>,
+{
+    input.validate(&config)?;
+    let msg = epg::QueryService {
+        service_id: input.service_id,
+    };
+    let _service = epg.call(msg).await??;
+    let msg = recording::AddManualRecordingSchedule {
+        service_id: input.service_id,
+        start_time: input.start_time,
+        end_time: input.end_time,
+        name: input.name,
+        options: input.options,
+        tags: input.tags,
+    };
+    let schedule = recording_manager.call(msg).await??;
+    Ok((StatusCode::CREATED, Json(schedule.into())))
+}
+
+/// Updates a recording schedule.
+///
+/// Recording options can be updated only while the recording schedule is in
+/// the `scheduled`, `tracking` or `rescheduling` state.  Tags can also be
+/// updated while recording.
+#[utoipa::path(
+    patch,
+    path = "/recording/schedules/{program_id}",
+    params(
+        ("program_id" = u64, Path, description = "Mirakurun program ID"),
+    ),
+    request_body = WebRecordingSchedulePatch,
+    responses(
+        (status = 200, description = "OK", body = WebRecordingSchedule),
+        (status = 400, description = "Bad Request"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "updateRecordingSchedule",
+)]
+pub(in crate::web::api) async fn update<R>(
+    State(ConfigExtractor(config)): State<ConfigExtractor>,
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+    Path(program_id): Path<ProgramId>,
+    Json(patch): Json<WebRecordingSchedulePatch>,
+) -> Result<Json<WebRecordingSchedule>, Error>
+where
+    R: Call<recording::QueryRecordingConflicts>,
+    R: Call<recording::UpdateRecordingSchedule>,
+{
+    patch.validate(&config)?;
+    let msg = recording::UpdateRecordingSchedule {
+        program_id,
+        options: patch.options,
+        tags: patch.tags,
+    };
+    let schedule = recording_manager.call(msg).await??;
+    let mut conflicts = query_conflicts(&recording_manager).await?;
+    let mut result: WebRecordingSchedule = schedule.into();
+    result.conflict = conflicts.remove(&program_id);
+    Ok(Json(result))
+}
+
 /// Deletes a recording schedule.
 #[utoipa::path(
     delete,
@@ -162,3 +259,20 @@ where
         .await?;
     Ok(())
 }
+
+// helpers
+
+async fn query_conflicts<R>(
+    recording_manager: &R,
+) -> Result<HashMap<ProgramId, RecordingConflict>, Error>
+where
+    R: Call<recording::QueryRecordingConflicts>,
+{
+    let conflicts = recording_manager
+        .call(recording::QueryRecordingConflicts)
+        .await??;
+    Ok(conflicts
+        .into_iter()
+        .map(|conflict| (conflict.program_id, conflict))
+        .collect())
+}
@@ -0,0 +1,121 @@
+use super::*;
+
+/// Lists recording series.
+#[utoipa::path(
+    get,
+    path = "/recording/series",
+    responses(
+        (status = 200, description = "OK", body = [WebRecordingSeries]),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "getRecordingSeriesList",
+)]
+pub(in crate::web::api) async fn list<R>(
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+) -> Result<Json<Vec<WebRecordingSeries>>, Error>
+where
+    R: Call<recording::QueryRecordingSeriesList>,
+{
+    let series = recording_manager
+        .call(recording::QueryRecordingSeriesList)
+        .await?;
+    Ok(Json(
+        series.into_iter().map(WebRecordingSeries::from).collect(),
+    ))
+}
+
+/// Gets a recording series.
+#[utoipa::path(
+    get,
+    path = "/recording/series/{id}",
+    params(
+        ("id" = u32, Path, description = "Recording series ID"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = WebRecordingSeries),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "getRecordingSeries",
+)]
+pub(in crate::web::api) async fn get<R>(
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+    Path(id): Path<u32>,
+) -> Result<Json<WebRecordingSeries>, Error>
+where
+    R: Call<recording::QueryRecordingSeries>,
+{
+    let series = recording_manager
+        .call(recording::QueryRecordingSeries { id })
+        .await??;
+    Ok(Json(series.into()))
+}
+
+/// Creates a recording series.
+///
+/// The series is identified by the SeriesDescriptor of a specified TV program.
+/// Recording schedules will be added for future episodes of the series
+/// broadcasted on the same service when the series is created and when EPG
+/// programs are updated.  Each episode will be scheduled only once even if it
+/// is rebroadcasted.
+///
+/// The series will be removed automatically when it expires or after the last
+/// episode is scheduled.
+#[utoipa::path(
+    post,
+    path = "/recording/series",
+    request_body = WebRecordingSeriesInput,
+    responses(
+        (status = 201, description = "Created", body = WebRecordingSeries),
+        (status = 400, description = "Bad Request"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "createRecordingSeries",
+)]
+pub(in crate::web::api) async fn create<R>(
+    State(ConfigExtractor(config)): State<ConfigExtractor>,
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+    Json(input): Json<WebRecordingSeriesInput>,
+) -> Result<(StatusCode, Json<WebRecordingSeries>), Error>
+where
+    R: Call<recording::AddRecordingSeries>,
+{
+    input.validate(&config)?;
+    let msg = recording::AddRecordingSeries {
+        program_id: input.program_id,
+        options: input.options,
+        tags: input.tags,
+    };
+    let series = recording_manager.call(msg).await??;
+    Ok((StatusCode::CREATED, Json(series.into())))
+}
+
+/// Deletes a recording series.
+///
+/// Recording schedules already added for the series won't be deleted.
+#[utoipa::path(
+    delete,
+    path = "/recording/series/{id}",
+    params(
+        ("id" = u32, Path, description = "Recording series ID"),
+    ),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "deleteRecordingSeries",
+)]
+pub(in crate::web::api) async fn delete<R>(
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+    Path(id): Path<u32>,
+) -> Result<(), Error>
+where
+    R: Call<recording::RemoveRecordingSeries>,
+{
+    recording_manager
+        .call(recording::RemoveRecordingSeries { id })
+        .await??;
+    Ok(())
+}
@@ -2,6 +2,13 @@ pub(super) mod stream;
 
 use super::*;
 
+use chrono_jst::Jst;
+
+use crate::epg::EpgProgram;
+use crate::onair;
+use crate::recording;
+use crate::recording::RecordingSchedule;
+
 /// Lists services.
 #[utoipa::path(
     get,
@@ -142,3 +149,180 @@ where
         .collect_vec();
     Ok(programs.into())
 }
+
+/// Records a TV program currently on air.
+///
+/// The TV program is determined from the on-air program tracked by mirakc.  If
+/// it's not available, the TV program is determined from EPG data.  Then, the
+/// recording starts immediately.
+///
+/// If `untilNext` is `true`, a recording schedule for the next TV program is
+/// also created if the next TV program is known.  The recording schedule of
+/// the current TV program comes first in the response.
+#[utoipa::path(
+    post,
+    path = "/services/{id}/record-now",
+    params(
+        ("id" = u64, Path, description = "Mirakurun service ID"),
+    ),
+    request_body = WebRecordNowInput,
+    responses(
+        (status = 201, description = "Created", body = [WebRecordingSchedule]),
+        (status = 400, description = "Bad Request"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "recordNow",
+)]
+pub(super) async fn record_now<E, R, O>(
+    State(ConfigExtractor(config)): State<ConfigExtractor>,
+    State(EpgExtractor(epg)): State<EpgExtractor<E>>,
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+    State(OnairProgramManagerExtractor(onair_manager)): State<OnairProgramManagerExtractor<O>>,
+    Path(service_id): Path<ServiceId>,
+    Json(input): Json<WebRecordNowInput>,
+) -> Result<(StatusCode, Json<Vec<WebRecordingSchedule>>), Error>
+where
+    E: Call<epg::QueryPrograms>,
+    E: Call<epg::QueryService>,
+    R: Call<recording::AddRecordingSchedule>,
+    R: Call<recording::QueryRecordingSchedule>,
+    R: Call<recording::StartRecording>,
+    O: Call<onair::QueryOnairProgram>,
+{
+    input.validate(&config)?;
+    let _service = epg.call(epg::QueryService { service_id }).await??;
+
+    let (current, next) = find_onair_programs(&epg, &onair_manager, service_id).await?;
+    let current = current.ok_or(Error::ProgramNotFound)?;
+
+    let mut results = vec![];
+
+    let program_id = current.id;
+    let schedule = RecordingSchedule::new(current, input.options.clone(), input.tags.clone());
+    let msg = recording::StartRecording { schedule };
+    recording_manager.call(msg).await??;
+    let msg = recording::QueryRecordingSchedule { program_id };
+    let schedule = recording_manager.call(msg).await??;
+    results.push(schedule.into());
+
+    if input.until_next {
+        if let Some(next) = next {
+            let program_id = next.id;
+            let schedule = RecordingSchedule::new(next, input.options, input.tags);
+            let msg = recording::AddRecordingSchedule { schedule };
+            let schedule = match recording_manager.call(msg).await? {
+                Ok(schedule) => schedule,
+                // The next TV program may have already been scheduled by a
+                // recording rule.
+                Err(Error::AlreadyExists) => {
+                    let msg = recording::QueryRecordingSchedule { program_id };
+                    recording_manager.call(msg).await??
+                }
+                Err(err) => return Err(err),
+            };
+            results.push(schedule.into());
+        }
+    }
+
+    Ok((StatusCode::CREATED, Json(results)))
+}
+
+/// Enables a service disabled at runtime.
+#[utoipa::path(
+    post,
+    path = "/services/{id}/enable",
+    params(
+        ("id" = u64, Path, description = "Mirakurun service ID"),
+    ),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "enableService",
+)]
+pub(super) async fn enable<E>(
+    State(EpgExtractor(epg)): State<EpgExtractor<E>>,
+    Path(service_id): Path<ServiceId>,
+) -> Result<(), Error>
+where
+    E: Call<epg::SetServiceEnabled>,
+{
+    epg.call(epg::SetServiceEnabled {
+        service_id,
+        enabled: true,
+    })
+    .await??;
+    Ok(())
+}
+
+/// Disables a service at runtime.
+///
+/// The service is hidden and excluded from EPG jobs until it's enabled.  The
+/// state is kept across restarts if `config.maintenance.state-dir` is
+/// specified.
+#[utoipa::path(
+    post,
+    path = "/services/{id}/disable",
+    params(
+        ("id" = u64, Path, description = "Mirakurun service ID"),
+    ),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "disableService",
+)]
+pub(super) async fn disable<E>(
+    State(EpgExtractor(epg)): State<EpgExtractor<E>>,
+    Path(service_id): Path<ServiceId>,
+) -> Result<(), Error>
+where
+    E: Call<epg::SetServiceEnabled>,
+{
+    epg.call(epg::SetServiceEnabled {
+        service_id,
+        enabled: false,
+    })
+    .await??;
+    Ok(())
+}
+
+async fn find_onair_programs<E, O>(
+    epg: &E,
+    onair_manager: &O,
+    service_id: ServiceId,
+) -> Result<(Option<Arc<EpgProgram>>, Option<Arc<EpgProgram>>), Error>
+where
+    E: Call<epg::QueryPrograms>,
+    O: Call<onair::QueryOnairProgram>,
+{
+    let msg = onair::QueryOnairProgram { service_id };
+    if let Ok(onair) = onair_manager.call(msg).await? {
+        if onair.current.is_some() {
+            return Ok((onair.current, onair.next));
+        }
+    }
+
+    // Fallback to EPG data.
+    let programs = epg.call(epg::QueryPrograms { service_id }).await?;
+    let now = Jst::now();
+    let current = programs
+        .values()
+        .find(|program| match (program.start_at, program.end_at()) {
+            (Some(start_at), Some(end_at)) => start_at <= now && now < end_at,
+            _ => false,
+        });
+    let current = match current {
+        Some(current) => current,
+        None => return Ok((None, None)),
+    };
+    let next = current.end_at().and_then(|end_at| {
+        programs
+            .values()
+            .filter(|program| matches!(program.start_at, Some(start_at) if start_at >= end_at))
+            .min_by_key(|program| program.start_at)
+    });
+    Ok((Some(Arc::new(current.clone())), next.cloned().map(Arc::new)))
+}
@@ -51,3 +51,148 @@ where
     let tuner = tuner_manager.call(tuner::QueryTuner(index)).await??;
     Ok(Json(tuner))
 }
+
+/// Puts a tuner into maintenance.
+///
+/// Users using the tuner keep using it, but no new user can use it.  The state
+/// is kept across restarts if `config.maintenance.state-dir` is specified.
+#[utoipa::path(
+    put,
+    path = "/tuners/{index}/maintenance",
+    params(
+        ("index" = usize, Path, description = "Tuner index"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = MirakurunTuner),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "enterTunerMaintenance",
+)]
+pub(super) async fn enter_maintenance<T>(
+    State(TunerManagerExtractor(tuner_manager)): State<TunerManagerExtractor<T>>,
+    Path(index): Path<usize>,
+) -> Result<Json<MirakurunTuner>, Error>
+where
+    T: Call<tuner::SetTunerMaintenance>,
+{
+    let msg = tuner::SetTunerMaintenance {
+        index,
+        maintenance: true,
+    };
+    let tuner = tuner_manager.call(msg).await??;
+    Ok(Json(tuner))
+}
+
+/// Brings a tuner back from maintenance.
+#[utoipa::path(
+    delete,
+    path = "/tuners/{index}/maintenance",
+    params(
+        ("index" = usize, Path, description = "Tuner index"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = MirakurunTuner),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "leaveTunerMaintenance",
+)]
+pub(super) async fn leave_maintenance<T>(
+    State(TunerManagerExtractor(tuner_manager)): State<TunerManagerExtractor<T>>,
+    Path(index): Path<usize>,
+) -> Result<Json<MirakurunTuner>, Error>
+where
+    T: Call<tuner::SetTunerMaintenance>,
+{
+    let msg = tuner::SetTunerMaintenance {
+        index,
+        maintenance: false,
+    };
+    let tuner = tuner_manager.call(msg).await??;
+    Ok(Json(tuner))
+}
+
+/// Lists tuner reservations.
+///
+/// Reservations defined in `config.tuner-reservations` are also included.
+#[utoipa::path(
+    get,
+    path = "/tuners/reservations",
+    responses(
+        (status = 200, description = "OK", body = [TunerReservation]),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "getTunerReservations",
+)]
+pub(super) async fn list_reservations<T>(
+    State(TunerManagerExtractor(tuner_manager)): State<TunerManagerExtractor<T>>,
+) -> Result<Json<Vec<TunerReservation>>, Error>
+where
+    T: Call<tuner::QueryTunerReservations>,
+{
+    let reservations = tuner_manager.call(tuner::QueryTunerReservations).await?;
+    Ok(Json(reservations))
+}
+
+/// Creates a tuner reservation.
+///
+/// Users who are not permitted to use the reserved tuner stop streaming when
+/// the reservation starts.
+///
+/// Reservations created via this endpoint are lost when mirakc restarts.
+#[utoipa::path(
+    post,
+    path = "/tuners/reservations",
+    request_body = WebTunerReservationInput,
+    responses(
+        (status = 201, description = "Created", body = TunerReservation),
+        (status = 400, description = "Bad Request"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "createTunerReservation",
+)]
+pub(super) async fn create_reservation<T>(
+    State(TunerManagerExtractor(tuner_manager)): State<TunerManagerExtractor<T>>,
+    Json(input): Json<WebTunerReservationInput>,
+) -> Result<(StatusCode, Json<TunerReservation>), Error>
+where
+    T: Call<tuner::AddTunerReservation>,
+{
+    let msg = tuner::AddTunerReservation {
+        reservation: input.into_reservation(),
+    };
+    let reservation = tuner_manager.call(msg).await??;
+    Ok((StatusCode::CREATED, Json(reservation)))
+}
+
+/// Deletes a tuner reservation.
+///
+/// Reservations defined in `config.tuner-reservations` cannot be deleted.
+#[utoipa::path(
+    delete,
+    path = "/tuners/reservations/{id}",
+    params(
+        ("id" = u32, Path, description = "Tuner reservation ID"),
+    ),
+    responses(
+        (status = 200, description = "OK"),
+        (status = 400, description = "Bad Request"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    ),
+    operation_id = "deleteTunerReservation",
+)]
+pub(super) async fn delete_reservation<T>(
+    State(TunerManagerExtractor(tuner_manager)): State<TunerManagerExtractor<T>>,
+    Path(id): Path<u32>,
+) -> Result<(), Error>
+where
+    T: Call<tuner::RemoveTunerReservation>,
+{
+    tuner_manager
+        .call(tuner::RemoveTunerReservation { id })
+        .await??;
+    Ok(())
+}
@@ -49,16 +49,27 @@ impl IntoResponse for Error {
             Error::RecordNotFound => error_response!(StatusCode::NOT_FOUND),
             Error::ScheduleNotFound => error_response!(StatusCode::NOT_FOUND),
             Error::RecorderNotFound => error_response!(StatusCode::NOT_FOUND),
+            Error::RuleNotFound => error_response!(StatusCode::NOT_FOUND),
+            Error::SeriesNotFound => error_response!(StatusCode::NOT_FOUND),
+            Error::JobNotFound => error_response!(StatusCode::NOT_FOUND),
+            Error::ReservationNotFound => error_response!(StatusCode::NOT_FOUND),
             Error::OutOfRange => error_response!(StatusCode::RANGE_NOT_SATISFIABLE),
             Error::NoContent => error_response!(StatusCode::NO_CONTENT),
             Error::NoLogoData => {
                 error_response!(StatusCode::SERVICE_UNAVAILABLE, "Logo Data Unavailable")
             }
             Error::AccessDenied => error_response!(StatusCode::FORBIDDEN),
+            Error::NoSeries => error_response!(StatusCode::BAD_REQUEST),
             Error::AlreadyExists => error_response!(StatusCode::BAD_REQUEST),
             Error::ProgramEnded => error_response!(StatusCode::BAD_REQUEST),
             Error::QuerystringError(_) => error_response!(StatusCode::BAD_REQUEST),
             Error::InvalidPath => error_response!(StatusCode::BAD_REQUEST),
+            Error::InvalidRule => error_response!(StatusCode::BAD_REQUEST),
+            Error::InvalidMargin => error_response!(StatusCode::BAD_REQUEST),
+            Error::InvalidTimeRange => error_response!(StatusCode::BAD_REQUEST),
+            Error::InvalidReservation => error_response!(StatusCode::BAD_REQUEST),
+            Error::InvalidScheduleState => error_response!(StatusCode::BAD_REQUEST),
+            Error::InvalidConfig(_) => error_response!(StatusCode::BAD_REQUEST, "Invalid Config"),
             _ => error_response!(StatusCode::INTERNAL_SERVER_ERROR),
         }
     }
@@ -0,0 +1,235 @@
+use super::*;
+
+use std::collections::BTreeMap;
+use std::task::Context;
+use std::task::Poll;
+use std::time::Instant;
+
+use axum::body::Body;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::Request;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use chrono_jst::Jst;
+use futures::future::BoxFuture;
+use tower::Layer;
+use tower::Service;
+
+use crate::metrics::MetricsWriter;
+use crate::recording::RecordingScheduleState;
+
+pub(super) async fn metrics<T, E, R, S>(
+    State(ConfigExtractor(config)): State<ConfigExtractor>,
+    State(TunerManagerExtractor(tuner_manager)): State<TunerManagerExtractor<T>>,
+    State(EpgExtractor(epg)): State<EpgExtractor<E>>,
+    State(RecordingManagerExtractor(recording_manager)): State<RecordingManagerExtractor<R>>,
+    State(TimeshiftManagerExtractor(timeshift_manager)): State<TimeshiftManagerExtractor<S>>,
+) -> Result<Response, Error>
+where
+    T: Call<crate::tuner::QueryTuners>,
+    E: Call<crate::epg::QueryPrograms>,
+    E: Call<crate::epg::QueryServices>,
+    R: Call<crate::recording::QueryRecordingSchedules>,
+    S: Call<crate::timeshift::QueryTimeshiftRecorders>,
+{
+    let mut w = MetricsWriter::default();
+
+    let tuners = tuner_manager.call(crate::tuner::QueryTuners).await?;
+    w.family(
+        "mirakc_tuner_state",
+        "gauge",
+        "The state of the tuner, either busy, free or fault.",
+    );
+    for tuner in tuners.iter() {
+        let state = if tuner.is_fault {
+            "fault"
+        } else if tuner.is_using {
+            "busy"
+        } else {
+            "free"
+        };
+        for label in ["busy", "free", "fault"] {
+            w.sample(
+                "mirakc_tuner_state",
+                &[
+                    ("tuner", &tuner.index),
+                    ("name", &tuner.name),
+                    ("state", &label),
+                ],
+                u8::from(label == state),
+            );
+        }
+    }
+    w.family(
+        "mirakc_tuner_users",
+        "gauge",
+        "The number of users of the tuner.",
+    );
+    for tuner in tuners.iter() {
+        w.sample(
+            "mirakc_tuner_users",
+            &[("tuner", &tuner.index), ("name", &tuner.name)],
+            tuner.users.len(),
+        );
+    }
+
+    if config.recording.is_enabled() {
+        let schedules = recording_manager
+            .call(crate::recording::QueryRecordingSchedules)
+            .await?;
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for state in [
+            RecordingScheduleState::Scheduled,
+            RecordingScheduleState::Tracking,
+            RecordingScheduleState::Recording,
+            RecordingScheduleState::Rescheduling,
+            RecordingScheduleState::Finished,
+            RecordingScheduleState::Failed,
+        ] {
+            counts.insert(schedule_state_label(&state), 0);
+        }
+        for schedule in schedules.iter() {
+            *counts
+                .entry(schedule_state_label(&schedule.state))
+                .or_default() += 1;
+        }
+        w.family(
+            "mirakc_recording_schedules",
+            "gauge",
+            "The number of recording schedules in each state.",
+        );
+        for (state, n) in counts.iter() {
+            w.sample("mirakc_recording_schedules", &[("state", state)], n);
+        }
+    }
+
+    if config.timeshift.is_enabled() {
+        let recorders = timeshift_manager
+            .call(crate::timeshift::QueryTimeshiftRecorders)
+            .await??;
+        let now = Jst::now();
+        w.family(
+            "mirakc_timeshift_recorder_duration_seconds",
+            "gauge",
+            "The duration of the records kept in the timeshift recorder.",
+        );
+        for recorder in recorders.iter() {
+            w.sample(
+                "mirakc_timeshift_recorder_duration_seconds",
+                &[("recorder", &recorder.name)],
+                recorder.duration.num_milliseconds() as f64 / 1000.0,
+            );
+        }
+        w.family(
+            "mirakc_timeshift_recorder_lag_seconds",
+            "gauge",
+            "The time elapsed since the timeshift recorder wrote data last time.",
+        );
+        for recorder in recorders.iter() {
+            if let Some(end_time) = recorder.end_time {
+                w.sample(
+                    "mirakc_timeshift_recorder_lag_seconds",
+                    &[("recorder", &recorder.name)],
+                    (now - end_time).num_milliseconds() as f64 / 1000.0,
+                );
+            }
+        }
+        w.family(
+            "mirakc_timeshift_recorder_recording",
+            "gauge",
+            "1 if the timeshift recorder is recording, 0 otherwise.",
+        );
+        for recorder in recorders.iter() {
+            w.sample(
+                "mirakc_timeshift_recorder_recording",
+                &[("recorder", &recorder.name)],
+                u8::from(recorder.recording),
+            );
+        }
+    }
+
+    let services = epg.call(crate::epg::QueryServices).await?;
+    w.family(
+        "mirakc_epg_programs",
+        "gauge",
+        "The number of TV programs of the service collected from EIT.",
+    );
+    for service in services.values() {
+        let programs = epg
+            .call(crate::epg::QueryPrograms {
+                service_id: service.id,
+            })
+            .await?;
+        w.sample(
+            "mirakc_epg_programs",
+            &[("service_id", &service.id), ("name", &service.name)],
+            programs.len(),
+        );
+    }
+
+    crate::metrics::render(&mut w);
+
+    Ok((
+        [(CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        w.into_string(),
+    )
+        .into_response())
+}
+
+fn schedule_state_label(state: &RecordingScheduleState) -> &'static str {
+    match state {
+        RecordingScheduleState::Scheduled => "scheduled",
+        RecordingScheduleState::Tracking => "tracking",
+        RecordingScheduleState::Recording => "recording",
+        RecordingScheduleState::Rescheduling => "rescheduling",
+        RecordingScheduleState::Finished => "finished",
+        RecordingScheduleState::Failed => "failed",
+    }
+}
+
+// A layer to collect metrics of HTTP requests.
+#[derive(Clone)]
+pub(super) struct MetricsLayer;
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsService(inner)
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct MetricsService<S>(S);
+
+impl<S> Service<Request<Body>> for MetricsService<S>
+where
+    S: Service<Request<Body>, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    // `BoxFuture` is a type alias for `Pin<Box<dyn Future + Send + 'a>>`
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let method = req.method().clone();
+        let start = Instant::now();
+        let fut = self.0.call(req);
+        Box::pin(async move {
+            let res = fut.await?;
+            // Streaming responses are measured until the response headers are
+            // ready.
+            crate::metrics::record_http_request(
+                method.as_str(),
+                res.status().as_u16(),
+                start.elapsed(),
+            );
+            Ok(res)
+        })
+    }
+}
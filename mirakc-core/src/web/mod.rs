@@ -19,9 +19,12 @@ use utoipa_swagger_ui::SwaggerUi;
 
 use crate::config::Config;
 use crate::error::Error;
+use crate::reloader::ConfigReloader;
 use crate::string_table::StringTable;
 use crate::web::access_control::AccessControlLayer;
 use crate::web::default_headers::DefaultHeadersLayer;
+use crate::web::metrics::MetricsLayer;
+use crate::web::reload::ReloadableRouter;
 use crate::web::uds::UdsListener;
 
 // macros
@@ -41,8 +44,10 @@ mod body;
 mod default_headers;
 mod error;
 mod escape;
+mod metrics;
 mod mount;
 mod qs;
+mod reload;
 mod sse;
 mod uds;
 
@@ -52,7 +57,7 @@ mod tests;
 pub(crate) use api::models::WebOnairProgram;
 
 pub async fn serve<T, E, R, S, O>(
-    config: Arc<Config>,
+    config_reloader: Arc<ConfigReloader>,
     string_table: Arc<StringTable>,
     tuner_manager: T,
     epg: E,
@@ -62,9 +67,14 @@ pub async fn serve<T, E, R, S, O>(
 ) -> Result<(), Error>
 where
     T: Clone + Send + Sync + 'static,
+    T: Call<crate::tuner::AddTunerReservation>,
     T: Call<crate::tuner::QueryTuner>,
+    T: Call<crate::tuner::QueryTunerReservations>,
     T: Call<crate::tuner::QueryTuners>,
     T: Call<crate::tuner::RegisterEmitter>,
+    T: Call<crate::tuner::ReloadConfig>,
+    T: Call<crate::tuner::RemoveTunerReservation>,
+    T: Call<crate::tuner::SetTunerMaintenance>,
     T: Call<crate::tuner::StartStreaming>,
     T: TriggerFactory<crate::tuner::StopStreaming>,
     T: TriggerFactory<crate::tuner::UnregisterEmitter>,
@@ -77,18 +87,42 @@ where
     E: Call<crate::epg::QueryService>,
     E: Call<crate::epg::QueryServices>,
     E: Call<crate::epg::RegisterEmitter>,
+    E: Call<crate::epg::ReloadConfig>,
+    E: Call<crate::epg::SearchPrograms>,
+    E: Call<crate::epg::SetChannelEnabled>,
+    E: Call<crate::epg::SetServiceEnabled>,
     E: TriggerFactory<crate::epg::UnregisterEmitter>,
     R: Clone + Send + Sync + 'static,
+    R: Call<crate::recording::AddManualRecordingSchedule>,
+    R: Call<crate::recording::AddRecordingRule>,
     R: Call<crate::recording::AddRecordingSchedule>,
+    R: Call<crate::recording::AddRecordingSeries>,
+    R: Call<crate::recording::QueryRecordingConflicts>,
+    R: Call<crate::recording::QueryRecordingHistory>,
+    R: Call<crate::recording::QueryRecordingJob>,
+    R: Call<crate::recording::QueryRecordingJobs>,
+    R: Call<crate::recording::QueryRecordingRecord>,
     R: Call<crate::recording::QueryRecordingRecorder>,
     R: Call<crate::recording::QueryRecordingRecorders>,
+    R: Call<crate::recording::QueryRecordingRecords>,
+    R: Call<crate::recording::QueryRecordingRule>,
+    R: Call<crate::recording::QueryRecordingRules>,
     R: Call<crate::recording::QueryRecordingSchedule>,
     R: Call<crate::recording::QueryRecordingSchedules>,
+    R: Call<crate::recording::QueryRecordingSeries>,
+    R: Call<crate::recording::QueryRecordingSeriesList>,
     R: Call<crate::recording::RegisterEmitter>,
+    R: Call<crate::recording::ReloadConfig>,
+    R: Call<crate::recording::RemoveRecordingRecord>,
+    R: Call<crate::recording::RemoveRecordingRule>,
     R: Call<crate::recording::RemoveRecordingSchedule>,
     R: Call<crate::recording::RemoveRecordingSchedules>,
+    R: Call<crate::recording::RemoveRecordingSeries>,
     R: Call<crate::recording::StartRecording>,
     R: Call<crate::recording::StopRecording>,
+    R: Call<crate::recording::UpdateRecordingRecord>,
+    R: Call<crate::recording::UpdateRecordingRule>,
+    R: Call<crate::recording::UpdateRecordingSchedule>,
     R: TriggerFactory<crate::recording::UnregisterEmitter>,
     S: Clone + Send + Sync + 'static,
     S: Call<crate::timeshift::CreateTimeshiftLiveStreamSource>,
@@ -98,6 +132,7 @@ where
     S: Call<crate::timeshift::QueryTimeshiftRecorder>,
     S: Call<crate::timeshift::QueryTimeshiftRecorders>,
     S: Call<crate::timeshift::RegisterEmitter>,
+    S: Call<crate::timeshift::ReloadConfig>,
     S: TriggerFactory<crate::timeshift::UnregisterEmitter>,
     O: Clone + Send + Sync + 'static,
     O: Call<crate::onair::QueryOnairProgram>,
@@ -106,21 +141,36 @@ where
     O: Call<crate::onair::SpawnTemporalTracker>,
     O: TriggerFactory<crate::onair::UnregisterEmitter>,
 {
-    // Disable caching.
-    let mut default_headers = HeaderMap::new();
-    default_headers.append(CACHE_CONTROL, header_value!("no-store"));
-
-    let app = build_app(&config)
-        .layer(DefaultHeadersLayer::new(default_headers))
-        .with_state(Arc::new(AppState {
-            config: config.clone(),
-            string_table,
-            tuner_manager,
-            epg,
-            recording_manager,
-            timeshift_manager,
-            onair_manager,
-        }));
+    let config = config_reloader.config();
+    let state = AppState {
+        config: config.clone(),
+        string_table,
+        config_reloader: config_reloader.clone(),
+        tuner_manager,
+        epg,
+        recording_manager,
+        timeshift_manager,
+        onair_manager,
+    };
+    let router = ReloadableRouter::new(make_app(state.clone()));
+
+    // Rebuild the app when the config is reloaded.
+    let mut config_rx = config_reloader.subscribe();
+    tokio::spawn({
+        let router = router.clone();
+        async move {
+            while config_rx.changed().await.is_ok() {
+                let config = config_rx.borrow_and_update().clone();
+                router.replace(make_app(AppState {
+                    config,
+                    ..state.clone()
+                }));
+                tracing::info!("Rebuilt the web app with the new config");
+            }
+        }
+    });
+
+    let app = Router::new().fallback_service(router);
 
     let http_servers = config
         .server
@@ -137,6 +187,92 @@ where
     Ok(())
 }
 
+fn make_app<T, E, R, S, O>(state: AppState<T, E, R, S, O>) -> Router
+where
+    T: Clone + Send + Sync + 'static,
+    T: Call<crate::tuner::AddTunerReservation>,
+    T: Call<crate::tuner::QueryTuner>,
+    T: Call<crate::tuner::QueryTunerReservations>,
+    T: Call<crate::tuner::QueryTuners>,
+    T: Call<crate::tuner::RegisterEmitter>,
+    T: Call<crate::tuner::ReloadConfig>,
+    T: Call<crate::tuner::RemoveTunerReservation>,
+    T: Call<crate::tuner::SetTunerMaintenance>,
+    T: Call<crate::tuner::StartStreaming>,
+    T: TriggerFactory<crate::tuner::StopStreaming>,
+    T: TriggerFactory<crate::tuner::UnregisterEmitter>,
+    E: Clone + Send + Sync + 'static,
+    E: Call<crate::epg::QueryChannel>,
+    E: Call<crate::epg::QueryChannels>,
+    E: Call<crate::epg::QueryClock>,
+    E: Call<crate::epg::QueryProgram>,
+    E: Call<crate::epg::QueryPrograms>,
+    E: Call<crate::epg::QueryService>,
+    E: Call<crate::epg::QueryServices>,
+    E: Call<crate::epg::RegisterEmitter>,
+    E: Call<crate::epg::ReloadConfig>,
+    E: Call<crate::epg::SearchPrograms>,
+    E: Call<crate::epg::SetChannelEnabled>,
+    E: Call<crate::epg::SetServiceEnabled>,
+    E: TriggerFactory<crate::epg::UnregisterEmitter>,
+    R: Clone + Send + Sync + 'static,
+    R: Call<crate::recording::AddManualRecordingSchedule>,
+    R: Call<crate::recording::AddRecordingRule>,
+    R: Call<crate::recording::AddRecordingSchedule>,
+    R: Call<crate::recording::AddRecordingSeries>,
+    R: Call<crate::recording::QueryRecordingConflicts>,
+    R: Call<crate::recording::QueryRecordingHistory>,
+    R: Call<crate::recording::QueryRecordingJob>,
+    R: Call<crate::recording::QueryRecordingJobs>,
+    R: Call<crate::recording::QueryRecordingRecord>,
+    R: Call<crate::recording::QueryRecordingRecorder>,
+    R: Call<crate::recording::QueryRecordingRecorders>,
+    R: Call<crate::recording::QueryRecordingRecords>,
+    R: Call<crate::recording::QueryRecordingRule>,
+    R: Call<crate::recording::QueryRecordingRules>,
+    R: Call<crate::recording::QueryRecordingSchedule>,
+    R: Call<crate::recording::QueryRecordingSchedules>,
+    R: Call<crate::recording::QueryRecordingSeries>,
+    R: Call<crate::recording::QueryRecordingSeriesList>,
+    R: Call<crate::recording::RegisterEmitter>,
+    R: Call<crate::recording::ReloadConfig>,
+    R: Call<crate::recording::RemoveRecordingRecord>,
+    R: Call<crate::recording::RemoveRecordingRule>,
+    R: Call<crate::recording::RemoveRecordingSchedule>,
+    R: Call<crate::recording::RemoveRecordingSchedules>,
+    R: Call<crate::recording::RemoveRecordingSeries>,
+    R: Call<crate::recording::StartRecording>,
+    R: Call<crate::recording::StopRecording>,
+    R: Call<crate::recording::UpdateRecordingRecord>,
+    R: Call<crate::recording::UpdateRecordingRule>,
+    R: Call<crate::recording::UpdateRecordingSchedule>,
+    R: TriggerFactory<crate::recording::UnregisterEmitter>,
+    S: Clone + Send + Sync + 'static,
+    S: Call<crate::timeshift::CreateTimeshiftLiveStreamSource>,
+    S: Call<crate::timeshift::CreateTimeshiftRecordStreamSource>,
+    S: Call<crate::timeshift::QueryTimeshiftRecord>,
+    S: Call<crate::timeshift::QueryTimeshiftRecords>,
+    S: Call<crate::timeshift::QueryTimeshiftRecorder>,
+    S: Call<crate::timeshift::QueryTimeshiftRecorders>,
+    S: Call<crate::timeshift::RegisterEmitter>,
+    S: Call<crate::timeshift::ReloadConfig>,
+    S: TriggerFactory<crate::timeshift::UnregisterEmitter>,
+    O: Clone + Send + Sync + 'static,
+    O: Call<crate::onair::QueryOnairProgram>,
+    O: Call<crate::onair::QueryOnairPrograms>,
+    O: Call<crate::onair::RegisterEmitter>,
+    O: Call<crate::onair::SpawnTemporalTracker>,
+    O: TriggerFactory<crate::onair::UnregisterEmitter>,
+{
+    // Disable caching.
+    let mut default_headers = HeaderMap::new();
+    default_headers.append(CACHE_CONTROL, header_value!("no-store"));
+
+    build_app(&state.config)
+        .layer(DefaultHeadersLayer::new(default_headers))
+        .with_state(Arc::new(state))
+}
+
 // http
 
 async fn serve_http(addr: SocketAddr, app: Router) -> hyper::Result<()> {
@@ -171,9 +307,14 @@ const X_MIRAKURUN_PRIORITY: &'static str = "x-mirakurun-priority";
 fn build_app<T, E, R, S, O>(config: &Config) -> Router<Arc<AppState<T, E, R, S, O>>>
 where
     T: Clone + Send + Sync + 'static,
+    T: Call<crate::tuner::AddTunerReservation>,
     T: Call<crate::tuner::QueryTuner>,
+    T: Call<crate::tuner::QueryTunerReservations>,
     T: Call<crate::tuner::QueryTuners>,
     T: Call<crate::tuner::RegisterEmitter>,
+    T: Call<crate::tuner::ReloadConfig>,
+    T: Call<crate::tuner::RemoveTunerReservation>,
+    T: Call<crate::tuner::SetTunerMaintenance>,
     T: Call<crate::tuner::StartStreaming>,
     T: TriggerFactory<crate::tuner::StopStreaming>,
     T: TriggerFactory<crate::tuner::UnregisterEmitter>,
@@ -186,18 +327,42 @@ where
     E: Call<crate::epg::QueryService>,
     E: Call<crate::epg::QueryServices>,
     E: Call<crate::epg::RegisterEmitter>,
+    E: Call<crate::epg::ReloadConfig>,
+    E: Call<crate::epg::SearchPrograms>,
+    E: Call<crate::epg::SetChannelEnabled>,
+    E: Call<crate::epg::SetServiceEnabled>,
     E: TriggerFactory<crate::epg::UnregisterEmitter>,
     R: Clone + Send + Sync + 'static,
+    R: Call<crate::recording::AddManualRecordingSchedule>,
+    R: Call<crate::recording::AddRecordingRule>,
     R: Call<crate::recording::AddRecordingSchedule>,
+    R: Call<crate::recording::AddRecordingSeries>,
+    R: Call<crate::recording::QueryRecordingConflicts>,
+    R: Call<crate::recording::QueryRecordingHistory>,
+    R: Call<crate::recording::QueryRecordingJob>,
+    R: Call<crate::recording::QueryRecordingJobs>,
+    R: Call<crate::recording::QueryRecordingRecord>,
     R: Call<crate::recording::QueryRecordingRecorder>,
     R: Call<crate::recording::QueryRecordingRecorders>,
+    R: Call<crate::recording::QueryRecordingRecords>,
+    R: Call<crate::recording::QueryRecordingRule>,
+    R: Call<crate::recording::QueryRecordingRules>,
     R: Call<crate::recording::QueryRecordingSchedule>,
     R: Call<crate::recording::QueryRecordingSchedules>,
+    R: Call<crate::recording::QueryRecordingSeries>,
+    R: Call<crate::recording::QueryRecordingSeriesList>,
     R: Call<crate::recording::RegisterEmitter>,
+    R: Call<crate::recording::ReloadConfig>,
+    R: Call<crate::recording::RemoveRecordingRecord>,
+    R: Call<crate::recording::RemoveRecordingRule>,
     R: Call<crate::recording::RemoveRecordingSchedule>,
     R: Call<crate::recording::RemoveRecordingSchedules>,
+    R: Call<crate::recording::RemoveRecordingSeries>,
     R: Call<crate::recording::StartRecording>,
     R: Call<crate::recording::StopRecording>,
+    R: Call<crate::recording::UpdateRecordingRecord>,
+    R: Call<crate::recording::UpdateRecordingRule>,
+    R: Call<crate::recording::UpdateRecordingSchedule>,
     R: TriggerFactory<crate::recording::UnregisterEmitter>,
     S: Clone + Send + Sync + 'static,
     S: Call<crate::timeshift::CreateTimeshiftLiveStreamSource>,
@@ -207,6 +372,7 @@ where
     S: Call<crate::timeshift::QueryTimeshiftRecorder>,
     S: Call<crate::timeshift::QueryTimeshiftRecorders>,
     S: Call<crate::timeshift::RegisterEmitter>,
+    S: Call<crate::timeshift::ReloadConfig>,
     S: TriggerFactory<crate::timeshift::UnregisterEmitter>,
     O: Clone + Send + Sync + 'static,
     O: Call<crate::onair::QueryOnairProgram>,
@@ -220,6 +386,7 @@ where
         .merge(SwaggerUi::new("/api/debug").url("/api/docs", api::Docs::generate(config)));
 
     router = router.route("/events", routing::get(sse::events));
+    router = router.route("/metrics", routing::get(metrics::metrics));
     router = mount::mount_entries(config, router);
 
     let mut default_headers = HeaderMap::new();
@@ -233,16 +400,20 @@ where
         // Append the default headers.
         .layer(DefaultHeadersLayer::new(default_headers))
         // Output tracing logs.
-        .layer(TraceLayer::new_for_http());
+        .layer(TraceLayer::new_for_http())
+        // Collect metrics of HTTP requests.
+        .layer(MetricsLayer);
 
     router
 }
 
 // state and extractors
 
+#[derive(Clone)]
 struct AppState<T, E, R, S, O> {
     config: Arc<Config>,
     string_table: Arc<StringTable>,
+    config_reloader: Arc<ConfigReloader>,
     tuner_manager: T,
     epg: E,
     recording_manager: R,
@@ -266,6 +437,14 @@ impl<T, E, R, S, O> FromRef<Arc<AppState<T, E, R, S, O>>> for StringTableExtract
     }
 }
 
+struct ConfigReloaderExtractor(Arc<ConfigReloader>);
+
+impl<T, E, R, S, O> FromRef<Arc<AppState<T, E, R, S, O>>> for ConfigReloaderExtractor {
+    fn from_ref(state: &Arc<AppState<T, E, R, S, O>>) -> Self {
+        Self(state.config_reloader.clone())
+    }
+}
+
 struct TunerManagerExtractor<T>(T);
 
 impl<T, E, R, S, O> FromRef<Arc<AppState<T, E, R, S, O>>> for TunerManagerExtractor<T>
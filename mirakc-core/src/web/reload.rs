@@ -0,0 +1,44 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
+
+use axum::body::Body;
+use axum::http::Request;
+use axum::response::Response;
+use axum::Router;
+use tower::Service;
+
+// A service to dispatch requests to the app which can be replaced with a new
+// one when the config is reloaded.
+//
+// Each request is processed by the app at the time when the request arrives.
+// So, running streams are not affected by replacing the app.
+#[derive(Clone)]
+pub(super) struct ReloadableRouter(Arc<Mutex<Router>>);
+
+impl ReloadableRouter {
+    pub(super) fn new(router: Router) -> Self {
+        ReloadableRouter(Arc::new(Mutex::new(router)))
+    }
+
+    pub(super) fn replace(&self, router: Router) {
+        *self.0.lock().unwrap() = router;
+    }
+}
+
+impl Service<Request<Body>> for ReloadableRouter {
+    type Response = Response;
+    type Error = Infallible;
+    type Future = <Router as Service<Request<Body>>>::Future;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let mut router = self.0.lock().unwrap().clone();
+        router.call(req)
+    }
+}
@@ -101,6 +101,19 @@ where
             None
         };
 
+    let _recording_job_updated_unregister_trigger = if config.recording.is_enabled() {
+        let id = recording_manager
+            .call(crate::recording::RegisterEmitter::RecordingJobUpdated(
+                feeder.clone().into(),
+            ))
+            .await?;
+        Some(
+            recording_manager.trigger(crate::recording::UnregisterEmitter::RecordingJobUpdated(id)),
+        )
+    } else {
+        None
+    };
+
     let _timeshift_event_unregister_trigger = if config.timeshift.is_enabled() {
         let id = timeshift_manager
             .call(crate::timeshift::RegisterEmitter(feeder.clone().into()))
@@ -130,6 +143,7 @@ where
         _recording_stopped_unregister_trigger,
         _recording_failed_unregister_trigger,
         _recording_rescheduled_unregister_trigger,
+        _recording_job_updated_unregister_trigger,
         _timeshift_event_unregister_trigger,
         _onair_program_changed_unregister_trigger,
     });
@@ -150,9 +164,9 @@ macro_rules! impl_emit {
             }
         }
 
-        impl Into<Emitter<$msg>> for EventFeeder {
-            fn into(self) -> Emitter<$msg> {
-                Emitter::new(self)
+        impl From<EventFeeder> for Emitter<$msg> {
+            fn from(feeder: EventFeeder) -> Self {
+                Emitter::new(feeder)
             }
         }
     };
@@ -169,6 +183,18 @@ impl Into<Event> for crate::tuner::Event {
                 .event("tuner.status-changed")
                 .json_data(TunerStatusChanged { tuner_index })
                 .unwrap(),
+            Self::Preempted {
+                id,
+                user,
+                preemptor,
+            } => Event::default()
+                .event("tuner.preempted")
+                .json_data(TunerPreempted {
+                    tuner_index: id.tuner_index(),
+                    user: user.get_mirakurun_model(),
+                    preemptor: preemptor.get_mirakurun_model(),
+                })
+                .unwrap(),
         }
     }
 }
@@ -225,6 +251,7 @@ impl Into<Event> for crate::recording::RecordingFailed {
             .json_data(RecordingFailed {
                 program_id: self.program_id.into(),
                 reason: self.reason,
+                ts_stats: self.ts_stats,
             })
             .unwrap()
     }
@@ -243,6 +270,21 @@ impl Into<Event> for crate::recording::RecordingRescheduled {
     }
 }
 
+impl_emit! {crate::recording::RecordingJobUpdated}
+
+impl From<crate::recording::RecordingJobUpdated> for Event {
+    fn from(msg: crate::recording::RecordingJobUpdated) -> Self {
+        Event::default()
+            .event("recording.job-updated")
+            .json_data(RecordingJobUpdated {
+                job_id: msg.job_id,
+                record_id: msg.record_id,
+                state: msg.state,
+            })
+            .unwrap()
+    }
+}
+
 // timeshift events
 
 impl_emit! {crate::timeshift::TimeshiftEvent}
@@ -331,6 +373,7 @@ struct EventStreamWrapper<S> {
     _recording_stopped_unregister_trigger: Option<Trigger<crate::recording::UnregisterEmitter>>,
     _recording_failed_unregister_trigger: Option<Trigger<crate::recording::UnregisterEmitter>>,
     _recording_rescheduled_unregister_trigger: Option<Trigger<crate::recording::UnregisterEmitter>>,
+    _recording_job_updated_unregister_trigger: Option<Trigger<crate::recording::UnregisterEmitter>>,
     _timeshift_event_unregister_trigger: Option<Trigger<crate::timeshift::UnregisterEmitter>>,
     _onair_program_changed_unregister_trigger: Option<Trigger<crate::onair::UnregisterEmitter>>,
 }
@@ -9,13 +9,20 @@ use axum::http::header::LOCATION;
 use axum::http::StatusCode;
 use axum_test_helper::TestClient;
 use axum_test_helper::TestResponse;
+use chrono::Duration;
+use chrono_jst::Jst;
 
 use crate::epg::stub::EpgStub;
+use crate::epg::ProgramSearchSort;
+use crate::models::ChannelType;
+use crate::models::ProgramId;
 use crate::onair::stub::OnairProgramManagerStub;
 use crate::recording::stub::RecordingManagerStub;
 use crate::recording::RecordingOptions;
 use crate::timeshift::stub::TimeshiftManagerStub;
 use crate::tuner::stub::TunerManagerStub;
+use crate::tuner_reservation::TunerReservationTarget;
+use crate::tuner_reservation::TunerReservationWindow;
 use api::models::*;
 use qs::*;
 
@@ -37,12 +44,30 @@ async fn test_get_status() {
     assert_eq!(res.status(), StatusCode::OK);
 }
 
+#[tokio::test]
+async fn test_post_config_reload() {
+    let res = post("/api/config/reload", ()).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
 #[tokio::test]
 async fn test_get_channels() {
     let res = get("/api/channels").await;
     assert_eq!(res.status(), StatusCode::OK);
 }
 
+#[tokio::test]
+async fn test_enable_channel() {
+    let res = post("/api/channels/GR/1/enable", ()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = post("/api/channels/GR/1/disable", ()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = post("/api/channels/GR/0/disable", ()).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
 #[tokio::test]
 async fn test_get_services() {
     let res = get("/api/services").await;
@@ -58,6 +83,18 @@ async fn test_get_service() {
     assert_eq!(res.status(), StatusCode::NOT_FOUND);
 }
 
+#[tokio::test]
+async fn test_enable_service() {
+    let res = post("/api/services/1/enable", ()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = post("/api/services/1/disable", ()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = post("/api/services/0/disable", ()).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
 #[tokio::test]
 async fn test_get_service_logo() {
     let res = get("/api/services/1/logo").await;
@@ -91,12 +128,112 @@ async fn test_get_service_programs() {
     assert_eq!(res.status(), StatusCode::NOT_FOUND);
 }
 
+#[tokio::test]
+async fn test_record_now() {
+    let options = RecordingOptions {
+        content_path: "now.m2ts".into(),
+        priority: 1,
+        pre_filters: vec![],
+        post_filters: vec![],
+        start_margin: None,
+        end_margin: None,
+    };
+
+    // Determined from EPG data.
+    let input = WebRecordNowInput {
+        options: options.clone(),
+        tags: Default::default(),
+        until_next: true,
+    };
+    let res = post("/api/services/1/record-now", input).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let schedules: serde_json::Value = res.json().await;
+    assert_eq!(schedules.as_array().unwrap().len(), 1);
+
+    // Determined from the on-air program.
+    let input = WebRecordNowInput {
+        options: options.clone(),
+        tags: Default::default(),
+        until_next: true,
+    };
+    let res = post("/api/services/2/record-now", input).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let schedules: serde_json::Value = res.json().await;
+    assert_eq!(schedules.as_array().unwrap().len(), 2);
+
+    let input = WebRecordNowInput {
+        options: options.clone(),
+        tags: Default::default(),
+        until_next: false,
+    };
+    let res = post("/api/services/2/record-now", input).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let schedules: serde_json::Value = res.json().await;
+    assert_eq!(schedules.as_array().unwrap().len(), 1);
+
+    // Error::ServiceNotFound
+    let input = WebRecordNowInput {
+        options,
+        tags: Default::default(),
+        until_next: false,
+    };
+    let res = post("/api/services/0/record-now", input).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
 #[tokio::test]
 async fn test_get_programs() {
     let res = get("/api/programs").await;
     assert_eq!(res.status(), StatusCode::OK);
 }
 
+#[tokio::test]
+async fn test_search_programs() {
+    let res = get("/api/programs/search").await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let result: serde_json::Value = res.json().await;
+    assert_eq!(result["total"], 1);
+    assert_eq!(result["programs"].as_array().unwrap().len(), 1);
+
+    let res = get("/api/programs/search?keyword=a&services[]=1&channelTypes[]=GR&free=true").await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = get("/api/programs/search?sort=unknown").await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let query: WebProgramSearchQuery = serde_qs::from_str("").unwrap();
+    assert_eq!(query.keyword, None);
+    assert!(query.genres.is_empty());
+    assert_eq!(query.sort, ProgramSearchSort::StartAt);
+    assert!(!query.desc);
+    assert_eq!(query.offset, 0);
+    assert_eq!(query.limit, 100);
+
+    let query: WebProgramSearchQuery = serde_qs::from_str(
+        "keyword=a+b&genres[0][lv1]=7&genres[1][lv1]=1&genres[1][lv2]=2\
+         &services[]=1&channelTypes[]=BS&since=1000&until=2000\
+         &minDuration=60000&maxDuration=3600000&free=false\
+         &sort=duration&desc=true&offset=10&limit=20",
+    )
+    .unwrap();
+    assert_eq!(query.keyword.as_deref(), Some("a b"));
+    assert_eq!(query.genres.len(), 2);
+    assert_eq!(query.genres[0].lv1, 7);
+    assert_eq!(query.genres[0].lv2, None);
+    assert_eq!(query.genres[1].lv2, Some(2));
+    assert_eq!(query.services, vec![1.into()]);
+    assert_eq!(query.channel_types, vec![ChannelType::BS]);
+    assert_eq!(query.since.unwrap().timestamp_millis(), 1000);
+    assert_eq!(query.until.unwrap().timestamp_millis(), 2000);
+    assert_eq!(query.min_duration, Some(Duration::minutes(1)));
+    assert_eq!(query.max_duration, Some(Duration::hours(1)));
+    assert_eq!(query.free, Some(false));
+    assert_eq!(query.sort, ProgramSearchSort::Duration);
+    assert!(query.desc);
+    assert_eq!(query.offset, 10);
+    assert_eq!(query.limit, 20);
+}
+
 #[tokio::test]
 async fn test_get_program() {
     let res = get("/api/programs/1").await;
@@ -121,6 +258,87 @@ async fn test_get_tuner() {
     assert_eq!(res.status(), StatusCode::NOT_FOUND);
 }
 
+#[tokio::test]
+async fn test_tuner_maintenance() {
+    let res = put("/api/tuners/1/maintenance", ()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let tuner: serde_json::Value = res.json().await;
+    assert_eq!(tuner["isMaintenance"], true);
+    assert_eq!(tuner["isFree"], false);
+
+    let res = delete("/api/tuners/1/maintenance").await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let tuner: serde_json::Value = res.json().await;
+    assert_eq!(tuner["isMaintenance"], false);
+
+    let res = put("/api/tuners/0/maintenance", ()).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_get_tuner_reservations() {
+    let res = get("/api/tuners/reservations").await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_create_tuner_reservation() {
+    let now = Jst::now();
+
+    let input = WebTunerReservationInput {
+        target: TunerReservationTarget::ChannelType {
+            channel_type: ChannelType::GR,
+        },
+        window: TunerReservationWindow::Once {
+            start_at: now,
+            end_at: now + Duration::hours(1),
+        },
+        owner: "owner".to_string(),
+        priority: 0,
+    };
+    let res = post("/api/tuners/reservations", input).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+
+    // Error::InvalidTimeRange
+    let input = WebTunerReservationInput {
+        target: TunerReservationTarget::ChannelType {
+            channel_type: ChannelType::GR,
+        },
+        window: TunerReservationWindow::Once {
+            start_at: now,
+            end_at: now,
+        },
+        owner: "owner".to_string(),
+        priority: 0,
+    };
+    let res = post("/api/tuners/reservations", input).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    // Error::InvalidReservation
+    let input = WebTunerReservationInput {
+        target: TunerReservationTarget::ChannelType {
+            channel_type: ChannelType::GR,
+        },
+        window: TunerReservationWindow::Once {
+            start_at: now,
+            end_at: now + Duration::hours(1),
+        },
+        owner: "owner".to_string(),
+        priority: 1000,
+    };
+    let res = post("/api/tuners/reservations", input).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_delete_tuner_reservation() {
+    let res = delete("/api/tuners/reservations/1").await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = delete("/api/tuners/reservations/0").await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
 #[tokio::test]
 async fn test_get_channel_stream() {
     let res = get("/api/channels/GR/ch/stream").await;
@@ -365,6 +583,8 @@ async fn test_create_recording_schedule() {
             priority: 1,
             pre_filters: vec![],
             post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
         },
         tags: Default::default(),
     };
@@ -379,6 +599,8 @@ async fn test_create_recording_schedule() {
             priority: 1,
             pre_filters: vec![],
             post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
         },
         tags: Default::default(),
     };
@@ -393,6 +615,40 @@ async fn test_create_recording_schedule() {
             priority: 1,
             pre_filters: vec![],
             post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
+        },
+        tags: Default::default(),
+    };
+    let res = post("/api/recording/schedules", input).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    // Error::InvalidMargin
+    let input = WebRecordingScheduleInput {
+        program_id: (0, 0, 4).into(),
+        options: RecordingOptions {
+            content_path: "4.m2ts".into(),
+            priority: 1,
+            pre_filters: vec![],
+            post_filters: vec![],
+            start_margin: Some(3_600_001),
+            end_margin: None,
+        },
+        tags: Default::default(),
+    };
+    let res = post("/api/recording/schedules", input).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    // Error::InvalidPath
+    let input = WebRecordingScheduleInput {
+        program_id: (0, 0, 4).into(),
+        options: RecordingOptions {
+            content_path: "{{title}.m2ts".into(),
+            priority: 1,
+            pre_filters: vec![],
+            post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
         },
         tags: Default::default(),
     };
@@ -407,6 +663,8 @@ async fn test_create_recording_schedule() {
             priority: 1,
             pre_filters: vec![],
             post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
         },
         tags: Default::default(),
     };
@@ -421,6 +679,8 @@ async fn test_create_recording_schedule() {
             priority: 1,
             pre_filters: vec![],
             post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
         },
         tags: Default::default(),
     };
@@ -435,6 +695,8 @@ async fn test_create_recording_schedule() {
             priority: 1,
             pre_filters: vec![],
             post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
         },
         tags: Default::default(),
     };
@@ -446,11 +708,118 @@ async fn test_create_recording_schedule() {
 async fn test_get_recording_schedule() {
     let res = get("/api/recording/schedules/1").await;
     assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.text().await.contains("\"conflict\":"));
+
+    let res = get("/api/recording/schedules/2").await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(!res.text().await.contains("\"conflict\":"));
 
     let res = get("/api/recording/schedules/0").await;
     assert_eq!(res.status(), StatusCode::NOT_FOUND);
 }
 
+#[tokio::test]
+async fn test_create_manual_recording_schedule() {
+    let now = Jst::now();
+
+    let input = WebManualRecordingScheduleInput {
+        service_id: (0, 1).into(),
+        start_time: now,
+        end_time: now + Duration::hours(1),
+        name: Some("manual".to_string()),
+        options: RecordingOptions {
+            content_path: "manual.m2ts".into(),
+            priority: 1,
+            pre_filters: vec![],
+            post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
+        },
+        tags: Default::default(),
+    };
+    let res = post("/api/recording/schedules/manual", input).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+
+    // Error::ServiceNotFound
+    let input = WebManualRecordingScheduleInput {
+        service_id: (0, 0).into(),
+        start_time: now,
+        end_time: now + Duration::hours(1),
+        name: None,
+        options: RecordingOptions {
+            content_path: "manual.m2ts".into(),
+            priority: 1,
+            pre_filters: vec![],
+            post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
+        },
+        tags: Default::default(),
+    };
+    let res = post("/api/recording/schedules/manual", input).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    // Error::InvalidTimeRange
+    let input = WebManualRecordingScheduleInput {
+        service_id: (0, 1).into(),
+        start_time: now,
+        end_time: now,
+        name: None,
+        options: RecordingOptions {
+            content_path: "manual.m2ts".into(),
+            priority: 1,
+            pre_filters: vec![],
+            post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
+        },
+        tags: Default::default(),
+    };
+    let res = post("/api/recording/schedules/manual", input).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_update_recording_schedule() {
+    let input = WebRecordingSchedulePatch {
+        options: None,
+        tags: Some(["tag".to_string()].into()),
+    };
+    let res = patch("/api/recording/schedules/2", input).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    // Error::ScheduleNotFound
+    let input = WebRecordingSchedulePatch {
+        options: None,
+        tags: None,
+    };
+    let res = patch("/api/recording/schedules/0", input).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    // Error::InvalidScheduleState
+    let input = WebRecordingSchedulePatch {
+        options: None,
+        tags: None,
+    };
+    let res = patch("/api/recording/schedules/1", input).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    // Error::InvalidPath
+    let input = WebRecordingSchedulePatch {
+        options: Some(RecordingOptions {
+            content_path: "../2.m2ts".into(),
+            priority: 1,
+            pre_filters: vec![],
+            post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
+        }),
+        tags: None,
+    };
+    let res = patch("/api/recording/schedules/2", input).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
 #[tokio::test]
 async fn test_delete_recording_schedule() {
     let res = delete("/api/recording/schedules/1").await;
@@ -469,6 +838,396 @@ async fn test_delete_recording_schedules() {
     assert_eq!(res.status(), StatusCode::OK);
 }
 
+#[tokio::test]
+async fn test_get_recording_rules() {
+    let res = get("/api/recording/rules").await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_create_recording_rule() {
+    // Ok
+    let input = WebRecordingRuleInput {
+        disabled: false,
+        condition: Default::default(),
+        options: RecordingOptions {
+            content_path: "{{{program_id}}}.m2ts".into(),
+            priority: 1,
+            pre_filters: vec![],
+            post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
+        },
+        tags: Default::default(),
+    };
+    let res = post("/api/recording/rules", input).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+
+    // Error::InvalidPath
+    let input = WebRecordingRuleInput {
+        disabled: false,
+        condition: Default::default(),
+        options: RecordingOptions {
+            content_path: "/{{{program_id}}}.m2ts".into(),
+            priority: 1,
+            pre_filters: vec![],
+            post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
+        },
+        tags: Default::default(),
+    };
+    let res = post("/api/recording/rules", input).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_get_recording_rule() {
+    let res = get("/api/recording/rules/1").await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = get("/api/recording/rules/0").await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_update_recording_rule() {
+    let input = WebRecordingRuleInput {
+        disabled: true,
+        condition: Default::default(),
+        options: RecordingOptions {
+            content_path: "{{{program_id}}}.m2ts".into(),
+            priority: 1,
+            pre_filters: vec![],
+            post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
+        },
+        tags: Default::default(),
+    };
+
+    let res = put("/api/recording/rules/1", &input).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = put("/api/recording/rules/0", &input).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_delete_recording_rule() {
+    let res = delete("/api/recording/rules/1").await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = delete("/api/recording/rules/0").await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_get_recording_series_list() {
+    let res = get("/api/recording/series").await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_create_recording_series() {
+    // Error::ProgramNotFound
+    let input = WebRecordingSeriesInput {
+        program_id: (0, 0, 0).into(),
+        options: RecordingOptions {
+            content_path: "{{{program_id}}}.m2ts".into(),
+            priority: 1,
+            pre_filters: vec![],
+            post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
+        },
+        tags: Default::default(),
+    };
+    let res = post("/api/recording/series", input).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    // Error::NoSeries
+    let input = WebRecordingSeriesInput {
+        program_id: (0, 0, 1).into(),
+        options: RecordingOptions {
+            content_path: "{{{program_id}}}.m2ts".into(),
+            priority: 1,
+            pre_filters: vec![],
+            post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
+        },
+        tags: Default::default(),
+    };
+    let res = post("/api/recording/series", input).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    // Ok
+    let input = WebRecordingSeriesInput {
+        program_id: (0, 0, 2).into(),
+        options: RecordingOptions {
+            content_path: "{{{program_id}}}.m2ts".into(),
+            priority: 1,
+            pre_filters: vec![],
+            post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
+        },
+        tags: Default::default(),
+    };
+    let res = post("/api/recording/series", input).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+
+    // Error::InvalidPath
+    let input = WebRecordingSeriesInput {
+        program_id: (0, 0, 2).into(),
+        options: RecordingOptions {
+            content_path: "../{{{program_id}}}.m2ts".into(),
+            priority: 1,
+            pre_filters: vec![],
+            post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
+        },
+        tags: Default::default(),
+    };
+    let res = post("/api/recording/series", input).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_get_recording_series() {
+    let res = get("/api/recording/series/1").await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = get("/api/recording/series/0").await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_delete_recording_series() {
+    let res = delete("/api/recording/series/1").await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = delete("/api/recording/series/0").await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_get_recording_conflicts() {
+    let res = get("/api/recording/conflicts").await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_get_recording_records() {
+    let res = get("/api/recording/records").await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_get_recording_record() {
+    let res = get("/api/recording/records/1").await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = get("/api/recording/records/0").await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_update_recording_record() {
+    let input = WebRecordingRecordPatch {
+        protected: Some(true),
+    };
+    let res = patch("/api/recording/records/1", input).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let input = WebRecordingRecordPatch {
+        protected: Some(true),
+    };
+    let res = patch("/api/recording/records/0", input).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_delete_recording_record() {
+    let res = delete("/api/recording/records/1").await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = delete("/api/recording/records/0").await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_get_recording_record_stream() {
+    let res = get("/api/recording/records/1/stream").await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers().contains_key(X_MIRAKURUN_TUNER_USER_ID));
+    assert_matches!(res.headers().get(ACCEPT_RANGES), Some(v) => {
+        assert_eq!(v, "bytes");
+    });
+    assert!(res.headers().contains_key("content-range"));
+    assert!(res.headers().contains_key("content-length"));
+
+    let res = get("/api/recording/records/1/stream?post-filters[]=cat").await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers().contains_key(X_MIRAKURUN_TUNER_USER_ID));
+    assert_matches!(res.headers().get(ACCEPT_RANGES), Some(v) => {
+        assert_eq!(v, "none");
+    });
+    assert!(!res.headers().contains_key("content-range"));
+    assert!(!res.headers().contains_key("content-length"));
+
+    let res = get("/api/recording/records/0/stream").await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_get_recording_history() {
+    let res = get("/api/recording/history").await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = get("/api/recording/history?since=0&until=1000&tag=tag1").await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = get("/api/recording/history?since=x").await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_import_from_epgstation() {
+    let now = Jst::now().timestamp_millis();
+    let input = serde_json::json!({
+        "rules": [
+            {
+                "id": 1,
+                "searchOption": { "keyword": "test", "GR": true },
+                "reserveOption": { "enable": true },
+            },
+            {
+                "id": 2,
+                "isTimeSpecification": true,
+                "searchOption": {},
+            },
+            {
+                "id": 3,
+            },
+        ],
+        "reserves": [
+            {
+                "id": 1,
+                "programId": ProgramId::from((0, 1, 3)).value(),
+                "channelId": 1,
+                "startAt": now,
+                "endAt": now + 3_600_000,
+            },
+            {
+                "id": 2,
+                "isTimeSpecified": true,
+                "channelId": 1,
+                "startAt": now,
+                "endAt": now + 3_600_000,
+                "name": "manual",
+            },
+            {
+                "id": 3,
+                "isSkip": true,
+                "programId": ProgramId::from((0, 1, 3)).value(),
+                "channelId": 1,
+                "startAt": now,
+                "endAt": now + 3_600_000,
+            },
+            {
+                "id": 4,
+                "programId": ProgramId::from((0, 1, 0)).value(),
+                "channelId": 1,
+                "startAt": now,
+                "endAt": now + 3_600_000,
+            },
+        ],
+        "tags": ["epgstation"],
+    });
+    let res = post("/api/recording/import/epgstation", input).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let result: WebImportResult = res.json().await;
+    assert_eq!(result.rules.len(), 3);
+    assert_matches!(
+        result.rules[0],
+        WebImportItem {
+            source_id: Some(1),
+            rule_id: Some(_),
+            error: None,
+            ..
+        }
+    );
+    assert_matches!(
+        result.rules[1],
+        WebImportItem {
+            source_id: Some(2),
+            error: Some(_),
+            ..
+        }
+    );
+    assert_matches!(
+        result.rules[2],
+        WebImportItem {
+            source_id: Some(3),
+            error: Some(_),
+            ..
+        }
+    );
+    assert_eq!(result.reserves.len(), 4);
+    assert_matches!(
+        result.reserves[0],
+        WebImportItem {
+            source_id: Some(1),
+            program_id: Some(_),
+            error: None,
+            ..
+        }
+    );
+    assert_matches!(
+        result.reserves[1],
+        WebImportItem {
+            source_id: Some(2),
+            program_id: Some(_),
+            error: None,
+            ..
+        }
+    );
+    assert_matches!(
+        result.reserves[2],
+        WebImportItem {
+            source_id: Some(3),
+            error: Some(_),
+            ..
+        }
+    );
+    assert_matches!(
+        result.reserves[3],
+        WebImportItem {
+            source_id: Some(4),
+            error: Some(_),
+            ..
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_get_recording_jobs() {
+    let res = get("/api/recording/jobs").await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_get_recording_job() {
+    let res = get("/api/recording/jobs/1").await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = get("/api/recording/jobs/0").await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
 #[tokio::test]
 async fn test_get_recording_recorders() {
     let res = get("/api/recording/recorders").await;
@@ -484,6 +1243,8 @@ async fn test_create_recording_recorder() {
             priority: 1,
             pre_filters: vec![],
             post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
         },
         tags: Default::default(),
     };
@@ -497,6 +1258,8 @@ async fn test_create_recording_recorder() {
             priority: 1,
             pre_filters: vec![],
             post_filters: vec![],
+            start_margin: None,
+            end_margin: None,
         },
         tags: Default::default(),
     };
@@ -665,6 +1428,17 @@ async fn test_get_events() {
     assert_eq!(res.status(), StatusCode::OK);
 }
 
+#[tokio::test]
+async fn test_get_metrics() {
+    let res = get("/metrics").await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let text = res.text().await;
+    assert!(text.contains("# TYPE mirakc_tuner_state gauge"));
+    assert!(text.contains("mirakc_recording_schedules{state=\"scheduled\"}"));
+    assert!(text.contains("# TYPE mirakc_timeshift_recorder_recording gauge"));
+    assert!(text.contains("# TYPE mirakc_http_request_duration_seconds histogram"));
+}
+
 #[tokio::test]
 async fn test_access_control_localhost() {
     let addr = "127.0.0.1:10000".parse().unwrap();
@@ -915,8 +1689,9 @@ async fn get_with_peer_addr(url: &str, addr: Option<SocketAddr>) -> TestResponse
     let app = build_app(&config)
         .layer(helper::ReplaceConnectInfoLayer::new(addr))
         .with_state(Arc::new(AppState {
-            config,
+            config: config.clone(),
             string_table: string_table_for_test(),
+            config_reloader: Arc::new(ConfigReloader::new("/path/to/config.yml", config)),
             tuner_manager: TunerManagerStub,
             epg: EpgStub,
             recording_manager: RecordingManagerStub,
@@ -944,6 +1719,22 @@ where
     TestClient::new(app).post(url).json(&data).send().await
 }
 
+async fn put<T>(url: &str, data: T) -> TestResponse
+where
+    T: serde::Serialize,
+{
+    let app = create_app();
+    TestClient::new(app).put(url).json(&data).send().await
+}
+
+async fn patch<T>(url: &str, data: T) -> TestResponse
+where
+    T: serde::Serialize,
+{
+    let app = create_app();
+    TestClient::new(app).patch(url).json(&data).send().await
+}
+
 async fn delete(url: &str) -> TestResponse {
     let app = create_app();
     TestClient::new(app).delete(url).send().await
@@ -952,8 +1743,10 @@ async fn delete(url: &str) -> TestResponse {
 fn create_app() -> Router {
     let config = config_for_test();
     build_app(&config).with_state(Arc::new(AppState {
-        config,
+        config: config.clone(),
         string_table: string_table_for_test(),
+        // The config file doesn't exist.
+        config_reloader: Arc::new(ConfigReloader::new("/path/to/config.yml", config)),
         tuner_manager: TunerManagerStub,
         epg: EpgStub,
         recording_manager: RecordingManagerStub,
@@ -52,6 +52,6 @@ async fn main() {
 
     match opt.command {
         Some(Command::RebuildTimeshift(opt)) => rebuild_timeshift::main(config, opt).await,
-        None => serve::main(config).await,
+        None => serve::main(config, opt.config).await,
     }
 }
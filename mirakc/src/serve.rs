@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use actlet::prelude::*;
@@ -5,7 +6,7 @@ use mirakc_core::*;
 use tokio::signal::unix::signal;
 use tokio::signal::unix::SignalKind;
 
-pub async fn main(config: Arc<config::Config>) {
+pub async fn main(config: Arc<config::Config>, config_path: PathBuf) {
     let string_table = string_table::load(&config.resource.strings_yaml);
 
     let system = System::new();
@@ -35,6 +36,14 @@ pub async fn main(config: Arc<config::Config>) {
         ))
         .await;
 
+    // Recordings need to know when their tuners are preempted.
+    tuner_manager
+        .call(tuner::RegisterEmitter(Emitter::new(
+            recording_manager.clone(),
+        )))
+        .await
+        .expect("Failed to register emitter for tuner::Event");
+
     let timeshift_manager = system
         .spawn_actor(timeshift::TimeshiftManager::new(
             config.clone(),
@@ -43,11 +52,34 @@ pub async fn main(config: Arc<config::Config>) {
         ))
         .await;
 
+    let config_reloader = Arc::new(reloader::ConfigReloader::new(config_path, config));
+
+    // Reload the config when SIGHUP is received.
+    let mut sighup = signal(SignalKind::hangup()).unwrap();
+    tokio::spawn({
+        let config_reloader = config_reloader.clone();
+        let tuner_manager = tuner_manager.clone();
+        let epg = epg.clone();
+        let recording_manager = recording_manager.clone();
+        let timeshift_manager = timeshift_manager.clone();
+        async move {
+            while sighup.recv().await.is_some() {
+                tracing::info!("SIGHUP received");
+                let result = config_reloader
+                    .reload(&tuner_manager, &epg, &recording_manager, &timeshift_manager)
+                    .await;
+                if let Err(err) = result {
+                    tracing::error!(%err, "Failed to reload the config");
+                }
+            }
+        }
+    });
+
     let mut sigint = signal(SignalKind::interrupt()).unwrap();
     let mut sigterm = signal(SignalKind::terminate()).unwrap();
 
     tokio::select! {
-        result = web::serve(config, string_table, tuner_manager, epg, recording_manager, timeshift_manager, onair_manager) => {
+        result = web::serve(config_reloader, string_table, tuner_manager, epg, recording_manager, timeshift_manager, onair_manager) => {
             match result {
                 Ok(_) => (),
                 Err(err) => tracing::error!(%err),